@@ -1,4 +1,6 @@
 fn main() {
+    // Only the desktop app needs Tauri's codegen; `--no-default-features` builds the CLI alone.
+    #[cfg(feature = "gui")]
     tauri_build::build();
 
     // Windows: Embed Common Controls v6 manifest for test binaries
@@ -0,0 +1,8 @@
+//! 无界面命令行工具，与桌面应用共用 `~/.cc-switch/cc-switch.db`
+//!
+//! 不依赖 Tauri/WebKit 的构建方式：`cargo build --bin cc-switch-cli --no-default-features`
+
+fn main() {
+    let code = cc_switch_lib::cli::run_cli(std::env::args().skip(1));
+    std::process::exit(code);
+}
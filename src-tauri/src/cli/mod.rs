@@ -0,0 +1,973 @@
+//! 无界面命令行入口（`cc-switch-cli`）
+//!
+//! 与桌面应用共用同一个 SQLite 数据库与服务层，便于在服务器、CI 或脚本中
+//! 管理供应商、MCP、提示词与 Skills。所有子命令都支持 `--json` 输出。
+
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::services::usage_export::{export_usage, UsageExportFormat, UsageGroupBy};
+use crate::services::usage_stats::LogFilters;
+use crate::services::{McpService, PromptService, ProviderService, SkillService};
+use crate::store::AppState;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::sync::Arc;
+
+mod serve;
+
+const USAGE: &str = "\
+用法: cc-switch-cli <命令> [参数] [--app <应用>] [--json]
+
+供应商:
+  provider list                          列出供应商（* 标记当前供应商）
+  provider current                       显示当前供应商
+  provider switch <id>                   切换供应商并写入 live 配置
+  provider add --name <名称> --settings <JSON|@文件> [--id <id>] [--website <URL>]
+  provider delete <id>                   删除供应商
+
+MCP:
+  mcp list                               列出 MCP 服务器
+  mcp enable <id> / mcp disable <id>     为 --app 指定的应用启用/停用服务器
+  mcp sync                               将已启用的服务器同步到各应用
+
+提示词:
+  prompt list                            列出提示词
+  prompt enable <id>                     启用提示词并写入应用文件
+
+Skills:
+  skill list                             列出已安装的 Skills
+  skill enable <id> / skill disable <id> 为 --app 指定的应用启用/停用 Skill
+  skill sync                             将已启用的 Skills 同步到 --app 指定的应用
+
+代理:
+  serve [--takeover <应用,...>] [--drain-timeout <秒>]
+                                         以无界面模式运行本地代理，直到收到 SIGTERM/Ctrl-C
+                                         --takeover 仅对本次运行生效，未指定时沿用已保存的接管状态；
+                                         退出前最多等待 --drain-timeout 秒（默认 30）让进行中的请求结束；
+                                         SIGHUP 重新加载配置
+  replay <request_id> [--provider <id>]  重放抓包记录（默认发往原供应商），并与原始响应对比
+
+使用量:
+  usage export [--format csv|jsonl] [--group-by provider|model|app|session|day]
+               [--from <YYYY-MM-DD>] [--to <YYYY-MM-DD>] [--provider <名称>] [--model <名称>]
+               [--client <标签>] [--status <状态码>] [--output <文件>]
+                                         导出请求明细或汇总（默认 CSV 输出到标准输出）；
+                                         仅在显式指定 --app 时按应用过滤；
+                                         session 以外的汇总读取聚合表，不支持 --status
+
+通用选项:
+  --app <应用>    claude（默认）、codex、gemini、opencode、openclaw
+  --json          以 JSON 输出，便于脚本处理
+  -h, --help      显示帮助
+  -V, --version   显示版本";
+
+/// 命令行错误
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    /// 参数错误（退出码 2）
+    #[error("{0}")]
+    Usage(String),
+    /// 执行错误（退出码 1）
+    #[error(transparent)]
+    App(#[from] AppError),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => 2,
+            CliError::App(_) => 1,
+        }
+    }
+}
+
+fn usage_err(msg: impl Into<String>) -> CliError {
+    CliError::Usage(msg.into())
+}
+
+/// 解析后的子命令
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Help,
+    Version,
+    ProviderList,
+    ProviderCurrent,
+    ProviderSwitch {
+        id: String,
+    },
+    ProviderAdd {
+        id: Option<String>,
+        name: String,
+        settings: String,
+        website: Option<String>,
+    },
+    ProviderDelete {
+        id: String,
+    },
+    McpList,
+    McpToggle {
+        id: String,
+        enabled: bool,
+    },
+    McpSync,
+    PromptList,
+    PromptEnable {
+        id: String,
+    },
+    SkillList,
+    SkillToggle {
+        id: String,
+        enabled: bool,
+    },
+    SkillSync,
+    Serve {
+        takeover: Option<Vec<AppType>>,
+        drain_timeout: u64,
+    },
+    Replay {
+        request_id: String,
+        provider: Option<String>,
+    },
+    UsageExport {
+        filters: LogFilters,
+        format: UsageExportFormat,
+        group_by: Option<UsageGroupBy>,
+        output: Option<String>,
+    },
+}
+
+/// 一次命令行调用
+#[derive(Debug, Clone, PartialEq)]
+pub struct Invocation {
+    pub command: Command,
+    pub app: AppType,
+    pub json: bool,
+}
+
+/// 解析命令行参数（不含程序名）
+pub fn parse_args<I, S>(args: I) -> Result<Invocation, CliError>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut positional = Vec::new();
+    let mut app = AppType::Claude;
+    let mut app_given = false;
+    let mut json = false;
+    let mut help = false;
+    let mut version = false;
+    let mut id = None;
+    let mut name = None;
+    let mut settings = None;
+    let mut website = None;
+    let mut takeover = None;
+    let mut drain_timeout = None;
+    let mut provider = None;
+    let mut format = None;
+    let mut group_by = None;
+    let mut from = None;
+    let mut to = None;
+    let mut model = None;
+    let mut client = None;
+    let mut status = None;
+    let mut output = None;
+
+    let mut iter = args.into_iter().map(Into::into);
+    while let Some(arg) = iter.next() {
+        // 支持 `--flag=value` 与 `--flag value` 两种写法
+        let (flag, inline) = match arg.split_once('=') {
+            Some((f, v)) if f.starts_with("--") => (f.to_string(), Some(v.to_string())),
+            _ => (arg.clone(), None),
+        };
+        let mut value = |flag: &str| -> Result<String, CliError> {
+            inline
+                .clone()
+                .or_else(|| iter.next())
+                .ok_or_else(|| usage_err(format!("{flag} 缺少参数值")))
+        };
+
+        match flag.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => help = true,
+            "-V" | "--version" => version = true,
+            "--app" => {
+                app = AppType::from_str(&value("--app")?).map_err(|e| usage_err(e.to_string()))?;
+                app_given = true;
+            }
+            "--id" => id = Some(value("--id")?),
+            "--name" => name = Some(value("--name")?),
+            "--settings" => settings = Some(value("--settings")?),
+            "--website" => website = Some(value("--website")?),
+            "--takeover" => takeover = Some(parse_takeover_apps(&value("--takeover")?)?),
+            "--drain-timeout" => drain_timeout = Some(value("--drain-timeout")?),
+            "--provider" => provider = Some(value("--provider")?),
+            "--format" => format = Some(value("--format")?),
+            "--group-by" => group_by = Some(value("--group-by")?),
+            "--from" => from = Some(value("--from")?),
+            "--to" => to = Some(value("--to")?),
+            "--model" => model = Some(value("--model")?),
+            "--client" => client = Some(value("--client")?),
+            "--status" => status = Some(value("--status")?),
+            "--output" => output = Some(value("--output")?),
+            other if other.starts_with('-') && other.len() > 1 => {
+                return Err(usage_err(format!("未知选项: {other}")));
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    let command = if version {
+        Command::Version
+    } else if help || positional.is_empty() {
+        Command::Help
+    } else {
+        let words: Vec<&str> = positional.iter().map(String::as_str).collect();
+        let target = |kind: &str| -> Result<String, CliError> {
+            words
+                .get(2)
+                .map(|s| s.to_string())
+                .ok_or_else(|| usage_err(format!("{kind} 缺少 <id> 参数")))
+        };
+
+        let command = match words[..2.min(words.len())] {
+            ["serve", ..] => Command::Serve {
+                takeover: takeover.clone(),
+                drain_timeout: drain_timeout
+                    .as_deref()
+                    .map(|s| {
+                        s.parse::<u64>()
+                            .map_err(|_| usage_err(format!("--drain-timeout 不是合法的秒数: {s}")))
+                    })
+                    .transpose()?
+                    .unwrap_or(serve::DEFAULT_DRAIN_TIMEOUT_SECS),
+            },
+            ["replay", ..] => Command::Replay {
+                request_id: words
+                    .get(1)
+                    .map(|s| s.to_string())
+                    .ok_or_else(|| usage_err("replay 缺少 <request_id> 参数"))?,
+                provider: provider.clone(),
+            },
+            ["usage", "export"] => Command::UsageExport {
+                filters: LogFilters {
+                    app_type: app_given.then(|| app.as_str().to_string()),
+                    provider_name: provider.clone(),
+                    model: model.clone(),
+                    status_code: status
+                        .as_deref()
+                        .map(|s| {
+                            s.parse::<u16>()
+                                .map_err(|_| usage_err(format!("--status 不是合法的状态码: {s}")))
+                        })
+                        .transpose()?,
+                    client_label: client.clone(),
+                    start_date: from.as_deref().map(|d| parse_date(d, false)).transpose()?,
+                    end_date: to.as_deref().map(|d| parse_date(d, true)).transpose()?,
+                },
+                format: format
+                    .as_deref()
+                    .map(UsageExportFormat::from_str)
+                    .transpose()
+                    .map_err(|e| usage_err(e.to_string()))?
+                    .unwrap_or(UsageExportFormat::Csv),
+                group_by: group_by
+                    .as_deref()
+                    .map(UsageGroupBy::from_str)
+                    .transpose()
+                    .map_err(|e| usage_err(e.to_string()))?,
+                output: output.clone(),
+            },
+            ["provider", "list"] => Command::ProviderList,
+            ["provider", "current"] => Command::ProviderCurrent,
+            ["provider", "switch"] => Command::ProviderSwitch {
+                id: target("provider switch")?,
+            },
+            ["provider", "add"] => Command::ProviderAdd {
+                id: id.clone(),
+                name: name
+                    .clone()
+                    .ok_or_else(|| usage_err("provider add 需要 --name"))?,
+                settings: settings
+                    .clone()
+                    .ok_or_else(|| usage_err("provider add 需要 --settings"))?,
+                website: website.clone(),
+            },
+            ["provider", "delete"] => Command::ProviderDelete {
+                id: target("provider delete")?,
+            },
+            ["mcp", "list"] => Command::McpList,
+            ["mcp", "sync"] => Command::McpSync,
+            ["mcp", action @ ("enable" | "disable")] => Command::McpToggle {
+                id: target("mcp")?,
+                enabled: action == "enable",
+            },
+            ["prompt", "list"] => Command::PromptList,
+            ["prompt", "enable"] => Command::PromptEnable {
+                id: target("prompt enable")?,
+            },
+            ["skill", "list"] => Command::SkillList,
+            ["skill", "sync"] => Command::SkillSync,
+            ["skill", action @ ("enable" | "disable")] => Command::SkillToggle {
+                id: target("skill")?,
+                enabled: action == "enable",
+            },
+            _ => return Err(usage_err(format!("未知命令: {}", positional.join(" ")))),
+        };
+
+        let max_words = match command {
+            Command::ProviderSwitch { .. }
+            | Command::ProviderDelete { .. }
+            | Command::McpToggle { .. }
+            | Command::PromptEnable { .. }
+            | Command::SkillToggle { .. } => 3,
+            Command::Serve { .. } => 1,
+            Command::Replay { .. } => 2,
+            _ => 2,
+        };
+        if words.len() > max_words {
+            return Err(usage_err(format!(
+                "多余的参数: {}",
+                words[max_words..].join(" ")
+            )));
+        }
+        command
+    };
+
+    Ok(Invocation { command, app, json })
+}
+
+/// 解析 `--from` / `--to` 日期（本地时区，`--to` 包含当天）
+fn parse_date(raw: &str, end_of_day: bool) -> Result<i64, CliError> {
+    let date = chrono::NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d")
+        .map_err(|_| usage_err(format!("日期格式应为 YYYY-MM-DD: {raw}")))?;
+    let date = if end_of_day {
+        date.succ_opt()
+            .ok_or_else(|| usage_err(format!("日期超出范围: {raw}")))?
+    } else {
+        date
+    };
+    let start = date
+        .and_hms_opt(0, 0, 0)
+        .and_then(|t| t.and_local_timezone(chrono::Local).earliest())
+        .ok_or_else(|| usage_err(format!("无法解析本地日期: {raw}")))?
+        .timestamp();
+    Ok(if end_of_day { start - 1 } else { start })
+}
+
+/// 解析 `--takeover claude,codex`（仅支持代理接管的应用）
+fn parse_takeover_apps(raw: &str) -> Result<Vec<AppType>, CliError> {
+    let mut apps = Vec::new();
+    for item in raw.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let app = AppType::from_str(item).map_err(|e| usage_err(e.to_string()))?;
+        if !matches!(app, AppType::Claude | AppType::Codex | AppType::Gemini) {
+            return Err(usage_err(format!("{item} 不支持代理接管")));
+        }
+        if !apps.contains(&app) {
+            apps.push(app);
+        }
+    }
+    Ok(apps)
+}
+
+/// 命令行入口，返回进程退出码
+pub fn run_cli<I, S>(args: I) -> i32
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let invocation = match parse_args(args) {
+        Ok(invocation) => invocation,
+        Err(e) => {
+            eprintln!("错误: {e}\n\n{USAGE}");
+            return e.exit_code();
+        }
+    };
+
+    match invocation.command {
+        Command::Help => {
+            println!("{USAGE}");
+            return 0;
+        }
+        Command::Version => {
+            println!("cc-switch-cli {}", env!("CARGO_PKG_VERSION"));
+            return 0;
+        }
+        _ => {}
+    }
+
+    let result = Database::init()
+        .map_err(CliError::from)
+        .and_then(|db| execute(&AppState::new(Arc::new(db)), &invocation));
+
+    match result {
+        Ok(output) => {
+            output.print(invocation.json);
+            0
+        }
+        Err(e) => {
+            if invocation.json {
+                eprintln!("{}", json!({ "error": e.to_string() }));
+            } else {
+                eprintln!("错误: {e}");
+            }
+            e.exit_code()
+        }
+    }
+}
+
+/// 命令输出（文本与 JSON 两种形式）
+#[derive(Debug)]
+pub struct Output {
+    pub text: String,
+    pub json: Value,
+}
+
+impl Output {
+    fn new(text: impl Into<String>, json: Value) -> Self {
+        Self {
+            text: text.into(),
+            json,
+        }
+    }
+
+    fn print(&self, json_output: bool) {
+        if json_output {
+            if !self.json.is_null() {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&self.json).unwrap_or_default()
+                );
+            }
+        } else if !self.text.is_empty() {
+            println!("{}", self.text);
+        }
+    }
+}
+
+fn skill_err(e: anyhow::Error) -> CliError {
+    CliError::App(AppError::Message(e.to_string()))
+}
+
+/// 执行子命令
+pub fn execute(state: &AppState, invocation: &Invocation) -> Result<Output, CliError> {
+    let app = invocation.app.clone();
+    let app_name = app.as_str().to_string();
+
+    let output = match &invocation.command {
+        Command::Help | Command::Version => Output::new("", Value::Null),
+        Command::ProviderList => {
+            let providers = ProviderService::list(state, app.clone())?;
+            let current = ProviderService::current(state, app)?;
+            let text = providers
+                .values()
+                .map(|p| {
+                    let marker = if p.id == current { "*" } else { " " };
+                    format!("{marker} {}\t{}", p.id, p.name)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let list: Vec<&Provider> = providers.values().collect();
+            Output::new(
+                text,
+                json!({ "app": app_name, "current": current, "providers": list }),
+            )
+        }
+        Command::ProviderCurrent => {
+            let current = ProviderService::current(state, app.clone())?;
+            let provider = ProviderService::list(state, app)?.shift_remove(&current);
+            let text = provider
+                .as_ref()
+                .map(|p| format!("{}\t{}", p.id, p.name))
+                .unwrap_or_default();
+            Output::new(text, json!({ "app": app_name, "current": provider }))
+        }
+        Command::ProviderSwitch { id } => {
+            ProviderService::switch(state, app, id)?;
+            Output::new(
+                format!("已切换 {app_name} 供应商: {id}"),
+                json!({ "app": app_name, "switched": id }),
+            )
+        }
+        Command::ProviderAdd {
+            id,
+            name,
+            settings,
+            website,
+        } => {
+            let settings_config = load_settings(settings)?;
+            let id = id
+                .clone()
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+            let provider =
+                Provider::with_id(id.clone(), name.clone(), settings_config, website.clone());
+            ProviderService::add(state, app, provider)?;
+            Output::new(
+                format!("已添加 {app_name} 供应商: {id}"),
+                json!({ "app": app_name, "added": id }),
+            )
+        }
+        Command::ProviderDelete { id } => {
+            ProviderService::delete(state, app, id)?;
+            Output::new(
+                format!("已删除 {app_name} 供应商: {id}"),
+                json!({ "app": app_name, "deleted": id }),
+            )
+        }
+        Command::McpList => {
+            let servers = McpService::get_all_servers(state)?;
+            let text = servers
+                .values()
+                .map(|s| {
+                    let apps = s
+                        .apps
+                        .enabled_apps()
+                        .iter()
+                        .map(|a| a.as_str().to_string())
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    format!("{}\t{}\t[{apps}]", s.id, s.name)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let list: Vec<_> = servers.values().collect();
+            Output::new(text, json!({ "servers": list }))
+        }
+        Command::McpToggle { id, enabled } => {
+            if !McpService::get_all_servers(state)?.contains_key(id) {
+                return Err(AppError::Message(format!("MCP 服务器不存在: {id}")).into());
+            }
+            McpService::toggle_app(state, id, app, *enabled)?;
+            let action = if *enabled { "启用" } else { "停用" };
+            Output::new(
+                format!("已为 {app_name} {action} MCP 服务器: {id}"),
+                json!({ "app": app_name, "id": id, "enabled": enabled }),
+            )
+        }
+        Command::McpSync => {
+            McpService::sync_all_enabled(state)?;
+            Output::new("已同步 MCP 服务器", json!({ "synced": true }))
+        }
+        Command::PromptList => {
+            let prompts = PromptService::get_prompts(state, app)?;
+            let text = prompts
+                .values()
+                .map(|p| {
+                    let marker = if p.enabled { "*" } else { " " };
+                    format!("{marker} {}\t{}", p.id, p.name)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let list: Vec<_> = prompts.values().collect();
+            Output::new(text, json!({ "app": app_name, "prompts": list }))
+        }
+        Command::PromptEnable { id } => {
+            PromptService::enable_prompt(state, app, id)?;
+            Output::new(
+                format!("已为 {app_name} 启用提示词: {id}"),
+                json!({ "app": app_name, "enabled": id }),
+            )
+        }
+        Command::SkillList => {
+            let skills = SkillService::get_all_installed(&state.db).map_err(skill_err)?;
+            let text = skills
+                .iter()
+                .map(|s| {
+                    let apps = s
+                        .apps
+                        .enabled_apps()
+                        .iter()
+                        .map(|a| a.as_str().to_string())
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    format!("{}\t{}\t[{apps}]", s.id, s.name)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            Output::new(text, json!({ "skills": skills }))
+        }
+        Command::SkillToggle { id, enabled } => {
+            SkillService::toggle_app(&state.db, id, &app, *enabled).map_err(skill_err)?;
+            let action = if *enabled { "启用" } else { "停用" };
+            Output::new(
+                format!("已为 {app_name} {action} Skill: {id}"),
+                json!({ "app": app_name, "id": id, "enabled": enabled }),
+            )
+        }
+        Command::Serve {
+            takeover,
+            drain_timeout,
+        } => {
+            serve::run(
+                state,
+                takeover.as_deref(),
+                std::time::Duration::from_secs(*drain_timeout),
+                invocation.json,
+            )?;
+            Output::new("", Value::Null)
+        }
+        Command::Replay {
+            request_id,
+            provider,
+        } => {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| AppError::Message(format!("创建异步运行时失败: {e}")))?;
+            let result = runtime.block_on(crate::proxy::replay::replay_capture(
+                &state.db,
+                request_id,
+                provider.as_deref(),
+            ))?;
+
+            let original = result
+                .original_status
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| "-".to_string());
+            let mut lines = vec![format!(
+                "重放 {} -> {}: 状态码 {original} -> {}，耗时 {}ms，{}",
+                result.request_id,
+                result.provider_id,
+                result.status,
+                result.latency_ms,
+                if result.identical {
+                    "响应一致"
+                } else {
+                    "响应不一致"
+                }
+            )];
+            if !result.identical {
+                lines.extend(
+                    result
+                        .diff
+                        .iter()
+                        .map(|line| format!("{}{}", line.prefix(), line.text)),
+                );
+            }
+            Output::new(lines.join("\n"), json!(result))
+        }
+        Command::UsageExport {
+            filters,
+            format,
+            group_by,
+            output,
+        } => match output {
+            Some(path) => {
+                let file = std::fs::File::create(path).map_err(|e| AppError::io(path, e))?;
+                let mut out = std::io::BufWriter::new(file);
+                let rows = export_usage(&state.db, filters, *group_by, *format, &mut out)?;
+                Output::new(
+                    format!("已导出 {rows} 行到 {path}"),
+                    json!({ "rows": rows, "output": path }),
+                )
+            }
+            // 直接写入标准输出，便于管道处理
+            None => {
+                let stdout = std::io::stdout();
+                let mut out = stdout.lock();
+                export_usage(&state.db, filters, *group_by, *format, &mut out)?;
+                Output::new("", Value::Null)
+            }
+        },
+        Command::SkillSync => {
+            SkillService::sync_to_app(&state.db, &app).map_err(skill_err)?;
+            Output::new(
+                format!("已同步 Skills 到 {app_name}"),
+                json!({ "app": app_name, "synced": true }),
+            )
+        }
+    };
+
+    Ok(output)
+}
+
+/// 读取 `--settings` 参数：内联 JSON 或 `@文件路径`
+fn load_settings(raw: &str) -> Result<Value, CliError> {
+    let content = match raw.strip_prefix('@') {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| AppError::io(path, e))?,
+        None => raw.to_string(),
+    };
+    let value: Value = serde_json::from_str(&content)
+        .map_err(|e| usage_err(format!("--settings 不是合法的 JSON: {e}")))?;
+    if !value.is_object() {
+        return Err(usage_err("--settings 必须是 JSON 对象"));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::env;
+    use tempfile::TempDir;
+
+    struct TempHome {
+        #[allow(dead_code)]
+        dir: TempDir,
+        original_home: Option<String>,
+        original_userprofile: Option<String>,
+    }
+
+    impl TempHome {
+        fn new() -> Self {
+            let dir = TempDir::new().expect("failed to create temp home");
+            let original_home = env::var("HOME").ok();
+            let original_userprofile = env::var("USERPROFILE").ok();
+
+            env::set_var("HOME", dir.path());
+            env::set_var("USERPROFILE", dir.path());
+
+            Self {
+                dir,
+                original_home,
+                original_userprofile,
+            }
+        }
+    }
+
+    impl Drop for TempHome {
+        fn drop(&mut self) {
+            match &self.original_home {
+                Some(value) => env::set_var("HOME", value),
+                None => env::remove_var("HOME"),
+            }
+
+            match &self.original_userprofile {
+                Some(value) => env::set_var("USERPROFILE", value),
+                None => env::remove_var("USERPROFILE"),
+            }
+        }
+    }
+
+    fn parse(args: &[&str]) -> Result<Invocation, CliError> {
+        parse_args(args.iter().copied())
+    }
+
+    fn run(state: &AppState, args: &[&str]) -> Result<Output, CliError> {
+        execute(state, &parse(args)?)
+    }
+
+    fn add_provider(state: &AppState, id: &str, base_url: &str) -> Output {
+        let settings = json!({ "env": { "ANTHROPIC_BASE_URL": base_url } }).to_string();
+        run(
+            state,
+            &[
+                "provider",
+                "add",
+                "--id",
+                id,
+                "--name",
+                &id.to_uppercase(),
+                "--settings",
+                &settings,
+                "--json",
+            ],
+        )
+        .expect("add provider")
+    }
+
+    #[test]
+    #[serial]
+    fn test_execute_provider_add_switch_delete() {
+        let _home = TempHome::new();
+        let state = AppState::new(Arc::new(Database::memory().expect("memory db")));
+
+        let output = add_provider(&state, "a", "https://a.example");
+        assert_eq!(output.json, json!({ "app": "claude", "added": "a" }));
+        add_provider(&state, "b", "https://b.example");
+
+        let output = run(&state, &["provider", "switch", "b", "--json"]).unwrap();
+        assert_eq!(output.json, json!({ "app": "claude", "switched": "b" }));
+        let live =
+            crate::config::read_json_file::<Value>(&crate::config::get_claude_settings_path())
+                .expect("live settings written");
+        assert_eq!(live["env"]["ANTHROPIC_BASE_URL"], "https://b.example");
+
+        let output = run(&state, &["provider", "list", "--json"]).unwrap();
+        assert_eq!(output.json["current"], "b");
+        let ids: Vec<&str> = output.json["providers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&"a") && ids.contains(&"b"));
+        assert!(output.text.lines().any(|line| line == "* b\tB"));
+
+        let output = run(&state, &["provider", "current", "--json"]).unwrap();
+        assert_eq!(output.json["current"]["name"], "B");
+
+        // 当前供应商不可删除，属于执行错误（退出码 1）
+        let err = run(&state, &["provider", "delete", "b"]).expect_err("current provider");
+        assert_eq!(err.exit_code(), 1);
+
+        let output = run(&state, &["provider", "delete", "a", "--json"]).unwrap();
+        assert_eq!(output.json, json!({ "app": "claude", "deleted": "a" }));
+        assert_eq!(output.text, "已删除 claude 供应商: a");
+        let output = run(&state, &["provider", "list", "--json"]).unwrap();
+        assert_eq!(output.json["providers"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    #[serial]
+    fn test_execute_switch_to_unknown_provider_fails() {
+        let _home = TempHome::new();
+        let state = AppState::new(Arc::new(Database::memory().expect("memory db")));
+
+        let err = run(&state, &["provider", "switch", "missing", "--app", "codex"])
+            .expect_err("unknown provider");
+        assert_eq!(err.exit_code(), 1);
+        assert!(err.to_string().contains("missing"));
+    }
+
+    #[test]
+    fn test_parse_provider_commands() {
+        let inv = parse(&["provider", "switch", "p1", "--app", "codex", "--json"]).unwrap();
+        assert_eq!(inv.command, Command::ProviderSwitch { id: "p1".into() });
+        assert_eq!(inv.app, AppType::Codex);
+        assert!(inv.json);
+
+        let inv = parse(&[
+            "provider",
+            "add",
+            "--name=Demo",
+            "--settings",
+            "{\"env\":{}}",
+            "--id",
+            "demo",
+        ])
+        .unwrap();
+        assert_eq!(
+            inv.command,
+            Command::ProviderAdd {
+                id: Some("demo".into()),
+                name: "Demo".into(),
+                settings: "{\"env\":{}}".into(),
+                website: None,
+            }
+        );
+        assert_eq!(inv.app, AppType::Claude);
+    }
+
+    #[test]
+    fn test_parse_mcp_prompt_skill_commands() {
+        assert_eq!(parse(&["mcp", "sync"]).unwrap().command, Command::McpSync);
+        assert_eq!(
+            parse(&["mcp", "disable", "fetch", "--app", "gemini"])
+                .unwrap()
+                .command,
+            Command::McpToggle {
+                id: "fetch".into(),
+                enabled: false
+            }
+        );
+        assert_eq!(
+            parse(&["prompt", "enable", "x"]).unwrap().command,
+            Command::PromptEnable { id: "x".into() }
+        );
+        assert_eq!(
+            parse(&["skill", "enable", "local:foo"]).unwrap().command,
+            Command::SkillToggle {
+                id: "local:foo".into(),
+                enabled: true
+            }
+        );
+        assert_eq!(
+            parse(&[
+                "serve",
+                "--takeover",
+                "claude,codex",
+                "--drain-timeout",
+                "5"
+            ])
+            .unwrap()
+            .command,
+            Command::Serve {
+                takeover: Some(vec![AppType::Claude, AppType::Codex]),
+                drain_timeout: 5,
+            }
+        );
+        assert_eq!(
+            parse(&["serve"]).unwrap().command,
+            Command::Serve {
+                takeover: None,
+                drain_timeout: serve::DEFAULT_DRAIN_TIMEOUT_SECS,
+            }
+        );
+        assert_eq!(
+            parse(&["replay", "req-1", "--provider", "p2"])
+                .unwrap()
+                .command,
+            Command::Replay {
+                request_id: "req-1".into(),
+                provider: Some("p2".into())
+            }
+        );
+        match parse(&[
+            "usage",
+            "export",
+            "--format",
+            "jsonl",
+            "--group-by",
+            "session",
+            "--from",
+            "2026-01-01",
+            "--to",
+            "2026-01-31",
+            "--status",
+            "200",
+        ])
+        .unwrap()
+        .command
+        {
+            Command::UsageExport {
+                filters,
+                format,
+                group_by,
+                output,
+            } => {
+                assert_eq!(format, UsageExportFormat::Jsonl);
+                assert_eq!(group_by, Some(UsageGroupBy::Session));
+                assert_eq!(output, None);
+                assert_eq!(filters.app_type, None);
+                assert_eq!(filters.status_code, Some(200));
+                let (from, to) = (filters.start_date.unwrap(), filters.end_date.unwrap());
+                assert_eq!(to - from, 31 * 86_400 - 1);
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+        assert_eq!(parse(&[]).unwrap().command, Command::Help);
+        assert_eq!(parse(&["-V"]).unwrap().command, Command::Version);
+    }
+
+    #[test]
+    fn test_parse_errors_are_usage_errors() {
+        for args in [
+            &["provider", "switch"][..],
+            &["provider", "add", "--name", "x"],
+            &["provider", "list", "extra"],
+            &["mcp", "frobnicate"],
+            &["provider", "list", "--app", "vim"],
+            &["provider", "list", "--bogus"],
+            &["provider", "list", "--app"],
+            &["serve", "--takeover", "opencode"],
+            &["serve", "extra"],
+            &["serve", "--drain-timeout", "-1"],
+            &["usage", "export", "--format", "xml"],
+            &["usage", "export", "--from", "2026/01/01"],
+        ] {
+            let err = parse(args).expect_err(&format!("{args:?} should fail"));
+            assert_eq!(err.exit_code(), 2, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn test_load_settings_requires_object() {
+        assert!(load_settings("{\"env\":{}}").unwrap().is_object());
+        assert!(load_settings("[1]").is_err());
+        assert!(load_settings("not json").is_err());
+    }
+}
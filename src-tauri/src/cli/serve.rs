@@ -0,0 +1,241 @@
+//! headless 代理守护进程（`cc-switch-cli serve`）
+//!
+//! 不启动 WebView，直接从数据库读取 `proxy_config` 启动本地代理：
+//! - 启动前恢复上次异常退出残留的 Live 接管
+//! - 对 `--takeover` 指定的应用（未指定时按数据库中保存的接管状态）接管 Live 配置；
+//!   `--takeover` 只对本次运行生效，退出时写回各应用原来的接管开关
+//! - 按请求日志保留策略定期清理过期的原始日志
+//! - SIGHUP：重新加载代理配置（`apply_runtime_config`）
+//! - SIGTERM / Ctrl-C：最多等待 `--drain-timeout` 秒让进行中的请求结束，
+//!   恢复 `proxy_live_backup` 中的 Live 配置后退出
+
+use super::{CliError, Output};
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::store::AppState;
+use serde_json::json;
+use std::time::Duration;
+
+/// 退出前等待进行中请求结束的默认时长（秒）
+pub const DEFAULT_DRAIN_TIMEOUT_SECS: u64 = 30;
+
+/// 守护进程收到的控制信号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ServeSignal {
+    Reload,
+    Shutdown,
+}
+
+/// 运行代理守护进程，直到收到退出信号
+pub fn run(
+    state: &AppState,
+    takeover: Option<&[AppType]>,
+    drain_timeout: Duration,
+    json_output: bool,
+) -> Result<(), CliError> {
+    init_logger();
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| AppError::Message(format!("创建异步运行时失败: {e}")))?;
+
+    runtime.block_on(serve(state, takeover, drain_timeout, json_output))
+}
+
+async fn serve(
+    state: &AppState,
+    takeover: Option<&[AppType]>,
+    drain_timeout: Duration,
+    json_output: bool,
+) -> Result<(), CliError> {
+    crate::recover_takeover_residue_on_startup(state).await;
+
+    let info = state
+        .proxy_service
+        .start()
+        .await
+        .map_err(AppError::Message)?;
+    tokio::spawn(crate::services::usage_retention::run_usage_retention_loop(
+        state.db.clone(),
+    ));
+    tokio::spawn(crate::services::budget_alert::run_alert_worker(
+        state.db.clone(),
+        None,
+    ));
+
+    // 接管前各应用的 `enabled` 开关，退出时写回，避免本次会话改变下次启动的接管状态
+    let mut saved_enabled = Vec::new();
+    match takeover {
+        Some(apps) => {
+            for app in apps {
+                let app_type = app.as_str();
+                match state.db.get_proxy_config_for_app(app_type).await {
+                    Ok(config) => saved_enabled.push((app_type, config.enabled)),
+                    Err(e) => log::warn!("读取 {app_type} 的接管状态失败: {e}"),
+                }
+                if let Err(e) = state
+                    .proxy_service
+                    .set_takeover_for_app(app_type, true)
+                    .await
+                {
+                    log::error!("接管 {app_type} 的 Live 配置失败: {e}");
+                    crate::cleanup_proxy_before_exit(state).await;
+                    restore_enabled_flags(state, &saved_enabled).await;
+                    return Err(AppError::Message(e).into());
+                }
+                log::info!("已接管 {app_type} 的 Live 配置");
+            }
+        }
+        // 未指定时沿用桌面端保存的接管状态
+        None => crate::restore_proxy_state_on_startup(state).await,
+    }
+
+    let taken_over = state
+        .proxy_service
+        .get_takeover_status()
+        .await
+        .map_err(AppError::Message)?;
+    let output = Output::new(
+        format!(
+            "代理已启动于 {}:{}（按 Ctrl-C 或发送 SIGTERM 退出，SIGHUP 重新加载配置）",
+            info.address, info.port
+        ),
+        json!({ "address": info.address, "port": info.port, "takeover": taken_over }),
+    );
+    output.print(json_output);
+
+    loop {
+        match wait_for_signal().await {
+            ServeSignal::Reload => {
+                log::info!("收到 SIGHUP，重新加载代理配置");
+                if let Err(e) = state.proxy_service.reload_runtime_config().await {
+                    log::error!("重新加载代理配置失败: {e}");
+                }
+            }
+            ServeSignal::Shutdown => {
+                log::info!("收到退出信号，等待进行中的请求结束...");
+                break;
+            }
+        }
+    }
+
+    if !state.proxy_service.drain_in_flight(drain_timeout).await {
+        log::warn!(
+            "等待 {} 秒后仍有请求未结束，继续停止代理",
+            drain_timeout.as_secs()
+        );
+    }
+    log::info!("正在停止代理并恢复 Live 配置...");
+    crate::cleanup_proxy_before_exit(state).await;
+    restore_enabled_flags(state, &saved_enabled).await;
+    Ok(())
+}
+
+/// 写回 `--takeover` 之前各应用的接管开关
+async fn restore_enabled_flags(state: &AppState, saved: &[(&str, bool)]) {
+    for &(app_type, enabled) in saved {
+        let mut config = match state.db.get_proxy_config_for_app(app_type).await {
+            Ok(config) => config,
+            Err(e) => {
+                log::warn!("读取 {app_type} 的接管状态失败: {e}");
+                continue;
+            }
+        };
+        if config.enabled == enabled {
+            continue;
+        }
+        config.enabled = enabled;
+        if let Err(e) = state.db.update_proxy_config_for_app(config).await {
+            log::warn!("恢复 {app_type} 的接管状态失败: {e}");
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> ServeSignal {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let (Ok(mut hangup), Ok(mut terminate)) = (
+        signal(SignalKind::hangup()),
+        signal(SignalKind::terminate()),
+    ) else {
+        log::warn!("注册 Unix 信号处理失败，仅响应 Ctrl-C");
+        let _ = tokio::signal::ctrl_c().await;
+        return ServeSignal::Shutdown;
+    };
+
+    tokio::select! {
+        _ = hangup.recv() => ServeSignal::Reload,
+        _ = terminate.recv() => ServeSignal::Shutdown,
+        _ = tokio::signal::ctrl_c() => ServeSignal::Shutdown,
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> ServeSignal {
+    let _ = tokio::signal::ctrl_c().await;
+    ServeSignal::Shutdown
+}
+
+/// 输出到 stderr 的简易日志器（级别由 `RUST_LOG` 控制，默认 info）
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!(
+                "{} {:<5} [{}] {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn init_logger() {
+    static LOGGER: StderrLogger = StderrLogger;
+    let level = std::env::var("RUST_LOG")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(log::LevelFilter::Info);
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_restore_enabled_flags_reverts_session_takeover() -> Result<(), AppError> {
+        let db = Arc::new(Database::memory()?);
+        let state = AppState::new(db.clone());
+
+        let mut config = db.get_proxy_config_for_app("claude").await?;
+        config.enabled = true;
+        db.update_proxy_config_for_app(config).await?;
+
+        restore_enabled_flags(&state, &[("claude", false)]).await;
+        assert!(!db.get_proxy_config_for_app("claude").await?.enabled);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_drain_returns_immediately_when_proxy_stopped() -> Result<(), AppError> {
+        let state = AppState::new(Arc::new(Database::memory()?));
+        assert!(state.proxy_service.drain_in_flight(Duration::ZERO).await);
+        Ok(())
+    }
+}
@@ -2,7 +2,10 @@
 //!
 //! 提供前端调用的 API 接口
 
+use crate::database::{ClientToken, ModelRoutingRule, RequestCapture, RequestCaptureSummary};
 use crate::error::AppError;
+use crate::proxy::client_auth::{issue_client_token, IssuedClientToken};
+use crate::proxy::replay::{replay_capture, ReplayResult};
 use crate::proxy::types::*;
 use crate::proxy::{CircuitBreakerConfig, CircuitBreakerStats};
 use crate::store::AppState;
@@ -407,3 +410,143 @@ pub async fn get_circuit_breaker_stats(
     let _ = (state, provider_id, app_type);
     Ok(None)
 }
+
+// ==================== Client Tokens ====================
+
+/// 获取代理客户端令牌列表（不含明文）
+#[tauri::command]
+pub async fn list_proxy_client_tokens(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<ClientToken>, String> {
+    state.db.list_client_tokens().map_err(|e| e.to_string())
+}
+
+/// 签发代理客户端令牌
+///
+/// 明文令牌只在返回值中出现一次，数据库仅保存哈希
+#[tauri::command]
+pub async fn create_proxy_client_token(
+    state: tauri::State<'_, AppState>,
+    label: String,
+) -> Result<IssuedClientToken, String> {
+    issue_client_token(&state.db, &label).map_err(|e| e.to_string())
+}
+
+/// 启用/停用代理客户端令牌
+#[tauri::command]
+pub async fn set_proxy_client_token_enabled(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    state
+        .db
+        .set_client_token_enabled(&id, enabled)
+        .map_err(|e| e.to_string())
+}
+
+/// 设置代理客户端令牌的 RPM/TPM 限制（为空表示不限制）
+#[tauri::command]
+pub async fn set_proxy_client_token_limits(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    rpm_limit: Option<u32>,
+    tpm_limit: Option<u32>,
+) -> Result<(), String> {
+    state
+        .db
+        .set_client_token_limits(&id, rpm_limit, tpm_limit)
+        .map_err(|e| e.to_string())
+}
+
+/// 删除代理客户端令牌
+#[tauri::command]
+pub async fn delete_proxy_client_token(
+    state: tauri::State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    state.db.delete_client_token(&id).map_err(|e| e.to_string())
+}
+
+// ==================== Model Routing Rules ====================
+
+/// 获取应用的模型路由规则（按匹配顺序排列）
+#[tauri::command]
+pub async fn list_model_routing_rules(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+) -> Result<Vec<ModelRoutingRule>, String> {
+    state
+        .db
+        .list_model_routing_rules(&app_type)
+        .map_err(|e| e.to_string())
+}
+
+/// 新增或更新模型路由规则
+#[tauri::command]
+pub async fn save_model_routing_rule(
+    state: tauri::State<'_, AppState>,
+    rule: ModelRoutingRule,
+) -> Result<ModelRoutingRule, String> {
+    state
+        .db
+        .save_model_routing_rule(&rule)
+        .map_err(|e| e.to_string())
+}
+
+/// 删除模型路由规则
+#[tauri::command]
+pub async fn delete_model_routing_rule(
+    state: tauri::State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    state
+        .db
+        .delete_model_routing_rule(&id)
+        .map_err(|e| e.to_string())
+}
+
+// ==================== Request Captures ====================
+
+/// 获取最近的请求抓包记录（不含请求体与响应体）
+#[tauri::command]
+pub async fn list_request_captures(
+    state: tauri::State<'_, AppState>,
+    app_type: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<RequestCaptureSummary>, String> {
+    state
+        .db
+        .list_request_captures(app_type.as_deref(), limit.unwrap_or(100))
+        .map_err(|e| e.to_string())
+}
+
+/// 获取完整的请求抓包记录
+#[tauri::command]
+pub async fn get_request_capture(
+    state: tauri::State<'_, AppState>,
+    request_id: String,
+) -> Result<Option<RequestCapture>, String> {
+    state
+        .db
+        .get_request_capture(&request_id)
+        .map_err(|e| e.to_string())
+}
+
+/// 清空请求抓包记录
+#[tauri::command]
+pub async fn clear_request_captures(state: tauri::State<'_, AppState>) -> Result<usize, String> {
+    state.db.clear_request_captures().map_err(|e| e.to_string())
+}
+
+/// 将抓包请求重放到指定供应商（为空时使用原供应商），返回与原始响应的对比
+#[tauri::command]
+pub async fn replay_request_capture(
+    state: tauri::State<'_, AppState>,
+    request_id: String,
+    provider_id: Option<String>,
+) -> Result<ReplayResult, String> {
+    replay_capture(&state.db, &request_id, provider_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -1,15 +1,54 @@
 #![allow(non_snake_case)]
 
 use crate::session_manager;
+use crate::session_manager::export::SessionExportFormat;
+use crate::session_manager::index::{SessionSearchHit, SessionSearchQuery};
+use crate::store::AppState;
+use tauri::State;
 
+/// List local sessions, each annotated with the proxy usage attributed to it
 #[tauri::command]
-pub async fn list_sessions() -> Result<Vec<session_manager::SessionMeta>, String> {
-    let sessions = tauri::async_runtime::spawn_blocking(session_manager::scan_sessions)
-        .await
-        .map_err(|e| format!("Failed to scan sessions: {e}"))?;
+pub async fn list_sessions(
+    state: State<'_, AppState>,
+) -> Result<Vec<session_manager::SessionMeta>, String> {
+    let db = state.db.clone();
+    let sessions = tauri::async_runtime::spawn_blocking(move || {
+        let mut sessions = session_manager::scan_sessions();
+        if let Err(e) = db.attach_session_usage(&mut sessions) {
+            log::warn!("Failed to attach session usage: {e}");
+        }
+        sessions
+    })
+    .await
+    .map_err(|e| format!("Failed to scan sessions: {e}"))?;
     Ok(sessions)
 }
 
+/// Full-text search across all local sessions (the index is refreshed incrementally first)
+#[tauri::command]
+pub async fn search_sessions(
+    state: State<'_, AppState>,
+    query: SessionSearchQuery,
+) -> Result<Vec<SessionSearchHit>, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let report = session_manager::index::sync_session_index(&db)?;
+        if report.indexed > 0 || report.removed > 0 {
+            log::debug!(
+                "Session index updated: {} indexed, {} removed",
+                report.indexed,
+                report.removed
+            );
+        }
+        let mut hits = session_manager::index::search_sessions(&db, &query)?;
+        db.attach_session_usage(hits.iter_mut().map(|hit| &mut hit.session))?;
+        Ok::<_, crate::error::AppError>(hits)
+    })
+    .await
+    .map_err(|e| format!("Failed to search sessions: {e}"))?
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_session_messages(
     providerId: String,
@@ -24,6 +63,25 @@ pub async fn get_session_messages(
     .map_err(|e| format!("Failed to load session messages: {e}"))?
 }
 
+/// Export a session transcript to a file; returns the number of exported messages
+#[tauri::command]
+pub async fn export_session(
+    providerId: String,
+    sourcePath: String,
+    format: SessionExportFormat,
+    filePath: String,
+) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (document, count) =
+            session_manager::export::export_session(&providerId, &sourcePath, format)?;
+        std::fs::write(&filePath, document)
+            .map_err(|e| format!("Failed to write {filePath}: {e}"))?;
+        Ok(count)
+    })
+    .await
+    .map_err(|e| format!("Failed to export session: {e}"))?
+}
+
 #[tauri::command]
 pub async fn launch_session_terminal(
     command: String,
@@ -41,6 +99,8 @@ pub async fn launch_session_terminal(
     let target = match preferred.as_deref() {
         Some("iterm2") => "iterm".to_string(),
         Some(t) => t.to_string(),
+        // Linux: resolved at launch ($TERMINAL, detected terminals, x-terminal-emulator)
+        None if cfg!(target_os = "linux") => "auto".to_string(),
         None => "terminal".to_string(), // Default to Terminal.app on macOS
     };
 
@@ -171,3 +171,24 @@ pub async fn set_log_config(
     );
     Ok(true)
 }
+
+/// 获取请求抓包配置
+#[tauri::command]
+pub async fn get_capture_config(
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<crate::proxy::types::CaptureConfig, String> {
+    state.db.get_capture_config().map_err(|e| e.to_string())
+}
+
+/// 设置请求抓包配置
+#[tauri::command]
+pub async fn set_capture_config(
+    state: tauri::State<'_, crate::AppState>,
+    config: crate::proxy::types::CaptureConfig,
+) -> Result<bool, String> {
+    state
+        .db
+        .set_capture_config(&config)
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
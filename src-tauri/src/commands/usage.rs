@@ -1,6 +1,16 @@
 //! 使用统计相关命令
 
+use crate::database::ModelPricingAlias;
 use crate::error::AppError;
+use crate::proxy::types::{BudgetAlertConfig, UsageRetentionConfig};
+use crate::services::cost_recompute::{recompute_costs, CostRecomputeReport};
+use crate::services::pricing_import::{
+    import_pricing_catalog, load_pricing_catalog, PricingImportDiff,
+};
+use crate::services::usage_export::{
+    export_usage as write_usage_export, UsageExportFormat, UsageGroupBy,
+};
+use crate::services::usage_retention::{apply_usage_retention, UsageRetentionReport};
 use crate::services::usage_stats::*;
 use crate::store::AppState;
 use tauri::State;
@@ -31,12 +41,68 @@ pub fn get_provider_stats(state: State<'_, AppState>) -> Result<Vec<ProviderStat
     state.db.get_provider_stats()
 }
 
+/// 获取客户端统计（按代理客户端令牌标签）
+#[tauri::command]
+pub fn get_client_stats(
+    state: State<'_, AppState>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+) -> Result<Vec<ClientStats>, AppError> {
+    state.db.get_client_stats(start_date, end_date)
+}
+
 /// 获取模型统计
 #[tauri::command]
 pub fn get_model_stats(state: State<'_, AppState>) -> Result<Vec<ModelStats>, AppError> {
     state.db.get_model_stats()
 }
 
+/// 获取请求日志保留策略
+#[tauri::command]
+pub fn get_usage_retention_config(
+    state: State<'_, AppState>,
+) -> Result<UsageRetentionConfig, AppError> {
+    state.db.get_usage_retention_config()
+}
+
+/// 更新请求日志保留策略
+#[tauri::command]
+pub fn set_usage_retention_config(
+    state: State<'_, AppState>,
+    config: UsageRetentionConfig,
+) -> Result<(), AppError> {
+    state.db.set_usage_retention_config(&config)
+}
+
+/// 立即按保留策略清理原始请求日志（聚合统计不受影响）
+#[tauri::command]
+pub async fn prune_usage_logs(
+    state: State<'_, AppState>,
+) -> Result<UsageRetentionReport, AppError> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let config = db.get_usage_retention_config()?;
+        apply_usage_retention(&db, &config)
+    })
+    .await
+    .map_err(|e| AppError::Message(format!("清理请求日志失败: {e}")))?
+}
+
+/// 获取预算提醒配置
+#[tauri::command]
+pub fn get_budget_alert_config(state: State<'_, AppState>) -> Result<BudgetAlertConfig, AppError> {
+    state.db.get_budget_alert_config()
+}
+
+/// 更新预算提醒配置
+#[tauri::command]
+pub fn set_budget_alert_config(
+    state: State<'_, AppState>,
+    config: BudgetAlertConfig,
+) -> Result<(), AppError> {
+    state.db.set_budget_alert_config(&config)
+}
+
 /// 获取请求日志列表
 #[tauri::command]
 pub fn get_request_logs(
@@ -48,6 +114,25 @@ pub fn get_request_logs(
     state.db.get_request_logs(&filters, page, page_size)
 }
 
+/// 导出使用量数据到文件（`group_by` 为空时导出请求明细），返回导出行数
+#[tauri::command]
+pub async fn export_usage(
+    state: State<'_, AppState>,
+    file_path: String,
+    filters: LogFilters,
+    format: UsageExportFormat,
+    group_by: Option<UsageGroupBy>,
+) -> Result<u64, AppError> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let file = std::fs::File::create(&file_path).map_err(|e| AppError::io(&file_path, e))?;
+        let mut out = std::io::BufWriter::new(file);
+        write_usage_export(&db, &filters, group_by, format, &mut out)
+    })
+    .await
+    .map_err(|e| AppError::Message(format!("导出使用量数据失败: {e}")))?
+}
+
 /// 获取单个请求详情
 #[tauri::command]
 pub fn get_request_detail(
@@ -140,6 +225,61 @@ pub fn update_model_pricing(
     Ok(())
 }
 
+/// 从 LiteLLM / OpenRouter 价格目录导入模型定价
+///
+/// `source` 为本地文件路径或 URL；`apply` 为 false 时只返回差异供预览
+#[tauri::command]
+pub async fn import_model_pricing(
+    state: State<'_, AppState>,
+    source: String,
+    apply: bool,
+) -> Result<PricingImportDiff, AppError> {
+    let catalog = load_pricing_catalog(&source).await?;
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || import_pricing_catalog(&db, &catalog, apply))
+        .await
+        .map_err(|e| AppError::Message(format!("导入模型定价失败: {e}")))?
+}
+
+/// 按当前模型定价重算历史请求成本
+///
+/// `dry_run` 为 true 时只返回成本变化，不写入数据库
+#[tauri::command]
+pub async fn recompute_usage_costs(
+    state: State<'_, AppState>,
+    filters: LogFilters,
+    use_current_multiplier: bool,
+    dry_run: bool,
+) -> Result<CostRecomputeReport, AppError> {
+    recompute_costs(state.db.clone(), filters, use_current_multiplier, dry_run).await
+}
+
+/// 获取模型定价别名
+#[tauri::command]
+pub fn get_model_pricing_aliases(
+    state: State<'_, AppState>,
+) -> Result<Vec<ModelPricingAlias>, AppError> {
+    state.db.list_model_pricing_aliases()
+}
+
+/// 新增或更新模型定价别名
+#[tauri::command]
+pub fn save_model_pricing_alias(
+    state: State<'_, AppState>,
+    alias: ModelPricingAlias,
+) -> Result<(), AppError> {
+    state.db.save_model_pricing_alias(&alias)
+}
+
+/// 删除模型定价别名
+#[tauri::command]
+pub fn delete_model_pricing_alias(
+    state: State<'_, AppState>,
+    pattern: String,
+) -> Result<(), AppError> {
+    state.db.delete_model_pricing_alias(&pattern)
+}
+
 /// 检查 Provider 使用限额
 #[tauri::command]
 pub fn check_provider_limits(
@@ -165,15 +305,3 @@ pub fn delete_model_pricing(state: State<'_, AppState>, model_id: String) -> Res
     log::info!("已删除模型定价: {model_id}");
     Ok(())
 }
-
-/// 模型定价信息
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ModelPricingInfo {
-    pub model_id: String,
-    pub display_name: String,
-    pub input_cost_per_million: String,
-    pub output_cost_per_million: String,
-    pub cache_read_cost_per_million: String,
-    pub cache_creation_cost_per_million: String,
-}
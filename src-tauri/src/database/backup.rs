@@ -15,6 +15,9 @@ use tempfile::NamedTempFile;
 
 const CC_SWITCH_SQL_EXPORT_HEADER: &str = "-- CC Switch SQLite 导出";
 
+/// 不参与导出的表前缀：会话全文索引由本机会话文件生成，FTS5 影子表也无法按普通表导入
+const SQL_EXPORT_SKIPPED_TABLE_PREFIXES: &[&str] = &["session_index_", "session_messages_fts"];
+
 impl Database {
     /// 导出为 SQLite 兼容的 SQL 文本（内存字符串）
     pub fn export_sql_string(&self) -> Result<String, AppError> {
@@ -240,12 +243,19 @@ impl Database {
         while let Some(row) = rows.next().map_err(|e| AppError::Database(e.to_string()))? {
             let obj_type: String = row.get(0).map_err(|e| AppError::Database(e.to_string()))?;
             let name: String = row.get(1).map_err(|e| AppError::Database(e.to_string()))?;
+            let tbl_name: String = row.get(2).map_err(|e| AppError::Database(e.to_string()))?;
             let sql: String = row.get(3).map_err(|e| AppError::Database(e.to_string()))?;
 
             // 跳过 SQLite 内部对象（如 sqlite_sequence）
             if name.starts_with("sqlite_") {
                 continue;
             }
+            if SQL_EXPORT_SKIPPED_TABLE_PREFIXES
+                .iter()
+                .any(|prefix| tbl_name.starts_with(prefix))
+            {
+                continue;
+            }
 
             output.push_str(&sql);
             output.push_str(";\n");
@@ -0,0 +1,96 @@
+//! 预算提醒状态 DAO
+//!
+//! 记录每个消费周期内已触发的提醒阈值，保证同一周期内每个阈值只提醒一次，
+//! 应用重启后也不会重复提醒。`provider_id` 为空字符串表示应用整体限额。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+
+impl Database {
+    /// 记录本周期内已达到的提醒阈值，返回此前未记录（即本次新触发）的阈值
+    ///
+    /// 同一范围内旧周期的记录会被一并清理。
+    pub fn record_budget_alert_thresholds(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        period: &str,
+        period_key: &str,
+        thresholds: &[u32],
+    ) -> Result<Vec<u32>, AppError> {
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tx.execute(
+            "DELETE FROM budget_alert_state
+             WHERE app_type = ?1 AND provider_id = ?2 AND period = ?3 AND period_key <> ?4",
+            rusqlite::params![app_type, provider_id, period, period_key],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let now = chrono::Utc::now().timestamp();
+        let mut fired = Vec::new();
+        for threshold in thresholds {
+            let inserted = tx
+                .execute(
+                    "INSERT OR IGNORE INTO budget_alert_state
+                        (app_type, provider_id, period, period_key, threshold, fired_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    rusqlite::params![app_type, provider_id, period, period_key, threshold, now],
+                )
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            if inserted > 0 {
+                fired.push(*threshold);
+            }
+        }
+
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(fired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_alert_thresholds_fire_once_per_period() -> Result<(), AppError> {
+        let db = Database::memory()?;
+
+        assert_eq!(
+            db.record_budget_alert_thresholds("claude", "p1", "daily", "2026-01-01", &[50])?,
+            vec![50]
+        );
+        assert_eq!(
+            db.record_budget_alert_thresholds("claude", "p1", "daily", "2026-01-01", &[50, 80])?,
+            vec![80]
+        );
+        assert!(db
+            .record_budget_alert_thresholds("claude", "p1", "daily", "2026-01-01", &[50, 80])?
+            .is_empty());
+
+        // 其他范围与周期互不影响
+        assert_eq!(
+            db.record_budget_alert_thresholds("claude", "", "daily", "2026-01-01", &[50])?,
+            vec![50]
+        );
+        assert_eq!(
+            db.record_budget_alert_thresholds("claude", "p1", "daily", "2026-01-02", &[50])?,
+            vec![50]
+        );
+
+        // 新周期开始后旧周期记录被清理
+        let conn = lock_conn!(db.conn);
+        let stale: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM budget_alert_state WHERE period_key = '2026-01-01' AND provider_id = 'p1'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        assert_eq!(stale, 0);
+        Ok(())
+    }
+}
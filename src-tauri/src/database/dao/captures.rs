@@ -0,0 +1,247 @@
+//! 请求抓包 DAO
+//!
+//! 保存代理发往上游的完整请求与原始响应（含 SSE 流），按 `request_id`
+//! 与 `proxy_request_logs` 关联。同一请求多次尝试时只保留最后一次。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+
+/// 完整的请求抓包记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestCapture {
+    pub request_id: String,
+    pub app_type: String,
+    pub provider_id: String,
+    pub method: String,
+    /// 上游 URL（查询参数中的密钥已遮蔽）
+    pub url: String,
+    /// 发往上游的请求头（认证类 Header 已遮蔽）
+    pub request_headers: Vec<(String, String)>,
+    /// 转换后的上游请求体
+    pub request_body: String,
+    pub request_truncated: bool,
+    pub response_status: Option<u16>,
+    #[serde(default)]
+    pub response_headers: Vec<(String, String)>,
+    /// 原始响应体（流式请求为完整 SSE 文本）
+    pub response_body: Option<String>,
+    pub response_truncated: bool,
+    /// 请求未得到完整响应时的错误信息
+    pub error_message: Option<String>,
+    pub created_at: i64,
+}
+
+/// 抓包列表项（不含请求体与响应体）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestCaptureSummary {
+    pub request_id: String,
+    pub app_type: String,
+    pub provider_id: String,
+    pub url: String,
+    pub response_status: Option<u16>,
+    pub error_message: Option<String>,
+    pub created_at: i64,
+}
+
+fn headers_to_json(headers: &[(String, String)]) -> Result<String, AppError> {
+    serde_json::to_string(headers).map_err(|e| AppError::Database(format!("序列化请求头失败: {e}")))
+}
+
+fn headers_from_json(json: Option<String>) -> Vec<(String, String)> {
+    json.and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn row_to_capture(row: &rusqlite::Row<'_>) -> rusqlite::Result<RequestCapture> {
+    Ok(RequestCapture {
+        request_id: row.get(0)?,
+        app_type: row.get(1)?,
+        provider_id: row.get(2)?,
+        method: row.get(3)?,
+        url: row.get(4)?,
+        request_headers: headers_from_json(row.get(5)?),
+        request_body: row.get(6)?,
+        request_truncated: row.get::<_, i64>(7)? != 0,
+        response_status: row.get(8)?,
+        response_headers: headers_from_json(row.get(9)?),
+        response_body: row.get(10)?,
+        response_truncated: row.get::<_, i64>(11)? != 0,
+        error_message: row.get(12)?,
+        created_at: row.get(13)?,
+    })
+}
+
+impl Database {
+    /// 保存抓包记录，并清理超过保留天数的旧记录
+    pub fn save_request_capture(
+        &self,
+        capture: &RequestCapture,
+        retention_days: u32,
+    ) -> Result<(), AppError> {
+        let request_headers = headers_to_json(&capture.request_headers)?;
+        let response_headers = headers_to_json(&capture.response_headers)?;
+        let cutoff = capture.created_at - i64::from(retention_days) * 86_400;
+
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT OR REPLACE INTO proxy_request_captures (
+                request_id, app_type, provider_id, method, url,
+                request_headers, request_body, request_truncated,
+                response_status, response_headers, response_body, response_truncated,
+                error_message, created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            rusqlite::params![
+                capture.request_id,
+                capture.app_type,
+                capture.provider_id,
+                capture.method,
+                capture.url,
+                request_headers,
+                capture.request_body,
+                capture.request_truncated,
+                capture.response_status,
+                response_headers,
+                capture.response_body,
+                capture.response_truncated,
+                capture.error_message,
+                capture.created_at,
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        conn.execute(
+            "DELETE FROM proxy_request_captures WHERE created_at < ?1",
+            [cutoff],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 根据 request_id 获取抓包记录
+    pub fn get_request_capture(
+        &self,
+        request_id: &str,
+    ) -> Result<Option<RequestCapture>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            "SELECT request_id, app_type, provider_id, method, url,
+                    request_headers, request_body, request_truncated,
+                    response_status, response_headers, response_body, response_truncated,
+                    error_message, created_at
+             FROM proxy_request_captures WHERE request_id = ?1",
+            [request_id],
+            row_to_capture,
+        )
+        .optional()
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 获取最近的抓包记录（按时间倒序）
+    pub fn list_request_captures(
+        &self,
+        app_type: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<RequestCaptureSummary>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT request_id, app_type, provider_id, url, response_status, error_message, created_at
+                 FROM proxy_request_captures
+                 WHERE ?1 IS NULL OR app_type = ?1
+                 ORDER BY created_at DESC, rowid DESC
+                 LIMIT ?2",
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let captures = stmt
+            .query_map(rusqlite::params![app_type, limit], |row| {
+                Ok(RequestCaptureSummary {
+                    request_id: row.get(0)?,
+                    app_type: row.get(1)?,
+                    provider_id: row.get(2)?,
+                    url: row.get(3)?,
+                    response_status: row.get(4)?,
+                    error_message: row.get(5)?,
+                    created_at: row.get(6)?,
+                })
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(captures)
+    }
+
+    /// 清空所有抓包记录，返回删除条数
+    pub fn clear_request_captures(&self) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute("DELETE FROM proxy_request_captures", [])
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture(request_id: &str, created_at: i64) -> RequestCapture {
+        RequestCapture {
+            request_id: request_id.to_string(),
+            app_type: "claude".to_string(),
+            provider_id: "p1".to_string(),
+            method: "POST".to_string(),
+            url: "https://api.example.com/v1/messages".to_string(),
+            request_headers: vec![("x-api-key".to_string(), "sk-1...cdef".to_string())],
+            request_body: r#"{"model":"m"}"#.to_string(),
+            request_truncated: false,
+            response_status: Some(200),
+            response_headers: vec![("content-type".to_string(), "application/json".to_string())],
+            response_body: Some(r#"{"ok":true}"#.to_string()),
+            response_truncated: false,
+            error_message: None,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_request_capture_roundtrip_and_retention() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let now = chrono::Utc::now().timestamp();
+
+        db.save_request_capture(&capture("old", now - 10 * 86_400), 30)?;
+        db.save_request_capture(&capture("req-1", now), 30)?;
+        assert_eq!(
+            db.get_request_capture("req-1")?,
+            Some(capture("req-1", now))
+        );
+        assert_eq!(db.list_request_captures(None, 10)?.len(), 2);
+        assert!(db.list_request_captures(Some("codex"), 10)?.is_empty());
+
+        // 同一请求重复保存时保留最后一次尝试
+        let mut retried = capture("req-1", now);
+        retried.response_status = Some(500);
+        db.save_request_capture(&retried, 30)?;
+        assert_eq!(
+            db.get_request_capture("req-1")?
+                .and_then(|c| c.response_status),
+            Some(500)
+        );
+
+        // 保留天数缩短后，旧记录在下次写入时被清理
+        db.save_request_capture(&capture("req-2", now), 7)?;
+        assert!(db.get_request_capture("old")?.is_none());
+        let ids: Vec<String> = db
+            .list_request_captures(None, 10)?
+            .into_iter()
+            .map(|c| c.request_id)
+            .collect();
+        assert_eq!(ids, vec!["req-2".to_string(), "req-1".to_string()]);
+
+        assert_eq!(db.clear_request_captures()?, 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+//! 代理客户端令牌 DAO
+//!
+//! 管理代理签发给客户端（团队成员、开发容器等）的访问令牌。
+//! 数据库仅保存令牌哈希与前缀，明文只在创建时返回一次。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+
+/// 代理客户端令牌（不含明文）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientToken {
+    pub id: String,
+    /// 令牌标签（客户端 / 使用者名称），写入请求日志用于按客户端统计
+    pub label: String,
+    /// 令牌前缀，便于在界面上辨认
+    pub token_prefix: String,
+    pub enabled: bool,
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+    /// 每分钟请求数限制（RPM）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpm_limit: Option<u32>,
+    /// 每分钟 Token 数限制（TPM）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tpm_limit: Option<u32>,
+}
+
+const CLIENT_TOKEN_COLUMNS: &str =
+    "id, label, token_prefix, enabled, created_at, last_used_at, rpm_limit, tpm_limit";
+
+fn row_to_client_token(row: &rusqlite::Row<'_>) -> rusqlite::Result<ClientToken> {
+    Ok(ClientToken {
+        id: row.get(0)?,
+        label: row.get(1)?,
+        token_prefix: row.get(2)?,
+        enabled: row.get::<_, i64>(3)? != 0,
+        created_at: row.get(4)?,
+        last_used_at: row.get(5)?,
+        rpm_limit: row.get(6)?,
+        tpm_limit: row.get(7)?,
+    })
+}
+
+impl Database {
+    /// 获取所有客户端令牌（按创建时间排序）
+    pub fn list_client_tokens(&self) -> Result<Vec<ClientToken>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {CLIENT_TOKEN_COLUMNS} FROM proxy_client_tokens ORDER BY created_at ASC, id ASC"
+            ))
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let tokens = stmt
+            .query_map([], row_to_client_token)
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(tokens)
+    }
+
+    /// 保存新的客户端令牌（调用方负责生成明文并计算哈希）
+    pub fn insert_client_token(
+        &self,
+        label: &str,
+        token_hash: &str,
+        token_prefix: &str,
+    ) -> Result<ClientToken, AppError> {
+        let token = ClientToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            label: label.to_string(),
+            token_prefix: token_prefix.to_string(),
+            enabled: true,
+            created_at: chrono::Utc::now().timestamp(),
+            last_used_at: None,
+            rpm_limit: None,
+            tpm_limit: None,
+        };
+
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO proxy_client_tokens (id, label, token_hash, token_prefix, enabled, created_at)
+             VALUES (?1, ?2, ?3, ?4, 1, ?5)",
+            rusqlite::params![
+                token.id,
+                token.label,
+                token_hash,
+                token.token_prefix,
+                token.created_at
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(token)
+    }
+
+    /// 根据令牌哈希查找已启用的客户端令牌
+    pub fn find_enabled_client_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<ClientToken>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            &format!(
+                "SELECT {CLIENT_TOKEN_COLUMNS} FROM proxy_client_tokens
+                 WHERE token_hash = ?1 AND enabled = 1"
+            ),
+            [token_hash],
+            row_to_client_token,
+        )
+        .optional()
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 更新令牌最近使用时间
+    pub fn touch_client_token(&self, id: &str) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE proxy_client_tokens SET last_used_at = ?2 WHERE id = ?1",
+            rusqlite::params![id, chrono::Utc::now().timestamp()],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 启用/停用客户端令牌
+    pub fn set_client_token_enabled(&self, id: &str, enabled: bool) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        let affected = conn
+            .execute(
+                "UPDATE proxy_client_tokens SET enabled = ?2 WHERE id = ?1",
+                rusqlite::params![id, if enabled { 1 } else { 0 }],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        if affected == 0 {
+            return Err(AppError::Message(format!("客户端令牌不存在: {id}")));
+        }
+        Ok(())
+    }
+
+    /// 设置客户端令牌的速率限制（None 或 0 表示不限制）
+    pub fn set_client_token_limits(
+        &self,
+        id: &str,
+        rpm_limit: Option<u32>,
+        tpm_limit: Option<u32>,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        let affected = conn
+            .execute(
+                "UPDATE proxy_client_tokens SET rpm_limit = ?2, tpm_limit = ?3 WHERE id = ?1",
+                rusqlite::params![
+                    id,
+                    rpm_limit.filter(|v| *v > 0),
+                    tpm_limit.filter(|v| *v > 0)
+                ],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        if affected == 0 {
+            return Err(AppError::Message(format!("客户端令牌不存在: {id}")));
+        }
+        Ok(())
+    }
+
+    /// 删除客户端令牌（历史请求日志中的标签保留）
+    pub fn delete_client_token(&self, id: &str) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute("DELETE FROM proxy_client_tokens WHERE id = ?1", [id])
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 是否要求客户端认证（读取 claude 行，三行镜像一致）
+    pub fn is_client_auth_required(&self) -> Result<bool, AppError> {
+        let conn = lock_conn!(self.conn);
+        let required = conn
+            .query_row(
+                "SELECT require_client_auth FROM proxy_config WHERE app_type = 'claude'",
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(required.unwrap_or(0) != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_token_lifecycle() -> Result<(), AppError> {
+        let db = Database::memory()?;
+
+        let token = db.insert_client_token("alice", "hash-a", "ccs-abcd")?;
+        assert!(token.enabled);
+        assert_eq!(db.list_client_tokens()?, vec![token.clone()]);
+
+        let found = db.find_enabled_client_token("hash-a")?.expect("token");
+        assert_eq!(found.label, "alice");
+        assert!(db.find_enabled_client_token("hash-b")?.is_none());
+
+        db.touch_client_token(&token.id)?;
+        let touched = db.find_enabled_client_token("hash-a")?.expect("token");
+        assert!(touched.last_used_at.is_some());
+
+        db.set_client_token_limits(&token.id, Some(60), Some(0))?;
+        let limited = db.find_enabled_client_token("hash-a")?.expect("token");
+        assert_eq!(limited.rpm_limit, Some(60));
+        assert_eq!(limited.tpm_limit, None);
+        assert!(db.set_client_token_limits("missing", None, None).is_err());
+
+        db.set_client_token_enabled(&token.id, false)?;
+        assert!(db.find_enabled_client_token("hash-a")?.is_none());
+        assert!(db.set_client_token_enabled("missing", true).is_err());
+
+        db.delete_client_token(&token.id)?;
+        assert!(db.list_client_tokens()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_token_hash_is_unique() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        db.insert_client_token("alice", "same-hash", "ccs-1111")?;
+        assert!(db
+            .insert_client_token("bob", "same-hash", "ccs-2222")
+            .is_err());
+        Ok(())
+    }
+}
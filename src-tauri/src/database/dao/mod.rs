@@ -2,18 +2,29 @@
 //!
 //! Database access operations for each domain
 
+pub mod budget_alerts;
+pub mod captures;
+pub mod client_tokens;
 pub mod failover;
 pub mod mcp;
+pub mod model_routing;
 pub mod omo;
+pub mod pricing_aliases;
 pub mod prompts;
 pub mod providers;
 pub mod proxy;
+pub mod session_index;
 pub mod settings;
 pub mod skills;
 pub mod stream_check;
 pub mod universal_providers;
+pub mod usage_rollup;
 
 // 所有 DAO 方法都通过 Database impl 提供，无需单独导出
 // 导出 FailoverQueueItem 供外部使用
+pub use captures::{RequestCapture, RequestCaptureSummary};
+pub use client_tokens::ClientToken;
 pub use failover::FailoverQueueItem;
+pub use model_routing::ModelRoutingRule;
 pub use omo::OmoGlobalConfig;
+pub use pricing_aliases::ModelPricingAlias;
@@ -0,0 +1,170 @@
+//! 模型路由规则 DAO
+//!
+//! 每个应用可配置若干规则：请求模型匹配规则的通配符模式时，
+//! 按规则自带的供应商列表顺序选择供应商（列表即该规则的故障转移顺序）。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+
+/// 模型路由规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelRoutingRule {
+    /// 规则 ID（新建时可为空，由数据库层生成）
+    #[serde(default)]
+    pub id: String,
+    pub app_type: String,
+    /// 模型名称通配符（`*` 匹配任意字符，`?` 匹配单个字符，忽略大小写）
+    pub pattern: String,
+    /// 按优先级排列的供应商 ID（首个为主供应商，其余为故障转移备选）
+    pub provider_ids: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 规则匹配顺序（越小越先匹配）
+    #[serde(default)]
+    pub sort_index: i64,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn row_to_rule(row: &rusqlite::Row<'_>) -> rusqlite::Result<ModelRoutingRule> {
+    let provider_ids: String = row.get(3)?;
+    Ok(ModelRoutingRule {
+        id: row.get(0)?,
+        app_type: row.get(1)?,
+        pattern: row.get(2)?,
+        provider_ids: serde_json::from_str(&provider_ids).unwrap_or_default(),
+        enabled: row.get::<_, i64>(4)? != 0,
+        sort_index: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+impl Database {
+    /// 获取应用的模型路由规则（按匹配顺序排列）
+    pub fn list_model_routing_rules(
+        &self,
+        app_type: &str,
+    ) -> Result<Vec<ModelRoutingRule>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, app_type, pattern, provider_ids, enabled, sort_index, created_at
+                 FROM model_routing_rules WHERE app_type = ?1
+                 ORDER BY sort_index ASC, created_at ASC, id ASC",
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let rules = stmt
+            .query_map([app_type], row_to_rule)
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(rules)
+    }
+
+    /// 新增或更新模型路由规则，返回保存后的规则
+    pub fn save_model_routing_rule(
+        &self,
+        rule: &ModelRoutingRule,
+    ) -> Result<ModelRoutingRule, AppError> {
+        let pattern = rule.pattern.trim();
+        if pattern.is_empty() {
+            return Err(AppError::InvalidInput("模型匹配模式不能为空".to_string()));
+        }
+        if rule.provider_ids.is_empty() {
+            return Err(AppError::InvalidInput(
+                "路由规则至少需要一个供应商".to_string(),
+            ));
+        }
+
+        let mut saved = rule.clone();
+        saved.pattern = pattern.to_string();
+        if saved.id.is_empty() {
+            saved.id = uuid::Uuid::new_v4().to_string();
+        }
+        if saved.created_at == 0 {
+            saved.created_at = chrono::Utc::now().timestamp();
+        }
+        let provider_ids = serde_json::to_string(&saved.provider_ids)
+            .map_err(|e| AppError::Database(format!("序列化供应商列表失败: {e}")))?;
+
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT OR REPLACE INTO model_routing_rules
+                (id, app_type, pattern, provider_ids, enabled, sort_index, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                saved.id,
+                saved.app_type,
+                saved.pattern,
+                provider_ids,
+                saved.enabled,
+                saved.sort_index,
+                saved.created_at,
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(saved)
+    }
+
+    /// 删除模型路由规则
+    pub fn delete_model_routing_rule(&self, id: &str) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute("DELETE FROM model_routing_rules WHERE id = ?1", [id])
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, sort_index: i64) -> ModelRoutingRule {
+        ModelRoutingRule {
+            id: String::new(),
+            app_type: "claude".to_string(),
+            pattern: pattern.to_string(),
+            provider_ids: vec!["cheap".to_string(), "official".to_string()],
+            enabled: true,
+            sort_index,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_model_routing_rule_lifecycle() -> Result<(), AppError> {
+        let db = Database::memory()?;
+
+        let opus = db.save_model_routing_rule(&rule("*opus*", 1))?;
+        let haiku = db.save_model_routing_rule(&rule(" *haiku* ", 0))?;
+        assert!(!haiku.id.is_empty());
+        assert_eq!(haiku.pattern, "*haiku*");
+
+        let rules = db.list_model_routing_rules("claude")?;
+        assert_eq!(rules, vec![haiku.clone(), opus.clone()]);
+        assert!(db.list_model_routing_rules("codex")?.is_empty());
+
+        let mut disabled = opus.clone();
+        disabled.enabled = false;
+        db.save_model_routing_rule(&disabled)?;
+        assert!(!db.list_model_routing_rules("claude")?[1].enabled);
+
+        let mut empty = rule("*", 0);
+        empty.provider_ids.clear();
+        assert!(db.save_model_routing_rule(&empty).is_err());
+        assert!(db.save_model_routing_rule(&rule("  ", 0)).is_err());
+
+        db.delete_model_routing_rule(&haiku.id)?;
+        assert_eq!(db.list_model_routing_rules("claude")?.len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+//! 模型定价别名 DAO
+//!
+//! 别名模式（通配符，`*` 匹配任意字符，`?` 匹配单个字符，忽略大小写）把不同写法的
+//! 模型 ID 指向同一条定价，例如 `claude-sonnet-4?5*` → `claude-sonnet-4-5-20250929`，
+//! 使 `claude-sonnet-4-5-20250929` 与 `anthropic/claude-sonnet-4.5` 按同一价格计费。
+//! 计费时精确匹配失败后按别名查找；导入价格目录时也按别名归并到已有定价。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::model_pattern::pattern_matches;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+/// 模型定价别名
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPricingAlias {
+    /// 模型 ID 通配符（匹配清洗后的模型名称）
+    pub pattern: String,
+    /// 指向的定价模型 ID
+    pub model_id: String,
+}
+
+/// 读取全部别名（更长、更具体的模式优先）
+pub(crate) fn load_pricing_aliases(conn: &Connection) -> Result<Vec<ModelPricingAlias>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT pattern, model_id FROM model_pricing_aliases
+             ORDER BY LENGTH(pattern) DESC, pattern ASC",
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    let aliases = stmt
+        .query_map([], |row| {
+            Ok(ModelPricingAlias {
+                pattern: row.get(0)?,
+                model_id: row.get(1)?,
+            })
+        })
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(aliases)
+}
+
+/// 返回第一个匹配模型 ID 的别名所指向的定价模型 ID
+pub(crate) fn match_pricing_alias<'a>(
+    aliases: &'a [ModelPricingAlias],
+    model_id: &str,
+) -> Option<&'a str> {
+    aliases
+        .iter()
+        .find(|alias| pattern_matches(&alias.pattern, model_id))
+        .map(|alias| alias.model_id.as_str())
+}
+
+impl Database {
+    /// 获取全部模型定价别名
+    pub fn list_model_pricing_aliases(&self) -> Result<Vec<ModelPricingAlias>, AppError> {
+        let conn = lock_conn!(self.conn);
+        load_pricing_aliases(&conn)
+    }
+
+    /// 新增或更新模型定价别名
+    pub fn save_model_pricing_alias(&self, alias: &ModelPricingAlias) -> Result<(), AppError> {
+        let pattern = alias.pattern.trim();
+        let model_id = alias.model_id.trim();
+        if pattern.is_empty() || model_id.is_empty() {
+            return Err(AppError::InvalidInput(
+                "别名模式与目标模型 ID 均不能为空".to_string(),
+            ));
+        }
+
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT OR REPLACE INTO model_pricing_aliases (pattern, model_id, created_at)
+             VALUES (?1, ?2, ?3)",
+            rusqlite::params![pattern, model_id, chrono::Utc::now().timestamp()],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 删除模型定价别名
+    pub fn delete_model_pricing_alias(&self, pattern: &str) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM model_pricing_aliases WHERE pattern = ?1",
+            [pattern],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pricing_alias_prefers_specific_pattern() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let alias = |pattern: &str, model_id: &str| ModelPricingAlias {
+            pattern: pattern.to_string(),
+            model_id: model_id.to_string(),
+        };
+        db.save_model_pricing_alias(&alias("claude-sonnet-4*", "claude-sonnet-4-20250514"))?;
+        db.save_model_pricing_alias(&alias(" claude-sonnet-4?5* ", "claude-sonnet-4-5-20250929"))?;
+        assert!(db.save_model_pricing_alias(&alias("", "x")).is_err());
+
+        let aliases = db.list_model_pricing_aliases()?;
+        assert_eq!(aliases[0].pattern, "claude-sonnet-4?5*");
+        assert_eq!(
+            match_pricing_alias(&aliases, "claude-sonnet-4.5"),
+            Some("claude-sonnet-4-5-20250929")
+        );
+        assert_eq!(
+            match_pricing_alias(&aliases, "claude-sonnet-4"),
+            Some("claude-sonnet-4-20250514")
+        );
+        assert_eq!(match_pricing_alias(&aliases, "gpt-5"), None);
+
+        db.delete_model_pricing_alias("claude-sonnet-4*")?;
+        assert_eq!(db.list_model_pricing_aliases()?.len(), 1);
+        Ok(())
+    }
+}
@@ -19,7 +19,8 @@ impl Database {
         let result = {
             let conn = lock_conn!(self.conn);
             conn.query_row(
-                "SELECT proxy_enabled, listen_address, listen_port, enable_logging
+                "SELECT proxy_enabled, listen_address, listen_port, enable_logging,
+                        require_client_auth
                  FROM proxy_config WHERE app_type = 'claude'",
                 [],
                 |row| {
@@ -28,6 +29,7 @@ impl Database {
                         listen_address: row.get(1)?,
                         listen_port: row.get::<_, i32>(2)? as u16,
                         enable_logging: row.get::<_, i32>(3)? != 0,
+                        require_client_auth: row.get::<_, i32>(4)? != 0,
                     })
                 },
             )
@@ -44,6 +46,7 @@ impl Database {
                     listen_address: "127.0.0.1".to_string(),
                     listen_port: 15721,
                     enable_logging: true,
+                    require_client_auth: false,
                 })
             }
             Err(e) => Err(AppError::Database(e.to_string())),
@@ -63,12 +66,14 @@ impl Database {
                 listen_address = ?2,
                 listen_port = ?3,
                 enable_logging = ?4,
+                require_client_auth = ?5,
                 updated_at = datetime('now')",
             rusqlite::params![
                 if config.proxy_enabled { 1 } else { 0 },
                 config.listen_address,
                 config.listen_port as i32,
                 if config.enable_logging { 1 } else { 0 },
+                if config.require_client_auth { 1 } else { 0 },
             ],
         )
         .map_err(|e| AppError::Database(e.to_string()))?;
@@ -200,7 +205,8 @@ impl Database {
                 "SELECT app_type, enabled, auto_failover_enabled,
                         max_retries, streaming_first_byte_timeout, streaming_idle_timeout, non_streaming_timeout,
                         circuit_failure_threshold, circuit_success_threshold, circuit_timeout_seconds,
-                        circuit_error_rate_threshold, circuit_min_requests
+                        circuit_error_rate_threshold, circuit_min_requests, routing_strategy,
+                        session_affinity_enabled, session_affinity_ttl_seconds
                  FROM proxy_config WHERE app_type = ?1",
                 [app_type],
                 |row| {
@@ -217,6 +223,9 @@ impl Database {
                         circuit_timeout_seconds: row.get::<_, i32>(9)? as u32,
                         circuit_error_rate_threshold: row.get(10)?,
                         circuit_min_requests: row.get::<_, i32>(11)? as u32,
+                        routing_strategy: RoutingStrategy::from_db(&row.get::<_, String>(12)?),
+                        session_affinity_enabled: row.get::<_, i32>(13)? != 0,
+                        session_affinity_ttl_seconds: row.get::<_, i32>(14)? as u32,
                     })
                 },
             )
@@ -241,6 +250,9 @@ impl Database {
                     circuit_timeout_seconds: 60,
                     circuit_error_rate_threshold: 0.6,
                     circuit_min_requests: 10,
+                    routing_strategy: RoutingStrategy::default(),
+                    session_affinity_enabled: false,
+                    session_affinity_ttl_seconds: 1800,
                 })
             }
             Err(e) => Err(AppError::Database(e.to_string())),
@@ -267,6 +279,9 @@ impl Database {
                 circuit_timeout_seconds = ?10,
                 circuit_error_rate_threshold = ?11,
                 circuit_min_requests = ?12,
+                routing_strategy = ?13,
+                session_affinity_enabled = ?14,
+                session_affinity_ttl_seconds = ?15,
                 updated_at = datetime('now')
              WHERE app_type = ?1",
             rusqlite::params![
@@ -282,6 +297,13 @@ impl Database {
                 config.circuit_timeout_seconds as i32,
                 config.circuit_error_rate_threshold,
                 config.circuit_min_requests as i32,
+                config.routing_strategy.as_str(),
+                if config.session_affinity_enabled {
+                    1
+                } else {
+                    0
+                },
+                config.session_affinity_ttl_seconds as i32,
             ],
         )
         .map_err(|e| AppError::Database(e.to_string()))?;
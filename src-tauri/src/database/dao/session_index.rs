@@ -0,0 +1,256 @@
+//! 会话全文索引 DAO
+//!
+//! `session_index_files` 记录已索引的会话文件（元数据 + mtime/size），
+//! `session_index_messages` 记录消息的角色与时间，消息正文存入 FTS5 表
+//! `session_messages_fts`（rowid 与 `session_index_messages.id` 一一对应）。
+
+use std::collections::HashMap;
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::session_manager::index::SessionSearchQuery;
+use crate::session_manager::{SessionMessage, SessionMeta};
+use rusqlite::Connection;
+
+/// 单次搜索最多读取的匹配消息数
+const MAX_MATCH_ROWS: i64 = 2000;
+
+/// trigram 分词器可以直接 MATCH 的最短词长
+const MIN_MATCH_CHARS: usize = 3;
+
+/// 一条匹配的消息及其所属会话
+#[derive(Debug, Clone)]
+pub struct SessionIndexMatch {
+    pub source_path: String,
+    pub session: SessionMeta,
+    pub role: String,
+    pub ts: Option<i64>,
+    pub content: String,
+    /// FTS5 bm25 分值（越小越相关；仅包含短词时为 0）
+    pub rank: f64,
+}
+
+fn delete_session_messages(conn: &Connection, source_path: &str) -> Result<(), AppError> {
+    conn.execute(
+        "DELETE FROM session_messages_fts WHERE rowid IN
+            (SELECT id FROM session_index_messages WHERE source_path = ?1)",
+        [source_path],
+    )
+    .map_err(|e| AppError::Database(e.to_string()))?;
+    conn.execute(
+        "DELETE FROM session_index_messages WHERE source_path = ?1",
+        [source_path],
+    )
+    .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(())
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+impl Database {
+    /// 获取已索引文件的 (mtime, size)，按文件路径索引
+    pub fn get_session_index_states(&self) -> Result<HashMap<String, (i64, i64)>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare("SELECT source_path, file_mtime, file_size FROM session_index_files")
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let states = stmt
+            .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .collect::<Result<HashMap<_, _>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(states)
+    }
+
+    /// 用最新解析结果替换一个会话文件的索引
+    pub fn replace_session_index_entry(
+        &self,
+        source_path: &str,
+        meta: &SessionMeta,
+        file_mtime: i64,
+        file_size: i64,
+        messages: &[SessionMessage],
+    ) -> Result<(), AppError> {
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        delete_session_messages(&tx, source_path)?;
+        tx.execute(
+            "INSERT OR REPLACE INTO session_index_files (
+                source_path, provider_id, session_id, title, summary, project_dir,
+                created_at, last_active_at, resume_command, file_mtime, file_size, indexed_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            rusqlite::params![
+                source_path,
+                meta.provider_id,
+                meta.session_id,
+                meta.title,
+                meta.summary,
+                meta.project_dir,
+                meta.created_at,
+                meta.last_active_at,
+                meta.resume_command,
+                file_mtime,
+                file_size,
+                chrono::Utc::now().timestamp(),
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        {
+            let mut insert_message = tx
+                .prepare(
+                    "INSERT INTO session_index_messages (source_path, role, ts) VALUES (?1, ?2, ?3)",
+                )
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            let mut insert_content = tx
+                .prepare("INSERT INTO session_messages_fts (rowid, content) VALUES (?1, ?2)")
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            for message in messages {
+                let id = insert_message
+                    .insert(rusqlite::params![source_path, message.role, message.ts])
+                    .map_err(|e| AppError::Database(e.to_string()))?;
+                insert_content
+                    .execute(rusqlite::params![id, message.content])
+                    .map_err(|e| AppError::Database(e.to_string()))?;
+            }
+        }
+
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 从索引中移除会话文件，返回移除的文件数
+    pub fn remove_session_index_entries(&self, source_paths: &[String]) -> Result<u32, AppError> {
+        if source_paths.is_empty() {
+            return Ok(0);
+        }
+
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let mut removed = 0;
+        for source_path in source_paths {
+            delete_session_messages(&tx, source_path)?;
+            removed += tx
+                .execute(
+                    "DELETE FROM session_index_files WHERE source_path = ?1",
+                    [source_path],
+                )
+                .map_err(|e| AppError::Database(e.to_string()))? as u32;
+        }
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(removed)
+    }
+
+    /// 查询同时包含全部关键词的消息，按相关度排序
+    ///
+    /// 不少于 3 个字符的关键词走 FTS5 MATCH（参与 bm25 排序），更短的关键词
+    /// （如两个字的中文词）退化为 LIKE 子串匹配。
+    pub fn search_session_index_messages(
+        &self,
+        query: &SessionSearchQuery,
+        terms: &[String],
+    ) -> Result<Vec<SessionIndexMatch>, AppError> {
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        let (long_terms, short_terms): (Vec<&String>, Vec<&String>) = terms
+            .iter()
+            .partition(|term| term.chars().count() >= MIN_MATCH_CHARS);
+        let rank_expr = if long_terms.is_empty() {
+            "0.0"
+        } else {
+            let match_expr = long_terms
+                .iter()
+                .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+                .collect::<Vec<_>>()
+                .join(" ");
+            conditions.push("session_messages_fts MATCH ?");
+            params.push(Box::new(match_expr));
+            "bm25(session_messages_fts)"
+        };
+        for term in short_terms {
+            conditions.push("session_messages_fts.content LIKE ? ESCAPE '\\'");
+            params.push(Box::new(format!("%{}%", escape_like(term))));
+        }
+
+        if let Some(provider_id) = query.provider_id.as_deref().filter(|v| !v.is_empty()) {
+            conditions.push("s.provider_id = ?");
+            params.push(Box::new(provider_id.to_string()));
+        }
+        if let Some(project_dir) = query
+            .project_dir
+            .as_deref()
+            .map(|dir| dir.trim().trim_end_matches(['/', '\\']))
+            .filter(|dir| !dir.is_empty())
+        {
+            conditions.push("(s.project_dir = ? OR s.project_dir LIKE ? ESCAPE '\\')");
+            params.push(Box::new(project_dir.to_string()));
+            params.push(Box::new(format!("{}/%", escape_like(project_dir))));
+        }
+        if let Some(start) = query.start_time {
+            conditions.push("COALESCE(s.last_active_at, s.created_at) >= ?");
+            params.push(Box::new(start));
+        }
+        if let Some(end) = query.end_time {
+            conditions.push("COALESCE(s.created_at, s.last_active_at) <= ?");
+            params.push(Box::new(end));
+        }
+        params.push(Box::new(MAX_MATCH_ROWS));
+
+        let sql = format!(
+            "SELECT m.source_path, m.role, m.ts, session_messages_fts.content, {rank_expr},
+                    s.provider_id, s.session_id, s.title, s.summary, s.project_dir,
+                    s.created_at, s.last_active_at, s.resume_command
+             FROM session_messages_fts
+             JOIN session_index_messages m ON m.id = session_messages_fts.rowid
+             JOIN session_index_files s ON s.source_path = m.source_path
+             WHERE {}
+             ORDER BY 5 ASC, m.ts DESC
+             LIMIT ?",
+            conditions.join(" AND ")
+        );
+
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        let rows = stmt
+            .query_map(params_refs.as_slice(), |row| {
+                let source_path: String = row.get(0)?;
+                Ok(SessionIndexMatch {
+                    session: SessionMeta {
+                        provider_id: row.get(5)?,
+                        session_id: row.get(6)?,
+                        title: row.get(7)?,
+                        summary: row.get(8)?,
+                        project_dir: row.get(9)?,
+                        created_at: row.get(10)?,
+                        last_active_at: row.get(11)?,
+                        source_path: Some(source_path.clone()),
+                        resume_command: row.get(12)?,
+                        usage: None,
+                    },
+                    source_path,
+                    role: row.get(1)?,
+                    ts: row.get(2)?,
+                    content: row.get(3)?,
+                    rank: row.get(4)?,
+                })
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(rows)
+    }
+}
@@ -204,4 +204,104 @@ impl Database {
             .map_err(|e| AppError::Database(format!("序列化日志配置失败: {e}")))?;
         self.set_setting("log_config", &json)
     }
+
+    // --- 抓包配置 ---
+
+    /// 获取请求抓包配置（默认关闭）
+    pub fn get_capture_config(&self) -> Result<crate::proxy::types::CaptureConfig, AppError> {
+        match self.get_setting("capture_config")? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Database(format!("解析抓包配置失败: {e}"))),
+            None => Ok(crate::proxy::types::CaptureConfig::default()),
+        }
+    }
+
+    /// 更新请求抓包配置
+    pub fn set_capture_config(
+        &self,
+        config: &crate::proxy::types::CaptureConfig,
+    ) -> Result<(), AppError> {
+        let json = serde_json::to_string(config)
+            .map_err(|e| AppError::Database(format!("序列化抓包配置失败: {e}")))?;
+        self.set_setting("capture_config", &json)
+    }
+
+    // --- 请求日志保留策略 ---
+
+    /// 获取请求日志保留策略（默认不清理）
+    pub fn get_usage_retention_config(
+        &self,
+    ) -> Result<crate::proxy::types::UsageRetentionConfig, AppError> {
+        match self.get_setting("usage_retention_config")? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Database(format!("解析日志保留策略失败: {e}"))),
+            None => Ok(crate::proxy::types::UsageRetentionConfig::default()),
+        }
+    }
+
+    /// 更新请求日志保留策略
+    pub fn set_usage_retention_config(
+        &self,
+        config: &crate::proxy::types::UsageRetentionConfig,
+    ) -> Result<(), AppError> {
+        if config.retention_days == 0 {
+            return Err(AppError::InvalidInput(
+                "日志保留天数至少为 1 天".to_string(),
+            ));
+        }
+        let json = serde_json::to_string(config)
+            .map_err(|e| AppError::Database(format!("序列化日志保留策略失败: {e}")))?;
+        self.set_setting("usage_retention_config", &json)
+    }
+
+    // --- 预算提醒 ---
+
+    /// 获取预算提醒配置（默认在 50/80/100% 时提醒）
+    pub fn get_budget_alert_config(
+        &self,
+    ) -> Result<crate::proxy::types::BudgetAlertConfig, AppError> {
+        match self.get_setting("budget_alert_config")? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Database(format!("解析预算提醒配置失败: {e}"))),
+            None => Ok(crate::proxy::types::BudgetAlertConfig::default()),
+        }
+    }
+
+    /// 更新预算提醒配置
+    pub fn set_budget_alert_config(
+        &self,
+        config: &crate::proxy::types::BudgetAlertConfig,
+    ) -> Result<(), AppError> {
+        if config.thresholds.iter().any(|t| *t == 0 || *t > 1000) {
+            return Err(AppError::InvalidInput(
+                "提醒阈值应在 1% 到 1000% 之间".to_string(),
+            ));
+        }
+        if let Some(url) = config.webhook_url.as_deref().map(str::trim) {
+            if !url.is_empty() && !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(AppError::InvalidInput(format!(
+                    "Webhook 地址必须以 http:// 或 https:// 开头: {url}"
+                )));
+            }
+        }
+        for (app_type, limit) in &config.app_limits {
+            for value in [&limit.limit_daily_usd, &limit.limit_monthly_usd]
+                .into_iter()
+                .flatten()
+            {
+                if !value.trim().is_empty() && value.trim().parse::<f64>().is_err() {
+                    return Err(AppError::InvalidInput(format!(
+                        "{app_type} 的消费限额不是合法数字: {value}"
+                    )));
+                }
+            }
+        }
+
+        let mut config = config.clone();
+        config.thresholds.sort_unstable();
+        config.thresholds.dedup();
+        let json = serde_json::to_string(&config)
+            .map_err(|e| AppError::Database(format!("序列化预算提醒配置失败: {e}")))?;
+        self.set_setting("budget_alert_config", &json)
+    }
 }
@@ -0,0 +1,354 @@
+//! 请求日志聚合 DAO
+//!
+//! `proxy_request_logs` 在写入时增量聚合到两张表：
+//! - `usage_rollup_hourly`：按整点小时（UTC 对齐）聚合，供时间范围统计与趋势使用
+//! - `usage_rollup_daily`：按本地日期聚合，供全量统计与今日/本月消费使用
+//!
+//! 统计查询读取聚合表，原始日志可以按保留策略清理而不影响历史统计。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::{params, Connection};
+use std::io::Write;
+
+/// 小时聚合桶长度（秒）
+pub(crate) const HOUR_SECONDS: i64 = 60 * 60;
+
+/// 每批清理的原始日志行数（避免长时间占用数据库锁）
+const PRUNE_BATCH_SIZE: i64 = 2000;
+
+/// 聚合表的增量（一条请求，或对已有请求的成本修正）
+#[derive(Debug, Clone, Default)]
+pub(crate) struct UsageRollupDelta<'a> {
+    pub created_at: i64,
+    pub app_type: &'a str,
+    pub provider_id: &'a str,
+    pub model: &'a str,
+    pub client_label: Option<&'a str>,
+    pub request_count: i64,
+    pub success_count: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cache_creation_tokens: i64,
+    pub total_cost: f64,
+    pub latency_ms: i64,
+}
+
+/// 将增量累加到小时与日聚合表
+pub(crate) fn apply_usage_rollup(
+    conn: &Connection,
+    delta: &UsageRollupDelta<'_>,
+) -> Result<(), AppError> {
+    let hour_start = delta.created_at.div_euclid(HOUR_SECONDS) * HOUR_SECONDS;
+    for (table, bucket_expr, bucket_value, bucket) in [
+        ("usage_rollup_hourly", "bucket_start", "?1", hour_start),
+        (
+            "usage_rollup_daily",
+            "day",
+            "date(?1, 'unixepoch', 'localtime')",
+            delta.created_at,
+        ),
+    ] {
+        let sql = format!(
+            "INSERT INTO {table} (
+                {bucket_expr}, app_type, provider_id, model, client_label,
+                request_count, success_count, input_tokens, output_tokens,
+                cache_read_tokens, cache_creation_tokens, total_cost_usd, latency_ms_sum
+            ) VALUES ({bucket_value}, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+            ON CONFLICT({bucket_expr}, app_type, provider_id, model, client_label) DO UPDATE SET
+                request_count = request_count + excluded.request_count,
+                success_count = success_count + excluded.success_count,
+                input_tokens = input_tokens + excluded.input_tokens,
+                output_tokens = output_tokens + excluded.output_tokens,
+                cache_read_tokens = cache_read_tokens + excluded.cache_read_tokens,
+                cache_creation_tokens = cache_creation_tokens + excluded.cache_creation_tokens,
+                total_cost_usd = total_cost_usd + excluded.total_cost_usd,
+                latency_ms_sum = latency_ms_sum + excluded.latency_ms_sum"
+        );
+        conn.execute(
+            &sql,
+            params![
+                bucket,
+                delta.app_type,
+                delta.provider_id,
+                delta.model,
+                delta.client_label.unwrap_or_default(),
+                delta.request_count,
+                delta.success_count,
+                delta.input_tokens,
+                delta.output_tokens,
+                delta.cache_read_tokens,
+                delta.cache_creation_tokens,
+                delta.total_cost,
+                delta.latency_ms,
+            ],
+        )
+        .map_err(|e| AppError::Database(format!("更新使用量聚合失败: {e}")))?;
+    }
+    Ok(())
+}
+
+/// 根据现有原始日志重建聚合表
+///
+/// 仅用于升级迁移（此时尚未清理过原始日志）；清理后重建会丢失已清理部分的统计。
+pub(crate) fn rebuild_usage_rollups(conn: &Connection) -> Result<(), AppError> {
+    for (table, bucket_col, bucket_expr) in [
+        (
+            "usage_rollup_hourly",
+            "bucket_start",
+            "(created_at / 3600) * 3600",
+        ),
+        (
+            "usage_rollup_daily",
+            "day",
+            "date(created_at, 'unixepoch', 'localtime')",
+        ),
+    ] {
+        conn.execute(&format!("DELETE FROM {table}"), [])
+            .map_err(|e| AppError::Database(format!("清空使用量聚合失败: {e}")))?;
+        conn.execute(
+            &format!(
+                "INSERT INTO {table} (
+                    {bucket_col}, app_type, provider_id, model, client_label,
+                    request_count, success_count, input_tokens, output_tokens,
+                    cache_read_tokens, cache_creation_tokens, total_cost_usd, latency_ms_sum
+                )
+                SELECT {bucket_expr}, app_type, provider_id, model, COALESCE(client_label, ''),
+                    COUNT(*),
+                    SUM(CASE WHEN status_code >= 200 AND status_code < 300 THEN 1 ELSE 0 END),
+                    SUM(input_tokens), SUM(output_tokens),
+                    SUM(cache_read_tokens), SUM(cache_creation_tokens),
+                    SUM(CAST(total_cost_usd AS REAL)), SUM(latency_ms)
+                FROM proxy_request_logs
+                GROUP BY 1, 2, 3, 4, 5"
+            ),
+            [],
+        )
+        .map_err(|e| AppError::Database(format!("重建使用量聚合失败: {e}")))?;
+    }
+    Ok(())
+}
+
+/// 构造时间范围内的统计数据源（子查询）及其参数
+///
+/// 完整覆盖的整点小时读取 `usage_rollup_hourly`（时间戳为小时起点），
+/// 范围首尾不足一小时的部分读取原始日志。子查询列：
+/// `ts, app_type, provider_id, model, client_label, request_count, success_count,
+/// input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
+/// total_cost, latency_ms_sum`，占用参数 `?1`–`?4`。
+pub(crate) fn usage_source(start: Option<i64>, end: Option<i64>) -> (&'static str, [i64; 4]) {
+    let start_ts = start.unwrap_or(i64::MIN);
+    let end_ts = end.unwrap_or(i64::MAX);
+    let full_start = match start {
+        Some(s) => (s + HOUR_SECONDS - 1).div_euclid(HOUR_SECONDS) * HOUR_SECONDS,
+        None => i64::MIN,
+    };
+    let full_end = match end {
+        Some(e) => (e + 1).div_euclid(HOUR_SECONDS) * HOUR_SECONDS,
+        None => i64::MAX,
+    }
+    .max(full_start);
+
+    const SQL: &str = "
+        SELECT bucket_start AS ts, app_type, provider_id, model,
+            NULLIF(client_label, '') AS client_label, request_count, success_count,
+            input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
+            total_cost_usd AS total_cost, latency_ms_sum
+        FROM usage_rollup_hourly
+        WHERE bucket_start >= ?2 AND bucket_start < ?3
+        UNION ALL
+        SELECT created_at, app_type, provider_id, model, client_label, 1,
+            CASE WHEN status_code >= 200 AND status_code < 300 THEN 1 ELSE 0 END,
+            input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
+            CAST(total_cost_usd AS REAL), latency_ms
+        FROM proxy_request_logs
+        WHERE created_at >= ?1 AND created_at < ?2 AND created_at <= ?4
+        UNION ALL
+        SELECT created_at, app_type, provider_id, model, client_label, 1,
+            CASE WHEN status_code >= 200 AND status_code < 300 THEN 1 ELSE 0 END,
+            input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
+            CAST(total_cost_usd AS REAL), latency_ms
+        FROM proxy_request_logs
+        WHERE created_at >= ?3 AND created_at <= ?4";
+
+    (SQL, [start_ts, full_start, full_end, end_ts])
+}
+
+impl Database {
+    /// 统计早于 `cutoff` 的原始请求日志条数
+    pub fn count_request_logs_before(&self, cutoff: i64) -> Result<u64, AppError> {
+        let conn = lock_conn!(self.conn);
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM proxy_request_logs WHERE created_at < ?1",
+                [cutoff],
+                |row| row.get(0),
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(count as u64)
+    }
+
+    /// 分批清理早于 `cutoff` 的原始请求日志，返回删除条数
+    ///
+    /// 聚合表不受影响。传入 `archive` 时，每批先以 JSON Lines 写入归档再删除。
+    pub fn prune_request_logs(
+        &self,
+        cutoff: i64,
+        mut archive: Option<&mut dyn Write>,
+    ) -> Result<u64, AppError> {
+        let mut pruned = 0u64;
+        loop {
+            let conn = lock_conn!(self.conn);
+
+            if let Some(writer) = archive.as_deref_mut() {
+                let mut stmt = conn
+                    .prepare(
+                        "SELECT * FROM proxy_request_logs WHERE created_at < ?1
+                         ORDER BY created_at, rowid LIMIT ?2",
+                    )
+                    .map_err(|e| AppError::Database(e.to_string()))?;
+                let columns: Vec<String> =
+                    stmt.column_names().into_iter().map(String::from).collect();
+                let mut rows = stmt
+                    .query(params![cutoff, PRUNE_BATCH_SIZE])
+                    .map_err(|e| AppError::Database(e.to_string()))?;
+                while let Some(row) = rows.next().map_err(|e| AppError::Database(e.to_string()))? {
+                    let mut record = serde_json::Map::new();
+                    for (idx, name) in columns.iter().enumerate() {
+                        let value = match row
+                            .get_ref(idx)
+                            .map_err(|e| AppError::Database(e.to_string()))?
+                        {
+                            rusqlite::types::ValueRef::Null => serde_json::Value::Null,
+                            rusqlite::types::ValueRef::Integer(v) => v.into(),
+                            rusqlite::types::ValueRef::Real(v) => v.into(),
+                            rusqlite::types::ValueRef::Text(v) => {
+                                String::from_utf8_lossy(v).into_owned().into()
+                            }
+                            rusqlite::types::ValueRef::Blob(_) => serde_json::Value::Null,
+                        };
+                        record.insert(name.clone(), value);
+                    }
+                    serde_json::to_writer(&mut *writer, &record)
+                        .map_err(|e| AppError::Message(format!("写入日志归档失败: {e}")))?;
+                    writer
+                        .write_all(b"\n")
+                        .map_err(|e| AppError::Message(format!("写入日志归档失败: {e}")))?;
+                }
+                writer
+                    .flush()
+                    .map_err(|e| AppError::Message(format!("写入日志归档失败: {e}")))?;
+            }
+
+            let deleted = conn
+                .execute(
+                    "DELETE FROM proxy_request_logs WHERE rowid IN (
+                        SELECT rowid FROM proxy_request_logs WHERE created_at < ?1
+                        ORDER BY created_at, rowid LIMIT ?2
+                    )",
+                    params![cutoff, PRUNE_BATCH_SIZE],
+                )
+                .map_err(|e| AppError::Database(format!("清理请求日志失败: {e}")))?;
+            pruned += deleted as u64;
+            if (deleted as i64) < PRUNE_BATCH_SIZE {
+                break;
+            }
+        }
+        Ok(pruned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert_log(conn: &Connection, id: &str, cost: &str, status: i64, created_at: i64) {
+        conn.execute(
+            "INSERT INTO proxy_request_logs (
+                request_id, provider_id, app_type, model,
+                input_tokens, output_tokens, total_cost_usd,
+                latency_ms, status_code, created_at
+            ) VALUES (?1, 'p1', 'claude', 'm', 10, 5, ?2, 100, ?3, ?4)",
+            params![id, cost, status, created_at],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_prune_keeps_rollups_and_archives_rows() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = lock_conn!(db.conn);
+            insert_log(&conn, "old-1", "0.5", 200, 1_000);
+            insert_log(&conn, "old-2", "0.25", 500, 2_000);
+            insert_log(&conn, "new", "1", 200, 10_000);
+            rebuild_usage_rollups(&conn)?;
+        }
+
+        assert_eq!(db.count_request_logs_before(5_000)?, 2);
+        let mut archive = Vec::new();
+        assert_eq!(db.prune_request_logs(5_000, Some(&mut archive))?, 2);
+        assert_eq!(db.prune_request_logs(5_000, None)?, 0);
+
+        let archived: Vec<serde_json::Value> = String::from_utf8(archive)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(archived.len(), 2);
+        assert_eq!(archived[0]["request_id"], "old-1");
+        assert_eq!(archived[1]["status_code"], 500);
+
+        // 原始日志已清理，聚合统计仍包含全部请求
+        let summary = db.get_usage_summary(None, None)?;
+        assert_eq!(summary.total_requests, 3);
+        assert_eq!(summary.total_cost, "1.750000");
+        Ok(())
+    }
+
+    #[test]
+    fn test_usage_source_mixes_rollups_and_raw_edges() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let conn = lock_conn!(db.conn);
+        for (id, created_at) in [("a", 3_000), ("b", 3_700), ("c", 7_300), ("d", 7_500)] {
+            insert_log(&conn, id, "1", 200, created_at);
+            apply_usage_rollup(
+                &conn,
+                &UsageRollupDelta {
+                    created_at,
+                    app_type: "claude",
+                    provider_id: "p1",
+                    model: "m",
+                    request_count: 1,
+                    success_count: 1,
+                    total_cost: 1.0,
+                    ..Default::default()
+                },
+            )?;
+        }
+
+        // [3500, 7400]：3600–7199 整小时取聚合（b），首尾取原始日志（c）
+        let (source, params) = usage_source(Some(3_500), Some(7_400));
+        let count: i64 = conn
+            .query_row(
+                &format!("SELECT SUM(request_count) FROM ({source})"),
+                params,
+                |row| row.get(0),
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        assert_eq!(count, 2);
+
+        // 不足一小时的范围完全读取原始日志
+        let (source, params) = usage_source(Some(7_400), Some(7_600));
+        let count: i64 = conn
+            .query_row(
+                &format!("SELECT SUM(request_count) FROM ({source})"),
+                params,
+                |row| row.get(0),
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        assert_eq!(count, 1);
+        Ok(())
+    }
+}
@@ -32,8 +32,16 @@ mod schema;
 mod tests;
 
 // DAO 类型导出供外部使用
+pub(crate) use dao::pricing_aliases::{load_pricing_aliases, match_pricing_alias};
+#[cfg(test)]
+pub(crate) use dao::usage_rollup::rebuild_usage_rollups;
+pub(crate) use dao::usage_rollup::{apply_usage_rollup, usage_source, UsageRollupDelta};
+pub use dao::ClientToken;
 pub use dao::FailoverQueueItem;
+pub use dao::ModelPricingAlias;
+pub use dao::ModelRoutingRule;
 pub use dao::OmoGlobalConfig;
+pub use dao::{RequestCapture, RequestCaptureSummary};
 
 use crate::config::get_app_config_dir;
 use crate::error::AppError;
@@ -48,7 +56,7 @@ const DB_BACKUP_RETAIN: usize = 10;
 
 /// 当前 Schema 版本号
 /// 每次修改表结构时递增，并在 schema.rs 中添加相应的迁移逻辑
-pub(crate) const SCHEMA_VERSION: i32 = 6;
+pub(crate) const SCHEMA_VERSION: i32 = 17;
 
 /// 安全地序列化 JSON，避免 unwrap panic
 pub(crate) fn to_json_string<T: Serialize>(value: &T) -> Result<String, AppError> {
@@ -122,6 +122,10 @@ impl Database {
             circuit_min_requests INTEGER NOT NULL DEFAULT 10,
             default_cost_multiplier TEXT NOT NULL DEFAULT '1',
             pricing_model_source TEXT NOT NULL DEFAULT 'response',
+            routing_strategy TEXT NOT NULL DEFAULT 'priority',
+            session_affinity_enabled INTEGER NOT NULL DEFAULT 0,
+            session_affinity_ttl_seconds INTEGER NOT NULL DEFAULT 1800,
+            require_client_auth INTEGER NOT NULL DEFAULT 0,
             created_at TEXT NOT NULL DEFAULT (datetime('now')), updated_at TEXT NOT NULL DEFAULT (datetime('now'))
         )", []).map_err(|e| AppError::Database(e.to_string()))?;
 
@@ -180,7 +184,9 @@ impl Database {
             total_cost_usd TEXT NOT NULL DEFAULT '0', latency_ms INTEGER NOT NULL, first_token_ms INTEGER,
             duration_ms INTEGER, status_code INTEGER NOT NULL, error_message TEXT, session_id TEXT,
             provider_type TEXT, is_streaming INTEGER NOT NULL DEFAULT 0,
-            cost_multiplier TEXT NOT NULL DEFAULT '1.0', created_at INTEGER NOT NULL
+            cost_multiplier TEXT NOT NULL DEFAULT '1.0', created_at INTEGER NOT NULL,
+            session_affinity TEXT, client_label TEXT,
+            attempts INTEGER NOT NULL DEFAULT 1
         )", []).map_err(|e| AppError::Database(e.to_string()))?;
 
         conn.execute("CREATE INDEX IF NOT EXISTS idx_request_logs_provider ON proxy_request_logs(provider_id, app_type)", [])
@@ -241,6 +247,27 @@ impl Database {
         )
         .map_err(|e| AppError::Database(e.to_string()))?;
 
+        // 17. Proxy Client Tokens 表（代理客户端令牌，仅保存哈希）
+        Self::create_proxy_client_tokens_table(conn)?;
+
+        // 18. Proxy Request Captures 表（完整请求/响应抓包，按 request_id 关联请求日志）
+        Self::create_proxy_request_captures_table(conn)?;
+
+        // 19. Model Routing Rules 表（按请求模型选择供应商）
+        Self::create_model_routing_rules_table(conn)?;
+
+        // 20. Usage Rollup 表（请求日志按小时/按日聚合）
+        Self::create_usage_rollup_tables(conn)?;
+
+        // 21. Budget Alert State 表（预算提醒已触发记录）
+        Self::create_budget_alert_state_table(conn)?;
+
+        // 22. Model Pricing Aliases 表（模型定价别名）
+        Self::create_model_pricing_aliases_table(conn)?;
+
+        // 23. Session Index 表（本地会话全文索引）
+        Self::create_session_index_tables(conn)?;
+
         // 尝试添加 live_takeover_active 列到 proxy_config 表
         let _ = conn.execute(
             "ALTER TABLE proxy_config ADD COLUMN live_takeover_active INTEGER NOT NULL DEFAULT 0",
@@ -367,6 +394,61 @@ impl Database {
                         Self::migrate_v5_to_v6(conn)?;
                         Self::set_user_version(conn, 6)?;
                     }
+                    6 => {
+                        log::info!("迁移数据库从 v6 到 v7（供应商路由策略）");
+                        Self::migrate_v6_to_v7(conn)?;
+                        Self::set_user_version(conn, 7)?;
+                    }
+                    7 => {
+                        log::info!("迁移数据库从 v7 到 v8（会话粘性路由）");
+                        Self::migrate_v7_to_v8(conn)?;
+                        Self::set_user_version(conn, 8)?;
+                    }
+                    8 => {
+                        log::info!("迁移数据库从 v8 到 v9（代理客户端令牌）");
+                        Self::migrate_v8_to_v9(conn)?;
+                        Self::set_user_version(conn, 9)?;
+                    }
+                    9 => {
+                        log::info!("迁移数据库从 v9 到 v10（速率限制）");
+                        Self::migrate_v9_to_v10(conn)?;
+                        Self::set_user_version(conn, 10)?;
+                    }
+                    10 => {
+                        log::info!("迁移数据库从 v10 到 v11（同供应商重试次数）");
+                        Self::migrate_v10_to_v11(conn)?;
+                        Self::set_user_version(conn, 11)?;
+                    }
+                    11 => {
+                        log::info!("迁移数据库从 v11 到 v12（请求抓包）");
+                        Self::migrate_v11_to_v12(conn)?;
+                        Self::set_user_version(conn, 12)?;
+                    }
+                    12 => {
+                        log::info!("迁移数据库从 v12 到 v13（模型路由规则）");
+                        Self::migrate_v12_to_v13(conn)?;
+                        Self::set_user_version(conn, 13)?;
+                    }
+                    13 => {
+                        log::info!("迁移数据库从 v13 到 v14（使用量聚合表）");
+                        Self::migrate_v13_to_v14(conn)?;
+                        Self::set_user_version(conn, 14)?;
+                    }
+                    14 => {
+                        log::info!("迁移数据库从 v14 到 v15（预算提醒状态表）");
+                        Self::migrate_v14_to_v15(conn)?;
+                        Self::set_user_version(conn, 15)?;
+                    }
+                    15 => {
+                        log::info!("迁移数据库从 v15 到 v16（模型定价别名表）");
+                        Self::migrate_v15_to_v16(conn)?;
+                        Self::set_user_version(conn, 16)?;
+                    }
+                    16 => {
+                        log::info!("迁移数据库从 v16 到 v17（会话全文索引表）");
+                        Self::migrate_v16_to_v17(conn)?;
+                        Self::set_user_version(conn, 17)?;
+                    }
                     _ => {
                         return Err(AppError::Database(format!(
                             "未知的数据库版本 {version}，无法迁移到 {SCHEMA_VERSION}"
@@ -695,6 +777,10 @@ impl Database {
             circuit_min_requests INTEGER NOT NULL DEFAULT 10,
             default_cost_multiplier TEXT NOT NULL DEFAULT '1',
             pricing_model_source TEXT NOT NULL DEFAULT 'response',
+            routing_strategy TEXT NOT NULL DEFAULT 'priority',
+            session_affinity_enabled INTEGER NOT NULL DEFAULT 0,
+            session_affinity_ttl_seconds INTEGER NOT NULL DEFAULT 1800,
+            require_client_auth INTEGER NOT NULL DEFAULT 0,
             created_at TEXT NOT NULL DEFAULT (datetime('now')), updated_at TEXT NOT NULL DEFAULT (datetime('now'))
         )", [])?;
 
@@ -978,6 +1064,308 @@ impl Database {
         Ok(())
     }
 
+    /// v6 -> v7: proxy_config 添加路由策略列
+    fn migrate_v6_to_v7(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_config")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "routing_strategy",
+                "TEXT NOT NULL DEFAULT 'priority'",
+            )?;
+        }
+
+        log::info!("v6 -> v7 迁移完成：已添加供应商路由策略字段");
+        Ok(())
+    }
+
+    /// v7 -> v8: 会话粘性路由配置与请求日志决策列
+    fn migrate_v7_to_v8(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_config")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "session_affinity_enabled",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "session_affinity_ttl_seconds",
+                "INTEGER NOT NULL DEFAULT 1800",
+            )?;
+        }
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(conn, "proxy_request_logs", "session_affinity", "TEXT")?;
+        }
+
+        log::info!("v7 -> v8 迁移完成：已添加会话粘性路由字段");
+        Ok(())
+    }
+
+    /// v8 -> v9 迁移：代理客户端令牌
+    ///
+    /// - 新建 proxy_client_tokens 表
+    /// - proxy_config 增加 require_client_auth 开关
+    /// - proxy_request_logs 增加 client_label 列，用于按客户端统计消费
+    fn migrate_v8_to_v9(conn: &Connection) -> Result<(), AppError> {
+        Self::create_proxy_client_tokens_table(conn)?;
+
+        if Self::table_exists(conn, "proxy_config")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "require_client_auth",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+        }
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(conn, "proxy_request_logs", "client_label", "TEXT")?;
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_request_logs_client ON proxy_request_logs(client_label)",
+                [],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+
+        log::info!("v8 -> v9 迁移完成：已添加代理客户端令牌");
+        Ok(())
+    }
+
+    /// v9 -> v10 迁移：客户端令牌 RPM/TPM 限制
+    fn migrate_v9_to_v10(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_client_tokens")? {
+            Self::add_column_if_missing(conn, "proxy_client_tokens", "rpm_limit", "INTEGER")?;
+            Self::add_column_if_missing(conn, "proxy_client_tokens", "tpm_limit", "INTEGER")?;
+        }
+
+        log::info!("v9 -> v10 迁移完成：已添加客户端令牌速率限制");
+        Ok(())
+    }
+
+    /// v10 -> v11 迁移：请求日志记录同一供应商上的尝试次数
+    fn migrate_v10_to_v11(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_request_logs",
+                "attempts",
+                "INTEGER NOT NULL DEFAULT 1",
+            )?;
+        }
+
+        log::info!("v10 -> v11 迁移完成：请求日志已记录重试次数");
+        Ok(())
+    }
+
+    /// v11 -> v12 迁移：新建请求抓包表
+    fn migrate_v11_to_v12(conn: &Connection) -> Result<(), AppError> {
+        Self::create_proxy_request_captures_table(conn)?;
+
+        log::info!("v11 -> v12 迁移完成：已添加请求抓包表");
+        Ok(())
+    }
+
+    /// v12 -> v13 迁移：新建模型路由规则表
+    fn migrate_v12_to_v13(conn: &Connection) -> Result<(), AppError> {
+        Self::create_model_routing_rules_table(conn)?;
+
+        log::info!("v12 -> v13 迁移完成：已添加模型路由规则表");
+        Ok(())
+    }
+
+    /// v13 -> v14 迁移：新建使用量聚合表，并由现有请求日志回填
+    fn migrate_v13_to_v14(conn: &Connection) -> Result<(), AppError> {
+        Self::create_usage_rollup_tables(conn)?;
+        if Self::table_exists(conn, "proxy_request_logs")?
+            && Self::has_column(conn, "proxy_request_logs", "created_at")?
+        {
+            crate::database::dao::usage_rollup::rebuild_usage_rollups(conn)?;
+        }
+
+        log::info!("v13 -> v14 迁移完成：已添加使用量聚合表");
+        Ok(())
+    }
+
+    /// v14 -> v15 迁移：添加预算提醒状态表
+    fn migrate_v14_to_v15(conn: &Connection) -> Result<(), AppError> {
+        Self::create_budget_alert_state_table(conn)?;
+
+        log::info!("v14 -> v15 迁移完成：已添加预算提醒状态表");
+        Ok(())
+    }
+
+    /// v15 -> v16 迁移：添加模型定价别名表
+    fn migrate_v15_to_v16(conn: &Connection) -> Result<(), AppError> {
+        Self::create_model_pricing_aliases_table(conn)?;
+
+        log::info!("v15 -> v16 迁移完成：已添加模型定价别名表");
+        Ok(())
+    }
+
+    /// v16 -> v17 迁移：添加会话全文索引表
+    fn migrate_v16_to_v17(conn: &Connection) -> Result<(), AppError> {
+        Self::create_session_index_tables(conn)?;
+
+        log::info!("v16 -> v17 迁移完成：已添加会话全文索引表");
+        Ok(())
+    }
+
+    fn create_proxy_client_tokens_table(conn: &Connection) -> Result<(), AppError> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS proxy_client_tokens (
+            id TEXT PRIMARY KEY, label TEXT NOT NULL,
+            token_hash TEXT NOT NULL UNIQUE, token_prefix TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL, last_used_at INTEGER,
+            rpm_limit INTEGER, tpm_limit INTEGER
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    fn create_proxy_request_captures_table(conn: &Connection) -> Result<(), AppError> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS proxy_request_captures (
+            request_id TEXT PRIMARY KEY, app_type TEXT NOT NULL, provider_id TEXT NOT NULL,
+            method TEXT NOT NULL, url TEXT NOT NULL,
+            request_headers TEXT NOT NULL, request_body TEXT NOT NULL,
+            request_truncated INTEGER NOT NULL DEFAULT 0,
+            response_status INTEGER, response_headers TEXT,
+            response_body TEXT, response_truncated INTEGER NOT NULL DEFAULT 0,
+            error_message TEXT, created_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_request_captures_created_at
+             ON proxy_request_captures(created_at)",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    fn create_model_routing_rules_table(conn: &Connection) -> Result<(), AppError> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS model_routing_rules (
+            id TEXT PRIMARY KEY, app_type TEXT NOT NULL, pattern TEXT NOT NULL,
+            provider_ids TEXT NOT NULL DEFAULT '[]',
+            enabled INTEGER NOT NULL DEFAULT 1,
+            sort_index INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_model_routing_rules_app
+             ON model_routing_rules(app_type, sort_index)",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    fn create_usage_rollup_tables(conn: &Connection) -> Result<(), AppError> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS usage_rollup_hourly (
+            bucket_start INTEGER NOT NULL, app_type TEXT NOT NULL, provider_id TEXT NOT NULL,
+            model TEXT NOT NULL, client_label TEXT NOT NULL DEFAULT '',
+            request_count INTEGER NOT NULL DEFAULT 0, success_count INTEGER NOT NULL DEFAULT 0,
+            input_tokens INTEGER NOT NULL DEFAULT 0, output_tokens INTEGER NOT NULL DEFAULT 0,
+            cache_read_tokens INTEGER NOT NULL DEFAULT 0, cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+            total_cost_usd REAL NOT NULL DEFAULT 0, latency_ms_sum INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (bucket_start, app_type, provider_id, model, client_label)
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS usage_rollup_daily (
+            day TEXT NOT NULL, app_type TEXT NOT NULL, provider_id TEXT NOT NULL,
+            model TEXT NOT NULL, client_label TEXT NOT NULL DEFAULT '',
+            request_count INTEGER NOT NULL DEFAULT 0, success_count INTEGER NOT NULL DEFAULT 0,
+            input_tokens INTEGER NOT NULL DEFAULT 0, output_tokens INTEGER NOT NULL DEFAULT 0,
+            cache_read_tokens INTEGER NOT NULL DEFAULT 0, cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+            total_cost_usd REAL NOT NULL DEFAULT 0, latency_ms_sum INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (day, app_type, provider_id, model, client_label)
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_usage_rollup_daily_provider
+             ON usage_rollup_daily(provider_id, app_type, day)",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    fn create_budget_alert_state_table(conn: &Connection) -> Result<(), AppError> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS budget_alert_state (
+            app_type TEXT NOT NULL, provider_id TEXT NOT NULL DEFAULT '',
+            period TEXT NOT NULL, period_key TEXT NOT NULL,
+            threshold INTEGER NOT NULL, fired_at INTEGER NOT NULL,
+            PRIMARY KEY (app_type, provider_id, period, period_key, threshold)
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    fn create_model_pricing_aliases_table(conn: &Connection) -> Result<(), AppError> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS model_pricing_aliases (
+            pattern TEXT PRIMARY KEY, model_id TEXT NOT NULL, created_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    fn create_session_index_tables(conn: &Connection) -> Result<(), AppError> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS session_index_files (
+            source_path TEXT PRIMARY KEY, provider_id TEXT NOT NULL, session_id TEXT NOT NULL,
+            title TEXT, summary TEXT, project_dir TEXT,
+            created_at INTEGER, last_active_at INTEGER, resume_command TEXT,
+            file_mtime INTEGER NOT NULL, file_size INTEGER NOT NULL, indexed_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS session_index_messages (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, source_path TEXT NOT NULL,
+            role TEXT NOT NULL, ts INTEGER
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_session_index_messages_source
+             ON session_index_messages(source_path)",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        // trigram 分词支持中文及任意子串匹配
+        conn.execute(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS session_messages_fts
+             USING fts5(content, tokenize = 'trigram')",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
     /// 插入默认模型定价数据
     /// 格式: (model_id, display_name, input, output, cache_read, cache_creation)
     /// 注意: model_id 使用短横线格式（如 claude-haiku-4-5），与 API 返回的模型名称标准化后一致
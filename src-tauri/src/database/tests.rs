@@ -633,3 +633,15 @@ fn schema_model_pricing_is_seeded_on_init() {
         gemini_count
     );
 }
+
+#[test]
+fn sql_export_skips_session_index_tables() {
+    let db = Database::memory().expect("create memory db");
+    let sql = db.export_sql_string().expect("export sql");
+
+    assert!(sql.contains("model_pricing_aliases"));
+    assert!(
+        !sql.contains("session_messages_fts") && !sql.contains("session_index_"),
+        "会话全文索引不应出现在 SQL 导出中"
+    );
+}
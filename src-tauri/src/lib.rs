@@ -1,9 +1,14 @@
+// 无界面构建只编译 CLI 用到的部分，桌面端专用的服务函数在此构建下未被引用
+#![cfg_attr(not(feature = "gui"), allow(dead_code, unused_imports))]
+
 mod app_config;
 mod app_store;
 mod auto_launch;
 mod claude_mcp;
 mod claude_plugin;
+pub mod cli;
 mod codex_config;
+#[cfg(feature = "gui")]
 mod commands;
 mod config;
 mod database;
@@ -13,6 +18,7 @@ mod gemini_config;
 mod gemini_mcp;
 mod init_status;
 mod mcp;
+mod model_pattern;
 mod openclaw_config;
 mod opencode_config;
 mod panic_hook;
@@ -25,12 +31,15 @@ mod services;
 mod session_manager;
 mod settings;
 mod store;
+#[cfg(feature = "gui")]
 mod tray;
 mod usage_script;
 
 pub use app_config::{AppType, McpApps, McpServer, MultiAppConfig};
 pub use codex_config::{get_codex_auth_path, get_codex_config_path, write_codex_live_atomic};
+#[cfg(feature = "gui")]
 pub use commands::open_provider_terminal;
+#[cfg(feature = "gui")]
 pub use commands::*;
 pub use config::{get_claude_mcp_path, get_claude_settings_path, read_json_file};
 pub use database::Database;
@@ -49,16 +58,50 @@ pub use services::{
 };
 pub use settings::{update_settings, AppSettings};
 pub use store::AppState;
+#[cfg(feature = "gui")]
 use tauri_plugin_deep_link::DeepLinkExt;
+#[cfg(feature = "gui")]
 use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
 
 use std::sync::Arc;
-#[cfg(target_os = "macos")]
+#[cfg(all(feature = "gui", target_os = "macos"))]
 use tauri::image::Image;
+#[cfg(feature = "gui")]
 use tauri::tray::{TrayIconBuilder, TrayIconEvent};
+#[cfg(feature = "gui")]
 use tauri::RunEvent;
+#[cfg(feature = "gui")]
 use tauri::{Emitter, Manager};
 
+/// 桌面端应用句柄，用于向前端发射事件与更新托盘
+#[cfg(feature = "gui")]
+pub type AppHandle = tauri::AppHandle;
+
+/// 无界面构建中不存在应用句柄：该类型无法构造，`Option<AppHandle>` 恒为 `None`
+#[cfg(not(feature = "gui"))]
+#[derive(Clone)]
+pub enum AppHandle {}
+
+/// 向前端发射事件
+#[cfg(feature = "gui")]
+pub(crate) fn emit_event<S: serde::Serialize + Clone>(
+    app: &AppHandle,
+    event: &str,
+    payload: S,
+) -> Result<(), String> {
+    app.emit(event, payload).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "gui"))]
+pub(crate) fn emit_event<S: serde::Serialize + Clone>(
+    app: &AppHandle,
+    _event: &str,
+    _payload: S,
+) -> Result<(), String> {
+    match *app {}
+}
+
+#[cfg(feature = "gui")]
 fn redact_url_for_log(url_str: &str) -> String {
     match url::Url::parse(url_str) {
         Ok(url) => {
@@ -95,6 +138,7 @@ fn redact_url_for_log(url_str: &str) -> String {
 /// - 解析 URL
 /// - 向前端发射 `deeplink-import` / `deeplink-error` 事件
 /// - 可选：在成功时聚焦主窗口
+#[cfg(feature = "gui")]
 fn handle_deeplink_url(
     app: &tauri::AppHandle,
     url_str: &str,
@@ -152,6 +196,7 @@ fn handle_deeplink_url(
 }
 
 /// 更新托盘菜单的Tauri命令
+#[cfg(feature = "gui")]
 #[tauri::command]
 async fn update_tray_menu(
     app: tauri::AppHandle,
@@ -173,7 +218,7 @@ async fn update_tray_menu(
     }
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(all(feature = "gui", target_os = "macos"))]
 fn macos_tray_icon() -> Option<Image<'static>> {
     const ICON_BYTES: &[u8] = include_bytes!("../icons/tray/macos/statusbar_template_3x.png");
 
@@ -186,6 +231,7 @@ fn macos_tray_icon() -> Option<Image<'static>> {
     }
 }
 
+#[cfg(feature = "gui")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // 设置 panic hook，在应用崩溃时记录日志到 <app_config_dir>/crash.log（默认 ~/.cc-switch/crash.log）
@@ -774,30 +820,23 @@ pub fn run() {
             tauri::async_runtime::spawn(async move {
                 let state = app_handle.state::<AppState>();
 
-                // 检查是否有 Live 备份（表示上次异常退出时可能处于接管状态）
-                let has_backups = match state.db.has_any_live_backup().await {
-                    Ok(v) => v,
-                    Err(e) => {
-                        log::error!("检查 Live 备份失败: {e}");
-                        false
-                    }
-                };
-                // 检查 Live 配置是否仍处于被接管状态（包含占位符）
-                let live_taken_over = state.proxy_service.detect_takeover_in_live_configs();
-
-                if has_backups || live_taken_over {
-                    log::warn!("检测到上次异常退出（存在接管残留），正在恢复 Live 配置...");
-                    if let Err(e) = state.proxy_service.recover_from_crash().await {
-                        log::error!("恢复 Live 配置失败: {e}");
-                    } else {
-                        log::info!("Live 配置已恢复");
-                    }
-                }
+                recover_takeover_residue_on_startup(&state).await;
 
                 // 检查 settings 表中的代理状态，自动恢复代理服务
                 restore_proxy_state_on_startup(&state).await;
             });
 
+            // 请求日志保留策略（定期清理过期的原始日志）
+            tauri::async_runtime::spawn(crate::services::usage_retention::run_usage_retention_loop(
+                app.state::<AppState>().db.clone(),
+            ));
+
+            // 预算提醒投递（前端事件 + Webhook）
+            tauri::async_runtime::spawn(crate::services::budget_alert::run_alert_worker(
+                app.state::<AppState>().db.clone(),
+                Some(app.handle().clone()),
+            ));
+
             // Linux: 禁用 WebKitGTK 硬件加速，防止 EGL 初始化失败导致白屏
             #[cfg(target_os = "linux")]
             {
@@ -866,6 +905,8 @@ pub fn run() {
             commands::set_rectifier_config,
             commands::get_log_config,
             commands::set_log_config,
+            commands::get_capture_config,
+            commands::set_capture_config,
             commands::restart_app,
             commands::check_for_updates,
             commands::is_portable_mode,
@@ -977,6 +1018,19 @@ pub fn run() {
             commands::is_proxy_running,
             commands::is_live_takeover_active,
             commands::switch_proxy_provider,
+            // Proxy client tokens
+            commands::list_proxy_client_tokens,
+            commands::create_proxy_client_token,
+            commands::set_proxy_client_token_enabled,
+            commands::set_proxy_client_token_limits,
+            commands::delete_proxy_client_token,
+            commands::list_model_routing_rules,
+            commands::save_model_routing_rule,
+            commands::delete_model_routing_rule,
+            commands::list_request_captures,
+            commands::get_request_capture,
+            commands::clear_request_captures,
+            commands::replay_request_capture,
             // Proxy failover commands
             commands::get_provider_health,
             commands::reset_circuit_breaker,
@@ -994,12 +1048,24 @@ pub fn run() {
             commands::get_usage_summary,
             commands::get_usage_trends,
             commands::get_provider_stats,
+            commands::get_client_stats,
             commands::get_model_stats,
+            commands::get_usage_retention_config,
+            commands::set_usage_retention_config,
+            commands::get_budget_alert_config,
+            commands::set_budget_alert_config,
+            commands::prune_usage_logs,
+            commands::export_usage,
             commands::get_request_logs,
             commands::get_request_detail,
             commands::get_model_pricing,
             commands::update_model_pricing,
             commands::delete_model_pricing,
+            commands::import_model_pricing,
+            commands::recompute_usage_costs,
+            commands::get_model_pricing_aliases,
+            commands::save_model_pricing_alias,
+            commands::delete_model_pricing_alias,
             commands::check_provider_limits,
             // Stream health check
             commands::stream_check_provider,
@@ -1008,7 +1074,9 @@ pub fn run() {
             commands::save_stream_check_config,
             // Session manager
             commands::list_sessions,
+            commands::search_sessions,
             commands::get_session_messages,
+            commands::export_session,
             commands::launch_session_terminal,
             commands::get_tool_versions,
             // Provider terminal
@@ -1175,39 +1243,68 @@ pub fn run() {
 /// 在应用退出前检查代理服务器状态，如果正在运行则停止代理并恢复 Live 配置。
 /// 确保 Claude Code/Codex/Gemini 的配置不会处于损坏状态。
 /// 使用 stop_with_restore_keep_state 保留 settings 表中的代理状态，下次启动时自动恢复。
+#[cfg(feature = "gui")]
 pub async fn cleanup_before_exit(app_handle: &tauri::AppHandle) {
     if let Some(state) = app_handle.try_state::<store::AppState>() {
-        let proxy_service = &state.proxy_service;
+        cleanup_proxy_before_exit(&state).await;
+    }
+}
 
-        // 退出时也需要兜底：代理可能已崩溃/未运行，但 Live 接管残留仍在（占位符/备份）。
-        let has_backups = match state.db.has_any_live_backup().await {
-            Ok(v) => v,
-            Err(e) => {
-                log::error!("退出时检查 Live 备份失败: {e}");
-                false
-            }
-        };
-        let live_taken_over = proxy_service.detect_takeover_in_live_configs();
-        let needs_restore = has_backups || live_taken_over;
-
-        if needs_restore {
-            log::info!("检测到接管残留，开始恢复 Live 配置（保留代理状态）...");
-            // 使用 keep_state 版本，保留 settings 表中的代理状态
-            if let Err(e) = proxy_service.stop_with_restore_keep_state().await {
-                log::error!("退出时恢复 Live 配置失败: {e}");
-            } else {
-                log::info!("已恢复 Live 配置（代理状态已保留，下次启动将自动恢复）");
-            }
-            return;
+/// 停止代理并恢复 Live 配置（桌面应用退出与 headless `serve` 共用）
+pub(crate) async fn cleanup_proxy_before_exit(state: &store::AppState) {
+    let proxy_service = &state.proxy_service;
+
+    // 退出时也需要兜底：代理可能已崩溃/未运行，但 Live 接管残留仍在（占位符/备份）。
+    let has_backups = match state.db.has_any_live_backup().await {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("退出时检查 Live 备份失败: {e}");
+            false
+        }
+    };
+    let live_taken_over = proxy_service.detect_takeover_in_live_configs();
+    let needs_restore = has_backups || live_taken_over;
+
+    if needs_restore {
+        log::info!("检测到接管残留，开始恢复 Live 配置（保留代理状态）...");
+        // 使用 keep_state 版本，保留 settings 表中的代理状态
+        if let Err(e) = proxy_service.stop_with_restore_keep_state().await {
+            log::error!("退出时恢复 Live 配置失败: {e}");
+        } else {
+            log::info!("已恢复 Live 配置（代理状态已保留，下次启动将自动恢复）");
+        }
+        return;
+    }
+
+    // 非接管模式：代理在运行则仅停止代理
+    if proxy_service.is_running().await {
+        log::info!("检测到代理服务器正在运行，开始停止...");
+        if let Err(e) = proxy_service.stop().await {
+            log::error!("退出时停止代理失败: {e}");
         }
+        log::info!("代理服务器清理完成");
+    }
+}
 
-        // 非接管模式：代理在运行则仅停止代理
-        if proxy_service.is_running().await {
-            log::info!("检测到代理服务器正在运行，开始停止...");
-            if let Err(e) = proxy_service.stop().await {
-                log::error!("退出时停止代理失败: {e}");
-            }
-            log::info!("代理服务器清理完成");
+/// 启动时检查上次异常退出的接管残留并恢复 Live 配置
+pub(crate) async fn recover_takeover_residue_on_startup(state: &store::AppState) {
+    // 检查是否有 Live 备份（表示上次异常退出时可能处于接管状态）
+    let has_backups = match state.db.has_any_live_backup().await {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("检查 Live 备份失败: {e}");
+            false
+        }
+    };
+    // 检查 Live 配置是否仍处于被接管状态（包含占位符）
+    let live_taken_over = state.proxy_service.detect_takeover_in_live_configs();
+
+    if has_backups || live_taken_over {
+        log::warn!("检测到上次异常退出（存在接管残留），正在恢复 Live 配置...");
+        if let Err(e) = state.proxy_service.recover_from_crash().await {
+            log::error!("恢复 Live 配置失败: {e}");
+        } else {
+            log::info!("Live 配置已恢复");
         }
     }
 }
@@ -1220,7 +1317,7 @@ pub async fn cleanup_before_exit(app_handle: &tauri::AppHandle) {
 ///
 /// 检查 `proxy_config.enabled` 字段，如果有任一应用的状态为 `true`，
 /// 则自动启动代理服务并接管对应应用的 Live 配置。
-async fn restore_proxy_state_on_startup(state: &store::AppState) {
+pub(crate) async fn restore_proxy_state_on_startup(state: &store::AppState) {
     // 收集需要恢复接管的应用列表（从 proxy_config.enabled 读取）
     let mut apps_to_restore = Vec::new();
     for app_type in ["claude", "codex", "gemini"] {
@@ -1268,6 +1365,7 @@ async fn restore_proxy_state_on_startup(state: &store::AppState) {
 // ============================================================
 
 /// 检测是否为中文环境
+#[cfg(feature = "gui")]
 fn is_chinese_locale() -> bool {
     std::env::var("LANG")
         .or_else(|_| std::env::var("LC_ALL"))
@@ -1278,6 +1376,7 @@ fn is_chinese_locale() -> bool {
 
 /// 显示迁移错误对话框
 /// 返回 true 表示用户选择重试，false 表示用户选择退出
+#[cfg(feature = "gui")]
 fn show_migration_error_dialog(app: &tauri::AppHandle, error: &str) -> bool {
     let title = if is_chinese_locale() {
         "配置迁移失败"
@@ -1329,6 +1428,7 @@ fn show_migration_error_dialog(app: &tauri::AppHandle, error: &str) -> bool {
 
 /// 显示数据库初始化/Schema 迁移失败对话框
 /// 返回 true 表示用户选择重试，false 表示用户选择退出
+#[cfg(feature = "gui")]
 fn show_database_init_error_dialog(
     app: &tauri::AppHandle,
     db_path: &std::path::Path,
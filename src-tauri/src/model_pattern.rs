@@ -0,0 +1,48 @@
+//! 模型名称通配符匹配
+//!
+//! 模型路由规则与模型定价别名共用同一套匹配语义。
+
+/// 通配符匹配（`*` 匹配任意个字符，`?` 匹配单个字符，忽略大小写）
+pub fn pattern_matches(pattern: &str, model: &str) -> bool {
+    let pattern: Vec<char> = pattern.trim().to_lowercase().chars().collect();
+    let model: Vec<char> = model.to_lowercase().chars().collect();
+
+    let (mut p, mut m) = (0, 0);
+    // 最近一个 `*` 的位置，以及它当前吞掉的模型字符位置（用于回溯）
+    let mut star: Option<(usize, usize)> = None;
+    while m < model.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == model[m]) {
+            p += 1;
+            m += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, m));
+            p += 1;
+        } else if let Some((star_p, star_m)) = star {
+            p = star_p + 1;
+            m = star_m + 1;
+            star = Some((star_p, star_m + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_matches() {
+        assert!(pattern_matches("*haiku*", "claude-haiku-4-5-20251001"));
+        assert!(pattern_matches("*HAIKU*", "claude-3-5-haiku"));
+        assert!(!pattern_matches("*haiku*", "claude-opus-4-6"));
+        assert!(pattern_matches("gpt-5*-codex", "gpt-5.1-codex"));
+        assert!(pattern_matches("gpt-5*-codex", "gpt-5-codex"));
+        assert!(!pattern_matches("gpt-5*-codex", "gpt-5-codex-mini"));
+        assert!(pattern_matches("claude-?-opus", "claude-3-opus"));
+        assert!(pattern_matches("*", "anything"));
+        assert!(pattern_matches("exact", "exact"));
+        assert!(!pattern_matches("exact", "exactly"));
+    }
+}
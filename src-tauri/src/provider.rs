@@ -224,6 +224,15 @@ pub struct ProviderMeta {
     /// 每月消费限额（USD）
     #[serde(rename = "limitMonthlyUsd", skip_serializing_if = "Option::is_none")]
     pub limit_monthly_usd: Option<String>,
+    /// 加权轮询权重（默认 1，0 表示仅作为备用）
+    #[serde(rename = "routingWeight", skip_serializing_if = "Option::is_none")]
+    pub routing_weight: Option<u32>,
+    /// 每分钟请求数限制（RPM）
+    #[serde(rename = "rateLimitRpm", skip_serializing_if = "Option::is_none")]
+    pub rate_limit_rpm: Option<u32>,
+    /// 每分钟 Token 数限制（TPM）
+    #[serde(rename = "rateLimitTpm", skip_serializing_if = "Option::is_none")]
+    pub rate_limit_tpm: Option<u32>,
     /// 供应商单独的模型测试配置
     #[serde(rename = "testConfig", skip_serializing_if = "Option::is_none")]
     pub test_config: Option<ProviderTestConfig>,
@@ -0,0 +1,290 @@
+//! 供应商消费限额模块
+//!
+//! 在代理请求路径中强制执行 `limitDailyUsd` / `limitMonthlyUsd` 硬限额：
+//! - 每个供应商的今日/本月累计消费仅在首次检查时从数据库加载一次
+//! - 之后由使用量记录在内存中累加，避免每个请求都执行一次 SQL 聚合
+//! - 跨天/跨月时自动清零对应窗口
+
+use crate::database::Database;
+use crate::provider::Provider;
+use chrono::{Datelike, Local, NaiveDate};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// 限额检查结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BudgetCheck {
+    /// 未设置限额或未超出
+    Within,
+    /// 超出每日限额
+    DailyExceeded { usage: f64, limit: f64 },
+    /// 超出每月限额
+    MonthlyExceeded { usage: f64, limit: f64 },
+}
+
+impl BudgetCheck {
+    /// 是否已超出限额
+    pub fn is_exceeded(&self) -> bool {
+        !matches!(self, BudgetCheck::Within)
+    }
+
+    /// 生成用户可读的描述
+    pub fn describe(&self, provider_name: &str) -> String {
+        match self {
+            BudgetCheck::Within => format!("{provider_name} 未超出消费限额"),
+            BudgetCheck::DailyExceeded { usage, limit } => {
+                format!("{provider_name} 今日消费 ${usage:.4} 已达到每日限额 ${limit:.2}")
+            }
+            BudgetCheck::MonthlyExceeded { usage, limit } => {
+                format!("{provider_name} 本月消费 ${usage:.4} 已达到每月限额 ${limit:.2}")
+            }
+        }
+    }
+}
+
+/// 供应商限额设置（来自 ProviderMeta）
+#[derive(Debug, Clone, Copy, Default)]
+struct ProviderLimits {
+    daily: Option<f64>,
+    monthly: Option<f64>,
+}
+
+impl ProviderLimits {
+    /// 从供应商 meta 解析限额，未设置或无法解析时返回 None
+    fn from_provider(provider: &Provider) -> Option<Self> {
+        let meta = provider.meta.as_ref()?;
+        let parse = |value: Option<&String>| {
+            value
+                .and_then(|s| s.trim().parse::<f64>().ok())
+                .filter(|v| *v > 0.0)
+        };
+        let limits = Self {
+            daily: parse(meta.limit_daily_usd.as_ref()),
+            monthly: parse(meta.limit_monthly_usd.as_ref()),
+        };
+
+        if limits.daily.is_none() && limits.monthly.is_none() {
+            None
+        } else {
+            Some(limits)
+        }
+    }
+}
+
+/// 单个供应商的消费窗口（本地时区）
+#[derive(Debug, Clone)]
+struct SpendWindow {
+    day: NaiveDate,
+    month: (i32, u32),
+    daily_usd: f64,
+    monthly_usd: f64,
+}
+
+impl SpendWindow {
+    fn new(today: NaiveDate, daily_usd: f64, monthly_usd: f64) -> Self {
+        Self {
+            day: today,
+            month: (today.year(), today.month()),
+            daily_usd,
+            monthly_usd,
+        }
+    }
+
+    /// 跨天/跨月时清零对应累计值
+    fn roll(&mut self, today: NaiveDate) {
+        if self.day != today {
+            self.day = today;
+            self.daily_usd = 0.0;
+        }
+        let month = (today.year(), today.month());
+        if self.month != month {
+            self.month = month;
+            self.monthly_usd = 0.0;
+        }
+    }
+
+    fn check(&self, limits: &ProviderLimits) -> BudgetCheck {
+        if let Some(limit) = limits.daily {
+            if self.daily_usd >= limit {
+                return BudgetCheck::DailyExceeded {
+                    usage: self.daily_usd,
+                    limit,
+                };
+            }
+        }
+        if let Some(limit) = limits.monthly {
+            if self.monthly_usd >= limit {
+                return BudgetCheck::MonthlyExceeded {
+                    usage: self.monthly_usd,
+                    limit,
+                };
+            }
+        }
+        BudgetCheck::Within
+    }
+}
+
+/// 供应商消费跟踪器
+pub struct ProviderBudgetTracker {
+    db: Arc<Database>,
+    /// 消费窗口 - key 格式: "app_type:provider_id"
+    windows: RwLock<HashMap<String, SpendWindow>>,
+}
+
+impl ProviderBudgetTracker {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self {
+            db,
+            windows: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 检查供应商是否超出消费限额
+    ///
+    /// 未设置限额的供应商直接放行，不会触发任何数据库查询。
+    pub async fn check(&self, app_type: &str, provider: &Provider) -> BudgetCheck {
+        let Some(limits) = ProviderLimits::from_provider(provider) else {
+            return BudgetCheck::Within;
+        };
+
+        let key = format!("{app_type}:{}", provider.id);
+        let today = Local::now().date_naive();
+
+        {
+            let mut windows = self.windows.write().await;
+            if let Some(window) = windows.get_mut(&key) {
+                window.roll(today);
+                return window.check(&limits);
+            }
+        }
+
+        // 首次检查：从数据库加载今日/本月累计消费
+        let (daily_usd, monthly_usd) = match self.db.get_provider_spend(&provider.id, app_type) {
+            Ok(totals) => totals,
+            Err(e) => {
+                log::warn!(
+                    "[{app_type}] 加载供应商 {} 累计消费失败，按 0 处理: {e}",
+                    provider.id
+                );
+                (0.0, 0.0)
+            }
+        };
+
+        let mut windows = self.windows.write().await;
+        let window = windows
+            .entry(key)
+            .or_insert_with(|| SpendWindow::new(today, daily_usd, monthly_usd));
+        window.roll(today);
+        window.check(&limits)
+    }
+
+    /// 累加一次请求的消费
+    ///
+    /// 仅更新已加载的窗口；尚未加载的供应商会在下一次检查时从数据库读取（已包含本次记录）。
+    pub async fn record_spend(&self, app_type: &str, provider_id: &str, cost_usd: f64) {
+        if cost_usd <= 0.0 {
+            return;
+        }
+        let key = format!("{app_type}:{provider_id}");
+        let today = Local::now().date_naive();
+
+        let mut windows = self.windows.write().await;
+        if let Some(window) = windows.get_mut(&key) {
+            window.roll(today);
+            window.daily_usd += cost_usd;
+            window.monthly_usd += cost_usd;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    fn provider_with_limits(daily: Option<&str>, monthly: Option<&str>) -> Provider {
+        let mut provider = Provider::with_id("p1".to_string(), "P1".to_string(), json!({}), None);
+        provider.meta = Some(ProviderMeta {
+            limit_daily_usd: daily.map(str::to_string),
+            limit_monthly_usd: monthly.map(str::to_string),
+            ..Default::default()
+        });
+        provider
+    }
+
+    #[test]
+    fn test_window_rolls_over_day_and_month() {
+        let day1 = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap();
+        let mut window = SpendWindow::new(day1, 5.0, 20.0);
+
+        window.roll(day1);
+        assert_eq!(window.daily_usd, 5.0);
+
+        let day2 = NaiveDate::from_ymd_opt(2025, 2, 1).unwrap();
+        window.roll(day2);
+        assert_eq!(window.daily_usd, 0.0);
+        assert_eq!(window.monthly_usd, 0.0);
+    }
+
+    #[test]
+    fn test_window_check_daily_before_monthly() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        let window = SpendWindow::new(today, 10.0, 100.0);
+        let limits = ProviderLimits {
+            daily: Some(10.0),
+            monthly: Some(50.0),
+        };
+        assert!(matches!(
+            window.check(&limits),
+            BudgetCheck::DailyExceeded { .. }
+        ));
+
+        let limits = ProviderLimits {
+            daily: None,
+            monthly: Some(50.0),
+        };
+        assert!(matches!(
+            window.check(&limits),
+            BudgetCheck::MonthlyExceeded { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tracker_accumulates_in_memory() {
+        let db = Arc::new(Database::memory().unwrap());
+        let tracker = ProviderBudgetTracker::new(db);
+        let provider = provider_with_limits(Some("1.0"), None);
+
+        assert_eq!(
+            tracker.check("claude", &provider).await,
+            BudgetCheck::Within
+        );
+
+        tracker.record_spend("claude", "p1", 0.6).await;
+        assert_eq!(
+            tracker.check("claude", &provider).await,
+            BudgetCheck::Within
+        );
+
+        tracker.record_spend("claude", "p1", 0.5).await;
+        assert!(tracker.check("claude", &provider).await.is_exceeded());
+
+        // 其他应用的同 ID 供应商独立统计
+        assert_eq!(tracker.check("codex", &provider).await, BudgetCheck::Within);
+    }
+
+    #[tokio::test]
+    async fn test_tracker_ignores_providers_without_limits() {
+        let db = Arc::new(Database::memory().unwrap());
+        let tracker = ProviderBudgetTracker::new(db);
+        let provider = provider_with_limits(None, Some("invalid"));
+
+        tracker.record_spend("claude", "p1", 100.0).await;
+        assert_eq!(
+            tracker.check("claude", &provider).await,
+            BudgetCheck::Within
+        );
+    }
+}
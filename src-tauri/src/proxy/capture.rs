@@ -0,0 +1,378 @@
+//! 请求抓包
+//!
+//! 开启 `capture_config` 后，记录每次发往上游的完整请求与原始响应，用于排查与重放：
+//! - 请求：转换后的请求体与实际发送的 Header（认证类 Header、URL 中的 `key` 参数已遮蔽）
+//! - 响应：状态码、Header 与原始响应体；流式响应边转发边记录完整 SSE 文本
+//! - 请求体/响应体超过 `max_body_bytes` 时截断；写入时顺带清理超过保留天数的记录
+//!
+//! 记录按 `request_id` 与请求日志关联，同一请求重试时保留最后一次尝试。
+
+use super::providers::mask_secret;
+use super::types::CaptureConfig;
+use crate::database::{Database, RequestCapture};
+use axum::http::HeaderMap;
+use bytes::Bytes;
+use futures::Stream;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// 需要遮蔽值的请求/响应 Header
+const MASKED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "x-api-key",
+    "x-goog-api-key",
+    "api-key",
+    "cookie",
+    "set-cookie",
+];
+
+/// 需要遮蔽值的 URL 查询参数（Gemini API Key）
+const MASKED_QUERY_PARAMS: &[&str] = &["key"];
+
+/// 遮蔽单个 Header 的值（`Bearer` 前缀保留，便于辨认认证方式）
+pub fn mask_header_value(name: &str, value: &str) -> String {
+    if !is_masked_header(name) {
+        return value.to_string();
+    }
+    match value.split_once(' ') {
+        Some((scheme, token)) if scheme.eq_ignore_ascii_case("bearer") => {
+            format!("{scheme} {}", mask_secret(token.trim()))
+        }
+        _ => mask_secret(value),
+    }
+}
+
+/// 判断 Header 值是否需要遮蔽（抓包中的值不能直接用于重放）
+pub fn is_masked_header(name: &str) -> bool {
+    MASKED_HEADERS.iter().any(|h| name.eq_ignore_ascii_case(h))
+}
+
+/// 收集 Header 并遮蔽敏感值
+pub fn collect_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes());
+            (
+                name.as_str().to_string(),
+                mask_header_value(name.as_str(), &value),
+            )
+        })
+        .collect()
+}
+
+/// 遮蔽 URL 查询参数中的密钥
+pub fn mask_url(url: &url::Url) -> String {
+    if !url
+        .query_pairs()
+        .any(|(k, _)| MASKED_QUERY_PARAMS.contains(&k.as_ref()))
+    {
+        return url.to_string();
+    }
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| {
+            let v = if MASKED_QUERY_PARAMS.contains(&k.as_ref()) {
+                mask_secret(&v)
+            } else {
+                v.into_owned()
+            };
+            (k.into_owned(), v)
+        })
+        .collect();
+    let mut masked = url.clone();
+    masked.query_pairs_mut().clear().extend_pairs(pairs);
+    masked.to_string()
+}
+
+/// 带大小上限的响应体缓冲区
+#[derive(Debug, Default)]
+struct BodyBuffer {
+    data: Vec<u8>,
+    limit: usize,
+    truncated: bool,
+}
+
+impl BodyBuffer {
+    fn new(limit: usize) -> Self {
+        Self {
+            data: Vec::new(),
+            limit,
+            truncated: false,
+        }
+    }
+
+    fn push(&mut self, chunk: &[u8]) {
+        let remaining = self.limit.saturating_sub(self.data.len());
+        if chunk.len() > remaining {
+            self.truncated = true;
+        }
+        self.data
+            .extend_from_slice(&chunk[..chunk.len().min(remaining)]);
+    }
+
+    fn take_string(&mut self) -> String {
+        String::from_utf8_lossy(&std::mem::take(&mut self.data)).into_owned()
+    }
+}
+
+/// 单个请求的抓包器（由 RequestContext 创建，抓包关闭时为 None）
+#[derive(Clone)]
+pub struct RequestCapturer {
+    db: Arc<Database>,
+    config: CaptureConfig,
+    request_id: String,
+    app_type: String,
+}
+
+impl RequestCapturer {
+    /// 读取抓包配置，未开启时返回 None
+    pub fn from_settings(db: &Arc<Database>, request_id: &str, app_type: &str) -> Option<Self> {
+        let config = db.get_capture_config().unwrap_or_default();
+        config.enabled.then(|| Self {
+            db: db.clone(),
+            config,
+            request_id: request_id.to_string(),
+            app_type: app_type.to_string(),
+        })
+    }
+
+    /// 记录即将发送的上游请求
+    pub fn begin(&self, provider_id: &str, request: &reqwest::Request) -> PendingCapture {
+        let mut body = BodyBuffer::new(self.config.max_body_bytes);
+        if let Some(bytes) = request.body().and_then(|b| b.as_bytes()) {
+            body.push(bytes);
+        }
+        let request_truncated = body.truncated;
+
+        PendingCapture {
+            db: self.db.clone(),
+            retention_days: self.config.retention_days,
+            response_body: BodyBuffer::new(self.config.max_body_bytes),
+            record: RequestCapture {
+                request_id: self.request_id.clone(),
+                app_type: self.app_type.clone(),
+                provider_id: provider_id.to_string(),
+                method: request.method().to_string(),
+                url: mask_url(request.url()),
+                request_headers: collect_headers(request.headers()),
+                request_body: body.take_string(),
+                request_truncated,
+                response_status: None,
+                response_headers: Vec::new(),
+                response_body: None,
+                response_truncated: false,
+                error_message: None,
+                created_at: chrono::Utc::now().timestamp(),
+            },
+        }
+    }
+}
+
+/// 已发送、等待响应的抓包记录
+pub struct PendingCapture {
+    db: Arc<Database>,
+    retention_days: u32,
+    response_body: BodyBuffer,
+    record: RequestCapture,
+}
+
+impl PendingCapture {
+    /// 记录响应状态码与 Header
+    pub fn response_head(&mut self, status: u16, headers: &HeaderMap) {
+        self.record.response_status = Some(status);
+        self.record.response_headers = collect_headers(headers);
+    }
+
+    /// 以完整响应体（或错误信息）结束抓包并保存
+    pub fn finish(mut self, body: Option<&[u8]>, error: Option<String>) {
+        if let Some(body) = body {
+            self.response_body.push(body);
+        }
+        self.complete(error);
+    }
+
+    /// 包装成功响应：边转发边记录响应体，流结束（或被丢弃）时保存
+    pub fn wrap_response(mut self, response: reqwest::Response) -> reqwest::Response {
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        self.response_head(status.as_u16(), &headers);
+
+        let stream = CaptureStream {
+            inner: Box::pin(response.bytes_stream()),
+            capture: Some(self),
+        };
+        let mut wrapped = axum::http::Response::new(reqwest::Body::wrap_stream(stream));
+        *wrapped.status_mut() = status;
+        *wrapped.version_mut() = version;
+        *wrapped.headers_mut() = headers;
+        reqwest::Response::from(wrapped)
+    }
+
+    fn complete(mut self, error: Option<String>) {
+        self.record.response_truncated = self.response_body.truncated;
+        if self.record.response_status.is_some() || !self.response_body.data.is_empty() {
+            self.record.response_body = Some(self.response_body.take_string());
+        }
+        self.record.error_message = error;
+
+        let PendingCapture {
+            db,
+            retention_days,
+            record,
+            ..
+        } = self;
+        let save = move || {
+            if let Err(e) = db.save_request_capture(&record, retention_days) {
+                log::warn!("保存请求抓包失败: request_id={}, {e}", record.request_id);
+            }
+        };
+        // 响应体可能较大，避免在异步任务中同步写库
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(save);
+            }
+            Err(_) => save(),
+        }
+    }
+}
+
+/// 记录响应体的流包装器
+struct CaptureStream {
+    inner: Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>,
+    capture: Option<PendingCapture>,
+}
+
+impl CaptureStream {
+    fn complete(&mut self, error: Option<String>) {
+        if let Some(capture) = self.capture.take() {
+            capture.complete(error);
+        }
+    }
+}
+
+impl Stream for CaptureStream {
+    type Item = reqwest::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let poll = self.inner.as_mut().poll_next(cx);
+        match &poll {
+            Poll::Ready(Some(Ok(chunk))) => {
+                if let Some(capture) = self.capture.as_mut() {
+                    capture.response_body.push(chunk);
+                }
+            }
+            Poll::Ready(Some(Err(e))) => self.complete(Some(format!("读取响应流失败: {e}"))),
+            Poll::Ready(None) => self.complete(None),
+            Poll::Pending => {}
+        }
+        poll
+    }
+}
+
+impl Drop for CaptureStream {
+    fn drop(&mut self) {
+        self.complete(Some("响应未读取完毕（客户端断开或超时）".to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[test]
+    fn test_mask_headers_and_url() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            "Bearer sk-1234567890abcdef".parse().unwrap(),
+        );
+        headers.insert("x-api-key", "sk-ant-abcdefghijkl".parse().unwrap());
+        headers.insert("content-type", "application/json".parse().unwrap());
+
+        let collected = collect_headers(&headers);
+        assert!(collected.contains(&("authorization".into(), "Bearer sk-1...cdef".into())));
+        assert!(collected.contains(&("x-api-key".into(), "sk-a...ijkl".into())));
+        assert!(collected.contains(&("content-type".into(), "application/json".into())));
+
+        let url = url::Url::parse(
+            "https://generativelanguage.googleapis.com/v1beta/models/g:streamGenerateContent?alt=sse&key=AIzaSyABCDEFGH",
+        )
+        .unwrap();
+        let masked = mask_url(&url);
+        assert!(masked.contains("alt=sse"));
+        assert!(masked.contains("key=AIza...EFGH"));
+        assert!(!masked.contains("AIzaSyABCDEFGH"));
+    }
+
+    #[test]
+    fn test_body_buffer_truncates_at_limit() {
+        let mut buffer = BodyBuffer::new(5);
+        buffer.push(b"abc");
+        buffer.push(b"defg");
+        assert!(buffer.truncated);
+        assert_eq!(buffer.take_string(), "abcde");
+    }
+
+    #[tokio::test]
+    async fn test_wrapped_response_records_streamed_body() {
+        let db = Arc::new(Database::memory().unwrap());
+        db.set_capture_config(&CaptureConfig {
+            enabled: true,
+            max_body_bytes: 1024,
+            retention_days: 7,
+        })
+        .unwrap();
+        let capturer = RequestCapturer::from_settings(&db, "req-stream", "claude").unwrap();
+
+        let request = reqwest::Client::new()
+            .post("https://api.example.com/v1/messages")
+            .header("x-api-key", "sk-ant-abcdefghijkl")
+            .json(&serde_json::json!({ "model": "m", "stream": true }))
+            .build()
+            .unwrap();
+        let capture = capturer.begin("p1", &request);
+
+        let chunks: Vec<reqwest::Result<Bytes>> = vec![
+            Ok(Bytes::from_static(b"event: a\ndata: 1\n\n")),
+            Ok(Bytes::from_static(b"event: b\ndata: 2\n\n")),
+        ];
+        let upstream = axum::http::Response::builder()
+            .status(200)
+            .header("content-type", "text/event-stream")
+            .body(reqwest::Body::wrap_stream(futures::stream::iter(chunks)))
+            .unwrap();
+        let response = capture.wrap_response(reqwest::Response::from(upstream));
+
+        let mut stream = response.bytes_stream();
+        let mut forwarded = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            forwarded.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(forwarded, b"event: a\ndata: 1\n\nevent: b\ndata: 2\n\n");
+
+        let mut saved = None;
+        for _ in 0..50 {
+            saved = db.get_request_capture("req-stream").unwrap();
+            if saved.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let saved = saved.expect("capture saved");
+        assert_eq!(saved.response_status, Some(200));
+        assert_eq!(
+            saved.response_body.as_deref(),
+            Some("event: a\ndata: 1\n\nevent: b\ndata: 2\n\n")
+        );
+        assert_eq!(saved.request_body, r#"{"model":"m","stream":true}"#);
+        assert!(saved
+            .request_headers
+            .contains(&("x-api-key".into(), "sk-a...ijkl".into())));
+        assert!(saved.error_message.is_none());
+    }
+}
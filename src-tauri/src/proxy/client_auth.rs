@@ -0,0 +1,317 @@
+//! 客户端认证模块
+//!
+//! 代理可向客户端签发访问令牌（`ccs-` 前缀），数据库只保存其 SHA-256 哈希。
+//! 中间件从 `Authorization: Bearer`、`x-api-key` 或 `x-goog-api-key` 中读取令牌：
+//! - 令牌有效：将 [`ClientIdentity`] 写入请求扩展，其标签会记录到请求日志
+//! - 开启 `require_client_auth` 时，缺少或无效令牌的请求返回 401
+//! - 令牌配置了 RPM/TPM 限制时，超限请求短暂排队，仍超限则返回带 `retry-after` 的 429
+//! - 仅 `/health` 无需认证；`/status`、`/metrics` 与模型列表需要认证但不计入速率限制
+//!   （`/metrics` 含供应商、消费与熔断信息，Prometheus 抓取时需配置 Bearer 令牌）
+//!
+//! 客户端携带的认证头不会被转发到上游（见 forwarder 的头部过滤）。
+
+use super::{
+    log_codes::auth as log_auth,
+    rate_limiter::{retry_after_secs, RateDecision, RateLimitConfig, MAX_QUEUE_WAIT},
+    server::ProxyState,
+    ProxyError,
+};
+use crate::database::{ClientToken, Database};
+use crate::error::AppError;
+use axum::{
+    extract::{Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// 代理签发令牌的前缀
+pub const CLIENT_TOKEN_PREFIX: &str = "ccs-";
+
+/// 界面展示用的令牌前缀长度（含 `ccs-`）
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+/// 无需认证的路径（健康检查不包含任何供应商信息）
+const PUBLIC_PATHS: &[&str] = &["/health"];
+
+/// 需要认证但不计入客户端 RPM/TPM 的路径（状态、监控与模型列表不产生模型调用）
+const UNMETERED_PATHS: &[&str] = &[
+    "/status",
+    "/metrics",
+    "/models",
+    "/v1/models",
+    "/claude/v1/models",
+    "/codex/v1/models",
+];
+
+/// 令牌最近使用时间的最小写入间隔（秒），避免每个请求都写数据库
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
+/// 请求路径的认证策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathPolicy {
+    /// 无需认证
+    Public,
+    /// 需要认证，不计入客户端速率限制
+    Unmetered,
+    /// 需要认证，并计入客户端速率限制
+    Metered,
+}
+
+fn path_policy(path: &str) -> PathPolicy {
+    if PUBLIC_PATHS.contains(&path) {
+        PathPolicy::Public
+    } else if UNMETERED_PATHS.contains(&path) {
+        PathPolicy::Unmetered
+    } else {
+        PathPolicy::Metered
+    }
+}
+
+/// 距上次记录超过写入间隔时才需要更新最近使用时间
+fn needs_touch(last_used_at: Option<i64>, now: i64) -> bool {
+    last_used_at.is_none_or(|last| now - last >= TOUCH_INTERVAL_SECS)
+}
+
+/// 已认证的客户端身份（存放在请求扩展中）
+#[derive(Debug, Clone)]
+pub struct ClientIdentity {
+    pub token_id: String,
+    pub label: String,
+    /// 令牌上配置的 RPM/TPM 限制
+    pub limits: RateLimitConfig,
+}
+
+/// 新签发的客户端令牌（明文仅在签发时返回一次）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssuedClientToken {
+    #[serde(flatten)]
+    pub record: ClientToken,
+    pub secret: String,
+}
+
+/// 生成新的客户端令牌明文
+pub fn generate_client_token() -> String {
+    format!(
+        "{CLIENT_TOKEN_PREFIX}{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// 计算令牌哈希（十六进制 SHA-256）
+pub fn hash_client_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// 签发客户端令牌
+///
+/// 返回令牌记录与明文；明文不会落库，只能在此时展示给用户。
+pub fn issue_client_token(db: &Database, label: &str) -> Result<IssuedClientToken, AppError> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Err(AppError::InvalidInput("令牌标签不能为空".to_string()));
+    }
+
+    let token = generate_client_token();
+    let prefix: String = token.chars().take(DISPLAY_PREFIX_LEN).collect();
+    let record = db.insert_client_token(label, &hash_client_token(&token), &prefix)?;
+    log::info!("已签发代理客户端令牌: label={label}, prefix={prefix}");
+    Ok(IssuedClientToken {
+        record,
+        secret: token,
+    })
+}
+
+/// 从请求头中提取客户端令牌
+pub fn extract_client_token(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| {
+            v.strip_prefix("Bearer ")
+                .or_else(|| v.strip_prefix("bearer "))
+        });
+
+    bearer
+        .or_else(|| headers.get("x-api-key").and_then(|v| v.to_str().ok()))
+        .or_else(|| headers.get("x-goog-api-key").and_then(|v| v.to_str().ok()))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// 根据请求头解析客户端身份
+fn resolve_identity(db: &Database, headers: &HeaderMap) -> Option<ClientIdentity> {
+    let token = extract_client_token(headers)?;
+    match db.find_enabled_client_token(&hash_client_token(token)) {
+        Ok(Some(record)) => {
+            if needs_touch(record.last_used_at, chrono::Utc::now().timestamp()) {
+                if let Err(e) = db.touch_client_token(&record.id) {
+                    log::debug!("更新客户端令牌使用时间失败: {e}");
+                }
+            }
+            Some(ClientIdentity {
+                limits: RateLimitConfig::new(record.rpm_limit, record.tpm_limit),
+                token_id: record.id,
+                label: record.label,
+            })
+        }
+        Ok(None) => None,
+        Err(e) => {
+            log::warn!("[{}] 查询客户端令牌失败: {e}", log_auth::LOOKUP_FAILED);
+            None
+        }
+    }
+}
+
+/// 客户端认证中间件
+pub async fn client_auth_middleware(
+    State(state): State<ProxyState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let policy = path_policy(request.uri().path());
+    if policy == PathPolicy::Public {
+        return next.run(request).await;
+    }
+
+    match resolve_identity(&state.db, request.headers()) {
+        Some(identity) => {
+            log::debug!(
+                "客户端认证通过: label={}, token_id={}",
+                identity.label,
+                identity.token_id
+            );
+            if policy == PathPolicy::Metered {
+                if let RateDecision::Limited { retry_after } = state
+                    .provider_router
+                    .acquire_client_rate(&identity.token_id, identity.limits, MAX_QUEUE_WAIT)
+                    .await
+                {
+                    log::warn!(
+                        "[{}] 客户端触发速率限制: label={}",
+                        log_auth::RATE_LIMITED,
+                        identity.label
+                    );
+                    return ProxyError::RateLimited {
+                        reason: format!("客户端 {} 已触发速率限制", identity.label),
+                        retry_after_secs: retry_after_secs(retry_after),
+                    }
+                    .into_response();
+                }
+            }
+            request.extensions_mut().insert(identity);
+        }
+        None => {
+            let required = state.db.is_client_auth_required().unwrap_or_else(|e| {
+                log::warn!("[{}] 读取客户端认证开关失败: {e}", log_auth::LOOKUP_FAILED);
+                // 读取失败时保守处理：按要求认证
+                true
+            });
+            if required {
+                log::warn!(
+                    "[{}] 拒绝未认证的客户端请求: {}",
+                    log_auth::REJECTED,
+                    request.uri().path()
+                );
+                return ProxyError::ClientUnauthorized("缺少或无效的代理客户端令牌".to_string())
+                    .into_response();
+            }
+        }
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_generate_and_hash_token() {
+        let token = generate_client_token();
+        assert!(token.starts_with(CLIENT_TOKEN_PREFIX));
+        assert_eq!(token.len(), CLIENT_TOKEN_PREFIX.len() + 64);
+        assert_ne!(token, generate_client_token());
+
+        let hash = hash_client_token(&token);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, hash_client_token(&token));
+    }
+
+    #[test]
+    fn test_extract_client_token_sources() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(extract_client_token(&headers), None);
+
+        headers.insert("x-goog-api-key", HeaderValue::from_static("ccs-goog"));
+        assert_eq!(extract_client_token(&headers), Some("ccs-goog"));
+
+        headers.insert("x-api-key", HeaderValue::from_static("ccs-key"));
+        assert_eq!(extract_client_token(&headers), Some("ccs-key"));
+
+        headers.insert(
+            "authorization",
+            HeaderValue::from_static("Bearer ccs-bearer"),
+        );
+        assert_eq!(extract_client_token(&headers), Some("ccs-bearer"));
+    }
+
+    #[test]
+    fn test_path_policy() {
+        assert_eq!(path_policy("/health"), PathPolicy::Public);
+        for path in ["/status", "/metrics", "/v1/models", "/claude/v1/models"] {
+            assert_eq!(path_policy(path), PathPolicy::Unmetered);
+        }
+        for path in [
+            "/v1/messages",
+            "/v1/responses",
+            "/v1beta/models/x:generateContent",
+        ] {
+            assert_eq!(path_policy(path), PathPolicy::Metered);
+        }
+    }
+
+    #[test]
+    fn test_needs_touch_throttles_writes() {
+        assert!(needs_touch(None, 1_000));
+        assert!(!needs_touch(Some(1_000), 1_000 + TOUCH_INTERVAL_SECS - 1));
+        assert!(needs_touch(Some(1_000), 1_000 + TOUCH_INTERVAL_SECS));
+    }
+
+    #[test]
+    fn test_issue_and_resolve_identity() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        assert!(issue_client_token(&db, "  ").is_err());
+
+        let IssuedClientToken {
+            record,
+            secret: token,
+        } = issue_client_token(&db, "alice")?;
+        assert!(token.starts_with(&record.token_prefix));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+        );
+        let identity = resolve_identity(&db, &headers).expect("identity");
+        assert_eq!(identity.label, "alice");
+        assert_eq!(identity.token_id, record.id);
+        assert!(identity.limits.is_unlimited());
+
+        db.set_client_token_limits(&record.id, Some(30), None)?;
+        let identity = resolve_identity(&db, &headers).expect("identity");
+        assert_eq!(identity.limits, RateLimitConfig::new(Some(30), None));
+
+        db.set_client_token_enabled(&record.id, false)?;
+        assert!(resolve_identity(&db, &headers).is_none());
+        Ok(())
+    }
+}
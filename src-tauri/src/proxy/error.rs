@@ -35,12 +35,28 @@ pub enum ProxyError {
     #[error("未配置供应商")]
     NoProvidersConfigured,
 
+    /// 供应商已超出每日/每月消费限额
+    #[error("供应商已超出消费限额: {0}")]
+    BudgetExceeded(String),
+
+    /// 触发供应商或客户端的 RPM/TPM 限制
+    #[error("请求过于频繁: {reason}")]
+    RateLimited {
+        reason: String,
+        retry_after_secs: u64,
+    },
+
     #[allow(dead_code)]
     #[error("Provider不健康: {0}")]
     ProviderUnhealthy(String),
 
     #[error("上游错误 (状态码 {status}): {body:?}")]
-    UpstreamError { status: u16, body: Option<String> },
+    UpstreamError {
+        status: u16,
+        body: Option<String>,
+        /// 上游 `retry-after` 头（秒）
+        retry_after: Option<u64>,
+    },
 
     #[error("超过最大重试次数")]
     MaxRetriesExceeded,
@@ -67,6 +83,10 @@ pub enum ProxyError {
     #[error("流式响应空闲超时: {0}秒无数据")]
     StreamIdleTimeout(u64),
 
+    /// 客户端未携带有效的代理令牌
+    #[error("客户端认证失败: {0}")]
+    ClientUnauthorized(String),
+
     /// 认证错误
     #[allow(dead_code)]
     #[error("认证失败: {0}")]
@@ -83,6 +103,7 @@ impl IntoResponse for ProxyError {
             ProxyError::UpstreamError {
                 status: upstream_status,
                 body: upstream_body,
+                ..
             } => {
                 let http_status =
                     StatusCode::from_u16(*upstream_status).unwrap_or(StatusCode::BAD_GATEWAY);
@@ -135,6 +156,12 @@ impl IntoResponse for ProxyError {
                     ProxyError::NoProvidersConfigured => {
                         (StatusCode::SERVICE_UNAVAILABLE, self.to_string())
                     }
+                    ProxyError::BudgetExceeded(_) => {
+                        (StatusCode::TOO_MANY_REQUESTS, self.to_string())
+                    }
+                    ProxyError::RateLimited { .. } => {
+                        (StatusCode::TOO_MANY_REQUESTS, self.to_string())
+                    }
                     ProxyError::ProviderUnhealthy(_) => {
                         (StatusCode::SERVICE_UNAVAILABLE, self.to_string())
                     }
@@ -154,6 +181,9 @@ impl IntoResponse for ProxyError {
                         (StatusCode::GATEWAY_TIMEOUT, self.to_string())
                     }
                     ProxyError::AuthError(_) => (StatusCode::UNAUTHORIZED, self.to_string()),
+                    ProxyError::ClientUnauthorized(_) => {
+                        (StatusCode::UNAUTHORIZED, self.to_string())
+                    }
                     ProxyError::Internal(_) => {
                         (StatusCode::INTERNAL_SERVER_ERROR, self.to_string())
                     }
@@ -171,7 +201,22 @@ impl IntoResponse for ProxyError {
             }
         };
 
-        (status, Json(body)).into_response()
+        let mut response = (status, Json(body)).into_response();
+        let retry_after = match &self {
+            ProxyError::RateLimited {
+                retry_after_secs, ..
+            } => Some(*retry_after_secs),
+            // 透传上游的 retry-after，便于客户端自行退避
+            ProxyError::UpstreamError { retry_after, .. } => *retry_after,
+            _ => None,
+        };
+        if let Some(secs) = retry_after {
+            response.headers_mut().insert(
+                axum::http::header::RETRY_AFTER,
+                axum::http::HeaderValue::from(secs),
+            );
+        }
+        response
     }
 }
 
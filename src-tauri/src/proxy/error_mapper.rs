@@ -11,6 +11,8 @@ use super::ProxyError;
 /// - 超时：504 Gateway Timeout
 /// - 连接失败：502 Bad Gateway
 /// - 无可用 Provider：503 Service Unavailable
+/// - 超出消费限额：429 Too Many Requests
+/// - 客户端认证失败：401 Unauthorized
 /// - 重试耗尽：503 Service Unavailable
 /// - 其他错误：500 Internal Server Error
 pub fn map_proxy_error_to_status(error: &ProxyError) -> u16 {
@@ -33,6 +35,15 @@ pub fn map_proxy_error_to_status(error: &ProxyError) -> u16 {
         // 未配置供应商：503 Service Unavailable
         ProxyError::NoProvidersConfigured => 503,
 
+        // 超出消费限额：429 Too Many Requests
+        ProxyError::BudgetExceeded(_) => 429,
+
+        // 触发 RPM/TPM 限制：429 Too Many Requests
+        ProxyError::RateLimited { .. } => 429,
+
+        // 客户端认证失败：401 Unauthorized
+        ProxyError::ClientUnauthorized(_) => 401,
+
         // 重试耗尽：503 Service Unavailable
         ProxyError::MaxRetriesExceeded => 503,
 
@@ -53,7 +64,7 @@ pub fn map_proxy_error_to_status(error: &ProxyError) -> u16 {
 /// 将 ProxyError 转换为用户友好的错误消息
 pub fn get_error_message(error: &ProxyError) -> String {
     match error {
-        ProxyError::UpstreamError { status, body } => {
+        ProxyError::UpstreamError { status, body, .. } => {
             if let Some(body) = body {
                 format!("上游错误 ({status}): {body}")
             } else {
@@ -65,6 +76,8 @@ pub fn get_error_message(error: &ProxyError) -> String {
         ProxyError::NoAvailableProvider => "无可用 Provider".to_string(),
         ProxyError::AllProvidersCircuitOpen => "所有供应商已熔断，无可用渠道".to_string(),
         ProxyError::NoProvidersConfigured => "未配置供应商".to_string(),
+        ProxyError::BudgetExceeded(msg) => format!("超出消费限额: {msg}"),
+        ProxyError::RateLimited { reason, .. } => format!("触发速率限制: {reason}"),
         ProxyError::MaxRetriesExceeded => "所有 Provider 都失败，重试耗尽".to_string(),
         ProxyError::ProviderUnhealthy(msg) => format!("Provider 不健康: {msg}"),
         ProxyError::DatabaseError(msg) => format!("数据库错误: {msg}"),
@@ -73,6 +86,32 @@ pub fn get_error_message(error: &ProxyError) -> String {
     }
 }
 
+/// 上游瞬时错误状态码（同一供应商上值得重试）
+const TRANSIENT_STATUS_CODES: &[u16] = &[408, 429, 500, 502, 503, 504, 529];
+
+/// 判断错误是否值得在同一供应商上重试
+///
+/// 仅限瞬时错误：超时、连接失败以及 408/429/5xx 过载类状态码；
+/// 认证失败、参数错误等 4xx 重试无意义，直接交给故障转移。
+pub fn is_transient_error(error: &ProxyError) -> bool {
+    match error {
+        ProxyError::Timeout(_) | ProxyError::ForwardFailed(_) => true,
+        ProxyError::UpstreamError { status, .. } => TRANSIENT_STATUS_CODES.contains(status),
+        _ => false,
+    }
+}
+
+/// 上游要求的重试等待时间（来自 `retry-after` 头）
+pub fn retry_after_hint(error: &ProxyError) -> Option<std::time::Duration> {
+    match error {
+        ProxyError::UpstreamError {
+            retry_after: Some(secs),
+            ..
+        } => Some(std::time::Duration::from_secs(*secs)),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -82,6 +121,7 @@ mod tests {
         let error = ProxyError::UpstreamError {
             status: 401,
             body: Some("Unauthorized".to_string()),
+            retry_after: None,
         };
         assert_eq!(map_proxy_error_to_status(&error), 401);
     }
@@ -104,11 +144,48 @@ mod tests {
         assert_eq!(map_proxy_error_to_status(&error), 503);
     }
 
+    #[test]
+    fn test_map_budget_exceeded_error() {
+        let error = ProxyError::BudgetExceeded("daily".to_string());
+        assert_eq!(map_proxy_error_to_status(&error), 429);
+    }
+
+    #[test]
+    fn test_map_rate_limited_error() {
+        let error = ProxyError::RateLimited {
+            reason: "rpm".to_string(),
+            retry_after_secs: 5,
+        };
+        assert_eq!(map_proxy_error_to_status(&error), 429);
+    }
+
+    #[test]
+    fn test_transient_error_classification() {
+        let upstream = |status| ProxyError::UpstreamError {
+            status,
+            body: None,
+            retry_after: Some(2),
+        };
+        assert!(is_transient_error(&upstream(429)));
+        assert!(is_transient_error(&upstream(529)));
+        assert!(!is_transient_error(&upstream(400)));
+        assert!(!is_transient_error(&upstream(401)));
+        assert!(is_transient_error(&ProxyError::Timeout("t".to_string())));
+        assert!(!is_transient_error(&ProxyError::ConfigError(
+            "c".to_string()
+        )));
+        assert_eq!(
+            retry_after_hint(&upstream(429)),
+            Some(std::time::Duration::from_secs(2))
+        );
+    }
+
     #[test]
     fn test_get_error_message() {
         let error = ProxyError::UpstreamError {
             status: 500,
             body: Some("Internal Server Error".to_string()),
+            retry_after: None,
         };
         let msg = get_error_message(&error);
         assert!(msg.contains("上游错误"));
@@ -12,6 +12,7 @@ use crate::error::AppError;
 use std::collections::HashSet;
 use std::str::FromStr;
 use std::sync::Arc;
+#[cfg(feature = "gui")]
 use tauri::{Emitter, Manager};
 use tokio::sync::RwLock;
 
@@ -43,7 +44,7 @@ impl FailoverSwitchManager {
     /// - `Err(e)` - 切换过程中发生错误
     pub async fn try_switch(
         &self,
-        app_handle: Option<&tauri::AppHandle>,
+        app_handle: Option<&crate::AppHandle>,
         app_type: &str,
         provider_id: &str,
         provider_name: &str,
@@ -76,7 +77,7 @@ impl FailoverSwitchManager {
 
     async fn do_switch(
         &self,
-        app_handle: Option<&tauri::AppHandle>,
+        app_handle: Option<&crate::AppHandle>,
         app_type: &str,
         provider_id: &str,
         provider_name: &str,
@@ -108,40 +109,51 @@ impl FailoverSwitchManager {
 
         // 3. 更新托盘菜单和发射事件
         if let Some(app) = app_handle {
-            // 更新托盘菜单
-            if let Some(app_state) = app.try_state::<crate::store::AppState>() {
-                // 更新 Live 备份（确保代理停止时恢复正确配置）
-                if let Ok(Some(provider)) = self.db.get_provider_by_id(provider_id, app_type) {
-                    if let Err(e) = app_state
-                        .proxy_service
-                        .update_live_backup_from_provider(app_type, &provider)
-                        .await
-                    {
-                        log::warn!("[FO-003] Live 备份更新失败: {e}");
-                    }
+            self.refresh_ui(app, app_type, provider_id).await;
+        }
+
+        Ok(true)
+    }
+
+    /// 切换后更新 Live 备份、重建托盘菜单并通知前端
+    #[cfg(feature = "gui")]
+    async fn refresh_ui(&self, app: &crate::AppHandle, app_type: &str, provider_id: &str) {
+        // 更新托盘菜单
+        if let Some(app_state) = app.try_state::<crate::store::AppState>() {
+            // 更新 Live 备份（确保代理停止时恢复正确配置）
+            if let Ok(Some(provider)) = self.db.get_provider_by_id(provider_id, app_type) {
+                if let Err(e) = app_state
+                    .proxy_service
+                    .update_live_backup_from_provider(app_type, &provider)
+                    .await
+                {
+                    log::warn!("[FO-003] Live 备份更新失败: {e}");
                 }
+            }
 
-                // 重建托盘菜单
-                if let Ok(new_menu) = crate::tray::create_tray_menu(app, app_state.inner()) {
-                    if let Some(tray) = app.tray_by_id("main") {
-                        if let Err(e) = tray.set_menu(Some(new_menu)) {
-                            log::error!("[Failover] 更新托盘菜单失败: {e}");
-                        }
+            // 重建托盘菜单
+            if let Ok(new_menu) = crate::tray::create_tray_menu(app, app_state.inner()) {
+                if let Some(tray) = app.tray_by_id("main") {
+                    if let Err(e) = tray.set_menu(Some(new_menu)) {
+                        log::error!("[Failover] 更新托盘菜单失败: {e}");
                     }
                 }
             }
+        }
 
-            // 发射事件到前端
-            let event_data = serde_json::json!({
-                "appType": app_type,
-                "providerId": provider_id,
-                "source": "failover"  // 标识来源是故障转移
-            });
-            if let Err(e) = app.emit("provider-switched", event_data) {
-                log::error!("[Failover] 发射事件失败: {e}");
-            }
+        // 发射事件到前端
+        let event_data = serde_json::json!({
+            "appType": app_type,
+            "providerId": provider_id,
+            "source": "failover"  // 标识来源是故障转移
+        });
+        if let Err(e) = app.emit("provider-switched", event_data) {
+            log::error!("[Failover] 发射事件失败: {e}");
         }
+    }
 
-        Ok(true)
+    #[cfg(not(feature = "gui"))]
+    async fn refresh_ui(&self, app: &crate::AppHandle, _app_type: &str, _provider_id: &str) {
+        match *app {}
     }
 }
@@ -4,10 +4,18 @@
 
 use super::{
     body_filter::filter_private_params_with_whitelist,
+    capture::{PendingCapture, RequestCapturer},
     error::*,
+    error_mapper::{is_transient_error, retry_after_hint},
     failover_switch::FailoverSwitchManager,
-    provider_router::ProviderRouter,
-    providers::{get_adapter, AuthInfo, AuthStrategy, ProviderAdapter, ProviderType},
+    log_codes::fwd as log_fwd,
+    provider_router::{CandidateSource, FailoverOutcome, ProviderRouter},
+    providers::{
+        get_adapter, transform_gemini::gemini_endpoint, AuthInfo, AuthStrategy, ClaudeAdapter,
+        ProviderAdapter, ProviderType,
+    },
+    rate_limiter::{retry_after_secs, RateDecision, MAX_QUEUE_WAIT},
+    retry_policy::{parse_retry_after, RetryPolicy},
     thinking_budget_rectifier::{rectify_thinking_budget, should_rectify_thinking_budget},
     thinking_rectifier::{
         normalize_thinking_type, rectify_anthropic_request, should_rectify_thinking_signature,
@@ -15,12 +23,19 @@ use super::{
     types::{ProxyStatus, RectifierConfig},
     ProxyError,
 };
+#[cfg(feature = "gui")]
 use crate::commands::CopilotAuthState;
+use crate::proxy::load_balancer::InFlightGuard;
+#[cfg(feature = "gui")]
 use crate::proxy::providers::copilot_auth::CopilotAuthManager;
+use crate::proxy::providers::gemini_oauth::GeminiOAuthManager;
 use crate::{app_config::AppType, provider::Provider};
 use reqwest::Response;
 use serde_json::Value;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "gui")]
 use tauri::Manager;
 use tokio::sync::RwLock;
 
@@ -80,6 +95,8 @@ const HEADER_BLACKLIST: &[&str] = &[
 pub struct ForwardResult {
     pub response: Response,
     pub provider: Provider,
+    /// 进行中请求计数守卫，需在响应（含流式传输）结束前保持持有
+    pub in_flight: InFlightGuard,
 }
 
 pub struct ForwardError {
@@ -95,13 +112,21 @@ pub struct RequestForwarder {
     /// 故障转移切换管理器
     failover_manager: Arc<FailoverSwitchManager>,
     /// AppHandle，用于发射事件和更新托盘
-    app_handle: Option<tauri::AppHandle>,
+    app_handle: Option<crate::AppHandle>,
     /// 请求开始时的"当前供应商 ID"（用于判断是否需要同步 UI/托盘）
     current_provider_id_at_start: String,
+    /// 候选供应商列表的来源（负载均衡的顺序不会触发当前供应商切换）
+    candidate_source: CandidateSource,
     /// 整流器配置
     rectifier_config: RectifierConfig,
     /// 非流式请求超时（秒）
     non_streaming_timeout: std::time::Duration,
+    /// 同供应商重试策略
+    retry_policy: RetryPolicy,
+    /// 本次请求的上游请求总次数（含故障转移、同供应商重试与整流重试）
+    attempts: AtomicU32,
+    /// 请求抓包器（未开启抓包时为 None）
+    capture: Option<RequestCapturer>,
 }
 
 impl RequestForwarder {
@@ -112,11 +137,13 @@ impl RequestForwarder {
         status: Arc<RwLock<ProxyStatus>>,
         current_providers: Arc<RwLock<std::collections::HashMap<String, (String, String)>>>,
         failover_manager: Arc<FailoverSwitchManager>,
-        app_handle: Option<tauri::AppHandle>,
+        app_handle: Option<crate::AppHandle>,
         current_provider_id_at_start: String,
+        candidate_source: CandidateSource,
         _streaming_first_byte_timeout: u64,
         _streaming_idle_timeout: u64,
         rectifier_config: RectifierConfig,
+        retry_policy: RetryPolicy,
     ) -> Self {
         Self {
             router,
@@ -125,9 +152,58 @@ impl RequestForwarder {
             failover_manager,
             app_handle,
             current_provider_id_at_start,
+            candidate_source,
             rectifier_config,
             non_streaming_timeout: std::time::Duration::from_secs(non_streaming_timeout),
+            retry_policy,
+            attempts: AtomicU32::new(0),
+            capture: None,
+        }
+    }
+
+    /// 设置请求抓包器
+    pub fn with_capture(mut self, capture: Option<RequestCapturer>) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    /// 记录请求成功时的故障转移
+    ///
+    /// 仅在离开首选候选时计入故障转移次数；按队列顺序故障转移时才异步切换当前供应商，
+    /// 更新 UI/托盘，并把"当前供应商"同步为实际使用的 provider。
+    fn record_failover(
+        &self,
+        status: &mut ProxyStatus,
+        first_provider_id: &str,
+        provider: &Provider,
+        app_type_str: &str,
+    ) {
+        let outcome = self.candidate_source.failover_outcome(
+            first_provider_id,
+            &provider.id,
+            &self.current_provider_id_at_start,
+        );
+        if outcome == FailoverOutcome::None {
+            return;
         }
+        status.failover_count += 1;
+        if outcome != FailoverOutcome::Switch {
+            return;
+        }
+
+        let fm = self.failover_manager.clone();
+        let ah = self.app_handle.clone();
+        let pid = provider.id.clone();
+        let pname = provider.name.clone();
+        let at = app_type_str.to_string();
+        tokio::spawn(async move {
+            let _ = fm.try_switch(ah.as_ref(), &at, &pid, &pname).await;
+        });
+    }
+
+    /// 本次请求的上游请求总次数（含故障转移、同供应商重试与整流重试）
+    pub fn attempts(&self) -> u32 {
+        self.attempts.load(Ordering::Relaxed)
     }
 
     /// 转发请求（带故障转移）
@@ -160,6 +236,11 @@ impl RequestForwarder {
         let mut last_error = None;
         let mut last_provider = None;
         let mut attempted_providers = 0usize;
+        // 因超出消费限额被跳过的供应商说明
+        let mut budget_rejections: Vec<String> = Vec::new();
+        // 因触发 RPM/TPM 限制被跳过的供应商说明，以及最短等待时间
+        let mut rate_rejections: Vec<String> = Vec::new();
+        let mut min_retry_after: Option<Duration> = None;
 
         // 整流器重试标记：确保整流最多触发一次
         let mut rectifier_retried = false;
@@ -167,9 +248,22 @@ impl RequestForwarder {
 
         // 单 Provider 场景下跳过熔断器检查（故障转移关闭时）
         let bypass_circuit_breaker = providers.len() == 1;
+        let first_provider_id = providers[0].id.as_str();
 
         // 依次尝试每个供应商
-        for provider in providers.iter() {
+        for (index, provider) in providers.iter().enumerate() {
+            // 消费硬限额：选择供应商后并发请求仍可能把其推过限额，发起请求前再检查一次（纯内存）
+            let budget = self
+                .router
+                .check_provider_budget(provider, app_type_str)
+                .await;
+            if budget.is_exceeded() {
+                let reason = budget.describe(&provider.name);
+                log::info!("[{app_type_str}] 跳过已超出消费限额的供应商: {reason}");
+                budget_rejections.push(reason);
+                continue;
+            }
+
             // 发起请求前先获取熔断器放行许可（HalfOpen 会占用探测名额）
             // 单 Provider 场景下跳过此检查，避免熔断器阻塞所有请求
             let (allowed, used_half_open_permit) = if bypass_circuit_breaker {
@@ -186,7 +280,37 @@ impl RequestForwarder {
                 continue;
             }
 
+            // RPM/TPM 限制（熔断器放行后再扣减，被熔断的请求不消耗额度）：
+            // 还有后续供应商时直接故障转移，最后一个供应商允许短暂排队
+            let max_wait = if index + 1 == providers.len() {
+                MAX_QUEUE_WAIT
+            } else {
+                Duration::ZERO
+            };
+            if let RateDecision::Limited { retry_after } = self
+                .router
+                .acquire_provider_rate(provider, app_type_str, max_wait)
+                .await
+            {
+                log::info!(
+                    "[{app_type_str}] 跳过已触发速率限制的供应商: {} (需等待 {}ms)",
+                    provider.name,
+                    retry_after.as_millis()
+                );
+                // 熔断器放行许可未被使用，原样归还
+                self.router
+                    .release_permit_neutral(&provider.id, app_type_str, used_half_open_permit)
+                    .await;
+                rate_rejections.push(provider.name.clone());
+                min_retry_after = Some(min_retry_after.map_or(retry_after, |d| d.min(retry_after)));
+                continue;
+            }
+
             attempted_providers += 1;
+            let in_flight = self
+                .router
+                .begin_provider_request(&provider.id, app_type_str)
+                .await;
 
             // 更新状态中的当前Provider信息
             {
@@ -197,9 +321,16 @@ impl RequestForwarder {
                 status.last_request_at = Some(chrono::Utc::now().to_rfc3339());
             }
 
-            // 转发请求（每个 Provider 只尝试一次，重试由客户端控制）
+            // 转发请求（瞬时错误先在同一 Provider 上退避重试，仍失败再故障转移）
             match self
-                .forward(provider, endpoint, &body, &headers, adapter.as_ref())
+                .forward_with_backoff(
+                    provider,
+                    app_type_str,
+                    endpoint,
+                    &body,
+                    &headers,
+                    adapter.as_ref(),
+                )
                 .await
             {
                 Ok(response) => {
@@ -229,22 +360,12 @@ impl RequestForwarder {
                         let mut status = self.status.write().await;
                         status.success_requests += 1;
                         status.last_error = None;
-                        let should_switch =
-                            self.current_provider_id_at_start.as_str() != provider.id.as_str();
-                        if should_switch {
-                            status.failover_count += 1;
-
-                            // 异步触发供应商切换，更新 UI/托盘，并把“当前供应商”同步为实际使用的 provider
-                            let fm = self.failover_manager.clone();
-                            let ah = self.app_handle.clone();
-                            let pid = provider.id.clone();
-                            let pname = provider.name.clone();
-                            let at = app_type_str.to_string();
-
-                            tokio::spawn(async move {
-                                let _ = fm.try_switch(ah.as_ref(), &at, &pid, &pname).await;
-                            });
-                        }
+                        self.record_failover(
+                            &mut status,
+                            first_provider_id,
+                            provider,
+                            app_type_str,
+                        );
                         // 重新计算成功率
                         if status.total_requests > 0 {
                             status.success_rate = (status.success_requests as f32
@@ -256,6 +377,7 @@ impl RequestForwarder {
                     return Ok(ForwardResult {
                         response,
                         provider: provider.clone(),
+                        in_flight,
                     });
                 }
                 Err(e) => {
@@ -321,7 +443,14 @@ impl RequestForwarder {
 
                                 // 使用同一供应商重试（不计入熔断器）
                                 match self
-                                    .forward(provider, endpoint, &body, &headers, adapter.as_ref())
+                                    .forward(
+                                        provider,
+                                        app_type_str,
+                                        endpoint,
+                                        &body,
+                                        &headers,
+                                        adapter.as_ref(),
+                                    )
                                     .await
                                 {
                                     Ok(response) => {
@@ -353,25 +482,12 @@ impl RequestForwarder {
                                             let mut status = self.status.write().await;
                                             status.success_requests += 1;
                                             status.last_error = None;
-                                            let should_switch =
-                                                self.current_provider_id_at_start.as_str()
-                                                    != provider.id.as_str();
-                                            if should_switch {
-                                                status.failover_count += 1;
-
-                                                // 异步触发供应商切换，更新 UI/托盘
-                                                let fm = self.failover_manager.clone();
-                                                let ah = self.app_handle.clone();
-                                                let pid = provider.id.clone();
-                                                let pname = provider.name.clone();
-                                                let at = app_type_str.to_string();
-
-                                                tokio::spawn(async move {
-                                                    let _ = fm
-                                                        .try_switch(ah.as_ref(), &at, &pid, &pname)
-                                                        .await;
-                                                });
-                                            }
+                                            self.record_failover(
+                                                &mut status,
+                                                first_provider_id,
+                                                provider,
+                                                app_type_str,
+                                            );
                                             if status.total_requests > 0 {
                                                 status.success_rate = (status.success_requests
                                                     as f32
@@ -383,6 +499,7 @@ impl RequestForwarder {
                                         return Ok(ForwardResult {
                                             response,
                                             provider: provider.clone(),
+                                            in_flight,
                                         });
                                     }
                                     Err(retry_err) => {
@@ -512,7 +629,14 @@ impl RequestForwarder {
 
                             // 使用同一供应商重试（不计入熔断器）
                             match self
-                                .forward(provider, endpoint, &body, &headers, adapter.as_ref())
+                                .forward(
+                                    provider,
+                                    app_type_str,
+                                    endpoint,
+                                    &body,
+                                    &headers,
+                                    adapter.as_ref(),
+                                )
                                 .await
                             {
                                 Ok(response) => {
@@ -541,22 +665,12 @@ impl RequestForwarder {
                                         let mut status = self.status.write().await;
                                         status.success_requests += 1;
                                         status.last_error = None;
-                                        let should_switch =
-                                            self.current_provider_id_at_start.as_str()
-                                                != provider.id.as_str();
-                                        if should_switch {
-                                            status.failover_count += 1;
-                                            let fm = self.failover_manager.clone();
-                                            let ah = self.app_handle.clone();
-                                            let pid = provider.id.clone();
-                                            let pname = provider.name.clone();
-                                            let at = app_type_str.to_string();
-                                            tokio::spawn(async move {
-                                                let _ = fm
-                                                    .try_switch(ah.as_ref(), &at, &pid, &pname)
-                                                    .await;
-                                            });
-                                        }
+                                        self.record_failover(
+                                            &mut status,
+                                            first_provider_id,
+                                            provider,
+                                            app_type_str,
+                                        );
                                         if status.total_requests > 0 {
                                             status.success_rate = (status.success_requests as f32
                                                 / status.total_requests as f32)
@@ -567,6 +681,7 @@ impl RequestForwarder {
                                     return Ok(ForwardResult {
                                         response,
                                         provider: provider.clone(),
+                                        in_flight,
                                     });
                                 }
                                 Err(retry_err) => {
@@ -701,6 +816,45 @@ impl RequestForwarder {
             }
         }
 
+        if let (0, Some(retry_after)) = (attempted_providers, min_retry_after) {
+            // 未发起任何请求且存在限流供应商：返回带 retry-after 的 429
+            let reason = format!("供应商已触发速率限制: {}", rate_rejections.join(", "));
+            {
+                let mut status = self.status.write().await;
+                status.failed_requests += 1;
+                status.last_error = Some(reason.clone());
+                if status.total_requests > 0 {
+                    status.success_rate =
+                        (status.success_requests as f32 / status.total_requests as f32) * 100.0;
+                }
+            }
+            return Err(ForwardError {
+                error: ProxyError::RateLimited {
+                    reason,
+                    retry_after_secs: retry_after_secs(retry_after),
+                },
+                provider: None,
+            });
+        }
+
+        if attempted_providers == 0 && !budget_rejections.is_empty() {
+            // 未发起任何请求且存在超限供应商：返回 429 而不是 503，便于客户端识别
+            let reasons = budget_rejections.join("; ");
+            {
+                let mut status = self.status.write().await;
+                status.failed_requests += 1;
+                status.last_error = Some(format!("供应商已超出消费限额: {reasons}"));
+                if status.total_requests > 0 {
+                    status.success_rate =
+                        (status.success_requests as f32 / status.total_requests as f32) * 100.0;
+                }
+            }
+            return Err(ForwardError {
+                error: ProxyError::BudgetExceeded(reasons),
+                provider: None,
+            });
+        }
+
         if attempted_providers == 0 {
             // providers 列表非空，但全部被熔断器拒绝（典型：HalfOpen 探测名额被占用）
             {
@@ -737,44 +891,120 @@ impl RequestForwarder {
         })
     }
 
+    /// 在同一 Provider 上转发请求，瞬时错误按重试策略退避后重试（每次重试都占用 RPM 额度）
+    async fn forward_with_backoff(
+        &self,
+        provider: &Provider,
+        app_type: &str,
+        endpoint: &str,
+        body: &Value,
+        headers: &axum::http::HeaderMap,
+        adapter: &dyn ProviderAdapter,
+    ) -> Result<Response, ProxyError> {
+        let mut retry = 0u32;
+        let mut waited = Duration::ZERO;
+        loop {
+            let error = match self
+                .forward(provider, app_type, endpoint, body, headers, adapter)
+                .await
+            {
+                Ok(response) => return Ok(response),
+                Err(e) => e,
+            };
+            if !is_transient_error(&error) {
+                return Err(error);
+            }
+            let Some(delay) = self
+                .retry_policy
+                .delay_for(retry, waited, retry_after_hint(&error))
+            else {
+                return Err(error);
+            };
+
+            retry += 1;
+            waited += delay;
+            log::info!(
+                "[{}] Provider {} 瞬时错误，{}ms 后第 {}/{} 次重试: {}",
+                log_fwd::SAME_PROVIDER_RETRY,
+                provider.name,
+                delay.as_millis(),
+                retry,
+                self.retry_policy.max_retries,
+                error
+            );
+            tokio::time::sleep(delay).await;
+
+            // 每次重试同样占用供应商 RPM 额度；额度用尽时不再等待，交给故障转移
+            if let RateDecision::Limited { retry_after } = self
+                .router
+                .acquire_provider_rate(provider, app_type, Duration::ZERO)
+                .await
+            {
+                log::info!(
+                    "[{}] Provider {} 已触发速率限制（需等待 {}ms），停止重试",
+                    log_fwd::SAME_PROVIDER_RETRY,
+                    provider.name,
+                    retry_after.as_millis()
+                );
+                return Err(error);
+            }
+        }
+    }
+
     /// 转发单个请求（使用适配器）
     async fn forward(
         &self,
         provider: &Provider,
+        app_type: &str,
         endpoint: &str,
         body: &Value,
         headers: &axum::http::HeaderMap,
         adapter: &dyn ProviderAdapter,
     ) -> Result<Response, ProxyError> {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+
         // 使用适配器提取 base_url
         let base_url = adapter.extract_base_url(provider)?;
 
-        // 检查是否需要格式转换
-        let needs_transform = adapter.needs_transform(provider);
+        // 检查是否需要格式转换（适配器可声明只转换部分端点，其余端点保持透传）
+        let transform_endpoint = if adapter.needs_transform(provider) {
+            adapter.transform_endpoint(endpoint)
+        } else {
+            None
+        };
+        let needs_transform = transform_endpoint.is_some();
+
+        // 应用模型映射（独立于格式转换）
+        let (mapped_body, _original_model, _mapped_model) =
+            super::model_mapper::apply_model_mapping(body.clone(), provider);
+
+        // 与 CCH 对齐：请求前不做 thinking 主动改写（仅保留兼容入口）
+        let mapped_body = normalize_thinking_type(mapped_body);
+
+        // Claude 供应商使用 Gemini 原生接口（apiFormat = "gemini"）
+        let is_gemini_format = needs_transform
+            && adapter.name() == "Claude"
+            && ClaudeAdapter::new().get_api_format(provider) == "gemini";
 
         // 确定有效端点
         // GitHub Copilot API 使用 /chat/completions（无 /v1 前缀）
+        // Gemini 端点包含模型名和流式标记，需根据映射后的请求体生成
         let is_copilot = base_url.contains("githubcopilot.com");
         let effective_endpoint =
             if needs_transform && adapter.name() == "Claude" && endpoint == "/v1/messages" {
-                if is_copilot {
-                    "/chat/completions"
+                if is_gemini_format {
+                    gemini_endpoint(&mapped_body)
+                } else if is_copilot {
+                    "/chat/completions".to_string()
                 } else {
-                    "/v1/chat/completions"
+                    "/v1/chat/completions".to_string()
                 }
             } else {
-                endpoint
+                transform_endpoint.unwrap_or_else(|| endpoint.to_string())
             };
 
         // 使用适配器构建 URL
-        let url = adapter.build_url(&base_url, effective_endpoint);
-
-        // 应用模型映射（独立于格式转换）
-        let (mapped_body, _original_model, _mapped_model) =
-            super::model_mapper::apply_model_mapping(body.clone(), provider);
-
-        // 与 CCH 对齐：请求前不做 thinking 主动改写（仅保留兼容入口）
-        let mapped_body = normalize_thinking_type(mapped_body);
+        let url = adapter.build_url(&base_url, &effective_endpoint);
 
         // 转换请求体（如果需要）
         let request_body = if needs_transform {
@@ -807,13 +1037,17 @@ impl RequestForwarder {
             {
                 continue;
             }
+            // Gemini 上游不需要 Anthropic 专有 Header
+            if is_gemini_format && key.as_str().starts_with("anthropic-") {
+                continue;
+            }
             request = request.header(key, value);
         }
 
         // 处理 anthropic-beta Header（仅 Claude）
         // 关键：确保包含 claude-code-20250219 标记，这是上游服务验证请求来源的依据
         // 如果客户端发送的 beta 标记中没有包含 claude-code-20250219，需要补充
-        if adapter.name() == "Claude" {
+        if adapter.name() == "Claude" && !is_gemini_format {
             const CLAUDE_CODE_BETA: &str = "claude-code-20250219";
             let beta_value = if let Some(beta) = headers.get("anthropic-beta") {
                 if let Ok(beta_str) = beta.to_str() {
@@ -851,30 +1085,22 @@ impl RequestForwarder {
         request = request.header("accept-encoding", "identity");
 
         // 使用适配器添加认证头
+        // Gemini CLI OAuth：保留未带认证头的请求，用于 401 后刷新 token 重试
+        let mut oauth_retry: Option<(reqwest::RequestBuilder, AuthInfo)> = None;
         if let Some(mut auth) = adapter.extract_auth(provider) {
             // GitHub Copilot 特殊处理：从 CopilotAuthManager 获取真实 token
             if auth.strategy == AuthStrategy::GitHubCopilot {
-                if let Some(app_handle) = &self.app_handle {
-                    let copilot_state = app_handle.state::<CopilotAuthState>();
-                    let copilot_auth: tokio::sync::RwLockReadGuard<'_, CopilotAuthManager> =
-                        copilot_state.0.read().await;
-                    match copilot_auth.get_valid_token().await {
-                        Ok(token) => {
-                            auth = AuthInfo::new(token, AuthStrategy::GitHubCopilot);
-                            log::debug!("[Copilot] 成功获取 Copilot token");
-                        }
-                        Err(e) => {
-                            log::error!("[Copilot] 获取 Copilot token 失败: {e}");
-                            return Err(ProxyError::AuthError(format!(
-                                "GitHub Copilot 认证失败: {e}"
-                            )));
-                        }
-                    }
-                } else {
-                    log::error!("[Copilot] AppHandle 不可用");
-                    return Err(ProxyError::AuthError(
-                        "GitHub Copilot 认证不可用（无 AppHandle）".to_string(),
-                    ));
+                auth = AuthInfo::new(self.copilot_token().await?, AuthStrategy::GitHubCopilot);
+            }
+            if auth.strategy == AuthStrategy::GoogleOAuth {
+                auth = self
+                    .router
+                    .gemini_oauth()
+                    .resolve_auth(provider, app_type, auth, false)
+                    .await
+                    .map_err(|e| ProxyError::AuthError(format!("Gemini OAuth 认证失败: {e}")))?;
+                if GeminiOAuthManager::can_refresh(&auth) {
+                    oauth_retry = request.try_clone().map(|r| (r, auth.clone()));
                 }
             }
             request = adapter.add_auth_headers(request, &auth);
@@ -882,7 +1108,7 @@ impl RequestForwarder {
 
         // anthropic-version 统一处理（仅 Claude）：优先使用客户端的版本号，否则使用默认值
         // 注意：只设置一次，避免重复
-        if adapter.name() == "Claude" {
+        if adapter.name() == "Claude" && !is_gemini_format {
             let version_str = headers
                 .get("anthropic-version")
                 .and_then(|v| v.to_str().ok())
@@ -906,32 +1132,124 @@ impl RequestForwarder {
         }
 
         // 发送请求
-        let response = request.json(&filtered_body).send().await.map_err(|e| {
-            if e.is_timeout() {
-                ProxyError::Timeout(format!("请求超时: {e}"))
-            } else if e.is_connect() {
-                ProxyError::ForwardFailed(format!("连接失败: {e}"))
-            } else {
-                ProxyError::ForwardFailed(e.to_string())
+        let (mut response, mut capture) = self.send(provider, request.json(&filtered_body)).await?;
+
+        // Gemini CLI OAuth：access_token 被上游拒绝时强制刷新并重试一次
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            if let Some((retry_request, auth)) = oauth_retry {
+                match self
+                    .router
+                    .gemini_oauth()
+                    .resolve_auth(provider, app_type, auth, true)
+                    .await
+                {
+                    Ok(auth) => {
+                        log::info!("[{tag}] 上游返回 401，已刷新 OAuth token 并重试");
+                        (response, capture) = self
+                            .send(
+                                provider,
+                                adapter
+                                    .add_auth_headers(retry_request, &auth)
+                                    .json(&filtered_body),
+                            )
+                            .await?;
+                    }
+                    Err(e) => log::warn!("[{tag}] 上游返回 401，刷新 OAuth token 失败: {e}"),
+                }
             }
-        })?;
+        }
 
         // 检查响应状态
         let status = response.status();
 
         if status.is_success() {
-            Ok(response)
+            Ok(match capture {
+                Some(capture) => capture.wrap_response(response),
+                None => response,
+            })
         } else {
             let status_code = status.as_u16();
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_retry_after);
+            if let Some(capture) = capture.as_mut() {
+                capture.response_head(status_code, response.headers());
+            }
             let body_text = response.text().await.ok();
+            if let Some(capture) = capture {
+                capture.finish(body_text.as_deref().map(str::as_bytes), None);
+            }
 
             Err(ProxyError::UpstreamError {
                 status: status_code,
                 body: body_text,
+                retry_after,
             })
         }
     }
 
+    /// 从桌面端的 CopilotAuthManager 获取 Copilot token
+    #[cfg(feature = "gui")]
+    async fn copilot_token(&self) -> Result<String, ProxyError> {
+        let Some(app_handle) = &self.app_handle else {
+            log::error!("[Copilot] AppHandle 不可用");
+            return Err(ProxyError::AuthError(
+                "GitHub Copilot 认证不可用（无 AppHandle）".to_string(),
+            ));
+        };
+        let copilot_state = app_handle.state::<CopilotAuthState>();
+        let copilot_auth: tokio::sync::RwLockReadGuard<'_, CopilotAuthManager> =
+            copilot_state.0.read().await;
+        match copilot_auth.get_valid_token().await {
+            Ok(token) => {
+                log::debug!("[Copilot] 成功获取 Copilot token");
+                Ok(token)
+            }
+            Err(e) => {
+                log::error!("[Copilot] 获取 Copilot token 失败: {e}");
+                Err(ProxyError::AuthError(format!(
+                    "GitHub Copilot 认证失败: {e}"
+                )))
+            }
+        }
+    }
+
+    /// 无界面模式没有 Copilot 登录状态
+    #[cfg(not(feature = "gui"))]
+    async fn copilot_token(&self) -> Result<String, ProxyError> {
+        log::error!("[Copilot] 无界面模式不支持 Copilot 认证");
+        Err(ProxyError::AuthError(
+            "GitHub Copilot 认证仅在桌面应用中可用".to_string(),
+        ))
+    }
+
+    /// 发送上游请求；开启抓包时记录请求内容，发送失败时直接保存抓包记录
+    async fn send(
+        &self,
+        provider: &Provider,
+        request: reqwest::RequestBuilder,
+    ) -> Result<(Response, Option<PendingCapture>), ProxyError> {
+        let (client, request) = request.build_split();
+        let request = request.map_err(map_send_error)?;
+        let capture = self
+            .capture
+            .as_ref()
+            .map(|capturer| capturer.begin(&provider.id, &request));
+
+        match client.execute(request).await {
+            Ok(response) => Ok((response, capture)),
+            Err(e) => {
+                let error = map_send_error(e);
+                if let Some(capture) = capture {
+                    capture.finish(None, Some(error.to_string()));
+                }
+                Err(error)
+            }
+        }
+    }
+
     fn categorize_proxy_error(&self, error: &ProxyError) -> ErrorCategory {
         match error {
             // 网络和上游错误：都应该尝试下一个供应商
@@ -955,6 +1273,17 @@ impl RequestForwarder {
     }
 }
 
+/// 将发送请求时的 reqwest 错误映射为 ProxyError
+fn map_send_error(e: reqwest::Error) -> ProxyError {
+    if e.is_timeout() {
+        ProxyError::Timeout(format!("请求超时: {e}"))
+    } else if e.is_connect() {
+        ProxyError::ForwardFailed(format!("连接失败: {e}"))
+    } else {
+        ProxyError::ForwardFailed(e.to_string())
+    }
+}
+
 /// 从 ProxyError 中提取错误消息
 fn extract_error_message(error: &ProxyError) -> Option<String> {
     match error {
@@ -962,3 +1291,91 @@ fn extract_error_message(error: &ProxyError) -> Option<String> {
         _ => Some(error.to_string()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::provider::ProviderMeta;
+    use axum::{http::StatusCode, routing::post, Router};
+    use serde_json::json;
+    use std::sync::atomic::AtomicUsize;
+
+    /// 总是返回 503 的上游，返回地址与调用计数
+    async fn unavailable_upstream() -> (String, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let app = Router::new().route(
+            "/v1/messages",
+            post(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async { (StatusCode::SERVICE_UNAVAILABLE, "overloaded") }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.ok();
+        });
+        (format!("http://{addr}"), calls)
+    }
+
+    #[tokio::test]
+    async fn test_same_provider_retries_respect_provider_rpm() {
+        let db = Arc::new(Database::memory().unwrap());
+        let (base_url, calls) = unavailable_upstream().await;
+        let mut provider = Provider::with_id(
+            "p1".to_string(),
+            "Relay".to_string(),
+            json!({ "env": { "ANTHROPIC_BASE_URL": base_url, "ANTHROPIC_AUTH_TOKEN": "sk-test" } }),
+            None,
+        );
+        provider.meta = Some(ProviderMeta {
+            rate_limit_rpm: Some(1),
+            ..Default::default()
+        });
+
+        let router = Arc::new(ProviderRouter::new(db.clone()));
+        let forwarder = RequestForwarder::new(
+            router.clone(),
+            30,
+            Arc::new(RwLock::new(ProxyStatus::default())),
+            Arc::new(RwLock::new(std::collections::HashMap::new())),
+            Arc::new(FailoverSwitchManager::new(db)),
+            None,
+            "p1".to_string(),
+            CandidateSource::default(),
+            0,
+            0,
+            RectifierConfig::default(),
+            RetryPolicy {
+                max_retries: 3,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(1),
+                ..RetryPolicy::default()
+            },
+        );
+
+        // 首次请求的额度由故障转移循环占用
+        assert!(matches!(
+            router
+                .acquire_provider_rate(&provider, "claude", Duration::ZERO)
+                .await,
+            RateDecision::Allowed
+        ));
+        let err = forwarder
+            .forward_with_backoff(
+                &provider,
+                "claude",
+                "/v1/messages",
+                &json!({ "model": "claude-3", "messages": [] }),
+                &axum::http::HeaderMap::new(),
+                &ClaudeAdapter::new(),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, ProxyError::UpstreamError { status: 503, .. }));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -5,9 +5,15 @@
 use crate::app_config::AppType;
 use crate::provider::Provider;
 use crate::proxy::{
+    capture::RequestCapturer,
+    client_auth::ClientIdentity,
     extract_session_id,
-    forwarder::RequestForwarder,
+    forwarder::{ForwardResult, RequestForwarder},
+    load_balancer::InFlightGuard,
+    provider_router::CandidateSource,
+    retry_policy::RetryPolicy,
     server::ProxyState,
+    session_affinity::AffinityDecision,
     types::{AppProxyConfig, RectifierConfig},
     ProxyError,
 };
@@ -33,6 +39,8 @@ pub struct StreamingTimeoutConfig {
 /// - 日志标签
 /// - Session ID（用于日志关联）
 pub struct RequestContext {
+    /// 请求 ID（写入请求日志与抓包记录，用于关联两者）
+    pub request_id: String,
     /// 请求开始时间
     pub start_time: Instant,
     /// 应用级代理配置（per-app，包含重试次数和超时配置）
@@ -41,6 +49,8 @@ pub struct RequestContext {
     pub provider: Provider,
     /// 完整的 Provider 列表（用于故障转移）
     providers: Vec<Provider>,
+    /// Provider 列表的来源（决定故障转移成功后是否切换当前供应商）
+    pub candidate_source: CandidateSource,
     /// 请求开始时的"当前供应商"（用于判断是否需要同步 UI/托盘）
     ///
     /// 这里使用本地 settings 的设备级 current provider。
@@ -59,6 +69,14 @@ pub struct RequestContext {
     pub session_id: String,
     /// 整流器配置
     pub rectifier_config: RectifierConfig,
+    /// 实际处理请求的 Provider 的进行中计数守卫（转发成功后设置）
+    pub in_flight: Option<InFlightGuard>,
+    /// 会话粘性决策（未启用会话粘性或 Session ID 为新生成时为 None）
+    pub session_affinity: Option<AffinityDecision>,
+    /// 客户端身份（客户端未携带代理签发的令牌时为 None）
+    pub client: Option<ClientIdentity>,
+    /// 本次请求的上游请求总次数（含故障转移与同供应商重试）
+    pub attempts: u32,
 }
 
 impl RequestContext {
@@ -81,6 +99,38 @@ impl RequestContext {
         app_type: AppType,
         tag: &'static str,
         app_type_str: &'static str,
+    ) -> Result<Self, ProxyError> {
+        // 从请求体提取模型名称
+        let request_model = body
+            .get("model")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown")
+            .to_string();
+
+        Self::with_request_model(
+            state,
+            body,
+            headers,
+            app_type,
+            tag,
+            app_type_str,
+            request_model,
+        )
+        .await
+    }
+
+    /// 使用指定的模型名称创建请求上下文
+    ///
+    /// 模型名称不在请求体中时使用（如 Gemini 的模型在 URI 中），
+    /// 模型路由规则按该名称匹配。
+    pub async fn with_request_model(
+        state: &ProxyState,
+        body: &serde_json::Value,
+        headers: &HeaderMap,
+        app_type: AppType,
+        tag: &'static str,
+        app_type_str: &'static str,
+        request_model: String,
     ) -> Result<Self, ProxyError> {
         let start_time = Instant::now();
 
@@ -97,13 +147,6 @@ impl RequestContext {
         let current_provider_id =
             crate::settings::get_current_provider(&app_type).unwrap_or_default();
 
-        // 从请求体提取模型名称
-        let request_model = body
-            .get("model")
-            .and_then(|m| m.as_str())
-            .unwrap_or("unknown")
-            .to_string();
-
         // 提取 Session ID
         let session_result = extract_session_id(headers, body, app_type_str);
         let session_id = session_result.session_id.clone();
@@ -117,19 +160,42 @@ impl RequestContext {
         );
 
         // 使用共享的 ProviderRouter 选择 Provider（熔断器状态跨请求保持）
+        // 请求模型命中模型路由规则时，使用规则中的供应商列表
         // 注意：只在这里调用一次，结果传递给 forwarder，避免重复消耗 HalfOpen 名额
-        let providers = state
+        let candidates = state
             .provider_router
-            .select_providers(app_type_str)
+            .select_providers_for_model(app_type_str, &request_model)
             .await
             .map_err(|e| match e {
                 crate::error::AppError::AllProvidersCircuitOpen => {
                     ProxyError::AllProvidersCircuitOpen
                 }
                 crate::error::AppError::NoProvidersConfigured => ProxyError::NoProvidersConfigured,
+                crate::error::AppError::ProviderBudgetExceeded(reason) => {
+                    ProxyError::BudgetExceeded(reason)
+                }
                 _ => ProxyError::DatabaseError(e.to_string()),
             })?;
 
+        // 会话粘性：仅对客户端提供的 Session ID 生效（新生成的 ID 每次都不同）
+        let (providers, session_affinity) = if app_config.auto_failover_enabled
+            && app_config.session_affinity_enabled
+            && session_result.client_provided
+        {
+            let (providers, decision) = state
+                .provider_router
+                .apply_session_affinity(
+                    app_type_str,
+                    &session_id,
+                    app_config.session_affinity_ttl_seconds,
+                    candidates.providers,
+                )
+                .await;
+            (providers, Some(decision))
+        } else {
+            (candidates.providers, None)
+        };
+
         let provider = providers
             .first()
             .cloned()
@@ -145,10 +211,12 @@ impl RequestContext {
         );
 
         Ok(Self {
+            request_id: uuid::Uuid::new_v4().to_string(),
             start_time,
             app_config,
             provider,
             providers,
+            candidate_source: candidates.source,
             current_provider_id,
             request_model,
             tag,
@@ -156,28 +224,36 @@ impl RequestContext {
             app_type,
             session_id,
             rectifier_config,
+            in_flight: None,
+            session_affinity,
+            client: None,
+            attempts: 1,
         })
     }
 
+    /// 关联客户端身份（由客户端认证中间件写入请求扩展）
+    pub fn with_client(mut self, client: Option<ClientIdentity>) -> Self {
+        self.client = client;
+        self
+    }
+
     /// 从 URI 提取模型名称（Gemini 专用）
     ///
     /// Gemini API 的模型名称在 URI 中，格式如：
     /// `/v1beta/models/gemini-pro:generateContent`
-    pub fn with_model_from_uri(mut self, uri: &axum::http::Uri) -> Self {
+    pub fn model_from_uri(uri: &axum::http::Uri) -> String {
         let endpoint = uri
             .path_and_query()
             .map(|pq| pq.as_str())
             .unwrap_or(uri.path());
 
-        self.request_model = endpoint
+        endpoint
             .split('/')
             .find(|s| s.starts_with("models/"))
             .and_then(|s| s.strip_prefix("models/"))
             .map(|s| s.split(':').next().unwrap_or(s))
             .unwrap_or("unknown")
-            .to_string();
-
-        self
+            .to_string()
     }
 
     /// 创建 RequestForwarder
@@ -213,10 +289,46 @@ impl RequestContext {
             state.failover_manager.clone(),
             state.app_handle.clone(),
             self.current_provider_id.clone(),
+            self.candidate_source,
             first_byte_timeout,
             idle_timeout,
             self.rectifier_config.clone(),
+            RetryPolicy::from_app_config(&self.app_config),
         )
+        .with_capture(RequestCapturer::from_settings(
+            &state.db,
+            &self.request_id,
+            self.app_type_str,
+        ))
+    }
+
+    /// 接收转发成功的结果
+    ///
+    /// 更新实际服务请求的 Provider，并在启用会话粘性时将会话绑定到该 Provider。
+    /// 若请求已故障转移到非首选 Provider，会话粘性决策记为 Fallback。
+    pub async fn accept_forward_result(
+        &mut self,
+        state: &ProxyState,
+        result: ForwardResult,
+    ) -> reqwest::Response {
+        if let Some(decision) = self.session_affinity {
+            if decision == AffinityDecision::Hit && result.provider.id != self.provider.id {
+                self.session_affinity = Some(AffinityDecision::Fallback);
+            }
+            state
+                .provider_router
+                .bind_session(
+                    self.app_type_str,
+                    &self.session_id,
+                    &result.provider.id,
+                    self.app_config.session_affinity_ttl_seconds,
+                )
+                .await;
+        }
+
+        self.provider = result.provider;
+        self.in_flight = Some(result.in_flight);
+        result.response
     }
 
     /// 获取 Provider 列表（用于故障转移）
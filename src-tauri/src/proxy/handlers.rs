@@ -5,24 +5,40 @@
 //! 重构后的结构：
 //! - 通用逻辑提取到 `handler_context` 和 `response_processor` 模块
 //! - 各 handler 只保留独特的业务逻辑
-//! - Claude 的格式转换逻辑保留在此文件（用于 OpenRouter 旧接口回退）
+//! - Claude 的格式转换逻辑保留在此文件（用于 OpenRouter 旧接口回退及 Gemini 原生接口）
+//! - Codex 的 Responses ↔ Chat 转换逻辑同样保留在此文件（`api_format = "openai_chat"`）
 
 use super::{
+    client_auth::ClientIdentity,
     error_mapper::{get_error_message, map_proxy_error_to_status},
     handler_config::{
         CLAUDE_PARSER_CONFIG, CODEX_PARSER_CONFIG, GEMINI_PARSER_CONFIG, OPENAI_PARSER_CONFIG,
     },
     handler_context::RequestContext,
-    providers::{get_adapter, streaming::create_anthropic_sse_stream, transform},
-    response_processor::{create_logged_passthrough_stream, process_response, SseUsageCollector},
+    load_balancer::InFlightStream,
+    metrics::{self, RequestObservation},
+    model_catalog::CatalogModel,
+    providers::{
+        get_adapter, streaming::create_anthropic_sse_stream,
+        streaming_gemini::create_anthropic_sse_stream_from_gemini,
+        streaming_responses::create_responses_sse_stream, transform, transform_gemini,
+        transform_responses, ClaudeAdapter,
+    },
+    response_processor::{
+        create_logged_passthrough_stream, create_usage_collector, log_usage_internal,
+        process_response, spawn_log_usage, SseUsageCollector,
+    },
     server::ProxyState,
     types::*,
     usage::parser::TokenUsage,
     ProxyError,
 };
 use crate::app_config::AppType;
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Extension, Json};
+use bytes::Bytes;
+use futures::Stream;
 use serde_json::{json, Value};
+use std::pin::Pin;
 
 // ============================================================================
 // 健康检查和状态查询（简单端点）
@@ -41,10 +57,99 @@ pub async fn health_check() -> (StatusCode, Json<Value>) {
 
 /// 获取服务状态
 pub async fn get_status(State(state): State<ProxyState>) -> Result<Json<ProxyStatus>, ProxyError> {
-    let status = state.status.read().await.clone();
+    let mut status = state.status.read().await.clone();
+    status.rate_limits = state.provider_router.rate_limit_levels().await;
     Ok(Json(status))
 }
 
+/// Prometheus 指标（文本格式）
+pub async fn get_metrics(State(state): State<ProxyState>) -> impl IntoResponse {
+    let mut status = state.status.read().await.clone();
+    if let Some(start) = *state.start_time.read().await {
+        status.uptime_seconds = start.elapsed().as_secs();
+    }
+    let in_flight = state.provider_router.in_flight_counts().await;
+    let circuits = state.provider_router.circuit_states().await;
+    let body = state.metrics.render(&status, &in_flight, &circuits);
+    (
+        [(axum::http::header::CONTENT_TYPE, metrics::CONTENT_TYPE)],
+        body,
+    )
+}
+
+// ============================================================================
+// 模型列表
+// ============================================================================
+
+/// 处理 /v1/models 请求
+///
+/// 携带 `anthropic-version` 头的请求视为 Claude 客户端，返回 Anthropic 格式的 Claude 模型；
+/// 其余请求返回 OpenAI 格式，合并所有应用的可用模型。
+pub async fn handle_models(
+    State(state): State<ProxyState>,
+    headers: axum::http::HeaderMap,
+) -> Json<Value> {
+    if headers.contains_key("anthropic-version") {
+        let models = state.model_catalog.list_models(&[AppType::Claude]).await;
+        return Json(anthropic_model_list(&models));
+    }
+
+    let models = state
+        .model_catalog
+        .list_models(&[AppType::Claude, AppType::Codex, AppType::Gemini])
+        .await;
+    Json(openai_model_list(&models))
+}
+
+/// 处理 /claude/v1/models 请求（Anthropic 格式）
+pub async fn handle_claude_models(State(state): State<ProxyState>) -> Json<Value> {
+    let models = state.model_catalog.list_models(&[AppType::Claude]).await;
+    Json(anthropic_model_list(&models))
+}
+
+/// 处理 /codex/v1/models 请求（OpenAI 格式）
+pub async fn handle_codex_models(State(state): State<ProxyState>) -> Json<Value> {
+    let models = state.model_catalog.list_models(&[AppType::Codex]).await;
+    Json(openai_model_list(&models))
+}
+
+fn openai_model_list(models: &[CatalogModel]) -> Value {
+    let created = chrono::Utc::now().timestamp();
+    let data: Vec<Value> = models
+        .iter()
+        .map(|model| {
+            json!({
+                "id": model.id,
+                "object": "model",
+                "created": created,
+                "owned_by": model.owned_by,
+            })
+        })
+        .collect();
+    json!({ "object": "list", "data": data })
+}
+
+fn anthropic_model_list(models: &[CatalogModel]) -> Value {
+    let created_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let data: Vec<Value> = models
+        .iter()
+        .map(|model| {
+            json!({
+                "type": "model",
+                "id": model.id,
+                "display_name": model.id,
+                "created_at": created_at,
+            })
+        })
+        .collect();
+    json!({
+        "data": data,
+        "has_more": false,
+        "first_id": models.first().map(|m| m.id.as_str()),
+        "last_id": models.last().map(|m| m.id.as_str()),
+    })
+}
+
 // ============================================================================
 // Claude API 处理器（包含格式转换逻辑）
 // ============================================================================
@@ -56,11 +161,13 @@ pub async fn get_status(State(state): State<ProxyState>) -> Result<Json<ProxySta
 /// - 现在 OpenRouter 已推出 Claude Code 兼容接口，默认不再启用该转换（逻辑保留以备回退）
 pub async fn handle_messages(
     State(state): State<ProxyState>,
+    client: Option<Extension<ClientIdentity>>,
     headers: axum::http::HeaderMap,
     Json(body): Json<Value>,
 ) -> Result<axum::response::Response, ProxyError> {
-    let mut ctx =
-        RequestContext::new(&state, &body, &headers, AppType::Claude, "Claude", "claude").await?;
+    let mut ctx = RequestContext::new(&state, &body, &headers, AppType::Claude, "Claude", "claude")
+        .await?
+        .with_client(client.map(|Extension(identity)| identity));
 
     let is_stream = body
         .get("stream")
@@ -69,7 +176,7 @@ pub async fn handle_messages(
 
     // 转发请求
     let forwarder = ctx.create_forwarder(&state);
-    let result = match forwarder
+    let outcome = forwarder
         .forward_with_retry(
             &AppType::Claude,
             "/v1/messages",
@@ -77,8 +184,9 @@ pub async fn handle_messages(
             headers,
             ctx.get_providers(),
         )
-        .await
-    {
+        .await;
+    ctx.attempts = forwarder.attempts();
+    let result = match outcome {
         Ok(result) => result,
         Err(mut err) => {
             if let Some(provider) = err.provider.take() {
@@ -89,8 +197,7 @@ pub async fn handle_messages(
         }
     };
 
-    ctx.provider = result.provider;
-    let response = result.response;
+    let response = ctx.accept_forward_result(&state, result).await;
 
     // 检查是否需要格式转换（OpenRouter 等中转服务）
     let adapter = get_adapter(&AppType::Claude);
@@ -107,7 +214,8 @@ pub async fn handle_messages(
 
 /// Claude 格式转换处理（独有逻辑）
 ///
-/// 处理 OpenRouter 旧 OpenAI 兼容接口的回退方案（当前默认不启用）
+/// - OpenAI Chat Completions：OpenRouter 旧 OpenAI 兼容接口的回退方案（当前默认不启用）
+/// - Gemini：`apiFormat = "gemini"` 的供应商直连 Gemini 原生接口
 async fn handle_claude_transform(
     response: reqwest::Response,
     ctx: &RequestContext,
@@ -116,11 +224,18 @@ async fn handle_claude_transform(
     is_stream: bool,
 ) -> Result<axum::response::Response, ProxyError> {
     let status = response.status();
+    let is_gemini = ClaudeAdapter::new().get_api_format(&ctx.provider) == "gemini";
 
     if is_stream {
-        // 流式响应转换 (OpenAI SSE → Anthropic SSE)
-        let stream = response.bytes_stream();
-        let sse_stream = create_anthropic_sse_stream(stream);
+        // 流式响应转换 (OpenAI / Gemini SSE → Anthropic SSE)
+        // 字节流持有进行中计数守卫，直到流结束
+        let stream = InFlightStream::new(response.bytes_stream(), ctx.in_flight.clone());
+        let sse_stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>> =
+            if is_gemini {
+                Box::pin(create_anthropic_sse_stream_from_gemini(stream))
+            } else {
+                Box::pin(create_anthropic_sse_stream(stream))
+            };
 
         // 创建使用量收集器
         let usage_collector = {
@@ -129,6 +244,11 @@ async fn handle_claude_transform(
             let model = ctx.request_model.clone();
             let status_code = status.as_u16();
             let start_time = ctx.start_time;
+            let client = ctx.client.clone();
+            let attempts = ctx.attempts;
+            let request_id = ctx.request_id.clone();
+            let session_id = ctx.session_id.clone();
+            let session_affinity = ctx.session_affinity;
 
             SseUsageCollector::new(start_time, move |events, first_token_ms| {
                 if let Some(usage) = TokenUsage::from_claude_stream_events(&events) {
@@ -136,10 +256,14 @@ async fn handle_claude_transform(
                     let state = state.clone();
                     let provider_id = provider_id.clone();
                     let model = model.clone();
+                    let client = client.clone();
+                    let request_id = request_id.clone();
+                    let session_id = session_id.clone();
 
                     tokio::spawn(async move {
-                        log_usage(
+                        log_usage_internal(
                             &state,
+                            request_id,
                             &provider_id,
                             "claude",
                             &model,
@@ -149,6 +273,10 @@ async fn handle_claude_transform(
                             first_token_ms,
                             true,
                             status_code,
+                            Some(session_id),
+                            session_affinity,
+                            client,
+                            attempts,
                         )
                         .await;
                     });
@@ -163,7 +291,11 @@ async fn handle_claude_transform(
 
         let logged_stream = create_logged_passthrough_stream(
             sse_stream,
-            "Claude/OpenRouter",
+            if is_gemini {
+                "Claude/Gemini"
+            } else {
+                "Claude/OpenRouter"
+            },
             Some(usage_collector),
             timeout_config,
         );
@@ -186,7 +318,7 @@ async fn handle_claude_transform(
         return Ok((headers, body).into_response());
     }
 
-    // 非流式响应转换 (OpenAI → Anthropic)
+    // 非流式响应转换 (OpenAI / Gemini → Anthropic)
     let response_headers = response.headers().clone();
 
     let body_bytes = response.bytes().await.map_err(|e| {
@@ -196,12 +328,17 @@ async fn handle_claude_transform(
 
     let body_str = String::from_utf8_lossy(&body_bytes);
 
-    let openai_response: Value = serde_json::from_slice(&body_bytes).map_err(|e| {
-        log::error!("[Claude] 解析 OpenAI 响应失败: {e}, body: {body_str}");
-        ProxyError::TransformError(format!("Failed to parse OpenAI response: {e}"))
+    let upstream_response: Value = serde_json::from_slice(&body_bytes).map_err(|e| {
+        log::error!("[Claude] 解析上游响应失败: {e}, body: {body_str}");
+        ProxyError::TransformError(format!("Failed to parse upstream response: {e}"))
     })?;
 
-    let anthropic_response = transform::openai_to_anthropic(openai_response).map_err(|e| {
+    let anthropic_response = if is_gemini {
+        transform_gemini::gemini_to_anthropic(upstream_response)
+    } else {
+        transform::openai_to_anthropic(upstream_response)
+    }
+    .map_err(|e| {
         log::error!("[Claude] 转换响应失败: {e}");
         e
     })?;
@@ -219,9 +356,15 @@ async fn handle_claude_transform(
             let state = state.clone();
             let provider_id = ctx.provider.id.clone();
             let model = model.to_string();
+            let client = ctx.client.clone();
+            let attempts = ctx.attempts;
+            let request_id = ctx.request_id.clone();
+            let session_id = ctx.session_id.clone();
+            let session_affinity = ctx.session_affinity;
             async move {
-                log_usage(
+                log_usage_internal(
                     &state,
+                    request_id,
                     &provider_id,
                     "claude",
                     &model,
@@ -231,6 +374,10 @@ async fn handle_claude_transform(
                     None,
                     false,
                     status.as_u16(),
+                    Some(session_id),
+                    session_affinity,
+                    client,
+                    attempts,
                 )
                 .await;
             }
@@ -269,11 +416,13 @@ async fn handle_claude_transform(
 /// 处理 /v1/chat/completions 请求（OpenAI Chat Completions API - Codex CLI）
 pub async fn handle_chat_completions(
     State(state): State<ProxyState>,
+    client: Option<Extension<ClientIdentity>>,
     headers: axum::http::HeaderMap,
     Json(body): Json<Value>,
 ) -> Result<axum::response::Response, ProxyError> {
-    let mut ctx =
-        RequestContext::new(&state, &body, &headers, AppType::Codex, "Codex", "codex").await?;
+    let mut ctx = RequestContext::new(&state, &body, &headers, AppType::Codex, "Codex", "codex")
+        .await?
+        .with_client(client.map(|Extension(identity)| identity));
 
     let is_stream = body
         .get("stream")
@@ -281,7 +430,7 @@ pub async fn handle_chat_completions(
         .unwrap_or(false);
 
     let forwarder = ctx.create_forwarder(&state);
-    let result = match forwarder
+    let outcome = forwarder
         .forward_with_retry(
             &AppType::Codex,
             "/chat/completions",
@@ -289,8 +438,9 @@ pub async fn handle_chat_completions(
             headers,
             ctx.get_providers(),
         )
-        .await
-    {
+        .await;
+    ctx.attempts = forwarder.attempts();
+    let result = match outcome {
         Ok(result) => result,
         Err(mut err) => {
             if let Some(provider) = err.provider.take() {
@@ -301,8 +451,7 @@ pub async fn handle_chat_completions(
         }
     };
 
-    ctx.provider = result.provider;
-    let response = result.response;
+    let response = ctx.accept_forward_result(&state, result).await;
 
     process_response(response, &ctx, &state, &OPENAI_PARSER_CONFIG).await
 }
@@ -310,11 +459,13 @@ pub async fn handle_chat_completions(
 /// 处理 /v1/responses 请求（OpenAI Responses API - Codex CLI 透传）
 pub async fn handle_responses(
     State(state): State<ProxyState>,
+    client: Option<Extension<ClientIdentity>>,
     headers: axum::http::HeaderMap,
     Json(body): Json<Value>,
 ) -> Result<axum::response::Response, ProxyError> {
-    let mut ctx =
-        RequestContext::new(&state, &body, &headers, AppType::Codex, "Codex", "codex").await?;
+    let mut ctx = RequestContext::new(&state, &body, &headers, AppType::Codex, "Codex", "codex")
+        .await?
+        .with_client(client.map(|Extension(identity)| identity));
 
     let is_stream = body
         .get("stream")
@@ -322,7 +473,7 @@ pub async fn handle_responses(
         .unwrap_or(false);
 
     let forwarder = ctx.create_forwarder(&state);
-    let result = match forwarder
+    let outcome = forwarder
         .forward_with_retry(
             &AppType::Codex,
             "/responses",
@@ -330,8 +481,9 @@ pub async fn handle_responses(
             headers,
             ctx.get_providers(),
         )
-        .await
-    {
+        .await;
+    ctx.attempts = forwarder.attempts();
+    let result = match outcome {
         Ok(result) => result,
         Err(mut err) => {
             if let Some(provider) = err.provider.take() {
@@ -342,12 +494,133 @@ pub async fn handle_responses(
         }
     };
 
-    ctx.provider = result.provider;
-    let response = result.response;
+    let response = ctx.accept_forward_result(&state, result).await;
+
+    // Codex 特有：Chat Completions 中转服务需要格式转换
+    let adapter = get_adapter(&AppType::Codex);
+    if adapter.needs_transform(&ctx.provider) {
+        return handle_codex_transform(response, &ctx, &state, is_stream).await;
+    }
 
     process_response(response, &ctx, &state, &CODEX_PARSER_CONFIG).await
 }
 
+/// Codex 格式转换处理
+///
+/// 上游返回 Chat Completions 格式，转换回 Responses 格式后再交给客户端
+async fn handle_codex_transform(
+    response: reqwest::Response,
+    ctx: &RequestContext,
+    state: &ProxyState,
+    is_stream: bool,
+) -> Result<axum::response::Response, ProxyError> {
+    let status = response.status();
+
+    // 上游错误响应原样透传
+    if !status.is_success() {
+        return process_response(response, ctx, state, &CODEX_PARSER_CONFIG).await;
+    }
+
+    if is_stream {
+        // 流式响应转换 (Chat SSE → Responses SSE)
+        // 字节流持有进行中计数守卫，直到流结束
+        let stream = InFlightStream::new(response.bytes_stream(), ctx.in_flight.clone());
+        let sse_stream = create_responses_sse_stream(stream);
+
+        // 转换后的 response.completed 事件携带 usage，可直接复用 Codex 解析配置
+        let usage_collector =
+            create_usage_collector(ctx, state, status.as_u16(), &CODEX_PARSER_CONFIG);
+
+        let logged_stream = create_logged_passthrough_stream(
+            sse_stream,
+            "Codex/Chat",
+            Some(usage_collector),
+            ctx.streaming_timeout_config(),
+        );
+
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(
+            "Content-Type",
+            axum::http::HeaderValue::from_static("text/event-stream"),
+        );
+        headers.insert(
+            "Cache-Control",
+            axum::http::HeaderValue::from_static("no-cache"),
+        );
+        headers.insert(
+            "Connection",
+            axum::http::HeaderValue::from_static("keep-alive"),
+        );
+
+        let body = axum::body::Body::from_stream(logged_stream);
+        return Ok((headers, body).into_response());
+    }
+
+    // 非流式响应转换 (Chat → Responses)
+    let response_headers = response.headers().clone();
+
+    let body_bytes = response.bytes().await.map_err(|e| {
+        log::error!("[Codex] 读取响应体失败: {e}");
+        ProxyError::ForwardFailed(format!("Failed to read response body: {e}"))
+    })?;
+
+    let chat_response: Value = serde_json::from_slice(&body_bytes).map_err(|e| {
+        log::error!(
+            "[Codex] 解析 Chat Completions 响应失败: {e}, body: {}",
+            String::from_utf8_lossy(&body_bytes)
+        );
+        ProxyError::TransformError(format!("Failed to parse Chat Completions response: {e}"))
+    })?;
+
+    let responses_response =
+        transform_responses::chat_to_responses(chat_response).map_err(|e| {
+            log::error!("[Codex] 转换响应失败: {e}");
+            e
+        })?;
+
+    // 记录使用量
+    if let Some(usage) = TokenUsage::from_codex_response_auto(&responses_response) {
+        let model = responses_response
+            .get("model")
+            .and_then(|m| m.as_str())
+            .filter(|m| !m.is_empty())
+            .unwrap_or(&ctx.request_model);
+        spawn_log_usage(
+            state,
+            ctx,
+            usage,
+            model,
+            &ctx.request_model,
+            status.as_u16(),
+            false,
+        );
+    }
+
+    // 构建响应
+    let mut builder = axum::response::Response::builder().status(status);
+
+    for (key, value) in response_headers.iter() {
+        if key.as_str().to_lowercase() != "content-length"
+            && key.as_str().to_lowercase() != "transfer-encoding"
+        {
+            builder = builder.header(key, value);
+        }
+    }
+
+    builder = builder.header("content-type", "application/json");
+
+    let response_body = serde_json::to_vec(&responses_response).map_err(|e| {
+        log::error!("[Codex] 序列化响应失败: {e}");
+        ProxyError::TransformError(format!("Failed to serialize response: {e}"))
+    })?;
+
+    let body = axum::body::Body::from(response_body);
+    builder.body(body).map_err(|e| {
+        log::error!("[Codex] 构建响应失败: {e}");
+        ProxyError::Internal(format!("Failed to build response: {e}"))
+    })
+}
+
 // ============================================================================
 // Gemini API 处理器
 // ============================================================================
@@ -355,14 +628,23 @@ pub async fn handle_responses(
 /// 处理 Gemini API 请求（透传，包括查询参数）
 pub async fn handle_gemini(
     State(state): State<ProxyState>,
+    client: Option<Extension<ClientIdentity>>,
     uri: axum::http::Uri,
     headers: axum::http::HeaderMap,
     Json(body): Json<Value>,
 ) -> Result<axum::response::Response, ProxyError> {
     // Gemini 的模型名称在 URI 中
-    let mut ctx = RequestContext::new(&state, &body, &headers, AppType::Gemini, "Gemini", "gemini")
-        .await?
-        .with_model_from_uri(&uri);
+    let mut ctx = RequestContext::with_request_model(
+        &state,
+        &body,
+        &headers,
+        AppType::Gemini,
+        "Gemini",
+        "gemini",
+        RequestContext::model_from_uri(&uri),
+    )
+    .await?
+    .with_client(client.map(|Extension(identity)| identity));
 
     // 提取完整的路径和查询参数
     let endpoint = uri
@@ -376,7 +658,7 @@ pub async fn handle_gemini(
         .unwrap_or(false);
 
     let forwarder = ctx.create_forwarder(&state);
-    let result = match forwarder
+    let outcome = forwarder
         .forward_with_retry(
             &AppType::Gemini,
             endpoint,
@@ -384,8 +666,9 @@ pub async fn handle_gemini(
             headers,
             ctx.get_providers(),
         )
-        .await
-    {
+        .await;
+    ctx.attempts = forwarder.attempts();
+    let result = match outcome {
         Ok(result) => result,
         Err(mut err) => {
             if let Some(provider) = err.provider.take() {
@@ -396,8 +679,7 @@ pub async fn handle_gemini(
         }
     };
 
-    ctx.provider = result.provider;
-    let response = result.response;
+    let response = ctx.accept_forward_result(&state, result).await;
 
     process_response(response, &ctx, &state, &GEMINI_PARSER_CONFIG).await
 }
@@ -417,10 +699,20 @@ fn log_forward_error(
     let logger = UsageLogger::new(&state.db);
     let status_code = map_proxy_error_to_status(error);
     let error_message = get_error_message(error);
-    let request_id = uuid::Uuid::new_v4().to_string();
+
+    state.metrics.observe(RequestObservation {
+        app_type: ctx.app_type_str,
+        provider_id: &ctx.provider.id,
+        model: &ctx.request_model,
+        status_code,
+        latency_ms: ctx.latency_ms(),
+        first_token_ms: None,
+        usage: None,
+        cost_usd: None,
+    });
 
     if let Err(e) = logger.log_error_with_context(
-        request_id,
+        ctx.request_id.clone(),
         ctx.provider.id.clone(),
         ctx.app_type_str.to_string(),
         ctx.request_model.clone(),
@@ -430,55 +722,10 @@ fn log_forward_error(
         is_streaming,
         Some(ctx.session_id.clone()),
         None,
+        ctx.session_affinity.map(|d| d.as_str().to_string()),
+        ctx.client.as_ref().map(|client| client.label.clone()),
+        ctx.attempts,
     ) {
         log::warn!("记录失败请求日志失败: {e}");
     }
 }
-
-/// 记录请求使用量
-#[allow(clippy::too_many_arguments)]
-async fn log_usage(
-    state: &ProxyState,
-    provider_id: &str,
-    app_type: &str,
-    model: &str,
-    request_model: &str,
-    usage: TokenUsage,
-    latency_ms: u64,
-    first_token_ms: Option<u64>,
-    is_streaming: bool,
-    status_code: u16,
-) {
-    use super::usage::logger::UsageLogger;
-
-    let logger = UsageLogger::new(&state.db);
-
-    let (multiplier, pricing_model_source) =
-        logger.resolve_pricing_config(provider_id, app_type).await;
-    let pricing_model = if pricing_model_source == "request" {
-        request_model
-    } else {
-        model
-    };
-
-    let request_id = uuid::Uuid::new_v4().to_string();
-
-    if let Err(e) = logger.log_with_calculation(
-        request_id,
-        provider_id.to_string(),
-        app_type.to_string(),
-        model.to_string(),
-        request_model.to_string(),
-        pricing_model.to_string(),
-        usage,
-        multiplier,
-        latency_ms,
-        first_token_ms,
-        status_code,
-        None,
-        None, // provider_type
-        is_streaming,
-    ) {
-        log::warn!("[USG-001] 记录使用量失败: {e}");
-    }
-}
@@ -0,0 +1,390 @@
+//! 负载均衡模块
+//!
+//! 在故障转移队列的基础上按路由策略重新排列供应商尝试顺序：
+//! - priority：保持队列顺序（默认）
+//! - weighted_round_robin：平滑加权轮询，权重来自 `meta.routingWeight`
+//! - least_in_flight：进行中请求最少的供应商优先
+//! - lowest_latency：EWMA 延迟最低的供应商优先（首次使用时从请求日志初始化）
+//!
+//! 无论哪种策略，返回的仍是完整的故障转移链，只有顺序不同。
+
+use crate::database::Database;
+use crate::provider::Provider;
+use crate::proxy::types::RoutingStrategy;
+use futures::Stream;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::RwLock;
+
+/// EWMA 平滑系数（越大越偏向最近的样本）
+const EWMA_ALPHA: f64 = 0.3;
+
+/// 初始化 EWMA 时读取的历史请求数
+const LATENCY_SEED_SAMPLES: u32 = 50;
+
+/// 未配置权重时的默认权重
+const DEFAULT_WEIGHT: i64 = 1;
+
+/// 进行中请求计数守卫
+///
+/// 所有克隆都被释放后计数减一。流式响应会把克隆交给响应流持有，
+/// 从而覆盖整个流式传输过程。
+#[derive(Clone)]
+pub struct InFlightGuard {
+    _token: Arc<InFlightToken>,
+}
+
+struct InFlightToken {
+    counter: Arc<AtomicUsize>,
+}
+
+impl Drop for InFlightToken {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 持有进行中计数守卫的响应流，流被丢弃（传输结束或客户端断开）时释放计数
+pub struct InFlightStream<S> {
+    inner: S,
+    _guard: Option<InFlightGuard>,
+}
+
+impl<S> InFlightStream<S> {
+    pub fn new(inner: S, guard: Option<InFlightGuard>) -> Self {
+        Self {
+            inner,
+            _guard: guard,
+        }
+    }
+}
+
+impl<S: Stream + Unpin> Stream for InFlightStream<S> {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// 负载均衡器
+pub struct LoadBalancer {
+    db: Arc<Database>,
+    /// 平滑加权轮询的当前权重 - key 格式: "app_type:provider_id"
+    current_weights: RwLock<HashMap<String, i64>>,
+    /// 进行中请求数 - key 格式: "app_type:provider_id"
+    in_flight: RwLock<HashMap<String, Arc<AtomicUsize>>>,
+    /// EWMA 延迟（毫秒），None 表示已初始化但没有历史样本
+    latencies: RwLock<HashMap<String, Option<f64>>>,
+}
+
+impl LoadBalancer {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self {
+            db,
+            current_weights: RwLock::new(HashMap::new()),
+            in_flight: RwLock::new(HashMap::new()),
+            latencies: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 按路由策略排列供应商
+    pub async fn order(
+        &self,
+        app_type: &str,
+        strategy: RoutingStrategy,
+        mut providers: Vec<Provider>,
+    ) -> Vec<Provider> {
+        if providers.len() < 2 {
+            return providers;
+        }
+
+        match strategy {
+            RoutingStrategy::Priority => {}
+            RoutingStrategy::WeightedRoundRobin => {
+                if let Some(index) = self.pick_weighted(app_type, &providers).await {
+                    let selected = providers.remove(index);
+                    providers.insert(0, selected);
+                }
+            }
+            RoutingStrategy::LeastInFlight => {
+                let mut counts = HashMap::new();
+                for provider in &providers {
+                    counts.insert(
+                        provider.id.clone(),
+                        self.in_flight_count(app_type, &provider.id).await,
+                    );
+                }
+                // 稳定排序：计数相同时保持队列顺序
+                providers.sort_by_key(|p| counts.get(&p.id).copied().unwrap_or(0));
+            }
+            RoutingStrategy::LowestLatency => {
+                let mut latencies = HashMap::new();
+                for provider in &providers {
+                    latencies.insert(
+                        provider.id.clone(),
+                        self.latency_ms(app_type, &provider.id).await,
+                    );
+                }
+                // 没有延迟样本的供应商排在最前，以便尽快采集样本
+                providers.sort_by(|a, b| {
+                    let la = latencies.get(&a.id).copied().flatten().unwrap_or(0.0);
+                    let lb = latencies.get(&b.id).copied().flatten().unwrap_or(0.0);
+                    la.total_cmp(&lb)
+                });
+            }
+        }
+
+        providers
+    }
+
+    /// 标记一次请求开始，返回的守卫释放时计数减一
+    pub async fn begin_request(&self, app_type: &str, provider_id: &str) -> InFlightGuard {
+        let counter = self.in_flight_counter(app_type, provider_id).await;
+        counter.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard {
+            _token: Arc::new(InFlightToken { counter }),
+        }
+    }
+
+    /// 记录一次成功请求的延迟样本
+    pub async fn record_latency(&self, app_type: &str, provider_id: &str, latency_ms: u64) {
+        let key = format!("{app_type}:{provider_id}");
+        let sample = latency_ms as f64;
+
+        let mut latencies = self.latencies.write().await;
+        let entry = latencies.entry(key).or_insert(None);
+        *entry = Some(match *entry {
+            Some(prev) => EWMA_ALPHA * sample + (1.0 - EWMA_ALPHA) * prev,
+            None => sample,
+        });
+    }
+
+    /// 平滑加权轮询（nginx 算法），返回被选中供应商的下标
+    ///
+    /// 权重为 0 的供应商不会被选为首选，但仍保留在故障转移链中。
+    async fn pick_weighted(&self, app_type: &str, providers: &[Provider]) -> Option<usize> {
+        let weights: Vec<i64> = providers.iter().map(provider_weight).collect();
+        let total: i64 = weights.iter().sum();
+        if total <= 0 {
+            return None;
+        }
+
+        let mut current_weights = self.current_weights.write().await;
+        let mut best: Option<(usize, i64)> = None;
+        for (index, provider) in providers.iter().enumerate() {
+            if weights[index] == 0 {
+                continue;
+            }
+            let current = current_weights
+                .entry(format!("{app_type}:{}", provider.id))
+                .or_insert(0);
+            *current += weights[index];
+            if best.is_none_or(|(_, value)| *current > value) {
+                best = Some((index, *current));
+            }
+        }
+
+        let (index, _) = best?;
+        if let Some(current) =
+            current_weights.get_mut(&format!("{app_type}:{}", providers[index].id))
+        {
+            *current -= total;
+        }
+        Some(index)
+    }
+
+    /// 所有供应商的进行中请求数（key 格式: "app_type:provider_id"）
+    pub async fn in_flight_snapshot(&self) -> Vec<(String, usize)> {
+        let counters = self.in_flight.read().await;
+        let mut snapshot: Vec<(String, usize)> = counters
+            .iter()
+            .map(|(key, counter)| (key.clone(), counter.load(Ordering::SeqCst)))
+            .collect();
+        snapshot.sort();
+        snapshot
+    }
+
+    async fn in_flight_counter(&self, app_type: &str, provider_id: &str) -> Arc<AtomicUsize> {
+        let key = format!("{app_type}:{provider_id}");
+        {
+            let counters = self.in_flight.read().await;
+            if let Some(counter) = counters.get(&key) {
+                return counter.clone();
+            }
+        }
+
+        let mut counters = self.in_flight.write().await;
+        counters
+            .entry(key)
+            .or_insert_with(|| Arc::new(AtomicUsize::new(0)))
+            .clone()
+    }
+
+    async fn in_flight_count(&self, app_type: &str, provider_id: &str) -> usize {
+        let key = format!("{app_type}:{provider_id}");
+        let counters = self.in_flight.read().await;
+        counters
+            .get(&key)
+            .map(|c| c.load(Ordering::SeqCst))
+            .unwrap_or(0)
+    }
+
+    /// 获取 EWMA 延迟，首次访问时从请求日志初始化
+    async fn latency_ms(&self, app_type: &str, provider_id: &str) -> Option<f64> {
+        let key = format!("{app_type}:{provider_id}");
+        {
+            let latencies = self.latencies.read().await;
+            if let Some(value) = latencies.get(&key) {
+                return *value;
+            }
+        }
+
+        let seed =
+            match self
+                .db
+                .get_provider_recent_latency(provider_id, app_type, LATENCY_SEED_SAMPLES)
+            {
+                Ok(value) => value,
+                Err(e) => {
+                    log::warn!("[{app_type}] 加载供应商 {provider_id} 历史延迟失败: {e}");
+                    None
+                }
+            };
+
+        let mut latencies = self.latencies.write().await;
+        *latencies.entry(key).or_insert(seed)
+    }
+}
+
+/// 读取供应商权重（未配置时为默认权重）
+fn provider_weight(provider: &Provider) -> i64 {
+    provider
+        .meta
+        .as_ref()
+        .and_then(|meta| meta.routing_weight)
+        .map(i64::from)
+        .unwrap_or(DEFAULT_WEIGHT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    fn provider(id: &str, weight: Option<u32>) -> Provider {
+        let mut provider = Provider::with_id(id.to_string(), id.to_uppercase(), json!({}), None);
+        if weight.is_some() {
+            provider.meta = Some(ProviderMeta {
+                routing_weight: weight,
+                ..Default::default()
+            });
+        }
+        provider
+    }
+
+    fn first_ids(ordered: &[Provider]) -> Vec<&str> {
+        ordered.iter().map(|p| p.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_priority_keeps_queue_order() {
+        let lb = LoadBalancer::new(Arc::new(Database::memory().unwrap()));
+        let ordered = lb
+            .order(
+                "claude",
+                RoutingStrategy::Priority,
+                vec![provider("a", None), provider("b", None)],
+            )
+            .await;
+        assert_eq!(first_ids(&ordered), vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_weighted_round_robin_distribution() {
+        let lb = LoadBalancer::new(Arc::new(Database::memory().unwrap()));
+        let providers = vec![provider("a", Some(3)), provider("b", Some(1))];
+
+        let mut picks = HashMap::new();
+        for _ in 0..8 {
+            let ordered = lb
+                .order(
+                    "claude",
+                    RoutingStrategy::WeightedRoundRobin,
+                    providers.clone(),
+                )
+                .await;
+            assert_eq!(ordered.len(), 2);
+            *picks.entry(ordered[0].id.clone()).or_insert(0) += 1;
+        }
+
+        assert_eq!(picks.get("a"), Some(&6));
+        assert_eq!(picks.get("b"), Some(&2));
+    }
+
+    #[tokio::test]
+    async fn test_weighted_round_robin_zero_weight_is_backup_only() {
+        let lb = LoadBalancer::new(Arc::new(Database::memory().unwrap()));
+        let providers = vec![provider("a", Some(0)), provider("b", None)];
+
+        for _ in 0..3 {
+            let ordered = lb
+                .order(
+                    "claude",
+                    RoutingStrategy::WeightedRoundRobin,
+                    providers.clone(),
+                )
+                .await;
+            assert_eq!(first_ids(&ordered), vec!["b", "a"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_least_in_flight_prefers_idle_provider() {
+        let lb = LoadBalancer::new(Arc::new(Database::memory().unwrap()));
+        let providers = vec![provider("a", None), provider("b", None)];
+
+        let guard = lb.begin_request("claude", "a").await;
+        let ordered = lb
+            .order("claude", RoutingStrategy::LeastInFlight, providers.clone())
+            .await;
+        assert_eq!(first_ids(&ordered), vec!["b", "a"]);
+
+        // 守卫的所有克隆释放后计数归零
+        let clone = guard.clone();
+        drop(guard);
+        assert_eq!(lb.in_flight_count("claude", "a").await, 1);
+        drop(clone);
+        assert_eq!(lb.in_flight_count("claude", "a").await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_lowest_latency_uses_ewma() {
+        let lb = LoadBalancer::new(Arc::new(Database::memory().unwrap()));
+        let providers = vec![provider("a", None), provider("b", None)];
+
+        lb.record_latency("claude", "a", 800).await;
+        lb.record_latency("claude", "b", 300).await;
+        let ordered = lb
+            .order("claude", RoutingStrategy::LowestLatency, providers.clone())
+            .await;
+        assert_eq!(first_ids(&ordered), vec!["b", "a"]);
+
+        // b 连续变慢后 EWMA 超过 a
+        for _ in 0..5 {
+            lb.record_latency("claude", "b", 2000).await;
+        }
+        let ordered = lb
+            .order("claude", RoutingStrategy::LowestLatency, providers)
+            .await;
+        assert_eq!(first_ids(&ordered), vec!["a", "b"]);
+    }
+}
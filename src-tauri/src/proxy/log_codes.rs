@@ -7,6 +7,7 @@
 //! - FO: Failover (故障转移)
 //! - RSP: Response (响应处理)
 //! - USG: Usage (使用量)
+//! - AUTH: Client Auth (客户端认证)
 
 #![allow(dead_code)]
 
@@ -32,6 +33,7 @@ pub mod srv {
 pub mod fwd {
     pub const PROVIDER_FAILED_RETRY: &str = "FWD-001";
     pub const ALL_PROVIDERS_FAILED: &str = "FWD-002";
+    pub const SAME_PROVIDER_RETRY: &str = "FWD-003";
 }
 
 /// 故障转移日志码
@@ -57,3 +59,10 @@ pub mod usg {
     pub const LOG_FAILED: &str = "USG-001";
     pub const PRICING_NOT_FOUND: &str = "USG-002";
 }
+
+/// 客户端认证日志码
+pub mod auth {
+    pub const REJECTED: &str = "AUTH-001";
+    pub const LOOKUP_FAILED: &str = "AUTH-002";
+    pub const RATE_LIMITED: &str = "AUTH-003";
+}
@@ -0,0 +1,445 @@
+//! Prometheus 指标模块
+//!
+//! 在内存中累计代理请求指标，并由 `/metrics` 以 Prometheus 文本格式（0.0.4）导出：
+//! - 请求数 / 错误数（按应用、供应商、模型、状态码）
+//! - 请求耗时与首字延迟（TTFT）直方图
+//! - Token 与成本计数器
+//! - 进行中请求数与熔断器状态（导出时从 ProviderRouter 读取）
+//!
+//! 指标数据来自使用量记录与失败请求记录，不额外查询数据库。
+
+use super::circuit_breaker::CircuitState;
+use super::types::ProxyStatus;
+use super::usage::parser::TokenUsage;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// 指标名前缀
+const PREFIX: &str = "cc_switch_proxy";
+
+/// 直方图桶上界（秒）
+const LATENCY_BUCKETS: &[f64] = &[
+    0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+/// Prometheus 文本格式的 Content-Type
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// 一次已完成请求的观测数据
+#[derive(Debug, Clone)]
+pub struct RequestObservation<'a> {
+    pub app_type: &'a str,
+    pub provider_id: &'a str,
+    pub model: &'a str,
+    pub status_code: u16,
+    pub latency_ms: u64,
+    pub first_token_ms: Option<u64>,
+    pub usage: Option<&'a TokenUsage>,
+    pub cost_usd: Option<f64>,
+}
+
+/// 固定桶直方图
+#[derive(Debug, Clone)]
+struct Histogram {
+    buckets: Vec<u64>,
+    count: u64,
+    sum: f64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0; LATENCY_BUCKETS.len()],
+            count: 0,
+            sum: 0.0,
+        }
+    }
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if value <= *bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+/// 供应商 + 模型维度的计数
+#[derive(Debug, Clone, Default)]
+struct ModelCounters {
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_read_tokens: u64,
+    cache_creation_tokens: u64,
+    cost_usd: f64,
+}
+
+/// (app_type, provider_id)
+type ProviderKey = (String, String);
+/// (app_type, provider_id, model)
+type ModelKey = (String, String, String);
+
+#[derive(Debug, Default)]
+struct MetricsInner {
+    /// (app_type, provider_id, model, status_code) -> 请求数
+    requests: BTreeMap<(String, String, String, u16), u64>,
+    /// (app_type, provider_id, status_code) -> 错误数（非 2xx）
+    errors: BTreeMap<(String, String, u16), u64>,
+    latency: BTreeMap<ProviderKey, Histogram>,
+    ttft: BTreeMap<ProviderKey, Histogram>,
+    models: BTreeMap<ModelKey, ModelCounters>,
+}
+
+/// 代理指标注册表
+#[derive(Debug, Default)]
+pub struct ProxyMetrics {
+    inner: Mutex<MetricsInner>,
+}
+
+impl ProxyMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次已完成（成功或失败）的请求
+    pub fn observe(&self, obs: RequestObservation<'_>) {
+        let Ok(mut inner) = self.inner.lock() else {
+            log::warn!("指标锁已中毒，跳过本次记录");
+            return;
+        };
+
+        let app = obs.app_type.to_string();
+        let provider = obs.provider_id.to_string();
+        let model = obs.model.to_string();
+
+        *inner
+            .requests
+            .entry((
+                app.clone(),
+                provider.clone(),
+                model.clone(),
+                obs.status_code,
+            ))
+            .or_default() += 1;
+        if !(200..300).contains(&obs.status_code) {
+            *inner
+                .errors
+                .entry((app.clone(), provider.clone(), obs.status_code))
+                .or_default() += 1;
+        }
+
+        let provider_key = (app.clone(), provider.clone());
+        inner
+            .latency
+            .entry(provider_key.clone())
+            .or_default()
+            .observe(obs.latency_ms as f64 / 1000.0);
+        if let Some(ttft) = obs.first_token_ms {
+            inner
+                .ttft
+                .entry(provider_key)
+                .or_default()
+                .observe(ttft as f64 / 1000.0);
+        }
+
+        if obs.usage.is_some() || obs.cost_usd.is_some() {
+            let counters = inner.models.entry((app, provider, model)).or_default();
+            if let Some(usage) = obs.usage {
+                counters.input_tokens += u64::from(usage.input_tokens);
+                counters.output_tokens += u64::from(usage.output_tokens);
+                counters.cache_read_tokens += u64::from(usage.cache_read_tokens);
+                counters.cache_creation_tokens += u64::from(usage.cache_creation_tokens);
+            }
+            if let Some(cost) = obs.cost_usd {
+                counters.cost_usd += cost;
+            }
+        }
+    }
+
+    /// 以 Prometheus 文本格式导出
+    ///
+    /// `in_flight` 与 `circuits` 的 key 格式均为 `app_type:provider_id`。
+    pub fn render(
+        &self,
+        status: &ProxyStatus,
+        in_flight: &[(String, usize)],
+        circuits: &[(String, CircuitState)],
+    ) -> String {
+        let mut out = String::new();
+
+        write_header(&mut out, "up", "gauge", "代理服务是否运行");
+        write_sample(&mut out, "up", &[], u8::from(status.running));
+        write_header(&mut out, "uptime_seconds", "gauge", "代理服务运行时长");
+        write_sample(&mut out, "uptime_seconds", &[], status.uptime_seconds);
+        write_header(&mut out, "active_connections", "gauge", "当前活动连接数");
+        write_sample(
+            &mut out,
+            "active_connections",
+            &[],
+            status.active_connections,
+        );
+        write_header(&mut out, "failovers_total", "counter", "故障转移次数");
+        write_sample(&mut out, "failovers_total", &[], status.failover_count);
+
+        if let Ok(inner) = self.inner.lock() {
+            write_header(&mut out, "requests_total", "counter", "已完成的请求数");
+            for ((app, provider, model, status_code), count) in &inner.requests {
+                let code = status_code.to_string();
+                write_sample(
+                    &mut out,
+                    "requests_total",
+                    &[
+                        ("app", app),
+                        ("provider", provider),
+                        ("model", model),
+                        ("status", &code),
+                    ],
+                    count,
+                );
+            }
+
+            write_header(&mut out, "errors_total", "counter", "失败请求数（非 2xx）");
+            for ((app, provider, status_code), count) in &inner.errors {
+                let code = status_code.to_string();
+                write_sample(
+                    &mut out,
+                    "errors_total",
+                    &[("app", app), ("provider", provider), ("status", &code)],
+                    count,
+                );
+            }
+
+            write_histograms(
+                &mut out,
+                "request_duration_seconds",
+                "请求总耗时",
+                &inner.latency,
+            );
+            write_histograms(
+                &mut out,
+                "time_to_first_token_seconds",
+                "流式响应首字延迟",
+                &inner.ttft,
+            );
+
+            write_header(&mut out, "tokens_total", "counter", "Token 用量");
+            for ((app, provider, model), counters) in &inner.models {
+                for (kind, value) in [
+                    ("input", counters.input_tokens),
+                    ("output", counters.output_tokens),
+                    ("cache_read", counters.cache_read_tokens),
+                    ("cache_creation", counters.cache_creation_tokens),
+                ] {
+                    write_sample(
+                        &mut out,
+                        "tokens_total",
+                        &[
+                            ("app", app),
+                            ("provider", provider),
+                            ("model", model),
+                            ("type", kind),
+                        ],
+                        value,
+                    );
+                }
+            }
+
+            write_header(&mut out, "cost_usd_total", "counter", "累计成本（USD）");
+            for ((app, provider, model), counters) in &inner.models {
+                write_sample(
+                    &mut out,
+                    "cost_usd_total",
+                    &[("app", app), ("provider", provider), ("model", model)],
+                    counters.cost_usd,
+                );
+            }
+        }
+
+        write_header(&mut out, "in_flight_requests", "gauge", "进行中的请求数");
+        for (key, count) in in_flight {
+            let (app, provider) = split_key(key);
+            write_sample(
+                &mut out,
+                "in_flight_requests",
+                &[("app", app), ("provider", provider)],
+                count,
+            );
+        }
+
+        write_header(
+            &mut out,
+            "circuit_state",
+            "gauge",
+            "熔断器状态（当前状态为 1）",
+        );
+        for (key, state) in circuits {
+            let (app, provider) = split_key(key);
+            for candidate in [
+                CircuitState::Closed,
+                CircuitState::Open,
+                CircuitState::HalfOpen,
+            ] {
+                let name = candidate.to_string();
+                write_sample(
+                    &mut out,
+                    "circuit_state",
+                    &[("app", app), ("provider", provider), ("state", &name)],
+                    u8::from(*state == candidate),
+                );
+            }
+        }
+
+        out
+    }
+}
+
+fn split_key(key: &str) -> (&str, &str) {
+    key.split_once(':').unwrap_or((key, ""))
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {PREFIX}_{name} {help}");
+    let _ = writeln!(out, "# TYPE {PREFIX}_{name} {kind}");
+}
+
+fn write_sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: impl ToString) {
+    let _ = write!(out, "{PREFIX}_{name}");
+    write_labels(out, labels);
+    let _ = writeln!(out, " {}", value.to_string());
+}
+
+fn write_labels(out: &mut String, labels: &[(&str, &str)]) {
+    if labels.is_empty() {
+        return;
+    }
+    out.push('{');
+    for (index, (key, value)) in labels.iter().enumerate() {
+        if index > 0 {
+            out.push(',');
+        }
+        let _ = write!(out, "{key}=\"{}\"", escape_label(value));
+    }
+    out.push('}');
+}
+
+fn write_histograms(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    histograms: &BTreeMap<ProviderKey, Histogram>,
+) {
+    write_header(out, name, "histogram", help);
+    for ((app, provider), histogram) in histograms {
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(&histogram.buckets) {
+            let le = bound.to_string();
+            write_sample(
+                out,
+                &format!("{name}_bucket"),
+                &[("app", app), ("provider", provider), ("le", &le)],
+                count,
+            );
+        }
+        let labels = [("app", app.as_str()), ("provider", provider.as_str())];
+        write_sample(
+            out,
+            &format!("{name}_bucket"),
+            &[labels[0], labels[1], ("le", "+Inf")],
+            histogram.count,
+        );
+        write_sample(out, &format!("{name}_sum"), &labels, histogram.sum);
+        write_sample(out, &format!("{name}_count"), &labels, histogram.count);
+    }
+}
+
+/// 转义标签值中的反斜杠、双引号与换行
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observation<'a>(status_code: u16, usage: Option<&'a TokenUsage>) -> RequestObservation<'a> {
+        RequestObservation {
+            app_type: "claude",
+            provider_id: "p1",
+            model: "claude-sonnet",
+            status_code,
+            latency_ms: 1200,
+            first_token_ms: Some(300),
+            usage,
+            cost_usd: usage.map(|_| 0.25),
+        }
+    }
+
+    #[test]
+    fn test_render_counters_and_histograms() {
+        let metrics = ProxyMetrics::new();
+        let usage = TokenUsage {
+            input_tokens: 100,
+            output_tokens: 50,
+            ..Default::default()
+        };
+        metrics.observe(observation(200, Some(&usage)));
+        metrics.observe(observation(200, Some(&usage)));
+        metrics.observe(observation(502, None));
+
+        let status = ProxyStatus {
+            running: true,
+            failover_count: 3,
+            ..Default::default()
+        };
+        let text = metrics.render(
+            &status,
+            &[("claude:p1".to_string(), 2)],
+            &[("claude:p1".to_string(), CircuitState::Open)],
+        );
+
+        assert!(text.contains("cc_switch_proxy_up 1\n"));
+        assert!(text.contains("cc_switch_proxy_failovers_total 3\n"));
+        assert!(text.contains(
+            "cc_switch_proxy_requests_total{app=\"claude\",provider=\"p1\",model=\"claude-sonnet\",status=\"200\"} 2\n"
+        ));
+        assert!(text.contains(
+            "cc_switch_proxy_errors_total{app=\"claude\",provider=\"p1\",status=\"502\"} 1\n"
+        ));
+        assert!(text.contains(
+            "cc_switch_proxy_tokens_total{app=\"claude\",provider=\"p1\",model=\"claude-sonnet\",type=\"input\"} 200\n"
+        ));
+        assert!(text.contains(
+            "cc_switch_proxy_cost_usd_total{app=\"claude\",provider=\"p1\",model=\"claude-sonnet\"} 0.5\n"
+        ));
+        assert!(text.contains(
+            "cc_switch_proxy_request_duration_seconds_bucket{app=\"claude\",provider=\"p1\",le=\"1\"} 0\n"
+        ));
+        assert!(text.contains(
+            "cc_switch_proxy_request_duration_seconds_bucket{app=\"claude\",provider=\"p1\",le=\"2.5\"} 3\n"
+        ));
+        assert!(text.contains(
+            "cc_switch_proxy_time_to_first_token_seconds_count{app=\"claude\",provider=\"p1\"} 3\n"
+        ));
+        assert!(
+            text.contains("cc_switch_proxy_in_flight_requests{app=\"claude\",provider=\"p1\"} 2\n")
+        );
+        assert!(text.contains(
+            "cc_switch_proxy_circuit_state{app=\"claude\",provider=\"p1\",state=\"open\"} 1\n"
+        ));
+        assert!(text.contains(
+            "cc_switch_proxy_circuit_state{app=\"claude\",provider=\"p1\",state=\"closed\"} 0\n"
+        ));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -3,7 +3,10 @@
 //! 提供本地HTTP代理服务，支持多Provider故障转移和请求透传
 
 pub mod body_filter;
+pub mod budget;
+pub mod capture;
 pub mod circuit_breaker;
+pub mod client_auth;
 pub mod error;
 pub mod error_mapper;
 pub(crate) mod failover_switch;
@@ -13,14 +16,22 @@ pub mod handler_context;
 mod handlers;
 mod health;
 pub mod http_client;
+pub mod load_balancer;
 pub mod log_codes;
+pub mod metrics;
+pub mod model_catalog;
 pub mod model_mapper;
+pub mod model_router;
 pub mod provider_router;
 pub mod providers;
+pub mod rate_limiter;
+pub mod replay;
 pub mod response_handler;
 pub mod response_processor;
+pub mod retry_policy;
 pub(crate) mod server;
 pub mod session;
+pub mod session_affinity;
 pub mod thinking_budget_rectifier;
 pub mod thinking_rectifier;
 pub(crate) mod types;
@@ -0,0 +1,427 @@
+//! 模型目录模块
+//!
+//! 为 `/v1/models` 端点聚合可用模型：
+//! - 拉取故障转移队列（或当前供应商）中各供应商的上游模型列表
+//! - 合并 `ModelMapping` 中配置的映射模型名
+//! - 结果按应用缓存，过期（TTL）后重新拉取
+
+use super::model_mapper::ModelMapping;
+use super::providers::{get_adapter, ClaudeAdapter, ProviderType};
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::provider::Provider;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// 模型目录缓存有效期
+const CATALOG_TTL: Duration = Duration::from_secs(300);
+
+/// 单个上游模型列表请求的超时时间
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 模型条目来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelSource {
+    /// 上游 `/models` 接口返回
+    Upstream,
+    /// 供应商模型映射配置
+    Mapping,
+}
+
+/// 模型目录条目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogModel {
+    pub id: String,
+    /// 提供该模型的供应商名称
+    pub owned_by: String,
+    pub app_type: String,
+    pub source: ModelSource,
+}
+
+/// 模型目录（带 TTL 缓存）
+pub struct ModelCatalog {
+    db: Arc<Database>,
+    ttl: Duration,
+    cache: RwLock<HashMap<String, (Instant, Vec<CatalogModel>)>>,
+}
+
+impl ModelCatalog {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self::with_ttl(db, CATALOG_TTL)
+    }
+
+    pub fn with_ttl(db: Arc<Database>, ttl: Duration) -> Self {
+        Self {
+            db,
+            ttl,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 获取多个应用合并后的模型列表（按 id 去重，先出现者优先）
+    pub async fn list_models(&self, app_types: &[AppType]) -> Vec<CatalogModel> {
+        let mut merged = Vec::new();
+        for app_type in app_types {
+            merged.extend(self.list_for_app(app_type.as_str()).await);
+        }
+        dedupe_models(merged)
+    }
+
+    /// 获取单个应用的模型列表，命中缓存时直接返回
+    pub async fn list_for_app(&self, app_type: &str) -> Vec<CatalogModel> {
+        if let Some(models) = self.cached(app_type).await {
+            return models;
+        }
+
+        let providers = self.catalog_providers(app_type).await;
+        let fetches = providers
+            .iter()
+            .map(|provider| fetch_upstream_models(app_type, provider));
+        let upstream = futures::future::join_all(fetches).await;
+
+        let mut models = Vec::new();
+        for (provider, ids) in providers.iter().zip(upstream) {
+            models.extend(ids.into_iter().map(|id| CatalogModel {
+                id,
+                owned_by: provider.name.clone(),
+                app_type: app_type.to_string(),
+                source: ModelSource::Upstream,
+            }));
+            models.extend(mapped_models(app_type, provider));
+        }
+        let models = dedupe_models(models);
+
+        log::debug!(
+            "[{app_type}] 模型目录已刷新: {} 个供应商, {} 个模型",
+            providers.len(),
+            models.len()
+        );
+        self.cache
+            .write()
+            .await
+            .insert(app_type.to_string(), (Instant::now(), models.clone()));
+        models
+    }
+
+    /// 清空缓存（供应商配置变化后可调用）
+    pub async fn invalidate(&self) {
+        self.cache.write().await.clear();
+    }
+
+    async fn cached(&self, app_type: &str) -> Option<Vec<CatalogModel>> {
+        let cache = self.cache.read().await;
+        cache
+            .get(app_type)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < self.ttl)
+            .map(|(_, models)| models.clone())
+    }
+
+    /// 获取参与模型目录的供应商
+    ///
+    /// 与 `ProviderRouter::select_providers` 的选择范围一致：故障转移开启时为故障转移队列，
+    /// 关闭时仅为当前供应商；但不触发熔断器与负载均衡的副作用。
+    async fn catalog_providers(&self, app_type: &str) -> Vec<Provider> {
+        let all_providers = match self.db.get_all_providers(app_type) {
+            Ok(providers) => providers,
+            Err(e) => {
+                log::warn!("[{app_type}] 读取供应商列表失败: {e}");
+                return Vec::new();
+            }
+        };
+
+        let auto_failover_enabled = match self.db.get_proxy_config_for_app(app_type).await {
+            Ok(config) => config.auto_failover_enabled,
+            Err(e) => {
+                log::warn!("[{app_type}] 读取 proxy_config 失败: {e}，按故障转移关闭处理");
+                false
+            }
+        };
+
+        let ids: Vec<String> = if auto_failover_enabled {
+            self.db
+                .get_failover_queue(app_type)
+                .map(|queue| queue.into_iter().map(|item| item.provider_id).collect())
+                .unwrap_or_default()
+        } else {
+            AppType::from_str(app_type)
+                .ok()
+                .and_then(|app_enum| {
+                    crate::settings::get_effective_current_provider(&self.db, &app_enum)
+                        .ok()
+                        .flatten()
+                })
+                .or_else(|| self.db.get_current_provider(app_type).ok().flatten())
+                .into_iter()
+                .collect()
+        };
+
+        ids.into_iter()
+            .filter_map(|id| all_providers.get(&id).cloned())
+            .collect()
+    }
+}
+
+/// 供应商模型映射中配置的模型名
+fn mapped_models(app_type: &str, provider: &Provider) -> Vec<CatalogModel> {
+    let mapping = ModelMapping::from_provider(provider);
+    [
+        mapping.default_model,
+        mapping.reasoning_model,
+        mapping.opus_model,
+        mapping.sonnet_model,
+        mapping.haiku_model,
+    ]
+    .into_iter()
+    .flatten()
+    .map(|id| CatalogModel {
+        id,
+        owned_by: provider.name.clone(),
+        app_type: app_type.to_string(),
+        source: ModelSource::Mapping,
+    })
+    .collect()
+}
+
+/// 按 id 去重，保留先出现的条目
+fn dedupe_models(models: Vec<CatalogModel>) -> Vec<CatalogModel> {
+    let mut seen = HashSet::new();
+    models
+        .into_iter()
+        .filter(|model| seen.insert(model.id.clone()))
+        .collect()
+}
+
+/// 根据应用类型与供应商格式确定上游模型列表端点
+///
+/// 返回 `None` 表示该供应商不支持（或无需）拉取模型列表。
+fn models_endpoint(app_type: &str, provider: &Provider) -> Option<&'static str> {
+    match app_type {
+        "claude" => {
+            let adapter = ClaudeAdapter::new();
+            if adapter.provider_type(provider) == ProviderType::GitHubCopilot {
+                return None;
+            }
+            match adapter.get_api_format(provider) {
+                "gemini" => Some("/v1beta/models"),
+                _ => Some("/v1/models"),
+            }
+        }
+        "codex" => Some("/models"),
+        "gemini" => Some("/v1beta/models"),
+        _ => None,
+    }
+}
+
+/// 拉取单个供应商的上游模型列表，失败时返回空列表
+async fn fetch_upstream_models(app_type: &str, provider: &Provider) -> Vec<String> {
+    let Some(endpoint) = models_endpoint(app_type, provider) else {
+        return Vec::new();
+    };
+    let Ok(app_enum) = AppType::from_str(app_type) else {
+        return Vec::new();
+    };
+
+    let adapter = get_adapter(&app_enum);
+    let base_url = match adapter.extract_base_url(provider) {
+        Ok(url) => url,
+        Err(e) => {
+            log::debug!("[{app_type}] 跳过模型列表拉取 ({}): {e}", provider.name);
+            return Vec::new();
+        }
+    };
+    let url = adapter.build_url(&base_url, endpoint);
+
+    let client = super::http_client::get_for_provider(
+        provider
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.proxy_config.as_ref()),
+    );
+    let mut request = client.get(&url).timeout(FETCH_TIMEOUT);
+    if let Some(auth) = adapter.extract_auth(provider) {
+        request = adapter.add_auth_headers(request, &auth);
+    }
+    if endpoint == "/v1/models" && app_type == "claude" {
+        request = request.header("anthropic-version", "2023-06-01");
+    }
+
+    let response = match request.send().await {
+        Ok(resp) => resp,
+        Err(e) => {
+            log::warn!("[{app_type}] 拉取模型列表失败 ({}): {e}", provider.name);
+            return Vec::new();
+        }
+    };
+    if !response.status().is_success() {
+        log::warn!(
+            "[{app_type}] 拉取模型列表失败 ({}): HTTP {}",
+            provider.name,
+            response.status()
+        );
+        return Vec::new();
+    }
+
+    match response.json::<Value>().await {
+        Ok(body) => parse_model_ids(&body),
+        Err(e) => {
+            log::warn!("[{app_type}] 解析模型列表失败 ({}): {e}", provider.name);
+            Vec::new()
+        }
+    }
+}
+
+/// 解析上游模型列表响应
+///
+/// 支持 OpenAI / Anthropic 的 `data[].id` 与 Gemini 的 `models[].name`（去除 `models/` 前缀）。
+fn parse_model_ids(body: &Value) -> Vec<String> {
+    if let Some(data) = body.get("data").and_then(|v| v.as_array()) {
+        return data
+            .iter()
+            .filter_map(|item| item.get("id").and_then(|v| v.as_str()))
+            .map(String::from)
+            .collect();
+    }
+
+    if let Some(models) = body.get("models").and_then(|v| v.as_array()) {
+        return models
+            .iter()
+            .filter_map(|item| item.get("name").and_then(|v| v.as_str()))
+            .map(|name| name.strip_prefix("models/").unwrap_or(name).to_string())
+            .collect();
+    }
+
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn provider_with_env(id: &str, env: Value) -> Provider {
+        Provider::with_id(
+            id.to_string(),
+            format!("Provider {id}"),
+            json!({ "env": env }),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_parse_openai_and_anthropic_lists() {
+        let body = json!({
+            "data": [
+                {"id": "claude-sonnet-4-5", "type": "model"},
+                {"id": "gpt-5", "object": "model"},
+                {"object": "model"}
+            ]
+        });
+        assert_eq!(parse_model_ids(&body), vec!["claude-sonnet-4-5", "gpt-5"]);
+    }
+
+    #[test]
+    fn test_parse_gemini_list_strips_prefix() {
+        let body = json!({
+            "models": [
+                {"name": "models/gemini-2.5-pro"},
+                {"name": "gemini-2.5-flash"}
+            ]
+        });
+        assert_eq!(
+            parse_model_ids(&body),
+            vec!["gemini-2.5-pro", "gemini-2.5-flash"]
+        );
+        assert!(parse_model_ids(&json!({"error": "nope"})).is_empty());
+    }
+
+    #[test]
+    fn test_mapped_models_and_dedupe() {
+        let provider = provider_with_env(
+            "a",
+            json!({
+                "ANTHROPIC_MODEL": "glm-4.6",
+                "ANTHROPIC_DEFAULT_SONNET_MODEL": "glm-4.6",
+                "ANTHROPIC_DEFAULT_HAIKU_MODEL": "glm-4.5-air"
+            }),
+        );
+        let mapped = mapped_models("claude", &provider);
+        assert_eq!(mapped.len(), 3);
+        assert!(mapped.iter().all(|m| m.source == ModelSource::Mapping));
+
+        let ids: Vec<String> = dedupe_models(mapped).into_iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec!["glm-4.6", "glm-4.5-air"]);
+    }
+
+    #[test]
+    fn test_models_endpoint_by_format() {
+        let anthropic = provider_with_env(
+            "a",
+            json!({"ANTHROPIC_BASE_URL": "https://api.example.com"}),
+        );
+        assert_eq!(models_endpoint("claude", &anthropic), Some("/v1/models"));
+
+        let mut gemini = anthropic.clone();
+        gemini.meta = Some(crate::provider::ProviderMeta {
+            api_format: Some("gemini".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(models_endpoint("claude", &gemini), Some("/v1beta/models"));
+
+        assert_eq!(models_endpoint("codex", &anthropic), Some("/models"));
+        assert_eq!(models_endpoint("opencode", &anthropic), None);
+    }
+
+    #[tokio::test]
+    async fn test_cache_respects_ttl() {
+        let db = Arc::new(Database::memory().expect("memory db"));
+        let catalog = ModelCatalog::with_ttl(db, Duration::from_secs(60));
+        let entry = CatalogModel {
+            id: "cached-model".to_string(),
+            owned_by: "Cached".to_string(),
+            app_type: "claude".to_string(),
+            source: ModelSource::Mapping,
+        };
+        catalog
+            .cache
+            .write()
+            .await
+            .insert("claude".to_string(), (Instant::now(), vec![entry.clone()]));
+        assert_eq!(catalog.list_for_app("claude").await, vec![entry]);
+
+        catalog.invalidate().await;
+        assert!(catalog.list_for_app("claude").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_catalog_providers_follow_failover_setting() {
+        let db = Arc::new(Database::memory().expect("memory db"));
+        for id in ["current", "queued"] {
+            db.save_provider("claude", &provider_with_env(id, json!({})))
+                .unwrap();
+        }
+        db.set_current_provider("claude", "current").unwrap();
+        db.add_to_failover_queue("claude", "queued").unwrap();
+        let catalog = ModelCatalog::with_ttl(db.clone(), Duration::from_secs(60));
+        let ids = |providers: Vec<Provider>| -> Vec<String> {
+            providers.into_iter().map(|p| p.id).collect()
+        };
+
+        // 故障转移关闭：队列中的供应商不会处理请求，不参与模型目录
+        assert_eq!(
+            ids(catalog.catalog_providers("claude").await),
+            vec!["current"]
+        );
+
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        config.auto_failover_enabled = true;
+        db.update_proxy_config_for_app(config).await.unwrap();
+        assert_eq!(
+            ids(catalog.catalog_providers("claude").await),
+            vec!["queued"]
+        );
+    }
+}
@@ -0,0 +1,49 @@
+//! 模型路由
+//!
+//! 按请求中的模型名称匹配应用的路由规则（如 `*haiku*` → 廉价中转，
+//! `*opus*` → 官方 Key），命中时由规则的供应商列表决定候选供应商及故障转移顺序。
+//! 未命中任何规则时回退到默认的供应商选择（当前供应商 / 故障转移队列）。
+
+use crate::database::ModelRoutingRule;
+use crate::model_pattern::pattern_matches;
+
+/// 返回第一条匹配请求模型的已启用规则
+pub fn find_rule<'a>(rules: &'a [ModelRoutingRule], model: &str) -> Option<&'a ModelRoutingRule> {
+    rules
+        .iter()
+        .filter(|rule| rule.enabled && !rule.provider_ids.is_empty())
+        .find(|rule| pattern_matches(&rule.pattern, model))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_rule_uses_first_enabled_match() {
+        let rule = |id: &str, pattern: &str, enabled: bool| ModelRoutingRule {
+            id: id.to_string(),
+            app_type: "claude".to_string(),
+            pattern: pattern.to_string(),
+            provider_ids: vec!["p".to_string()],
+            enabled,
+            sort_index: 0,
+            created_at: 0,
+        };
+        let rules = vec![
+            rule("disabled", "*haiku*", false),
+            rule("haiku", "*haiku*", true),
+            rule("all", "*", true),
+        ];
+
+        assert_eq!(
+            find_rule(&rules, "claude-haiku-4-5").map(|r| r.id.as_str()),
+            Some("haiku")
+        );
+        assert_eq!(
+            find_rule(&rules, "claude-opus-4-6").map(|r| r.id.as_str()),
+            Some("all")
+        );
+        assert!(find_rule(&rules[..2], "claude-opus-4-6").is_none());
+    }
+}
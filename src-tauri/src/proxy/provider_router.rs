@@ -6,24 +6,136 @@ use crate::app_config::AppType;
 use crate::database::Database;
 use crate::error::AppError;
 use crate::provider::Provider;
-use crate::proxy::circuit_breaker::{AllowResult, CircuitBreaker, CircuitBreakerConfig};
+use crate::proxy::budget::{BudgetCheck, ProviderBudgetTracker};
+use crate::proxy::circuit_breaker::{
+    AllowResult, CircuitBreaker, CircuitBreakerConfig, CircuitState,
+};
+use crate::proxy::load_balancer::{InFlightGuard, LoadBalancer};
+use crate::proxy::model_router;
+use crate::proxy::providers::gemini_oauth::GeminiOAuthManager;
+use crate::proxy::rate_limiter::{RateDecision, RateLimitConfig, RateLimitLevel, RateLimiter};
+use crate::proxy::session_affinity::{AffinityDecision, SessionAffinity};
+use crate::proxy::types::RoutingStrategy;
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
+/// 候选供应商列表的来源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CandidateSource {
+    /// 故障转移关闭：仅当前供应商
+    #[default]
+    Current,
+    /// 故障转移队列（priority 策略，保持队列顺序）
+    FailoverQueue,
+    /// 负载均衡策略重新排列的故障转移队列
+    Balancer,
+    /// 模型路由规则指定的供应商列表
+    ModelRule,
+}
+
+/// 请求成功后的故障转移判定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailoverOutcome {
+    /// 首选候选直接成功，不是故障转移
+    None,
+    /// 已故障转移，但不改写当前供应商
+    Counted,
+    /// 已故障转移，并把实际使用的供应商切换为当前供应商
+    Switch,
+}
+
+impl CandidateSource {
+    /// 判定请求由 `served_id` 成功处理时是否发生了故障转移、是否需要切换当前供应商
+    ///
+    /// 只有离开首选候选才算故障转移。负载均衡与模型路由规则会有意使用非当前供应商，
+    /// 因此只有按故障转移队列顺序尝试时，故障转移才会把实际使用的供应商切换为当前供应商。
+    pub fn failover_outcome(
+        self,
+        first_id: &str,
+        served_id: &str,
+        current_id: &str,
+    ) -> FailoverOutcome {
+        if served_id == first_id {
+            FailoverOutcome::None
+        } else if self == Self::FailoverQueue && served_id != current_id {
+            FailoverOutcome::Switch
+        } else {
+            FailoverOutcome::Counted
+        }
+    }
+}
+
+/// 已排好尝试顺序的候选供应商
+#[derive(Debug, Clone)]
+pub struct ProviderCandidates {
+    pub providers: Vec<Provider>,
+    pub source: CandidateSource,
+}
+
+/// 供应商筛选过程中的统计（用于在没有可用供应商时给出准确的错误）
+#[derive(Default)]
+struct Selection {
+    providers: Vec<Provider>,
+    source: CandidateSource,
+    total: usize,
+    circuit_open: usize,
+    budget_exceeded: Vec<String>,
+}
+
+impl Selection {
+    fn finish(self, app_type: &str) -> Result<ProviderCandidates, AppError> {
+        if !self.providers.is_empty() {
+            return Ok(ProviderCandidates {
+                providers: self.providers,
+                source: self.source,
+            });
+        }
+        if !self.budget_exceeded.is_empty()
+            && self.circuit_open + self.budget_exceeded.len() == self.total
+        {
+            let reasons = self.budget_exceeded.join("; ");
+            log::warn!("[{app_type}] 所有可用供应商均已超出消费限额: {reasons}");
+            Err(AppError::ProviderBudgetExceeded(reasons))
+        } else if self.total > 0 && self.circuit_open == self.total {
+            log::warn!("[{app_type}] [FO-004] 所有供应商均已熔断");
+            Err(AppError::AllProvidersCircuitOpen)
+        } else {
+            log::warn!("[{app_type}] [FO-005] 未配置供应商");
+            Err(AppError::NoProvidersConfigured)
+        }
+    }
+}
+
 /// 供应商路由器
 pub struct ProviderRouter {
     /// 数据库连接
     db: Arc<Database>,
     /// 熔断器管理器 - key 格式: "app_type:provider_id"
     circuit_breakers: Arc<RwLock<HashMap<String, Arc<CircuitBreaker>>>>,
+    /// 消费限额跟踪器（内存累计今日/本月消费）
+    budget_tracker: Arc<ProviderBudgetTracker>,
+    /// 负载均衡器（加权轮询/进行中请求数/EWMA 延迟）
+    load_balancer: Arc<LoadBalancer>,
+    /// 会话粘性绑定（Session ID → 供应商）
+    session_affinity: Arc<SessionAffinity>,
+    /// RPM/TPM 令牌桶（供应商与客户端令牌）
+    rate_limiter: Arc<RateLimiter>,
+    /// Gemini CLI OAuth access_token 刷新与缓存
+    gemini_oauth: Arc<GeminiOAuthManager>,
 }
 
 impl ProviderRouter {
     /// 创建新的供应商路由器
     pub fn new(db: Arc<Database>) -> Self {
         Self {
+            budget_tracker: Arc::new(ProviderBudgetTracker::new(db.clone())),
+            load_balancer: Arc::new(LoadBalancer::new(db.clone())),
+            session_affinity: Arc::new(SessionAffinity::new()),
+            rate_limiter: Arc::new(RateLimiter::new()),
+            gemini_oauth: Arc::new(GeminiOAuthManager::new(db.clone())),
             db,
             circuit_breakers: Arc::new(RwLock::new(HashMap::new())),
         }
@@ -33,20 +145,15 @@ impl ProviderRouter {
     ///
     /// 返回按优先级排序的可用供应商列表：
     /// - 故障转移关闭时：仅返回当前供应商
-    /// - 故障转移开启时：仅使用故障转移队列，按队列顺序依次尝试（P1 → P2 → ...）
-    pub async fn select_providers(&self, app_type: &str) -> Result<Vec<Provider>, AppError> {
-        let mut result = Vec::new();
-        let mut total_providers = 0usize;
-        let mut circuit_open_count = 0usize;
+    /// - 故障转移开启时：仅使用故障转移队列，再按 proxy_config 中的路由策略排列尝试顺序
+    ///   （默认 priority 即队列顺序 P1 → P2 → ...）
+    ///
+    /// 超出每日/每月消费限额的供应商视为不可用（开启故障转移时跳过，关闭时直接拒绝）。
+    pub async fn select_providers(&self, app_type: &str) -> Result<ProviderCandidates, AppError> {
+        let mut selection = Selection::default();
 
         // 检查该应用的自动故障转移开关是否开启（从 proxy_config 表读取）
-        let auto_failover_enabled = match self.db.get_proxy_config_for_app(app_type).await {
-            Ok(config) => config.auto_failover_enabled,
-            Err(e) => {
-                log::error!("[{app_type}] 读取 proxy_config 失败: {e}，默认禁用故障转移");
-                false
-            }
-        };
+        let (auto_failover_enabled, routing_strategy) = self.failover_settings(app_type).await;
 
         if auto_failover_enabled {
             // 故障转移开启：仅按队列顺序依次尝试（P1 → P2 → ...）
@@ -60,22 +167,24 @@ impl ProviderRouter {
                 .map(|item| item.provider_id)
                 .collect();
 
-            total_providers = ordered_ids.len();
+            selection.total = ordered_ids.len();
 
             for provider_id in ordered_ids {
                 let Some(provider) = all_providers.get(&provider_id).cloned() else {
                     continue;
                 };
-
-                let circuit_key = format!("{app_type}:{}", provider.id);
-                let breaker = self.get_or_create_circuit_breaker(&circuit_key).await;
-
-                if breaker.is_available().await {
-                    result.push(provider);
-                } else {
-                    circuit_open_count += 1;
-                }
+                self.admit(app_type, provider, true, &mut selection).await;
             }
+
+            selection.source = if routing_strategy == RoutingStrategy::Priority {
+                CandidateSource::FailoverQueue
+            } else {
+                CandidateSource::Balancer
+            };
+            selection.providers = self
+                .load_balancer
+                .order(app_type, routing_strategy, selection.providers)
+                .await;
         } else {
             // 故障转移关闭：仅使用当前供应商，跳过熔断器检查
             let current_id = AppType::from_str(app_type)
@@ -89,23 +198,237 @@ impl ProviderRouter {
 
             if let Some(current_id) = current_id {
                 if let Some(current) = self.db.get_provider_by_id(&current_id, app_type)? {
-                    total_providers = 1;
-                    result.push(current);
+                    selection.total = 1;
+                    self.admit(app_type, current, false, &mut selection).await;
                 }
             }
         }
 
-        if result.is_empty() {
-            if total_providers > 0 && circuit_open_count == total_providers {
-                log::warn!("[{app_type}] [FO-004] 所有供应商均已熔断");
-                return Err(AppError::AllProvidersCircuitOpen);
-            } else {
-                log::warn!("[{app_type}] [FO-005] 未配置供应商");
-                return Err(AppError::NoProvidersConfigured);
+        selection.finish(app_type)
+    }
+
+    /// 按请求模型选择可用的供应商
+    ///
+    /// 请求模型命中应用的模型路由规则时，候选供应商取自规则的供应商列表，并按列表顺序尝试：
+    /// - 故障转移开启时：跳过已熔断或超出消费限额的供应商，依次使用列表中的后续供应商
+    /// - 故障转移关闭时：仅使用列表中的第一个供应商
+    ///
+    /// 未命中任何规则时与 [`select_providers`](Self::select_providers) 相同。
+    pub async fn select_providers_for_model(
+        &self,
+        app_type: &str,
+        model: &str,
+    ) -> Result<ProviderCandidates, AppError> {
+        let rules = match self.db.list_model_routing_rules(app_type) {
+            Ok(rules) => rules,
+            Err(e) => {
+                log::warn!("[{app_type}] 读取模型路由规则失败: {e}，使用默认供应商选择");
+                Vec::new()
+            }
+        };
+        let Some(rule) = model_router::find_rule(&rules, model) else {
+            return self.select_providers(app_type).await;
+        };
+
+        log::info!(
+            "[{app_type}] 模型 {model} 命中路由规则 {}，候选供应商: {}",
+            rule.pattern,
+            rule.provider_ids.join(" → ")
+        );
+
+        let (auto_failover_enabled, _) = self.failover_settings(app_type).await;
+        let all_providers = self.db.get_all_providers(app_type)?;
+        let mut candidates = rule
+            .provider_ids
+            .iter()
+            .filter_map(|id| all_providers.get(id).cloned());
+
+        let mut selection = Selection {
+            source: CandidateSource::ModelRule,
+            ..Default::default()
+        };
+        if auto_failover_enabled {
+            selection.total = rule.provider_ids.len();
+            for provider in candidates {
+                self.admit(app_type, provider, true, &mut selection).await;
             }
+        } else if let Some(first) = candidates.next() {
+            selection.total = 1;
+            self.admit(app_type, first, false, &mut selection).await;
         }
 
-        Ok(result)
+        selection.finish(app_type)
+    }
+
+    /// 读取应用的故障转移开关与路由策略
+    async fn failover_settings(&self, app_type: &str) -> (bool, RoutingStrategy) {
+        match self.db.get_proxy_config_for_app(app_type).await {
+            Ok(config) => (config.auto_failover_enabled, config.routing_strategy),
+            Err(e) => {
+                log::error!("[{app_type}] 读取 proxy_config 失败: {e}，默认禁用故障转移");
+                (false, RoutingStrategy::Priority)
+            }
+        }
+    }
+
+    /// 检查熔断器（可选）与消费限额，可用时加入候选列表
+    async fn admit(
+        &self,
+        app_type: &str,
+        provider: Provider,
+        check_circuit: bool,
+        selection: &mut Selection,
+    ) {
+        if check_circuit {
+            let circuit_key = format!("{app_type}:{}", provider.id);
+            let breaker = self.get_or_create_circuit_breaker(&circuit_key).await;
+
+            if !breaker.is_available().await {
+                selection.circuit_open += 1;
+                return;
+            }
+        }
+
+        let budget = self.budget_tracker.check(app_type, &provider).await;
+        if budget.is_exceeded() {
+            let reason = budget.describe(&provider.name);
+            log::info!("[{app_type}] 跳过已超出消费限额的供应商: {reason}");
+            selection.budget_exceeded.push(reason);
+            return;
+        }
+
+        selection.providers.push(provider);
+    }
+
+    /// 按会话粘性调整供应商顺序
+    ///
+    /// 会话已绑定且绑定的供应商仍在候选列表中时将其移到队首；
+    /// 绑定的供应商已熔断或被跳过时保持原顺序（Fallback），由请求成功后重新绑定。
+    pub async fn apply_session_affinity(
+        &self,
+        app_type: &str,
+        session_id: &str,
+        ttl_seconds: u32,
+        providers: Vec<Provider>,
+    ) -> (Vec<Provider>, AffinityDecision) {
+        self.session_affinity
+            .apply(
+                app_type,
+                session_id,
+                Duration::from_secs(ttl_seconds as u64),
+                providers,
+            )
+            .await
+    }
+
+    /// 将会话绑定（或续期）到实际服务请求的供应商
+    pub async fn bind_session(
+        &self,
+        app_type: &str,
+        session_id: &str,
+        provider_id: &str,
+        ttl_seconds: u32,
+    ) {
+        self.session_affinity
+            .bind(
+                app_type,
+                session_id,
+                provider_id,
+                Duration::from_secs(ttl_seconds as u64),
+            )
+            .await;
+    }
+
+    /// 检查供应商是否超出消费限额（内存计算，不查询数据库）
+    pub async fn check_provider_budget(&self, provider: &Provider, app_type: &str) -> BudgetCheck {
+        self.budget_tracker.check(app_type, provider).await
+    }
+
+    /// 累加供应商消费（由使用量记录调用）
+    pub async fn record_provider_spend(&self, provider_id: &str, app_type: &str, cost_usd: f64) {
+        self.budget_tracker
+            .record_spend(app_type, provider_id, cost_usd)
+            .await;
+    }
+
+    /// 尝试占用供应商的 RPM/TPM 额度（等待时间不超过 `max_wait` 时排队）
+    pub async fn acquire_provider_rate(
+        &self,
+        provider: &Provider,
+        app_type: &str,
+        max_wait: Duration,
+    ) -> RateDecision {
+        self.rate_limiter
+            .acquire_or_wait(
+                &RateLimiter::provider_key(app_type, &provider.id),
+                RateLimitConfig::from_provider(provider),
+                max_wait,
+            )
+            .await
+    }
+
+    /// 尝试占用客户端令牌的 RPM/TPM 额度（等待时间不超过 `max_wait` 时排队）
+    pub async fn acquire_client_rate(
+        &self,
+        token_id: &str,
+        limits: RateLimitConfig,
+        max_wait: Duration,
+    ) -> RateDecision {
+        self.rate_limiter
+            .acquire_or_wait(&RateLimiter::client_key(token_id), limits, max_wait)
+            .await
+    }
+
+    /// 扣减供应商与客户端的 TPM 额度（由使用量记录调用）
+    pub async fn record_rate_tokens(
+        &self,
+        provider_id: &str,
+        app_type: &str,
+        client_token_id: Option<&str>,
+        tokens: u64,
+    ) {
+        self.rate_limiter
+            .record_tokens(&RateLimiter::provider_key(app_type, provider_id), tokens)
+            .await;
+        if let Some(token_id) = client_token_id {
+            self.rate_limiter
+                .record_tokens(&RateLimiter::client_key(token_id), tokens)
+                .await;
+        }
+    }
+
+    /// 当前所有令牌桶水位
+    pub async fn rate_limit_levels(&self) -> Vec<RateLimitLevel> {
+        self.rate_limiter.levels().await
+    }
+
+    /// Gemini CLI OAuth 管理器
+    pub fn gemini_oauth(&self) -> &GeminiOAuthManager {
+        &self.gemini_oauth
+    }
+
+    /// 标记供应商开始处理一次请求（用于 least_in_flight 策略）
+    pub async fn begin_provider_request(&self, provider_id: &str, app_type: &str) -> InFlightGuard {
+        self.load_balancer
+            .begin_request(app_type, provider_id)
+            .await
+    }
+
+    /// 所有供应商的进行中请求数（用于指标导出）
+    pub async fn in_flight_counts(&self) -> Vec<(String, usize)> {
+        self.load_balancer.in_flight_snapshot().await
+    }
+
+    /// 记录供应商成功请求的延迟样本（用于 lowest_latency 策略）
+    pub async fn record_provider_latency(
+        &self,
+        provider_id: &str,
+        app_type: &str,
+        latency_ms: u64,
+    ) {
+        self.load_balancer
+            .record_latency(app_type, provider_id, latency_ms)
+            .await;
     }
 
     /// 请求执行前获取熔断器“放行许可”
@@ -218,6 +541,17 @@ impl ProviderRouter {
         }
     }
 
+    /// 所有熔断器的当前状态（key 格式: "app_type:provider_id"）
+    pub async fn circuit_states(&self) -> Vec<(String, CircuitState)> {
+        let breakers = self.circuit_breakers.read().await;
+        let mut states = Vec::with_capacity(breakers.len());
+        for (key, breaker) in breakers.iter() {
+            states.push((key.clone(), breaker.get_state().await));
+        }
+        states.sort_by(|a, b| a.0.cmp(&b.0));
+        states
+    }
+
     /// 获取或创建熔断器
     async fn get_or_create_circuit_breaker(&self, key: &str) -> Arc<CircuitBreaker> {
         // 先尝试读锁获取
@@ -334,7 +668,7 @@ mod tests {
         db.add_to_failover_queue("claude", "b").unwrap();
 
         let router = ProviderRouter::new(db.clone());
-        let providers = router.select_providers("claude").await.unwrap();
+        let providers = router.select_providers("claude").await.unwrap().providers;
 
         assert_eq!(providers.len(), 1);
         assert_eq!(providers[0].id, "a");
@@ -367,7 +701,7 @@ mod tests {
         db.update_proxy_config_for_app(config).await.unwrap();
 
         let router = ProviderRouter::new(db.clone());
-        let providers = router.select_providers("claude").await.unwrap();
+        let providers = router.select_providers("claude").await.unwrap().providers;
 
         assert_eq!(providers.len(), 2);
         // 故障转移开启时：仅按队列顺序选择（忽略当前供应商）
@@ -375,6 +709,85 @@ mod tests {
         assert_eq!(providers[1].id, "a");
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_select_providers_for_model_uses_matching_rule() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        for id in ["main", "cheap", "backup"] {
+            let provider = Provider::with_id(id.to_string(), id.to_string(), json!({}), None);
+            db.save_provider("claude", &provider).unwrap();
+        }
+        db.set_current_provider("claude", "main").unwrap();
+        db.add_to_failover_queue("claude", "main").unwrap();
+        db.save_model_routing_rule(&crate::database::ModelRoutingRule {
+            id: String::new(),
+            app_type: "claude".to_string(),
+            pattern: "*haiku*".to_string(),
+            provider_ids: vec![
+                "cheap".to_string(),
+                "missing".to_string(),
+                "backup".to_string(),
+            ],
+            enabled: true,
+            sort_index: 0,
+            created_at: 0,
+        })
+        .unwrap();
+
+        let router = ProviderRouter::new(db.clone());
+        let ids = |candidates: ProviderCandidates| -> Vec<String> {
+            candidates.providers.into_iter().map(|p| p.id).collect()
+        };
+
+        // 故障转移关闭：命中规则时只使用规则中的第一个供应商，未命中时使用当前供应商
+        let haiku = router
+            .select_providers_for_model("claude", "claude-haiku-4-5")
+            .await
+            .unwrap();
+        assert_eq!(ids(haiku), vec!["cheap"]);
+        let opus = router
+            .select_providers_for_model("claude", "claude-opus-4-6")
+            .await
+            .unwrap();
+        assert_eq!(ids(opus), vec!["main"]);
+
+        // 故障转移开启：按规则列表顺序，跳过不存在和已熔断的供应商
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        config.auto_failover_enabled = true;
+        config.circuit_failure_threshold = 1;
+        db.update_proxy_config_for_app(config).await.unwrap();
+        db.update_circuit_breaker_config(&CircuitBreakerConfig {
+            failure_threshold: 1,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let haiku = router
+            .select_providers_for_model("claude", "claude-haiku-4-5")
+            .await
+            .unwrap();
+        assert_eq!(haiku.source, CandidateSource::ModelRule);
+        // 规则内的故障转移只计数，不会把规则供应商切换为当前供应商
+        assert_eq!(
+            haiku.source.failover_outcome("cheap", "backup", "main"),
+            FailoverOutcome::Counted
+        );
+        assert_eq!(ids(haiku), vec!["cheap", "backup"]);
+
+        router
+            .record_result("cheap", "claude", false, false, Some("fail".to_string()))
+            .await
+            .unwrap();
+        let haiku = router
+            .select_providers_for_model("claude", "claude-haiku-4-5")
+            .await
+            .unwrap();
+        assert_eq!(ids(haiku), vec!["backup"]);
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_failover_enabled_uses_queue_only_even_if_current_not_in_queue() {
@@ -399,7 +812,7 @@ mod tests {
         db.update_proxy_config_for_app(config).await.unwrap();
 
         let router = ProviderRouter::new(db.clone());
-        let providers = router.select_providers("claude").await.unwrap();
+        let providers = router.select_providers("claude").await.unwrap().providers;
 
         assert_eq!(providers.len(), 1);
         assert_eq!(providers[0].id, "b");
@@ -442,7 +855,7 @@ mod tests {
             .await
             .unwrap();
 
-        let providers = router.select_providers("claude").await.unwrap();
+        let providers = router.select_providers("claude").await.unwrap().providers;
         assert_eq!(providers.len(), 2);
 
         assert!(router.allow_provider_request("b", "claude").await.allowed);
@@ -500,4 +913,170 @@ mod tests {
         assert!(third.allowed);
         assert!(third.used_half_open_permit);
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_select_providers_skips_over_budget_provider() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        let mut provider_a =
+            Provider::with_id("a".to_string(), "Provider A".to_string(), json!({}), None);
+        provider_a.sort_index = Some(1);
+        provider_a.meta = Some(crate::provider::ProviderMeta {
+            limit_daily_usd: Some("1.0".to_string()),
+            ..Default::default()
+        });
+        let mut provider_b =
+            Provider::with_id("b".to_string(), "Provider B".to_string(), json!({}), None);
+        provider_b.sort_index = Some(2);
+
+        db.save_provider("claude", &provider_a).unwrap();
+        db.save_provider("claude", &provider_b).unwrap();
+        db.add_to_failover_queue("claude", "a").unwrap();
+        db.add_to_failover_queue("claude", "b").unwrap();
+
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        config.auto_failover_enabled = true;
+        db.update_proxy_config_for_app(config).await.unwrap();
+
+        let router = ProviderRouter::new(db.clone());
+        let providers = router.select_providers("claude").await.unwrap().providers;
+        assert_eq!(providers.len(), 2);
+
+        // 内存累计超过每日限额后，a 被跳过
+        router.record_provider_spend("a", "claude", 1.5).await;
+        let providers = router.select_providers("claude").await.unwrap().providers;
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].id, "b");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_failover_disabled_rejects_over_budget_current_provider() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        let mut provider_a =
+            Provider::with_id("a".to_string(), "Provider A".to_string(), json!({}), None);
+        provider_a.meta = Some(crate::provider::ProviderMeta {
+            limit_monthly_usd: Some("2".to_string()),
+            ..Default::default()
+        });
+        db.save_provider("claude", &provider_a).unwrap();
+        db.set_current_provider("claude", "a").unwrap();
+
+        // 已有的今日消费记录在首次检查时从数据库加载
+        let seed_spend = || -> Result<(), AppError> {
+            let conn = crate::database::lock_conn!(db.conn);
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, total_cost_usd,
+                    latency_ms, status_code, created_at
+                ) VALUES ('r1', 'a', 'claude', 'm', '2.5', 10, 200, strftime('%s', 'now'))",
+                [],
+            )?;
+            crate::database::rebuild_usage_rollups(&conn)
+        };
+        seed_spend().unwrap();
+
+        let router = ProviderRouter::new(db.clone());
+        let err = router.select_providers("claude").await.unwrap_err();
+        assert!(matches!(err, AppError::ProviderBudgetExceeded(_)));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_routing_strategy_round_trips_and_reorders_queue() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        let mut provider_a =
+            Provider::with_id("a".to_string(), "Provider A".to_string(), json!({}), None);
+        provider_a.sort_index = Some(1);
+        let mut provider_b =
+            Provider::with_id("b".to_string(), "Provider B".to_string(), json!({}), None);
+        provider_b.sort_index = Some(2);
+
+        db.save_provider("claude", &provider_a).unwrap();
+        db.save_provider("claude", &provider_b).unwrap();
+        db.add_to_failover_queue("claude", "a").unwrap();
+        db.add_to_failover_queue("claude", "b").unwrap();
+
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        assert_eq!(config.routing_strategy, RoutingStrategy::Priority);
+        config.auto_failover_enabled = true;
+        config.routing_strategy = RoutingStrategy::LeastInFlight;
+        db.update_proxy_config_for_app(config).await.unwrap();
+        assert_eq!(
+            db.get_proxy_config_for_app("claude")
+                .await
+                .unwrap()
+                .routing_strategy,
+            RoutingStrategy::LeastInFlight
+        );
+
+        let router = ProviderRouter::new(db.clone());
+        let _guard = router.begin_provider_request("a", "claude").await;
+        let providers = router.select_providers("claude").await.unwrap().providers;
+
+        // a 有进行中的请求，b 排到前面，a 仍保留在故障转移链中
+        assert_eq!(providers.len(), 2);
+        assert_eq!(providers[0].id, "b");
+        assert_eq!(providers[1].id, "a");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_balanced_traffic_leaves_current_provider_unchanged() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        for id in ["a", "b"] {
+            let provider = Provider::with_id(id.to_string(), id.to_string(), json!({}), None);
+            db.save_provider("claude", &provider).unwrap();
+            db.add_to_failover_queue("claude", id).unwrap();
+        }
+        db.set_current_provider("claude", "a").unwrap();
+
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        config.auto_failover_enabled = true;
+        config.routing_strategy = RoutingStrategy::LeastInFlight;
+        db.update_proxy_config_for_app(config.clone())
+            .await
+            .unwrap();
+
+        let router = ProviderRouter::new(db.clone());
+        let _guard = router.begin_provider_request("a", "claude").await;
+        let candidates = router.select_providers("claude").await.unwrap();
+        assert_eq!(candidates.source, CandidateSource::Balancer);
+        assert_eq!(candidates.providers[0].id, "b");
+
+        // 均衡到非当前供应商或从它故障转移，都不会切换当前供应商
+        let first = candidates.providers[0].id.as_str();
+        for served in &candidates.providers {
+            assert_ne!(
+                candidates.source.failover_outcome(first, &served.id, "a"),
+                FailoverOutcome::Switch
+            );
+        }
+        assert_eq!(
+            candidates.source.failover_outcome(first, "a", "a"),
+            FailoverOutcome::Counted
+        );
+
+        // priority 策略下离开首选供应商才切换
+        config.routing_strategy = RoutingStrategy::Priority;
+        db.update_proxy_config_for_app(config).await.unwrap();
+        let candidates = router.select_providers("claude").await.unwrap();
+        assert_eq!(candidates.source, CandidateSource::FailoverQueue);
+        assert_eq!(
+            candidates.source.failover_outcome("a", "a", "a"),
+            FailoverOutcome::None
+        );
+        assert_eq!(
+            candidates.source.failover_outcome("a", "b", "a"),
+            FailoverOutcome::Switch
+        );
+    }
 }
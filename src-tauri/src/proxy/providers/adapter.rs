@@ -95,6 +95,17 @@ pub trait ProviderAdapter: Send + Sync {
         false
     }
 
+    /// 格式转换时实际请求的上游端点
+    ///
+    /// 仅在 `needs_transform` 返回 `true` 时调用。返回 `None` 表示该端点不做格式转换（透传）。
+    /// 默认实现转换所有端点且端点保持不变。
+    ///
+    /// # Arguments
+    /// * `endpoint` - 客户端请求的端点
+    fn transform_endpoint(&self, endpoint: &str) -> Option<String> {
+        Some(endpoint.to_string())
+    }
+
     /// 转换请求体
     ///
     /// 将请求体从一种格式转换为另一种格式（如 Anthropic → OpenAI）。
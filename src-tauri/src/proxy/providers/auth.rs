@@ -40,42 +40,33 @@ impl AuthInfo {
     /// 如果 key 长度不足8位，则返回 `***`
     #[allow(dead_code)]
     pub fn masked_key(&self) -> String {
-        if self.api_key.chars().count() > 8 {
-            let prefix: String = self.api_key.chars().take(4).collect();
-            let suffix: String = self
-                .api_key
-                .chars()
-                .rev()
-                .take(4)
-                .collect::<Vec<_>>()
-                .into_iter()
-                .rev()
-                .collect();
-            format!("{prefix}...{suffix}")
-        } else {
-            "***".to_string()
-        }
+        mask_secret(&self.api_key)
     }
 
     /// 返回遮蔽后的 access_token（用于日志输出）
     #[allow(dead_code)]
     pub fn masked_access_token(&self) -> Option<String> {
-        self.access_token.as_ref().map(|token| {
-            if token.chars().count() > 8 {
-                let prefix: String = token.chars().take(4).collect();
-                let suffix: String = token
-                    .chars()
-                    .rev()
-                    .take(4)
-                    .collect::<Vec<_>>()
-                    .into_iter()
-                    .rev()
-                    .collect();
-                format!("{prefix}...{suffix}")
-            } else {
-                "***".to_string()
-            }
-        })
+        self.access_token.as_deref().map(mask_secret)
+    }
+}
+
+/// 遮蔽密钥（用于日志输出与抓包记录）
+///
+/// 显示前4位和后4位，中间用 `...` 代替；长度不足9位时返回 `***`
+pub fn mask_secret(secret: &str) -> String {
+    if secret.chars().count() > 8 {
+        let prefix: String = secret.chars().take(4).collect();
+        let suffix: String = secret
+            .chars()
+            .rev()
+            .take(4)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect();
+        format!("{prefix}...{suffix}")
+    } else {
+        "***".to_string()
     }
 }
 
@@ -1,10 +1,11 @@
 //! Claude (Anthropic) Provider Adapter
 //!
-//! 支持透传模式和 OpenAI Chat Completions 格式转换模式
+//! 支持透传模式和 OpenAI Chat Completions / Gemini 格式转换模式
 //!
 //! ## API 格式
 //! - **anthropic** (默认): Anthropic Messages API 格式，直接透传
 //! - **openai_chat**: OpenAI Chat Completions 格式，需要 Anthropic ↔ OpenAI 转换
+//! - **gemini**: Gemini generateContent 格式，需要 Anthropic ↔ Gemini 转换（使用 Google 认证）
 //!
 //! ## 认证模式
 //! - **Claude**: Anthropic 官方 API (x-api-key + anthropic-version)
@@ -12,7 +13,7 @@
 //! - **OpenRouter**: 已支持 Claude Code 兼容接口，默认透传
 //! - **GitHubCopilot**: GitHub Copilot (OAuth + Copilot Token)
 
-use super::{AuthInfo, AuthStrategy, ProviderAdapter, ProviderType};
+use super::{AuthInfo, AuthStrategy, GeminiAdapter, ProviderAdapter, ProviderType};
 use crate::provider::Provider;
 use crate::proxy::error::ProxyError;
 use reqwest::RequestBuilder;
@@ -83,15 +84,12 @@ impl ClaudeAdapter {
     /// 从 provider.meta.api_format 读取格式设置：
     /// - "anthropic" (默认): Anthropic Messages API 格式，直接透传
     /// - "openai_chat": OpenAI Chat Completions 格式，需要格式转换
-    fn get_api_format(&self, provider: &Provider) -> &'static str {
+    /// - "gemini": Gemini generateContent 格式，需要格式转换
+    pub fn get_api_format(&self, provider: &Provider) -> &'static str {
         // 1) Preferred: meta.apiFormat (SSOT, never written to Claude Code config)
         if let Some(meta) = provider.meta.as_ref() {
             if let Some(api_format) = meta.api_format.as_deref() {
-                return if api_format == "openai_chat" {
-                    "openai_chat"
-                } else {
-                    "anthropic"
-                };
+                return normalize_api_format(api_format);
             }
         }
 
@@ -101,11 +99,7 @@ impl ClaudeAdapter {
             .get("api_format")
             .and_then(|v| v.as_str())
         {
-            return if api_format == "openai_chat" {
-                "openai_chat"
-            } else {
-                "anthropic"
-            };
+            return normalize_api_format(api_format);
         }
 
         // 3) Backward compatibility: legacy openrouter_compat_mode (bool/number/string)
@@ -172,6 +166,15 @@ impl ClaudeAdapter {
                 log::debug!("[Claude] 使用 ANTHROPIC_API_KEY");
                 return Some(key.to_string());
             }
+            // Gemini key（apiFormat = "gemini"）
+            if let Some(key) = env
+                .get("GEMINI_API_KEY")
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+            {
+                log::debug!("[Claude] 使用 GEMINI_API_KEY");
+                return Some(key.to_string());
+            }
             // OpenRouter key
             if let Some(key) = env
                 .get("OPENROUTER_API_KEY")
@@ -209,6 +212,15 @@ impl ClaudeAdapter {
     }
 }
 
+/// 规范化 API 格式，未知值回退为 "anthropic"
+fn normalize_api_format(api_format: &str) -> &'static str {
+    match api_format {
+        "openai_chat" => "openai_chat",
+        "gemini" => "gemini",
+        _ => "anthropic",
+    }
+}
+
 impl Default for ClaudeAdapter {
     fn default() -> Self {
         Self::new()
@@ -271,6 +283,20 @@ impl ProviderAdapter for ClaudeAdapter {
             ));
         }
 
+        // Gemini 格式：沿用 GeminiAdapter 的认证方式（API Key 或 OAuth access_token）
+        if self.get_api_format(provider) == "gemini" {
+            let key = self.extract_key(provider)?;
+            return Some(
+                match GeminiAdapter::new()
+                    .parse_oauth_credentials(&key)
+                    .filter(|creds| !creds.access_token.is_empty())
+                {
+                    Some(creds) => AuthInfo::with_access_token(key, creds.access_token),
+                    None => AuthInfo::new(key, AuthStrategy::Google),
+                },
+            );
+        }
+
         let strategy = match provider_type {
             ProviderType::OpenRouter => AuthStrategy::Bearer,
             ProviderType::ClaudeAuth => AuthStrategy::ClaudeAuth,
@@ -299,6 +325,10 @@ impl ProviderAdapter for ClaudeAdapter {
         while base.contains("/v1/v1") {
             base = base.replace("/v1/v1", "/v1");
         }
+        // Gemini 格式同理去除重复的 /v1beta/v1beta
+        while base.contains("/v1beta/v1beta") {
+            base = base.replace("/v1beta/v1beta", "/v1beta");
+        }
 
         // GitHub Copilot 不需要 ?beta=true 参数
         if base_url.contains("githubcopilot.com") {
@@ -342,7 +372,10 @@ impl ProviderAdapter for ClaudeAdapter {
                 .header("Editor-Version", "vscode/1.85.0")
                 .header("Editor-Plugin-Version", "copilot/1.150.0")
                 .header("Copilot-Integration-Id", "vscode-chat"),
-            _ => request,
+            // Gemini 格式: x-goog-api-key 或 OAuth Bearer
+            AuthStrategy::Google | AuthStrategy::GoogleOAuth => {
+                GeminiAdapter::new().add_auth_headers(request, auth)
+            }
         }
     }
 
@@ -355,14 +388,18 @@ impl ProviderAdapter for ClaudeAdapter {
         // 根据 api_format 配置决定是否需要格式转换
         // - "anthropic" (默认): 直接透传，无需转换
         // - "openai_chat": 需要 Anthropic ↔ OpenAI 格式转换
-        self.get_api_format(provider) == "openai_chat"
+        // - "gemini": 需要 Anthropic ↔ Gemini 格式转换
+        self.get_api_format(provider) != "anthropic"
     }
 
     fn transform_request(
         &self,
         body: serde_json::Value,
-        _provider: &Provider,
+        provider: &Provider,
     ) -> Result<serde_json::Value, ProxyError> {
+        if self.get_api_format(provider) == "gemini" && !self.is_github_copilot(provider) {
+            return super::transform_gemini::anthropic_to_gemini(body);
+        }
         super::transform::anthropic_to_openai(body)
     }
 
@@ -745,4 +782,49 @@ mod tests {
         // GitHub Copilot always needs transform
         assert!(adapter.needs_transform(&copilot));
     }
+
+    #[test]
+    fn test_gemini_format_uses_google_auth() {
+        let adapter = ClaudeAdapter::new();
+        let meta = ProviderMeta {
+            api_format: Some("gemini".to_string()),
+            ..Default::default()
+        };
+
+        let api_key = create_provider_with_meta(
+            json!({
+                "env": {
+                    "ANTHROPIC_BASE_URL": "https://generativelanguage.googleapis.com",
+                    "GEMINI_API_KEY": "AIza-test-key"
+                }
+            }),
+            meta.clone(),
+        );
+        assert!(adapter.needs_transform(&api_key));
+        let auth = adapter.extract_auth(&api_key).unwrap();
+        assert_eq!(auth.strategy, AuthStrategy::Google);
+        assert_eq!(auth.api_key, "AIza-test-key");
+
+        let oauth = create_provider_with_meta(
+            json!({
+                "env": {
+                    "ANTHROPIC_BASE_URL": "https://generativelanguage.googleapis.com",
+                    "ANTHROPIC_AUTH_TOKEN": "ya29.test-access-token"
+                }
+            }),
+            meta,
+        );
+        let auth = adapter.extract_auth(&oauth).unwrap();
+        assert_eq!(auth.strategy, AuthStrategy::GoogleOAuth);
+        assert_eq!(auth.access_token.as_deref(), Some("ya29.test-access-token"));
+
+        let url = adapter.build_url(
+            "https://generativelanguage.googleapis.com/v1beta",
+            "/v1beta/models/gemini-2.5-pro:generateContent",
+        );
+        assert_eq!(
+            url,
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-pro:generateContent"
+        );
+    }
 }
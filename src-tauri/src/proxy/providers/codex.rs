@@ -1,6 +1,10 @@
 //! Codex (OpenAI) Provider Adapter
 //!
-//! 仅透传模式，支持直连 OpenAI API
+//! 默认透传模式，支持直连 OpenAI API
+//!
+//! ## API 格式
+//! - **responses** (默认): Responses API 直接透传
+//! - **openai_chat**: Responses ↔ Chat Completions 格式转换，用于仅支持 Chat API 的中转服务
 //!
 //! ## 客户端检测
 //! 支持检测官方 Codex 客户端 (codex_vscode, codex_cli_rs)
@@ -33,6 +37,20 @@ impl CodexAdapter {
         CODEX_CLIENT_REGEX.is_match(user_agent)
     }
 
+    /// 获取 API 格式
+    ///
+    /// 从 `meta.apiFormat` 读取，未配置时为 "responses"（透传）
+    fn get_api_format(&self, provider: &Provider) -> &'static str {
+        match provider
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.api_format.as_deref())
+        {
+            Some("openai_chat") => "openai_chat",
+            _ => "responses",
+        }
+    }
+
     /// 从 Provider 配置中提取 API Key
     fn extract_key(&self, provider: &Provider) -> Option<String> {
         // 1. 尝试从 env 中获取
@@ -177,6 +195,29 @@ impl ProviderAdapter for CodexAdapter {
     fn add_auth_headers(&self, request: RequestBuilder, auth: &AuthInfo) -> RequestBuilder {
         request.header("Authorization", format!("Bearer {}", auth.api_key))
     }
+
+    fn needs_transform(&self, provider: &Provider) -> bool {
+        // - "responses" (默认): 直接透传
+        // - "openai_chat": 需要 Responses ↔ Chat Completions 格式转换
+        self.get_api_format(provider) == "openai_chat"
+    }
+
+    fn transform_endpoint(&self, endpoint: &str) -> Option<String> {
+        // Responses ↔ Chat 转换只作用于 /responses，/chat/completions 请求保持透传
+        (endpoint == "/responses").then(|| "/chat/completions".to_string())
+    }
+
+    fn transform_request(
+        &self,
+        body: serde_json::Value,
+        _provider: &Provider,
+    ) -> Result<serde_json::Value, ProxyError> {
+        super::transform_responses::responses_to_chat(body)
+    }
+
+    fn transform_response(&self, body: serde_json::Value) -> Result<serde_json::Value, ProxyError> {
+        super::transform_responses::chat_to_responses(body)
+    }
 }
 
 #[cfg(test)]
@@ -299,4 +340,22 @@ mod tests {
             "prefix_codex_cli_rs/1.0.0"
         ));
     }
+
+    #[test]
+    fn test_needs_transform_by_api_format() {
+        let adapter = CodexAdapter::new();
+        let mut provider = create_provider(json!({"base_url": "https://api.example.com/v1"}));
+        assert!(!adapter.needs_transform(&provider));
+
+        provider.meta = Some(crate::provider::ProviderMeta {
+            api_format: Some("openai_chat".to_string()),
+            ..Default::default()
+        });
+        assert!(adapter.needs_transform(&provider));
+        assert_eq!(
+            adapter.transform_endpoint("/responses").as_deref(),
+            Some("/chat/completions")
+        );
+        assert_eq!(adapter.transform_endpoint("/chat/completions"), None);
+    }
 }
@@ -6,34 +6,47 @@
 //! - **Gemini**: API Key 认证 (x-goog-api-key)
 //! - **GeminiCli**: OAuth Bearer 认证 (用于 Gemini CLI)
 
+use super::gemini_oauth::default_oauth_client;
 use super::{AuthInfo, AuthStrategy, ProviderAdapter, ProviderType};
 use crate::provider::Provider;
 use crate::proxy::error::ProxyError;
 use reqwest::RequestBuilder;
 
+/// access_token 过期前提前刷新的秒数
+const TOKEN_REFRESH_BUFFER_SECONDS: i64 = 60;
+
 /// Gemini 适配器
 pub struct GeminiAdapter;
 
 /// OAuth 凭证结构
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct OAuthCredentials {
     pub access_token: String,
     pub refresh_token: Option<String>,
     pub client_id: Option<String>,
     pub client_secret: Option<String>,
+    /// access_token 过期时间（Unix 秒，来自 `expiry_date` 毫秒时间戳）
+    pub expires_at: Option<i64>,
+    /// 凭证自带的 Token 端点（`token_uri`）
+    pub token_uri: Option<String>,
 }
 
-#[allow(dead_code)]
 impl OAuthCredentials {
-    /// 检查是否需要刷新 token（有 refresh_token 但没有有效的 access_token）
+    /// 检查是否需要刷新 token（有 refresh_token，且 access_token 缺失或即将过期）
     pub fn needs_refresh(&self) -> bool {
-        self.refresh_token.is_some() && self.access_token.is_empty()
+        if self.refresh_token.is_none() {
+            return false;
+        }
+        if self.access_token.is_empty() {
+            return true;
+        }
+        self.expires_at
+            .is_some_and(|at| at - chrono::Utc::now().timestamp() < TOKEN_REFRESH_BUFFER_SECONDS)
     }
 
-    /// 检查是否可以刷新 token
+    /// 检查是否可以刷新 token（缺少 OAuth 客户端时刷新会返回 `MissingClient`）
     pub fn can_refresh(&self) -> bool {
-        self.refresh_token.is_some() && self.client_id.is_some() && self.client_secret.is_some()
+        self.refresh_token.is_some()
     }
 }
 
@@ -78,6 +91,8 @@ impl GeminiAdapter {
                 refresh_token: None,
                 client_id: None,
                 client_secret: None,
+                expires_at: None,
+                token_uri: None,
             });
         }
 
@@ -93,14 +108,27 @@ impl GeminiAdapter {
                     .get("refresh_token")
                     .and_then(|v| v.as_str())
                     .map(|s| s.to_string());
-                let client_id = json
-                    .get("client_id")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string());
-                let client_secret = json
-                    .get("client_secret")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string());
+                let str_field = |name: &str| {
+                    json.get(name)
+                        .and_then(|v| v.as_str())
+                        .filter(|s| !s.is_empty())
+                        .map(|s| s.to_string())
+                };
+                // 未显式提供客户端时使用本机 Gemini CLI 的客户端（见 `default_oauth_client`）
+                let (client_id, client_secret) =
+                    match (str_field("client_id"), str_field("client_secret")) {
+                        (Some(id), Some(secret)) => (Some(id), Some(secret)),
+                        _ if refresh_token.is_some() => match default_oauth_client() {
+                            Some(client) => (Some(client.client_id), Some(client.client_secret)),
+                            None => (None, None),
+                        },
+                        (id, secret) => (id, secret),
+                    };
+                let expires_at = json
+                    .get("expiry_date")
+                    .and_then(|v| v.as_i64())
+                    .map(|ms| ms / 1000);
+                let token_uri = str_field("token_uri");
 
                 // 如果有 access_token 或 refresh_token，返回凭证
                 if !access_token.is_empty() || refresh_token.is_some() {
@@ -109,6 +137,8 @@ impl GeminiAdapter {
                         refresh_token,
                         client_id,
                         client_secret,
+                        expires_at,
+                        token_uri,
                     });
                 }
             }
@@ -118,7 +148,7 @@ impl GeminiAdapter {
     }
 
     /// 从 Provider 配置中提取原始 API Key
-    fn extract_key_raw(&self, provider: &Provider) -> Option<String> {
+    pub(crate) fn extract_key_raw(&self, provider: &Provider) -> Option<String> {
         if let Some(env) = provider.settings_config.get("env") {
             // 使用 GEMINI_API_KEY
             if let Some(key) = env.get("GEMINI_API_KEY").and_then(|v| v.as_str()) {
@@ -406,11 +436,51 @@ mod tests {
         let adapter = GeminiAdapter::new();
         let creds = adapter
             .parse_oauth_credentials(
-                "{\"access_token\":\"ya29.test\",\"refresh_token\":\"1//refresh\"}",
+                &json!({
+                    "access_token": "ya29.test",
+                    "refresh_token": "1//refresh",
+                    "client_id": "client.apps.googleusercontent.com",
+                    "client_secret": "secret"
+                })
+                .to_string(),
             )
             .unwrap();
         assert_eq!(creds.access_token, "ya29.test");
         assert_eq!(creds.refresh_token, Some("1//refresh".to_string()));
+        assert!(creds.can_refresh());
+        assert_eq!(
+            creds.client_id.as_deref(),
+            Some("client.apps.googleusercontent.com")
+        );
+    }
+
+    #[test]
+    fn test_oauth_credentials_needs_refresh_by_expiry() {
+        let adapter = GeminiAdapter::new();
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let creds_json = |expiry_ms: i64| {
+            json!({
+                "access_token": "ya29.old",
+                "refresh_token": "1//refresh",
+                "expiry_date": expiry_ms
+            })
+            .to_string()
+        };
+
+        let fresh = adapter
+            .parse_oauth_credentials(&creds_json(now_ms + 3_600_000))
+            .unwrap();
+        assert!(!fresh.needs_refresh());
+
+        let expiring = adapter
+            .parse_oauth_credentials(&creds_json(now_ms + 10_000))
+            .unwrap();
+        assert!(expiring.needs_refresh());
+
+        // 没有 refresh_token 时无法刷新
+        let direct = adapter.parse_oauth_credentials("ya29.direct").unwrap();
+        assert!(!direct.needs_refresh());
+        assert!(!direct.can_refresh());
     }
 
     #[test]
@@ -0,0 +1,575 @@
+//! Gemini CLI OAuth Token 刷新
+//!
+//! GeminiCli 供应商使用的 access_token（`ya29.` 开头）约 1 小时过期。
+//! 当凭证 JSON 中包含 refresh_token 时：
+//! 1. 请求前检查 `expiry_date`，即将过期则通过 refresh_token grant 换取新 token
+//! 2. 上游返回 401 时强制刷新一次并重试请求
+//! 3. 新 token 缓存在内存中，并写回供应商的 settings_config（重启后仍然有效）
+//!
+//! 刷新需要 OAuth 客户端（client_id/client_secret）。`~/.gemini/oauth_creds.json` 通常不含客户端，
+//! 凭证未携带时依次从环境变量、`oauth_creds.json` 与本机安装的 Gemini CLI 中读取。
+
+use super::{AuthInfo, GeminiAdapter};
+use crate::database::Database;
+use crate::provider::Provider;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{Mutex, RwLock};
+
+/// Google OAuth Token 端点
+const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+
+/// 覆盖 Token 端点的环境变量（用于本地联调/测试）
+const TOKEN_URL_ENV: &str = "CC_SWITCH_GEMINI_TOKEN_URL";
+
+/// 提前刷新的秒数（与 Copilot 保持一致）
+const TOKEN_REFRESH_BUFFER_SECONDS: i64 = 60;
+
+/// 指定 OAuth 客户端的环境变量
+const CLIENT_ID_ENV: &str = "CC_SWITCH_GEMINI_OAUTH_CLIENT_ID";
+const CLIENT_SECRET_ENV: &str = "CC_SWITCH_GEMINI_OAUTH_CLIENT_SECRET";
+
+/// Gemini CLI 安装目录中定义 OAuth 客户端的文件（相对 `gemini` 可执行文件所在的各级目录）
+const CLI_CLIENT_FILES: [&str; 3] = [
+    "node_modules/@google/gemini-cli-core/dist/src/code_assist/oauth2.js",
+    "node_modules/@google/gemini-cli/node_modules/@google/gemini-cli-core/dist/src/code_assist/oauth2.js",
+    "node_modules/@google/gemini-cli/bundle/gemini.js",
+];
+
+/// Gemini OAuth 错误
+#[derive(Debug, thiserror::Error)]
+pub enum GeminiOAuthError {
+    #[error("凭证缺少 refresh_token，无法刷新")]
+    NotRefreshable,
+
+    #[error(
+        "未找到 Gemini CLI 的 OAuth 客户端：请在凭证中填写 client_id/client_secret，\
+         或设置环境变量 {CLIENT_ID_ENV}/{CLIENT_SECRET_ENV}，或安装 Gemini CLI"
+    )]
+    MissingClient,
+
+    #[error("刷新 access_token 失败: {0}")]
+    RefreshFailed(String),
+
+    #[error("网络错误: {0}")]
+    NetworkError(String),
+
+    #[error("解析错误: {0}")]
+    ParseError(String),
+}
+
+impl From<reqwest::Error> for GeminiOAuthError {
+    fn from(err: reqwest::Error) -> Self {
+        GeminiOAuthError::NetworkError(err.to_string())
+    }
+}
+
+/// Token 端点响应
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<i64>,
+    /// 部分情况下 Google 会轮换 refresh_token
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+/// 内存缓存的 access_token
+#[derive(Debug, Clone)]
+struct CachedToken {
+    access_token: String,
+    expires_at: Option<i64>,
+}
+
+impl CachedToken {
+    fn is_expiring_soon(&self) -> bool {
+        self.expires_at
+            .is_some_and(|at| at - chrono::Utc::now().timestamp() < TOKEN_REFRESH_BUFFER_SECONDS)
+    }
+}
+
+/// Gemini CLI OAuth 管理器（代理运行期间共享）
+pub struct GeminiOAuthManager {
+    db: Arc<Database>,
+    /// Token 端点覆盖（优先于凭证中的 token_uri）
+    token_url: Option<String>,
+    http_client: Client,
+    /// 按 refresh_token 缓存的最新 access_token
+    tokens: RwLock<HashMap<String, CachedToken>>,
+    /// 串行化刷新，避免并发请求同时刷新同一凭证
+    refresh_lock: Mutex<()>,
+}
+
+impl GeminiOAuthManager {
+    pub fn new(db: Arc<Database>) -> Self {
+        let token_url = std::env::var(TOKEN_URL_ENV)
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+        Self::with_token_url(db, token_url)
+    }
+
+    pub fn with_token_url(db: Arc<Database>, token_url: Option<String>) -> Self {
+        Self {
+            db,
+            token_url,
+            http_client: Client::new(),
+            tokens: RwLock::new(HashMap::new()),
+            refresh_lock: Mutex::new(()),
+        }
+    }
+
+    /// 检查认证信息是否可以刷新
+    pub fn can_refresh(auth: &AuthInfo) -> bool {
+        GeminiAdapter::new()
+            .parse_oauth_credentials(&auth.api_key)
+            .is_some_and(|creds| creds.can_refresh())
+    }
+
+    /// 返回可用的认证信息
+    ///
+    /// - `force_refresh = false`：缓存或凭证中的 token 仍有效时直接使用，否则刷新
+    /// - `force_refresh = true`：上游返回 401 后调用，强制刷新（并发请求已刷新过则复用）
+    ///
+    /// `app_type` 为供应商所属应用，刷新后的凭证写回该应用下的供应商配置。
+    pub async fn resolve_auth(
+        &self,
+        provider: &Provider,
+        app_type: &str,
+        auth: AuthInfo,
+        force_refresh: bool,
+    ) -> Result<AuthInfo, GeminiOAuthError> {
+        let Some(creds) = GeminiAdapter::new().parse_oauth_credentials(&auth.api_key) else {
+            return Ok(auth);
+        };
+        let refresh_token = match creds.refresh_token.clone() {
+            Some(token) => token,
+            None if force_refresh => return Err(GeminiOAuthError::NotRefreshable),
+            None => return Ok(auth),
+        };
+
+        let cached = self.tokens.read().await.get(&refresh_token).cloned();
+        if !force_refresh {
+            if let Some(token) = cached.filter(|t| !t.is_expiring_soon()) {
+                return Ok(AuthInfo::with_access_token(
+                    auth.api_key,
+                    token.access_token,
+                ));
+            }
+            if !creds.needs_refresh() {
+                return Ok(auth);
+            }
+        }
+
+        let _guard = self.refresh_lock.lock().await;
+
+        // 等锁期间其他请求可能已完成刷新
+        let failed_token = auth.access_token.clone().unwrap_or_default();
+        if let Some(token) = self.tokens.read().await.get(&refresh_token) {
+            if !token.is_expiring_soon() && token.access_token != failed_token {
+                return Ok(AuthInfo::with_access_token(
+                    auth.api_key,
+                    token.access_token.clone(),
+                ));
+            }
+        }
+
+        let (Some(client_id), Some(client_secret)) =
+            (creds.client_id.as_deref(), creds.client_secret.as_deref())
+        else {
+            return Err(GeminiOAuthError::MissingClient);
+        };
+        let token_url = self
+            .token_url
+            .clone()
+            .or_else(|| creds.token_uri.clone())
+            .unwrap_or_else(|| GOOGLE_TOKEN_URL.to_string());
+        log::info!("[GeminiOAuth] 刷新供应商 {} 的 access_token", provider.name);
+
+        let response = self
+            .http_client
+            .post(&token_url)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token.as_str()),
+                ("client_id", client_id),
+                ("client_secret", client_secret),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(GeminiOAuthError::RefreshFailed(format!("{status}: {text}")));
+        }
+
+        let token: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| GeminiOAuthError::ParseError(e.to_string()))?;
+        let expires_at = token
+            .expires_in
+            .map(|secs| chrono::Utc::now().timestamp() + secs);
+
+        self.tokens.write().await.insert(
+            refresh_token.clone(),
+            CachedToken {
+                access_token: token.access_token.clone(),
+                expires_at,
+            },
+        );
+
+        let api_key = updated_credentials(
+            &auth.api_key,
+            &token.access_token,
+            expires_at,
+            token.refresh_token.as_deref(),
+        )
+        .unwrap_or(auth.api_key);
+        if let Err(e) = self.persist(provider, app_type, &api_key) {
+            log::warn!("[GeminiOAuth] 写回供应商凭证失败: {e}");
+        }
+
+        log::info!(
+            "[GeminiOAuth] access_token 刷新成功，过期时间: {}",
+            expires_at.unwrap_or_default()
+        );
+        Ok(AuthInfo::with_access_token(api_key, token.access_token))
+    }
+
+    /// 将刷新后的凭证写回供应商配置（保持原字段位置）
+    fn persist(
+        &self,
+        provider: &Provider,
+        app_type: &str,
+        api_key: &str,
+    ) -> Result<(), crate::error::AppError> {
+        let Some(mut stored) = self.db.get_provider_by_id(&provider.id, app_type)? else {
+            return Ok(());
+        };
+
+        let settings = &mut stored.settings_config;
+        let slot = if settings
+            .get("env")
+            .and_then(|env| env.get("GEMINI_API_KEY"))
+            .is_some()
+        {
+            settings
+                .get_mut("env")
+                .and_then(|env| env.get_mut("GEMINI_API_KEY"))
+        } else if settings.get("apiKey").is_some() {
+            settings.get_mut("apiKey")
+        } else {
+            settings.get_mut("api_key")
+        };
+
+        match slot {
+            Some(value) => *value = Value::String(api_key.to_string()),
+            None => return Ok(()),
+        }
+        self.db
+            .update_provider_settings_config(app_type, &provider.id, &stored.settings_config)
+    }
+}
+
+/// 刷新 token 使用的 OAuth 客户端
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OAuthClient {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// 凭证未携带客户端时使用的 OAuth 客户端
+///
+/// 依次查找环境变量、`~/.gemini/oauth_creds.json` 与本机安装的 Gemini CLI；找到后在进程内缓存。
+pub fn default_oauth_client() -> Option<OAuthClient> {
+    static CLIENT: OnceLock<OAuthClient> = OnceLock::new();
+    if let Some(client) = CLIENT.get() {
+        return Some(client.clone());
+    }
+
+    let client = env_client()
+        .or_else(oauth_creds_client)
+        .or_else(installed_cli_client)?;
+    Some(CLIENT.get_or_init(|| client).clone())
+}
+
+fn env_client() -> Option<OAuthClient> {
+    let read = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+    Some(OAuthClient {
+        client_id: read(CLIENT_ID_ENV)?,
+        client_secret: read(CLIENT_SECRET_ENV)?,
+    })
+}
+
+fn oauth_creds_client() -> Option<OAuthClient> {
+    let path = crate::gemini_config::get_gemini_dir().join("oauth_creds.json");
+    let json: Value = serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+    let field = |name: &str| {
+        json.get(name)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    Some(OAuthClient {
+        client_id: field("client_id")?,
+        client_secret: field("client_secret")?,
+    })
+}
+
+/// 从 PATH 中的 `gemini` 出发，在其所在的各级目录中查找 Gemini CLI 源码里的客户端常量
+fn installed_cli_client() -> Option<OAuthClient> {
+    let entry = std::fs::canonicalize(find_gemini_executable()?).ok()?;
+    std::iter::once(entry.clone())
+        .chain(entry.ancestors().skip(1).take(6).flat_map(|dir| {
+            CLI_CLIENT_FILES
+                .iter()
+                .map(move |file| dir.join(file))
+                .collect::<Vec<_>>()
+        }))
+        .filter(|path| path.is_file())
+        .find_map(|path| read_client_constants(&path))
+}
+
+fn find_gemini_executable() -> Option<PathBuf> {
+    let names: &[&str] = if cfg!(windows) {
+        &["gemini.cmd", "gemini.exe", "gemini"]
+    } else {
+        &["gemini"]
+    };
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+}
+
+fn read_client_constants(path: &Path) -> Option<OAuthClient> {
+    parse_client_constants(&std::fs::read_to_string(path).ok()?)
+}
+
+/// 解析 `OAUTH_CLIENT_ID = '...'` 与 `OAUTH_CLIENT_SECRET = '...'`
+fn parse_client_constants(source: &str) -> Option<OAuthClient> {
+    let constant = |name: &str| {
+        let re = regex::Regex::new(&format!(r#"\b{name}\s*=\s*['"]([^'"]+)['"]"#)).ok()?;
+        Some(re.captures(source)?[1].to_string())
+    };
+    Some(OAuthClient {
+        client_id: constant("OAUTH_CLIENT_ID")?,
+        client_secret: constant("OAUTH_CLIENT_SECRET")?,
+    })
+}
+
+/// 生成更新 access_token / expiry_date 后的凭证 JSON
+fn updated_credentials(
+    raw: &str,
+    access_token: &str,
+    expires_at: Option<i64>,
+    refresh_token: Option<&str>,
+) -> Option<String> {
+    let mut json: Value = serde_json::from_str(raw).ok()?;
+    let obj = json.as_object_mut()?;
+    obj.insert("access_token".into(), Value::String(access_token.into()));
+    if let Some(at) = expires_at {
+        // 与 Gemini CLI 一致，expiry_date 为毫秒时间戳
+        obj.insert("expiry_date".into(), Value::from(at * 1000));
+    }
+    if let Some(token) = refresh_token {
+        obj.insert("refresh_token".into(), Value::String(token.into()));
+    }
+    serde_json::to_string(&json).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::Provider;
+    use axum::{routing::post, Form, Json, Router};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 启动本地 Token 端点桩服务，返回 URL 与调用计数
+    async fn spawn_token_stub() -> (String, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let app = Router::new().route(
+            "/token",
+            post(move |Form(form): Form<HashMap<String, String>>| {
+                let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                async move {
+                    assert_eq!(form.get("grant_type").unwrap(), "refresh_token");
+                    assert_eq!(form.get("refresh_token").unwrap(), "1//refresh");
+                    assert!(form.get("client_id").is_some_and(|v| !v.is_empty()));
+                    Json(json!({
+                        "access_token": format!("ya29.new-{n}"),
+                        "expires_in": 3599,
+                        "token_type": "Bearer"
+                    }))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.ok();
+        });
+        (format!("http://{addr}/token"), calls)
+    }
+
+    fn oauth_provider(db: &Database, expiry_ms: i64) -> Provider {
+        oauth_provider_for(db, "gemini", expiry_ms)
+    }
+
+    fn oauth_provider_for(db: &Database, app_type: &str, expiry_ms: i64) -> Provider {
+        let creds = json!({
+            "access_token": "ya29.old",
+            "refresh_token": "1//refresh",
+            "client_id": "client.apps.googleusercontent.com",
+            "client_secret": "secret",
+            "expiry_date": expiry_ms
+        })
+        .to_string();
+        let provider = Provider::with_id(
+            "gcli".to_string(),
+            "Gemini CLI".to_string(),
+            json!({ "env": { "GEMINI_API_KEY": creds } }),
+            None,
+        );
+        db.save_provider(app_type, &provider).unwrap();
+        provider
+    }
+
+    fn auth_of(provider: &Provider) -> AuthInfo {
+        use super::super::ProviderAdapter;
+        GeminiAdapter::new().extract_auth(provider).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_refreshes_expired_token_and_persists() {
+        let db = Arc::new(Database::memory().unwrap());
+        let (url, calls) = spawn_token_stub().await;
+        let manager = GeminiOAuthManager::with_token_url(db.clone(), Some(url));
+        let provider = oauth_provider(&db, chrono::Utc::now().timestamp_millis() - 1000);
+
+        let auth = manager
+            .resolve_auth(&provider, "gemini", auth_of(&provider), false)
+            .await
+            .unwrap();
+        assert_eq!(auth.access_token.as_deref(), Some("ya29.new-1"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // 写回数据库的凭证包含新 token 与毫秒级过期时间
+        let stored = db.get_provider_by_id("gcli", "gemini").unwrap().unwrap();
+        let creds = GeminiAdapter::new()
+            .parse_oauth_credentials(
+                stored.settings_config["env"]["GEMINI_API_KEY"]
+                    .as_str()
+                    .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(creds.access_token, "ya29.new-1");
+        assert!(!creds.needs_refresh());
+
+        // 旧凭证再次进入时命中内存缓存，不再请求端点
+        let cached = manager
+            .resolve_auth(&provider, "gemini", auth_of(&provider), false)
+            .await
+            .unwrap();
+        assert_eq!(cached.access_token.as_deref(), Some("ya29.new-1"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_force_refresh_after_unauthorized() {
+        let db = Arc::new(Database::memory().unwrap());
+        let (url, calls) = spawn_token_stub().await;
+        let manager = GeminiOAuthManager::with_token_url(db.clone(), Some(url));
+        let provider = oauth_provider(&db, chrono::Utc::now().timestamp_millis() + 3_600_000);
+
+        // 未过期时直接使用原 token
+        let auth = manager
+            .resolve_auth(&provider, "gemini", auth_of(&provider), false)
+            .await
+            .unwrap();
+        assert_eq!(auth.access_token.as_deref(), Some("ya29.old"));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        // 上游 401 后强制刷新
+        let refreshed = manager
+            .resolve_auth(&provider, "gemini", auth, true)
+            .await
+            .unwrap();
+        assert_eq!(refreshed.access_token.as_deref(), Some("ya29.new-1"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_plain_access_token_is_not_refreshable() {
+        let db = Arc::new(Database::memory().unwrap());
+        let manager = GeminiOAuthManager::with_token_url(db, None);
+        let provider = Provider::with_id(
+            "plain".to_string(),
+            "Plain".to_string(),
+            json!({ "env": { "GEMINI_API_KEY": "ya29.plain" } }),
+            None,
+        );
+        let auth = auth_of(&provider);
+        assert!(!GeminiOAuthManager::can_refresh(&auth));
+
+        let same = manager
+            .resolve_auth(&provider, "gemini", auth.clone(), false)
+            .await
+            .unwrap();
+        assert_eq!(same.access_token.as_deref(), Some("ya29.plain"));
+        assert!(matches!(
+            manager.resolve_auth(&provider, "gemini", auth, true).await,
+            Err(GeminiOAuthError::NotRefreshable)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_refreshed_credentials_persist_under_provider_app() {
+        let db = Arc::new(Database::memory().unwrap());
+        let (url, _calls) = spawn_token_stub().await;
+        let manager = GeminiOAuthManager::with_token_url(db.clone(), Some(url));
+        // Claude 应用下使用 Gemini 格式的供应商
+        let provider =
+            oauth_provider_for(&db, "claude", chrono::Utc::now().timestamp_millis() - 1000);
+
+        manager
+            .resolve_auth(&provider, "claude", auth_of(&provider), false)
+            .await
+            .unwrap();
+
+        let stored = db.get_provider_by_id("gcli", "claude").unwrap().unwrap();
+        let key = stored.settings_config["env"]["GEMINI_API_KEY"]
+            .as_str()
+            .unwrap();
+        assert!(key.contains("ya29.new-1"));
+        assert!(db.get_provider_by_id("gcli", "gemini").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_client_constants_from_cli_source() {
+        let source = r#"
+const OAUTH_CLIENT_ID = '123-abc.apps.googleusercontent.com';
+// OAuth Secret value used to initiate OAuth2Client class.
+const OAUTH_CLIENT_SECRET = "installed-app-secret";
+"#;
+        assert_eq!(
+            parse_client_constants(source),
+            Some(OAuthClient {
+                client_id: "123-abc.apps.googleusercontent.com".to_string(),
+                client_secret: "installed-app-secret".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_client_constants("const OAUTH_CLIENT_ID = 'id-only';"),
+            None
+        );
+    }
+}
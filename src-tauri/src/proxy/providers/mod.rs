@@ -8,8 +8,11 @@
 //! - `claude`: Claude (Anthropic) 适配器
 //! - `codex`: Codex (OpenAI) 适配器
 //! - `gemini`: Gemini (Google) 适配器
+//! - `gemini_oauth`: Gemini CLI OAuth Token 刷新
 //! - `models`: API 数据模型
 //! - `transform`: 格式转换
+//! - `transform_gemini`: Anthropic ↔ Gemini 格式转换
+//! - `transform_responses`: Responses ↔ Chat Completions 格式转换
 
 mod adapter;
 mod auth;
@@ -17,9 +20,14 @@ mod claude;
 mod codex;
 pub mod copilot_auth;
 mod gemini;
+pub mod gemini_oauth;
 pub mod models;
 pub mod streaming;
+pub mod streaming_gemini;
+pub mod streaming_responses;
 pub mod transform;
+pub mod transform_gemini;
+pub mod transform_responses;
 
 use crate::app_config::AppType;
 use crate::provider::Provider;
@@ -27,7 +35,7 @@ use serde::{Deserialize, Serialize};
 
 // 公开导出
 pub use adapter::ProviderAdapter;
-pub use auth::{AuthInfo, AuthStrategy};
+pub use auth::{mask_secret, AuthInfo, AuthStrategy};
 pub use claude::ClaudeAdapter;
 pub use codex::CodexAdapter;
 pub use gemini::GeminiAdapter;
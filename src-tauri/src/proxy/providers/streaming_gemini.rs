@@ -0,0 +1,431 @@
+//! Gemini 流式响应转换模块
+//!
+//! 实现 Gemini SSE (`streamGenerateContent?alt=sse`) → Anthropic SSE 格式转换，
+//! 供 `api_format = "gemini"` 的 Claude 供应商使用
+
+use super::transform_gemini::{convert_usage, map_finish_reason, new_message_id, new_tool_use_id};
+use bytes::Bytes;
+use futures::stream::{Stream, StreamExt};
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockType {
+    Thinking,
+    Text,
+}
+
+/// Gemini SSE → Anthropic SSE 转换状态机
+///
+/// Gemini 流没有结束标记，usage 随每个 chunk 累计下发，
+/// 因此 `message_delta` / `message_stop` 在上游流结束时发送。
+#[derive(Debug, Default)]
+pub struct GeminiStreamConverter {
+    message_started: bool,
+    content_index: usize,
+    current_block: Option<BlockType>,
+    has_tool_use: bool,
+    finish_reason: Option<String>,
+    usage: Option<Value>,
+    finished: bool,
+}
+
+impl GeminiStreamConverter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 处理一条 SSE data 行，返回需要下发的 Anthropic SSE 事件
+    pub fn process_data(&mut self, data: &str) -> Vec<String> {
+        let mut events = Vec::new();
+        if self.finished {
+            return events;
+        }
+
+        let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+            return events;
+        };
+
+        if !self.message_started {
+            self.message_started = true;
+            let id = chunk
+                .get("responseId")
+                .and_then(|i| i.as_str())
+                .map(|id| format!("msg_{id}"))
+                .unwrap_or_else(new_message_id);
+            let model = chunk
+                .get("modelVersion")
+                .and_then(|m| m.as_str())
+                .unwrap_or_default();
+            push_event(
+                &mut events,
+                "message_start",
+                json!({
+                    "type": "message_start",
+                    "message": {
+                        "id": id,
+                        "type": "message",
+                        "role": "assistant",
+                        "model": model,
+                        "content": [],
+                        "stop_reason": null,
+                        "stop_sequence": null,
+                        "usage": {
+                            "input_tokens": 0,
+                            "output_tokens": 0
+                        }
+                    }
+                }),
+            );
+        }
+
+        if let Some(usage) = chunk.get("usageMetadata") {
+            self.usage = Some(usage.clone());
+        }
+
+        let Some(candidate) = chunk
+            .get("candidates")
+            .and_then(|c| c.as_array())
+            .and_then(|c| c.first())
+        else {
+            return events;
+        };
+
+        if let Some(parts) = candidate
+            .get("content")
+            .and_then(|c| c.get("parts"))
+            .and_then(|p| p.as_array())
+        {
+            for part in parts {
+                self.process_part(&mut events, part);
+            }
+        }
+
+        if let Some(reason) = candidate.get("finishReason").and_then(|r| r.as_str()) {
+            self.finish_reason = Some(reason.to_string());
+        }
+
+        events
+    }
+
+    /// 上游流结束
+    pub fn finish_stream(&mut self) -> Vec<String> {
+        let mut events = Vec::new();
+        if self.finished || !self.message_started {
+            return events;
+        }
+        self.finished = true;
+        self.close_block(&mut events);
+
+        let stop_reason = map_finish_reason(self.finish_reason.as_deref(), self.has_tool_use);
+        let usage = self
+            .usage
+            .as_ref()
+            .map(convert_usage)
+            .unwrap_or_else(|| json!({"input_tokens": 0, "output_tokens": 0}));
+        push_event(
+            &mut events,
+            "message_delta",
+            json!({
+                "type": "message_delta",
+                "delta": {
+                    "stop_reason": stop_reason,
+                    "stop_sequence": null
+                },
+                "usage": usage
+            }),
+        );
+        push_event(&mut events, "message_stop", json!({"type": "message_stop"}));
+        events
+    }
+
+    fn process_part(&mut self, events: &mut Vec<String>, part: &Value) {
+        let signature = part.get("thoughtSignature").and_then(|s| s.as_str());
+        let is_thought = part.get("thought").and_then(|t| t.as_bool()) == Some(true);
+
+        if is_thought {
+            let text = part.get("text").and_then(|t| t.as_str()).unwrap_or("");
+            self.ensure_block(events, BlockType::Thinking);
+            if !text.is_empty() {
+                push_event(
+                    events,
+                    "content_block_delta",
+                    json!({
+                        "type": "content_block_delta",
+                        "index": self.content_index,
+                        "delta": {"type": "thinking_delta", "thinking": text}
+                    }),
+                );
+            }
+            if let Some(signature) = signature {
+                self.push_signature(events, signature);
+            }
+            return;
+        }
+
+        // 非思考 part 上的签名单独放入空 thinking 块，便于下一轮回传
+        if let Some(signature) = signature {
+            self.close_block(events);
+            self.ensure_block(events, BlockType::Thinking);
+            self.push_signature(events, signature);
+            self.close_block(events);
+        }
+
+        if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+            if text.is_empty() {
+                return;
+            }
+            self.ensure_block(events, BlockType::Text);
+            push_event(
+                events,
+                "content_block_delta",
+                json!({
+                    "type": "content_block_delta",
+                    "index": self.content_index,
+                    "delta": {"type": "text_delta", "text": text}
+                }),
+            );
+        } else if let Some(call) = part.get("functionCall") {
+            // Gemini 的 functionCall 一次性完整下发
+            self.close_block(events);
+            self.has_tool_use = true;
+            push_event(
+                events,
+                "content_block_start",
+                json!({
+                    "type": "content_block_start",
+                    "index": self.content_index,
+                    "content_block": {
+                        "type": "tool_use",
+                        "id": new_tool_use_id(),
+                        "name": call.get("name").and_then(|n| n.as_str()).unwrap_or(""),
+                        "input": {}
+                    }
+                }),
+            );
+            let args = call.get("args").cloned().unwrap_or(json!({}));
+            push_event(
+                events,
+                "content_block_delta",
+                json!({
+                    "type": "content_block_delta",
+                    "index": self.content_index,
+                    "delta": {
+                        "type": "input_json_delta",
+                        "partial_json": serde_json::to_string(&args).unwrap_or_default()
+                    }
+                }),
+            );
+            push_event(
+                events,
+                "content_block_stop",
+                json!({"type": "content_block_stop", "index": self.content_index}),
+            );
+            self.content_index += 1;
+        }
+    }
+
+    fn push_signature(&mut self, events: &mut Vec<String>, signature: &str) {
+        push_event(
+            events,
+            "content_block_delta",
+            json!({
+                "type": "content_block_delta",
+                "index": self.content_index,
+                "delta": {"type": "signature_delta", "signature": signature}
+            }),
+        );
+    }
+
+    fn ensure_block(&mut self, events: &mut Vec<String>, block: BlockType) {
+        if self.current_block == Some(block) {
+            return;
+        }
+        self.close_block(events);
+
+        let content_block = match block {
+            BlockType::Thinking => json!({"type": "thinking", "thinking": ""}),
+            BlockType::Text => json!({"type": "text", "text": ""}),
+        };
+        push_event(
+            events,
+            "content_block_start",
+            json!({
+                "type": "content_block_start",
+                "index": self.content_index,
+                "content_block": content_block
+            }),
+        );
+        self.current_block = Some(block);
+    }
+
+    fn close_block(&mut self, events: &mut Vec<String>) {
+        if self.current_block.take().is_some() {
+            push_event(
+                events,
+                "content_block_stop",
+                json!({"type": "content_block_stop", "index": self.content_index}),
+            );
+            self.content_index += 1;
+        }
+    }
+}
+
+fn push_event(events: &mut Vec<String>, event_type: &str, event: Value) {
+    events.push(format!(
+        "event: {event_type}\ndata: {}\n\n",
+        serde_json::to_string(&event).unwrap_or_default()
+    ));
+}
+
+/// 创建 Anthropic SSE 流（上游为 Gemini SSE）
+pub fn create_anthropic_sse_stream_from_gemini(
+    stream: impl Stream<Item = Result<Bytes, reqwest::Error>> + Send + 'static,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send {
+    async_stream::stream! {
+        let mut buffer = String::new();
+        let mut converter = GeminiStreamConverter::new();
+
+        tokio::pin!(stream);
+
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(bytes) => {
+                    buffer.push_str(&String::from_utf8_lossy(&bytes));
+                    // Gemini SSE 可能使用 \r\n 分隔
+                    if buffer.contains('\r') {
+                        buffer = buffer.replace("\r\n", "\n");
+                    }
+
+                    while let Some(pos) = buffer.find("\n\n") {
+                        let block = buffer[..pos].to_string();
+                        buffer = buffer[pos + 2..].to_string();
+
+                        for line in block.lines() {
+                            if let Some(data) = line.strip_prefix("data:") {
+                                for event in converter.process_data(data.trim_start()) {
+                                    yield Ok(Bytes::from(event));
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::error!("[Claude/Gemini] Stream error: {e}");
+                    let error_event = json!({
+                        "type": "error",
+                        "error": {
+                            "type": "stream_error",
+                            "message": format!("Stream error: {e}")
+                        }
+                    });
+                    let sse_data = format!("event: error\ndata: {}\n\n",
+                        serde_json::to_string(&error_event).unwrap_or_default());
+                    yield Ok(Bytes::from(sse_data));
+                    return;
+                }
+            }
+        }
+
+        // 处理末尾没有空行结尾的数据
+        for line in buffer.lines() {
+            if let Some(data) = line.strip_prefix("data:") {
+                for event in converter.process_data(data.trim_start()) {
+                    yield Ok(Bytes::from(event));
+                }
+            }
+        }
+
+        for event in converter.finish_stream() {
+            yield Ok(Bytes::from(event));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_events(events: &[String]) -> Vec<Value> {
+        events
+            .iter()
+            .map(|e| {
+                let data = e.lines().find_map(|l| l.strip_prefix("data: ")).unwrap();
+                serde_json::from_str(data).unwrap()
+            })
+            .collect()
+    }
+
+    fn run(chunks: &[Value]) -> Vec<Value> {
+        let mut converter = GeminiStreamConverter::new();
+        let mut events = Vec::new();
+        for chunk in chunks {
+            events.extend(converter.process_data(&chunk.to_string()));
+        }
+        events.extend(converter.finish_stream());
+        parse_events(&events)
+    }
+
+    #[test]
+    fn test_text_stream_with_usage() {
+        let events = run(&[
+            json!({"responseId": "r1", "modelVersion": "gemini-2.5-flash", "candidates": [{"content": {"role": "model", "parts": [{"text": "Hel"}]}}]}),
+            json!({"candidates": [{"content": {"parts": [{"text": "lo"}]}, "finishReason": "STOP"}],
+                   "usageMetadata": {"promptTokenCount": 12, "candidatesTokenCount": 3}}),
+        ]);
+
+        let types: Vec<&str> = events.iter().map(|e| e["type"].as_str().unwrap()).collect();
+        assert_eq!(
+            types,
+            vec![
+                "message_start",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop",
+            ]
+        );
+        assert_eq!(events[0]["message"]["id"], "msg_r1");
+        assert_eq!(events[5]["delta"]["stop_reason"], "end_turn");
+        assert_eq!(events[5]["usage"]["input_tokens"], 12);
+        assert_eq!(events[5]["usage"]["output_tokens"], 3);
+
+        // 转换结果可被 Claude 流式 usage 解析器识别
+        let usage =
+            crate::proxy::usage::parser::TokenUsage::from_claude_stream_events(&events).unwrap();
+        assert_eq!(usage.input_tokens, 12);
+        assert_eq!(usage.output_tokens, 3);
+    }
+
+    #[test]
+    fn test_thinking_and_function_call_stream() {
+        let events = run(&[
+            json!({"candidates": [{"content": {"parts": [{"text": "plan", "thought": true}]}}]}),
+            json!({"candidates": [{"content": {"parts": [
+                {"functionCall": {"name": "Read", "args": {"path": "a.rs"}}, "thoughtSignature": "sig"}
+            ]}, "finishReason": "STOP"}]}),
+        ]);
+
+        assert_eq!(events[1]["content_block"]["type"], "thinking");
+        assert_eq!(events[2]["delta"]["thinking"], "plan");
+        // 思考块关闭后，签名单独成块
+        assert_eq!(events[3]["type"], "content_block_stop");
+        assert_eq!(events[4]["index"], 1);
+        assert_eq!(events[5]["delta"]["signature"], "sig");
+        assert_eq!(events[7]["content_block"]["type"], "tool_use");
+        assert_eq!(events[7]["index"], 2);
+        assert_eq!(events[8]["delta"]["partial_json"], "{\"path\":\"a.rs\"}");
+        let delta = events
+            .iter()
+            .find(|e| e["type"] == "message_delta")
+            .unwrap();
+        assert_eq!(delta["delta"]["stop_reason"], "tool_use");
+    }
+
+    #[test]
+    fn test_empty_stream_emits_nothing() {
+        let mut converter = GeminiStreamConverter::new();
+        assert!(converter.finish_stream().is_empty());
+    }
+}
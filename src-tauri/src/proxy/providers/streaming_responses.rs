@@ -0,0 +1,606 @@
+//! Responses 流式响应转换模块
+//!
+//! 实现 OpenAI Chat Completions SSE → Responses SSE 格式转换，
+//! 供 `api_format = "openai_chat"` 的 Codex 供应商使用
+
+use super::transform_responses::{
+    build_response, function_call_item, message_item, new_item_id, reasoning_item, response_id,
+};
+use bytes::Bytes;
+use futures::stream::{Stream, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Chat Completions 流式响应数据结构
+#[derive(Debug, Deserialize)]
+struct ChatStreamChunk {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    created: Option<i64>,
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    #[serde(default)]
+    usage: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: Option<Delta>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Delta {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    reasoning_content: Option<String>, // DeepSeek 等的推理内容
+    #[serde(default)]
+    reasoning: Option<String>, // OpenRouter 的推理内容
+    #[serde(default)]
+    tool_calls: Option<Vec<DeltaToolCall>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeltaToolCall {
+    #[serde(default)]
+    index: usize,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    function: Option<DeltaFunction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeltaFunction {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    arguments: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemKind {
+    Message,
+    Reasoning,
+    FunctionCall,
+}
+
+/// 正在构建的 output item
+#[derive(Debug)]
+struct OutputItem {
+    kind: ItemKind,
+    id: String,
+    /// 文本 / 推理摘要 / 函数参数
+    text: String,
+    call_id: String,
+    name: String,
+    done: bool,
+}
+
+impl OutputItem {
+    fn to_json(&self, status: &str) -> Value {
+        match self.kind {
+            ItemKind::Message => message_item(&self.id, &self.text, status),
+            ItemKind::Reasoning => reasoning_item(&self.id, &self.text),
+            ItemKind::FunctionCall => {
+                let mut item = function_call_item(&self.id, &self.call_id, &self.name, &self.text);
+                item["status"] = json!(status);
+                item
+            }
+        }
+    }
+}
+
+/// Chat SSE → Responses SSE 转换状态机
+///
+/// usage 通常在 finish_reason 之后的独立 chunk 中下发，
+/// 因此 `response.completed` 延迟到 `[DONE]` 或上游流结束时发送。
+#[derive(Debug, Default)]
+pub struct ResponsesStreamConverter {
+    response_id: Option<String>,
+    model: String,
+    created_at: Option<i64>,
+    sequence_number: u64,
+    items: Vec<OutputItem>,
+    /// 当前打开的 message / reasoning item 下标
+    open_text_item: Option<usize>,
+    /// Chat tool_call index → output item 下标
+    tool_items: HashMap<usize, usize>,
+    finish_reason: Option<String>,
+    usage: Option<Value>,
+    completed: bool,
+}
+
+impl ResponsesStreamConverter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 处理一条 SSE data 行，返回需要下发的 Responses SSE 事件
+    pub fn process_data(&mut self, data: &str) -> Vec<String> {
+        let mut events = Vec::new();
+        if self.completed {
+            return events;
+        }
+
+        if data.trim() == "[DONE]" {
+            log::debug!("[Codex/Chat] <<< Chat SSE: [DONE]");
+            self.finish(&mut events);
+            return events;
+        }
+
+        let Ok(chunk) = serde_json::from_str::<ChatStreamChunk>(data) else {
+            return events;
+        };
+
+        if self.response_id.is_none() {
+            self.response_id = Some(response_id(chunk.id.as_deref()));
+            self.model = chunk.model.clone().unwrap_or_default();
+            self.created_at = chunk.created;
+            let response = self.snapshot("in_progress", Vec::new());
+            self.emit(
+                &mut events,
+                "response.created",
+                json!({"response": response.clone()}),
+            );
+            self.emit(
+                &mut events,
+                "response.in_progress",
+                json!({"response": response}),
+            );
+        }
+
+        if let Some(usage) = chunk.usage.filter(|u| !u.is_null()) {
+            self.usage = Some(usage);
+        }
+
+        for choice in chunk.choices {
+            let delta = choice.delta.unwrap_or_default();
+
+            if let Some(reasoning) = delta.reasoning_content.or(delta.reasoning) {
+                if !reasoning.is_empty() {
+                    self.push_text(&mut events, ItemKind::Reasoning, &reasoning);
+                }
+            }
+
+            if let Some(content) = delta.content {
+                if !content.is_empty() {
+                    self.push_text(&mut events, ItemKind::Message, &content);
+                }
+            }
+
+            for tool_call in delta.tool_calls.unwrap_or_default() {
+                self.push_tool_call(&mut events, tool_call);
+            }
+
+            if choice.finish_reason.is_some() {
+                self.finish_reason = choice.finish_reason;
+            }
+        }
+
+        events
+    }
+
+    /// 上游流结束（未收到 [DONE] 时兜底）
+    pub fn finish_stream(&mut self) -> Vec<String> {
+        let mut events = Vec::new();
+        if !self.completed && self.response_id.is_some() {
+            self.finish(&mut events);
+        }
+        events
+    }
+
+    /// 上游流出错
+    pub fn fail(&mut self, message: &str) -> Vec<String> {
+        let mut events = Vec::new();
+        if self.completed {
+            return events;
+        }
+        self.completed = true;
+        if self.response_id.is_none() {
+            self.response_id = Some(response_id(None));
+        }
+        let mut response = self.snapshot("failed", Vec::new());
+        response["error"] = json!({"code": "stream_error", "message": message});
+        self.emit(
+            &mut events,
+            "response.failed",
+            json!({"response": response}),
+        );
+        events
+    }
+
+    fn push_text(&mut self, events: &mut Vec<String>, kind: ItemKind, text: &str) {
+        let index = match self.open_text_item {
+            Some(index) if self.items[index].kind == kind => index,
+            _ => {
+                self.close_text_item(events);
+                let index = self.open_item(events, kind, String::new(), String::new());
+                self.open_text_item = Some(index);
+                index
+            }
+        };
+
+        self.items[index].text.push_str(text);
+        let item_id = self.items[index].id.clone();
+        match kind {
+            ItemKind::Message => self.emit(
+                events,
+                "response.output_text.delta",
+                json!({"item_id": item_id, "output_index": index, "content_index": 0, "delta": text}),
+            ),
+            _ => self.emit(
+                events,
+                "response.reasoning_summary_text.delta",
+                json!({"item_id": item_id, "output_index": index, "summary_index": 0, "delta": text}),
+            ),
+        }
+    }
+
+    fn push_tool_call(&mut self, events: &mut Vec<String>, tool_call: DeltaToolCall) {
+        let (name, arguments) = match tool_call.function {
+            Some(f) => (f.name, f.arguments),
+            None => (None, None),
+        };
+
+        let index = match self.tool_items.get(&tool_call.index) {
+            Some(index) => *index,
+            None => {
+                self.close_text_item(events);
+                let index = self.open_item(
+                    events,
+                    ItemKind::FunctionCall,
+                    tool_call.id.unwrap_or_default(),
+                    name.unwrap_or_default(),
+                );
+                self.tool_items.insert(tool_call.index, index);
+                index
+            }
+        };
+
+        if let Some(arguments) = arguments.filter(|a| !a.is_empty()) {
+            self.items[index].text.push_str(&arguments);
+            let item_id = self.items[index].id.clone();
+            self.emit(
+                events,
+                "response.function_call_arguments.delta",
+                json!({"item_id": item_id, "output_index": index, "delta": arguments}),
+            );
+        }
+    }
+
+    fn open_item(
+        &mut self,
+        events: &mut Vec<String>,
+        kind: ItemKind,
+        call_id: String,
+        name: String,
+    ) -> usize {
+        let prefix = match kind {
+            ItemKind::Message => "msg",
+            ItemKind::Reasoning => "rs",
+            ItemKind::FunctionCall => "fc",
+        };
+        let item = OutputItem {
+            kind,
+            id: new_item_id(prefix),
+            text: String::new(),
+            call_id,
+            name,
+            done: false,
+        };
+        let index = self.items.len();
+        let item_id = item.id.clone();
+        let item_json = item.to_json("in_progress");
+        self.items.push(item);
+
+        self.emit(
+            events,
+            "response.output_item.added",
+            json!({"output_index": index, "item": item_json}),
+        );
+        match kind {
+            ItemKind::Message => self.emit(
+                events,
+                "response.content_part.added",
+                json!({
+                    "item_id": item_id,
+                    "output_index": index,
+                    "content_index": 0,
+                    "part": {"type": "output_text", "text": "", "annotations": []}
+                }),
+            ),
+            ItemKind::Reasoning => self.emit(
+                events,
+                "response.reasoning_summary_part.added",
+                json!({
+                    "item_id": item_id,
+                    "output_index": index,
+                    "summary_index": 0,
+                    "part": {"type": "summary_text", "text": ""}
+                }),
+            ),
+            ItemKind::FunctionCall => {}
+        }
+        index
+    }
+
+    fn close_text_item(&mut self, events: &mut Vec<String>) {
+        if let Some(index) = self.open_text_item.take() {
+            self.close_item(events, index);
+        }
+    }
+
+    fn close_item(&mut self, events: &mut Vec<String>, index: usize) {
+        if self.items[index].done {
+            return;
+        }
+        self.items[index].done = true;
+
+        let item_id = self.items[index].id.clone();
+        let text = self.items[index].text.clone();
+        match self.items[index].kind {
+            ItemKind::Message => {
+                self.emit(
+                    events,
+                    "response.output_text.done",
+                    json!({"item_id": item_id, "output_index": index, "content_index": 0, "text": text}),
+                );
+                self.emit(
+                    events,
+                    "response.content_part.done",
+                    json!({
+                        "item_id": item_id,
+                        "output_index": index,
+                        "content_index": 0,
+                        "part": {"type": "output_text", "text": text, "annotations": []}
+                    }),
+                );
+            }
+            ItemKind::Reasoning => {
+                self.emit(
+                    events,
+                    "response.reasoning_summary_text.done",
+                    json!({"item_id": item_id, "output_index": index, "summary_index": 0, "text": text}),
+                );
+                self.emit(
+                    events,
+                    "response.reasoning_summary_part.done",
+                    json!({
+                        "item_id": item_id,
+                        "output_index": index,
+                        "summary_index": 0,
+                        "part": {"type": "summary_text", "text": text}
+                    }),
+                );
+            }
+            ItemKind::FunctionCall => {
+                self.emit(
+                    events,
+                    "response.function_call_arguments.done",
+                    json!({"item_id": item_id, "output_index": index, "arguments": text}),
+                );
+            }
+        }
+
+        let item = self.items[index].to_json("completed");
+        self.emit(
+            events,
+            "response.output_item.done",
+            json!({"output_index": index, "item": item}),
+        );
+    }
+
+    fn finish(&mut self, events: &mut Vec<String>) {
+        self.close_text_item(events);
+        for index in 0..self.items.len() {
+            self.close_item(events, index);
+        }
+        self.completed = true;
+
+        let output = self.items.iter().map(|i| i.to_json("completed")).collect();
+        let response = build_response(
+            self.response_id.as_deref().unwrap_or_default(),
+            &self.model,
+            self.created_at,
+            self.finish_reason.as_deref(),
+            output,
+            self.usage.as_ref(),
+        );
+        let event_type = if response["status"] == "incomplete" {
+            "response.incomplete"
+        } else {
+            "response.completed"
+        };
+        self.emit(events, event_type, json!({"response": response}));
+    }
+
+    /// 当前 response 快照（用于 created / in_progress / failed 事件）
+    fn snapshot(&self, status: &str, output: Vec<Value>) -> Value {
+        json!({
+            "id": self.response_id.clone().unwrap_or_default(),
+            "object": "response",
+            "created_at": self.created_at.unwrap_or_else(|| chrono::Utc::now().timestamp()),
+            "status": status,
+            "model": self.model,
+            "output": output,
+        })
+    }
+
+    fn emit(&mut self, events: &mut Vec<String>, event_type: &str, mut payload: Value) {
+        payload["type"] = json!(event_type);
+        payload["sequence_number"] = json!(self.sequence_number);
+        self.sequence_number += 1;
+        events.push(format!(
+            "event: {event_type}\ndata: {}\n\n",
+            serde_json::to_string(&payload).unwrap_or_default()
+        ));
+    }
+}
+
+/// 创建 Responses SSE 流
+pub fn create_responses_sse_stream(
+    stream: impl Stream<Item = Result<Bytes, reqwest::Error>> + Send + 'static,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send {
+    async_stream::stream! {
+        let mut buffer = String::new();
+        let mut converter = ResponsesStreamConverter::new();
+
+        tokio::pin!(stream);
+
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(bytes) => {
+                    buffer.push_str(&String::from_utf8_lossy(&bytes));
+
+                    while let Some(pos) = buffer.find("\n\n") {
+                        let block = buffer[..pos].to_string();
+                        buffer = buffer[pos + 2..].to_string();
+
+                        for line in block.lines() {
+                            if let Some(data) = line.strip_prefix("data:") {
+                                for event in converter.process_data(data.trim_start()) {
+                                    yield Ok(Bytes::from(event));
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::error!("[Codex/Chat] Stream error: {e}");
+                    for event in converter.fail(&format!("Stream error: {e}")) {
+                        yield Ok(Bytes::from(event));
+                    }
+                    return;
+                }
+            }
+        }
+
+        for event in converter.finish_stream() {
+            yield Ok(Bytes::from(event));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_types(events: &[String]) -> Vec<String> {
+        events
+            .iter()
+            .map(|e| {
+                e.lines()
+                    .next()
+                    .and_then(|l| l.strip_prefix("event: "))
+                    .unwrap_or_default()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    fn event_data(event: &str) -> Value {
+        let data = event
+            .lines()
+            .find_map(|l| l.strip_prefix("data: "))
+            .unwrap();
+        serde_json::from_str(data).unwrap()
+    }
+
+    fn run(chunks: &[Value]) -> Vec<String> {
+        let mut converter = ResponsesStreamConverter::new();
+        let mut events = Vec::new();
+        for chunk in chunks {
+            events.extend(converter.process_data(&chunk.to_string()));
+        }
+        events.extend(converter.process_data("[DONE]"));
+        events.extend(converter.finish_stream());
+        events
+    }
+
+    #[test]
+    fn test_text_stream_emits_responses_events_with_usage() {
+        let events = run(&[
+            json!({"id": "chatcmpl-1", "model": "m", "choices": [{"delta": {"role": "assistant", "content": "Hel"}}]}),
+            json!({"id": "chatcmpl-1", "model": "m", "choices": [{"delta": {"content": "lo"}, "finish_reason": "stop"}]}),
+            json!({"id": "chatcmpl-1", "model": "m", "choices": [], "usage": {"prompt_tokens": 10, "completion_tokens": 2}}),
+        ]);
+
+        assert_eq!(
+            event_types(&events),
+            vec![
+                "response.created",
+                "response.in_progress",
+                "response.output_item.added",
+                "response.content_part.added",
+                "response.output_text.delta",
+                "response.output_text.delta",
+                "response.output_text.done",
+                "response.content_part.done",
+                "response.output_item.done",
+                "response.completed",
+            ]
+        );
+
+        let completed = event_data(events.last().unwrap());
+        assert_eq!(completed["response"]["id"], "resp_1");
+        assert_eq!(
+            completed["response"]["output"][0]["content"][0]["text"],
+            "Hello"
+        );
+        assert_eq!(completed["response"]["usage"]["input_tokens"], 10);
+        assert_eq!(completed["response"]["usage"]["output_tokens"], 2);
+        assert_eq!(completed["sequence_number"], 9);
+    }
+
+    #[test]
+    fn test_reasoning_then_tool_calls() {
+        let events = run(&[
+            json!({"id": "c", "model": "m", "choices": [{"delta": {"reasoning_content": "think"}}]}),
+            json!({"id": "c", "model": "m", "choices": [{"delta": {"tool_calls": [
+                {"index": 0, "id": "call_a", "function": {"name": "shell", "arguments": "{\"cmd\""}}
+            ]}}]}),
+            json!({"id": "c", "model": "m", "choices": [{"delta": {"tool_calls": [
+                {"index": 0, "function": {"arguments": ":\"ls\"}"}},
+                {"index": 1, "id": "call_b", "function": {"name": "read", "arguments": "{}"}}
+            ]}, "finish_reason": "tool_calls"}]}),
+        ]);
+
+        let types = event_types(&events);
+        assert!(types.contains(&"response.reasoning_summary_text.delta".to_string()));
+        assert_eq!(
+            types
+                .iter()
+                .filter(|t| *t == "response.output_item.done")
+                .count(),
+            3
+        );
+
+        let completed = event_data(events.last().unwrap());
+        let output = completed["response"]["output"].as_array().unwrap();
+        assert_eq!(output[0]["type"], "reasoning");
+        assert_eq!(output[0]["summary"][0]["text"], "think");
+        assert_eq!(output[1]["call_id"], "call_a");
+        assert_eq!(output[1]["arguments"], "{\"cmd\":\"ls\"}");
+        assert_eq!(output[2]["name"], "read");
+    }
+
+    #[test]
+    fn test_stream_without_done_is_finished_once() {
+        let mut converter = ResponsesStreamConverter::new();
+        let chunk = json!({"id": "c", "model": "m", "choices": [{"delta": {"content": "x"}, "finish_reason": "length"}]});
+        converter.process_data(&chunk.to_string());
+
+        let events = converter.finish_stream();
+        assert_eq!(event_types(&events).last().unwrap(), "response.incomplete");
+        assert!(converter.finish_stream().is_empty());
+        assert!(converter.fail("boom").is_empty());
+    }
+}
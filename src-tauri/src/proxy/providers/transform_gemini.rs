@@ -0,0 +1,582 @@
+//! Gemini 格式转换模块
+//!
+//! 实现 Anthropic Messages ↔ Gemini generateContent 格式转换，
+//! 用于 `api_format = "gemini"` 的 Claude 供应商直连 Gemini 原生接口
+//!
+//! Gemini 的 thoughtSignature 通过 Anthropic thinking 块的 `signature` 字段往返：
+//! 响应中的签名写入 thinking 块，下一轮请求时再挂回紧随其后的 part。
+
+use crate::proxy::error::ProxyError;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// JSON Schema 中 Gemini 不支持的字段
+const UNSUPPORTED_SCHEMA_KEYS: &[&str] = &[
+    "$schema",
+    "$id",
+    "additionalProperties",
+    "default",
+    "examples",
+    "propertyNames",
+];
+
+/// 根据请求体构建 Gemini 端点
+///
+/// 流式请求使用 `streamGenerateContent?alt=sse`，否则使用 `generateContent`
+pub fn gemini_endpoint(body: &Value) -> String {
+    let model = body
+        .get("model")
+        .and_then(|m| m.as_str())
+        .unwrap_or_default();
+    let model = model.strip_prefix("models/").unwrap_or(model);
+    let is_stream = body
+        .get("stream")
+        .and_then(|s| s.as_bool())
+        .unwrap_or(false);
+
+    if is_stream {
+        format!("/v1beta/models/{model}:streamGenerateContent?alt=sse")
+    } else {
+        format!("/v1beta/models/{model}:generateContent")
+    }
+}
+
+/// Anthropic 请求 → Gemini 请求
+pub fn anthropic_to_gemini(body: Value) -> Result<Value, ProxyError> {
+    let mut result = json!({});
+
+    // NOTE: 模型名写入 URL（见 gemini_endpoint），请求体中不包含 model 字段。
+
+    // system → systemInstruction
+    if let Some(system) = body.get("system") {
+        let text = match system {
+            Value::String(s) => s.clone(),
+            Value::Array(blocks) => blocks
+                .iter()
+                .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            _ => String::new(),
+        };
+        if !text.is_empty() {
+            result["systemInstruction"] = json!({"parts": [{"text": text}]});
+        }
+    }
+
+    // messages → contents
+    let mut contents = Vec::new();
+    // tool_use id → 函数名（functionResponse 需要函数名而非 id）
+    let mut tool_names: HashMap<String, String> = HashMap::new();
+
+    if let Some(messages) = body.get("messages").and_then(|m| m.as_array()) {
+        for msg in messages {
+            let role = match msg.get("role").and_then(|r| r.as_str()).unwrap_or("user") {
+                "assistant" => "model",
+                _ => "user",
+            };
+            let parts = convert_content_to_parts(msg.get("content"), &mut tool_names)?;
+            if parts.is_empty() {
+                continue;
+            }
+
+            // Gemini 要求角色交替，相邻同角色消息合并
+            if let Some(last) = contents.last_mut() {
+                let last: &mut Value = last;
+                if last.get("role").and_then(|r| r.as_str()) == Some(role) {
+                    if let Some(last_parts) = last.get_mut("parts").and_then(|p| p.as_array_mut()) {
+                        last_parts.extend(parts);
+                        continue;
+                    }
+                }
+            }
+            contents.push(json!({"role": role, "parts": parts}));
+        }
+    }
+    result["contents"] = json!(contents);
+
+    // generationConfig
+    let mut generation_config = Map::new();
+    if let Some(v) = body.get("max_tokens") {
+        generation_config.insert("maxOutputTokens".to_string(), v.clone());
+    }
+    if let Some(v) = body.get("temperature") {
+        generation_config.insert("temperature".to_string(), v.clone());
+    }
+    if let Some(v) = body.get("top_p") {
+        generation_config.insert("topP".to_string(), v.clone());
+    }
+    if let Some(v) = body.get("top_k") {
+        generation_config.insert("topK".to_string(), v.clone());
+    }
+    if let Some(v) = body.get("stop_sequences") {
+        generation_config.insert("stopSequences".to_string(), v.clone());
+    }
+    if let Some(thinking) = body.get("thinking") {
+        match thinking.get("type").and_then(|t| t.as_str()) {
+            Some("enabled") => {
+                let mut config = json!({"includeThoughts": true});
+                if let Some(budget) = thinking.get("budget_tokens") {
+                    config["thinkingBudget"] = budget.clone();
+                }
+                generation_config.insert("thinkingConfig".to_string(), config);
+            }
+            // adaptive 交给 Gemini 动态决定思考预算
+            Some("adaptive") => {
+                generation_config.insert(
+                    "thinkingConfig".to_string(),
+                    json!({"includeThoughts": true, "thinkingBudget": -1}),
+                );
+            }
+            _ => {}
+        }
+    }
+    if !generation_config.is_empty() {
+        result["generationConfig"] = Value::Object(generation_config);
+    }
+
+    // 转换 tools (过滤 BatchTool)
+    if let Some(tools) = body.get("tools").and_then(|t| t.as_array()) {
+        let declarations: Vec<Value> = tools
+            .iter()
+            .filter(|t| t.get("type").and_then(|v| v.as_str()) != Some("BatchTool"))
+            .filter_map(|t| {
+                let name = t.get("name").and_then(|n| n.as_str())?;
+                let mut declaration = json!({"name": name});
+                if let Some(description) = t.get("description") {
+                    declaration["description"] = description.clone();
+                }
+                if let Some(schema) = t.get("input_schema") {
+                    declaration["parameters"] = clean_schema(schema.clone());
+                }
+                Some(declaration)
+            })
+            .collect();
+
+        if !declarations.is_empty() {
+            result["tools"] = json!([{"functionDeclarations": declarations}]);
+        }
+    }
+
+    if let Some(tool_choice) = body.get("tool_choice") {
+        let config = match tool_choice.get("type").and_then(|t| t.as_str()) {
+            Some("any") => Some(json!({"mode": "ANY"})),
+            Some("none") => Some(json!({"mode": "NONE"})),
+            Some("tool") => tool_choice
+                .get("name")
+                .map(|name| json!({"mode": "ANY", "allowedFunctionNames": [name]})),
+            Some("auto") => Some(json!({"mode": "AUTO"})),
+            _ => None,
+        };
+        if let Some(config) = config {
+            result["toolConfig"] = json!({"functionCallingConfig": config});
+        }
+    }
+
+    Ok(result)
+}
+
+/// 转换 Anthropic content 为 Gemini parts
+fn convert_content_to_parts(
+    content: Option<&Value>,
+    tool_names: &mut HashMap<String, String>,
+) -> Result<Vec<Value>, ProxyError> {
+    let Some(content) = content else {
+        return Ok(Vec::new());
+    };
+
+    if let Some(text) = content.as_str() {
+        return Ok(vec![json!({"text": text})]);
+    }
+
+    let Some(blocks) = content.as_array() else {
+        return Ok(Vec::new());
+    };
+
+    let mut parts = Vec::new();
+    // 待挂载到下一个 part 的 thoughtSignature
+    let mut pending_signature: Option<String> = None;
+
+    for block in blocks {
+        let block_type = block.get("type").and_then(|t| t.as_str()).unwrap_or("");
+        let mut part = match block_type {
+            "text" => {
+                let text = block.get("text").and_then(|t| t.as_str()).unwrap_or("");
+                json!({"text": text})
+            }
+            "image" => {
+                let source = block.get("source").cloned().unwrap_or(json!({}));
+                match source.get("type").and_then(|t| t.as_str()) {
+                    Some("url") => json!({
+                        "fileData": {
+                            "mimeType": source.get("media_type").and_then(|m| m.as_str()).unwrap_or("image/png"),
+                            "fileUri": source.get("url").and_then(|u| u.as_str()).unwrap_or("")
+                        }
+                    }),
+                    _ => json!({
+                        "inlineData": {
+                            "mimeType": source.get("media_type").and_then(|m| m.as_str()).unwrap_or("image/png"),
+                            "data": source.get("data").and_then(|d| d.as_str()).unwrap_or("")
+                        }
+                    }),
+                }
+            }
+            "tool_use" => {
+                let id = block.get("id").and_then(|i| i.as_str()).unwrap_or("");
+                let name = block.get("name").and_then(|n| n.as_str()).unwrap_or("");
+                tool_names.insert(id.to_string(), name.to_string());
+                json!({
+                    "functionCall": {
+                        "name": name,
+                        "args": block.get("input").cloned().unwrap_or(json!({}))
+                    }
+                })
+            }
+            "tool_result" => {
+                let tool_use_id = block
+                    .get("tool_use_id")
+                    .and_then(|i| i.as_str())
+                    .unwrap_or("");
+                let name = tool_names
+                    .get(tool_use_id)
+                    .cloned()
+                    .unwrap_or_else(|| tool_use_id.to_string());
+                let output = match block.get("content") {
+                    Some(Value::String(s)) => s.clone(),
+                    Some(Value::Array(items)) => items
+                        .iter()
+                        .filter_map(|i| i.get("text").and_then(|t| t.as_str()))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    Some(other) => other.to_string(),
+                    None => String::new(),
+                };
+                let is_error = block
+                    .get("is_error")
+                    .and_then(|e| e.as_bool())
+                    .unwrap_or(false);
+                let response = if is_error {
+                    json!({"error": output})
+                } else {
+                    json!({"content": output})
+                };
+                json!({"functionResponse": {"name": name, "response": response}})
+            }
+            // 思考内容不回传，仅保留签名
+            "thinking" => {
+                pending_signature = block
+                    .get("signature")
+                    .and_then(|s| s.as_str())
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_string());
+                continue;
+            }
+            _ => continue,
+        };
+
+        if let Some(signature) = pending_signature.take() {
+            part["thoughtSignature"] = json!(signature);
+        }
+        parts.push(part);
+    }
+
+    Ok(parts)
+}
+
+/// 清理 JSON Schema 中 Gemini 不支持的字段
+fn clean_schema(mut schema: Value) -> Value {
+    match &mut schema {
+        Value::Object(obj) => {
+            for key in UNSUPPORTED_SCHEMA_KEYS {
+                obj.remove(*key);
+            }
+            // Gemini 的 string 只支持 enum / date-time 两种 format
+            if obj.get("type").and_then(|t| t.as_str()) == Some("string") {
+                if let Some(format) = obj.get("format").and_then(|f| f.as_str()) {
+                    if format != "enum" && format != "date-time" {
+                        obj.remove("format");
+                    }
+                }
+            }
+            for (key, value) in obj.iter_mut() {
+                // properties 的键是字段名，不能按 schema 关键字过滤
+                if key == "properties" {
+                    if let Some(properties) = value.as_object_mut() {
+                        for property in properties.values_mut() {
+                            *property = clean_schema(property.take());
+                        }
+                        continue;
+                    }
+                }
+                *value = clean_schema(value.take());
+            }
+        }
+        Value::Array(items) => {
+            for item in items.iter_mut() {
+                *item = clean_schema(item.take());
+            }
+        }
+        _ => {}
+    }
+    schema
+}
+
+/// Gemini 响应 → Anthropic 响应
+pub fn gemini_to_anthropic(body: Value) -> Result<Value, ProxyError> {
+    let candidate = body
+        .get("candidates")
+        .and_then(|c| c.as_array())
+        .and_then(|c| c.first())
+        .ok_or_else(|| ProxyError::TransformError("No candidates in response".to_string()))?;
+
+    let mut content = Vec::new();
+    let mut has_tool_use = false;
+
+    if let Some(parts) = candidate
+        .get("content")
+        .and_then(|c| c.get("parts"))
+        .and_then(|p| p.as_array())
+    {
+        for part in parts {
+            let signature = part.get("thoughtSignature").and_then(|s| s.as_str());
+            let is_thought = part.get("thought").and_then(|t| t.as_bool()) == Some(true);
+
+            if is_thought {
+                content.push(json!({
+                    "type": "thinking",
+                    "thinking": part.get("text").and_then(|t| t.as_str()).unwrap_or(""),
+                    "signature": signature.unwrap_or("")
+                }));
+                continue;
+            }
+
+            // 非思考 part 上的签名单独放入空 thinking 块，便于下一轮回传
+            if let Some(signature) = signature {
+                content.push(json!({"type": "thinking", "thinking": "", "signature": signature}));
+            }
+
+            if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                content.push(json!({"type": "text", "text": text}));
+            } else if let Some(call) = part.get("functionCall") {
+                has_tool_use = true;
+                content.push(json!({
+                    "type": "tool_use",
+                    "id": new_tool_use_id(),
+                    "name": call.get("name").and_then(|n| n.as_str()).unwrap_or(""),
+                    "input": call.get("args").cloned().unwrap_or(json!({}))
+                }));
+            }
+        }
+    }
+
+    let stop_reason = map_finish_reason(
+        candidate.get("finishReason").and_then(|r| r.as_str()),
+        has_tool_use,
+    );
+
+    let mut result = json!({
+        "id": body.get("responseId").and_then(|i| i.as_str()).map(|id| format!("msg_{id}")).unwrap_or_else(new_message_id),
+        "type": "message",
+        "role": "assistant",
+        "content": content,
+        "model": body.get("modelVersion").and_then(|m| m.as_str()).unwrap_or(""),
+        "stop_reason": stop_reason,
+        "stop_sequence": null,
+    });
+
+    result["usage"] = body
+        .get("usageMetadata")
+        .map(convert_usage)
+        .unwrap_or_else(|| json!({"input_tokens": 0, "output_tokens": 0}));
+
+    Ok(result)
+}
+
+/// Gemini usageMetadata → Anthropic usage
+///
+/// Gemini 的 promptTokenCount 包含缓存命中部分，需要拆分为 input / cache_read
+pub(crate) fn convert_usage(usage: &Value) -> Value {
+    let get = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    let prompt = get("promptTokenCount");
+    let cached = get("cachedContentTokenCount");
+
+    json!({
+        "input_tokens": prompt.saturating_sub(cached),
+        "output_tokens": get("candidatesTokenCount") + get("thoughtsTokenCount"),
+        "cache_read_input_tokens": cached,
+    })
+}
+
+/// 映射停止原因
+pub(crate) fn map_finish_reason(finish_reason: Option<&str>, has_tool_use: bool) -> &'static str {
+    if has_tool_use {
+        return "tool_use";
+    }
+    match finish_reason {
+        Some("MAX_TOKENS") => "max_tokens",
+        Some("SAFETY")
+        | Some("RECITATION")
+        | Some("PROHIBITED_CONTENT")
+        | Some("BLOCKLIST")
+        | Some("SPII") => "refusal",
+        _ => "end_turn",
+    }
+}
+
+pub(crate) fn new_tool_use_id() -> String {
+    format!("toolu_{}", uuid::Uuid::new_v4().simple())
+}
+
+pub(crate) fn new_message_id() -> String {
+    format!("msg_{}", uuid::Uuid::new_v4().simple())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gemini_endpoint() {
+        assert_eq!(
+            gemini_endpoint(&json!({"model": "gemini-2.5-pro", "stream": true})),
+            "/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse"
+        );
+        assert_eq!(
+            gemini_endpoint(&json!({"model": "models/gemini-2.5-flash"})),
+            "/v1beta/models/gemini-2.5-flash:generateContent"
+        );
+    }
+
+    #[test]
+    fn test_anthropic_to_gemini_basic() {
+        let input = json!({
+            "model": "gemini-2.5-pro",
+            "max_tokens": 1024,
+            "system": [{"type": "text", "text": "You are Claude."}],
+            "messages": [{"role": "user", "content": "Hello"}],
+            "thinking": {"type": "enabled", "budget_tokens": 2048}
+        });
+
+        let result = anthropic_to_gemini(input).unwrap();
+        assert!(result.get("model").is_none());
+        assert_eq!(
+            result["systemInstruction"]["parts"][0]["text"],
+            "You are Claude."
+        );
+        assert_eq!(result["contents"][0]["role"], "user");
+        assert_eq!(result["contents"][0]["parts"][0]["text"], "Hello");
+        assert_eq!(result["generationConfig"]["maxOutputTokens"], 1024);
+        assert_eq!(
+            result["generationConfig"]["thinkingConfig"]["thinkingBudget"],
+            2048
+        );
+    }
+
+    #[test]
+    fn test_anthropic_to_gemini_tools_and_signature_roundtrip() {
+        let input = json!({
+            "model": "gemini-2.5-pro",
+            "messages": [
+                {"role": "user", "content": [
+                    {"type": "text", "text": "read it"},
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/jpeg", "data": "AAA"}}
+                ]},
+                {"role": "assistant", "content": [
+                    {"type": "thinking", "thinking": "", "signature": "sig-1"},
+                    {"type": "tool_use", "id": "toolu_1", "name": "Read", "input": {"path": "a.rs"}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": [{"type": "text", "text": "fn main() {}"}]}
+                ]}
+            ],
+            "tools": [{
+                "name": "Read",
+                "description": "Read a file",
+                "input_schema": {
+                    "$schema": "http://json-schema.org/draft-07/schema#",
+                    "type": "object",
+                    "additionalProperties": false,
+                    "properties": {
+                        "path": {"type": "string", "format": "uri"},
+                        "default": {"type": "boolean", "default": false}
+                    }
+                }
+            }],
+            "tool_choice": {"type": "tool", "name": "Read"}
+        });
+
+        let result = anthropic_to_gemini(input).unwrap();
+        let contents = result["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 3);
+        assert_eq!(
+            contents[0]["parts"][1]["inlineData"]["mimeType"],
+            "image/jpeg"
+        );
+        assert_eq!(contents[1]["role"], "model");
+        assert_eq!(contents[1]["parts"][0]["functionCall"]["name"], "Read");
+        assert_eq!(contents[1]["parts"][0]["thoughtSignature"], "sig-1");
+        assert_eq!(contents[2]["parts"][0]["functionResponse"]["name"], "Read");
+        assert_eq!(
+            contents[2]["parts"][0]["functionResponse"]["response"]["content"],
+            "fn main() {}"
+        );
+
+        let params = &result["tools"][0]["functionDeclarations"][0]["parameters"];
+        assert!(params.get("$schema").is_none());
+        assert!(params.get("additionalProperties").is_none());
+        assert!(params["properties"]["path"].get("format").is_none());
+        // 名为 default 的字段保留，字段内的 default 关键字被移除
+        assert_eq!(params["properties"]["default"], json!({"type": "boolean"}));
+        assert_eq!(
+            result["toolConfig"]["functionCallingConfig"]["allowedFunctionNames"][0],
+            "Read"
+        );
+    }
+
+    #[test]
+    fn test_gemini_to_anthropic_with_thoughts_and_function_call() {
+        let input = json!({
+            "responseId": "abc",
+            "modelVersion": "gemini-2.5-pro",
+            "candidates": [{
+                "content": {"role": "model", "parts": [
+                    {"text": "plan", "thought": true},
+                    {"functionCall": {"name": "Read", "args": {"path": "a.rs"}}, "thoughtSignature": "sig-2"}
+                ]},
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": {
+                "promptTokenCount": 100,
+                "cachedContentTokenCount": 30,
+                "candidatesTokenCount": 10,
+                "thoughtsTokenCount": 5
+            }
+        });
+
+        let result = gemini_to_anthropic(input).unwrap();
+        assert_eq!(result["id"], "msg_abc");
+        assert_eq!(result["stop_reason"], "tool_use");
+        let content = result["content"].as_array().unwrap();
+        assert_eq!(content[0]["type"], "thinking");
+        assert_eq!(content[0]["thinking"], "plan");
+        assert_eq!(content[1]["signature"], "sig-2");
+        assert_eq!(content[2]["type"], "tool_use");
+        assert_eq!(content[2]["input"]["path"], "a.rs");
+        assert_eq!(result["usage"]["input_tokens"], 70);
+        assert_eq!(result["usage"]["cache_read_input_tokens"], 30);
+        assert_eq!(result["usage"]["output_tokens"], 15);
+    }
+
+    #[test]
+    fn test_gemini_to_anthropic_max_tokens() {
+        let input = json!({
+            "candidates": [{
+                "content": {"parts": [{"text": "partial"}]},
+                "finishReason": "MAX_TOKENS"
+            }]
+        });
+
+        let result = gemini_to_anthropic(input).unwrap();
+        assert_eq!(result["stop_reason"], "max_tokens");
+        assert_eq!(result["content"][0]["text"], "partial");
+    }
+}
@@ -0,0 +1,546 @@
+//! Responses API 格式转换模块
+//!
+//! 实现 OpenAI Responses ↔ Chat Completions 格式转换，
+//! 用于 Codex 供应商接入仅支持 `/v1/chat/completions` 的中转服务
+
+use crate::proxy::error::ProxyError;
+use serde_json::{json, Map, Value};
+
+/// Responses 请求 → Chat Completions 请求
+pub fn responses_to_chat(body: Value) -> Result<Value, ProxyError> {
+    let mut result = json!({});
+
+    // NOTE: 模型映射由上游统一处理（proxy::model_mapper），格式转换层只做结构转换。
+    if let Some(model) = body.get("model").and_then(|m| m.as_str()) {
+        result["model"] = json!(model);
+    }
+
+    let mut messages = Vec::new();
+
+    // instructions → system message
+    if let Some(instructions) = body.get("instructions").and_then(|i| i.as_str()) {
+        if !instructions.is_empty() {
+            messages.push(json!({"role": "system", "content": instructions}));
+        }
+    }
+
+    // 转换 input
+    match body.get("input") {
+        Some(Value::String(text)) => {
+            messages.push(json!({"role": "user", "content": text}));
+        }
+        Some(Value::Array(items)) => {
+            for item in items {
+                convert_input_item(item, &mut messages)?;
+            }
+        }
+        Some(Value::Null) | None => {}
+        Some(_) => {
+            return Err(ProxyError::TransformError(
+                "Unsupported input type in Responses request".to_string(),
+            ));
+        }
+    }
+
+    result["messages"] = json!(messages);
+
+    // 转换参数
+    if let Some(v) = body.get("max_output_tokens") {
+        result["max_tokens"] = v.clone();
+    }
+    if let Some(v) = body.get("temperature") {
+        result["temperature"] = v.clone();
+    }
+    if let Some(v) = body.get("top_p") {
+        result["top_p"] = v.clone();
+    }
+    if let Some(v) = body.get("parallel_tool_calls") {
+        result["parallel_tool_calls"] = v.clone();
+    }
+    if let Some(effort) = body
+        .get("reasoning")
+        .and_then(|r| r.get("effort"))
+        .and_then(|e| e.as_str())
+    {
+        result["reasoning_effort"] = json!(effort);
+    }
+    if let Some(stream) = body.get("stream") {
+        result["stream"] = stream.clone();
+        if stream.as_bool() == Some(true) {
+            // 流式请求需要显式要求 usage，否则无法统计用量
+            result["stream_options"] = json!({"include_usage": true});
+        }
+    }
+
+    // text.format → response_format
+    if let Some(format) = body.get("text").and_then(|t| t.get("format")) {
+        match format.get("type").and_then(|t| t.as_str()) {
+            Some("json_schema") => {
+                let mut schema = Map::new();
+                for key in ["name", "schema", "strict", "description"] {
+                    if let Some(v) = format.get(key) {
+                        schema.insert(key.to_string(), v.clone());
+                    }
+                }
+                result["response_format"] =
+                    json!({"type": "json_schema", "json_schema": Value::Object(schema)});
+            }
+            Some("json_object") => {
+                result["response_format"] = json!({"type": "json_object"});
+            }
+            _ => {}
+        }
+    }
+
+    // 转换 tools（仅 function 类型，内置工具如 web_search 在 Chat API 中不可用）
+    if let Some(tools) = body.get("tools").and_then(|t| t.as_array()) {
+        let chat_tools: Vec<Value> = tools
+            .iter()
+            .filter(|t| t.get("type").and_then(|v| v.as_str()) == Some("function"))
+            .map(|t| {
+                let mut function = json!({
+                    "name": t.get("name").and_then(|n| n.as_str()).unwrap_or(""),
+                    "parameters": t.get("parameters").cloned().unwrap_or(json!({"type": "object", "properties": {}}))
+                });
+                if let Some(description) = t.get("description") {
+                    function["description"] = description.clone();
+                }
+                if let Some(strict) = t.get("strict") {
+                    function["strict"] = strict.clone();
+                }
+                json!({"type": "function", "function": function})
+            })
+            .collect();
+
+        let skipped = tools.len() - chat_tools.len();
+        if skipped > 0 {
+            log::debug!("[Codex/Chat] 跳过 {skipped} 个 Chat Completions 不支持的内置工具");
+        }
+        if !chat_tools.is_empty() {
+            result["tools"] = json!(chat_tools);
+        }
+    }
+
+    if let Some(tool_choice) = body.get("tool_choice") {
+        result["tool_choice"] = match tool_choice {
+            Value::Object(obj) if obj.get("type").and_then(|t| t.as_str()) == Some("function") => {
+                json!({
+                    "type": "function",
+                    "function": {"name": obj.get("name").cloned().unwrap_or(json!(""))}
+                })
+            }
+            other => other.clone(),
+        };
+    }
+
+    Ok(result)
+}
+
+/// 转换单个 input item 并追加到 messages
+fn convert_input_item(item: &Value, messages: &mut Vec<Value>) -> Result<(), ProxyError> {
+    let item_type = item.get("type").and_then(|t| t.as_str());
+
+    match item_type {
+        // 省略 type 的 item 视为 message
+        Some("message") | None => {
+            let role = match item.get("role").and_then(|r| r.as_str()).unwrap_or("user") {
+                "developer" => "system",
+                other => other,
+            };
+            let content = convert_message_content(item.get("content"));
+            messages.push(json!({"role": role, "content": content}));
+        }
+        Some("function_call") => {
+            let tool_call = json!({
+                "id": item.get("call_id").and_then(|c| c.as_str()).unwrap_or(""),
+                "type": "function",
+                "function": {
+                    "name": item.get("name").and_then(|n| n.as_str()).unwrap_or(""),
+                    "arguments": item.get("arguments").and_then(|a| a.as_str()).unwrap_or("{}")
+                }
+            });
+
+            // 连续的 function_call 合并到同一条 assistant 消息
+            if let Some(last) = messages.last_mut() {
+                if last.get("role").and_then(|r| r.as_str()) == Some("assistant") {
+                    match last.get_mut("tool_calls").and_then(|t| t.as_array_mut()) {
+                        Some(tool_calls) => tool_calls.push(tool_call),
+                        None => last["tool_calls"] = json!([tool_call]),
+                    }
+                    return Ok(());
+                }
+            }
+            messages.push(json!({
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [tool_call]
+            }));
+        }
+        Some("function_call_output") => {
+            let output = match item.get("output") {
+                Some(Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+                None => String::new(),
+            };
+            messages.push(json!({
+                "role": "tool",
+                "tool_call_id": item.get("call_id").and_then(|c| c.as_str()).unwrap_or(""),
+                "content": output
+            }));
+        }
+        // reasoning 等 item 在 Chat API 中没有对应结构，直接丢弃
+        Some(other) => {
+            log::debug!("[Codex/Chat] 跳过不支持的 input item 类型: {other}");
+        }
+    }
+
+    Ok(())
+}
+
+/// 转换 message content（字符串或 content part 数组）
+fn convert_message_content(content: Option<&Value>) -> Value {
+    let Some(content) = content else {
+        return Value::Null;
+    };
+
+    if let Some(text) = content.as_str() {
+        return json!(text);
+    }
+
+    let Some(parts) = content.as_array() else {
+        return Value::Null;
+    };
+
+    let mut chat_parts = Vec::new();
+    let mut all_text = true;
+    for part in parts {
+        match part.get("type").and_then(|t| t.as_str()).unwrap_or("") {
+            "input_text" | "output_text" | "text" => {
+                if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                    chat_parts.push(json!({"type": "text", "text": text}));
+                }
+            }
+            "input_image" => {
+                let url = part
+                    .get("image_url")
+                    .and_then(|u| u.as_str())
+                    .unwrap_or_default();
+                let mut image_url = json!({"url": url});
+                if let Some(detail) = part.get("detail") {
+                    image_url["detail"] = detail.clone();
+                }
+                chat_parts.push(json!({"type": "image_url", "image_url": image_url}));
+                all_text = false;
+            }
+            _ => {}
+        }
+    }
+
+    // 纯文本时合并为字符串，兼容不支持 content 数组的中转服务
+    if all_text {
+        let text: Vec<&str> = chat_parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+            .collect();
+        json!(text.join("\n"))
+    } else {
+        json!(chat_parts)
+    }
+}
+
+/// Chat Completions 响应 → Responses 响应
+pub fn chat_to_responses(body: Value) -> Result<Value, ProxyError> {
+    let choice = body
+        .get("choices")
+        .and_then(|c| c.as_array())
+        .and_then(|c| c.first())
+        .ok_or_else(|| ProxyError::TransformError("No choices in response".to_string()))?;
+
+    let message = choice
+        .get("message")
+        .ok_or_else(|| ProxyError::TransformError("No message in choice".to_string()))?;
+
+    let mut output = Vec::new();
+
+    // 推理内容（DeepSeek 等中转返回的 reasoning_content）
+    if let Some(reasoning) = message
+        .get("reasoning_content")
+        .or_else(|| message.get("reasoning"))
+        .and_then(|r| r.as_str())
+    {
+        if !reasoning.is_empty() {
+            output.push(reasoning_item(&new_item_id("rs"), reasoning));
+        }
+    }
+
+    if let Some(text) = message.get("content").and_then(|c| c.as_str()) {
+        if !text.is_empty() {
+            output.push(message_item(&new_item_id("msg"), text, "completed"));
+        }
+    }
+
+    if let Some(tool_calls) = message.get("tool_calls").and_then(|t| t.as_array()) {
+        for tc in tool_calls {
+            let empty_obj = json!({});
+            let func = tc.get("function").unwrap_or(&empty_obj);
+            output.push(function_call_item(
+                &new_item_id("fc"),
+                tc.get("id").and_then(|i| i.as_str()).unwrap_or(""),
+                func.get("name").and_then(|n| n.as_str()).unwrap_or(""),
+                func.get("arguments")
+                    .and_then(|a| a.as_str())
+                    .unwrap_or("{}"),
+            ));
+        }
+    }
+
+    let finish_reason = choice.get("finish_reason").and_then(|r| r.as_str());
+
+    Ok(build_response(
+        &response_id(body.get("id").and_then(|i| i.as_str())),
+        body.get("model").and_then(|m| m.as_str()).unwrap_or(""),
+        body.get("created").and_then(|c| c.as_i64()),
+        finish_reason,
+        output,
+        body.get("usage"),
+    ))
+}
+
+/// 构建完整的 Responses 响应对象
+pub(crate) fn build_response(
+    id: &str,
+    model: &str,
+    created_at: Option<i64>,
+    finish_reason: Option<&str>,
+    output: Vec<Value>,
+    chat_usage: Option<&Value>,
+) -> Value {
+    let (status, incomplete_details) = match finish_reason {
+        Some("length") => ("incomplete", json!({"reason": "max_output_tokens"})),
+        Some("content_filter") => ("incomplete", json!({"reason": "content_filter"})),
+        _ => ("completed", Value::Null),
+    };
+
+    let mut response = json!({
+        "id": id,
+        "object": "response",
+        "created_at": created_at.unwrap_or_else(|| chrono::Utc::now().timestamp()),
+        "status": status,
+        "model": model,
+        "output": output,
+        "incomplete_details": incomplete_details,
+    });
+
+    if let Some(usage) = chat_usage.and_then(convert_usage) {
+        response["usage"] = usage;
+    }
+
+    response
+}
+
+/// Chat usage (prompt_tokens/completion_tokens) → Responses usage (input_tokens/output_tokens)
+pub(crate) fn convert_usage(usage: &Value) -> Option<Value> {
+    let input_tokens = usage.get("prompt_tokens").and_then(|v| v.as_u64())?;
+    let output_tokens = usage
+        .get("completion_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let cached_tokens = usage
+        .get("prompt_tokens_details")
+        .and_then(|d| d.get("cached_tokens"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let reasoning_tokens = usage
+        .get("completion_tokens_details")
+        .and_then(|d| d.get("reasoning_tokens"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+
+    Some(json!({
+        "input_tokens": input_tokens,
+        "input_tokens_details": {"cached_tokens": cached_tokens},
+        "output_tokens": output_tokens,
+        "output_tokens_details": {"reasoning_tokens": reasoning_tokens},
+        "total_tokens": usage
+            .get("total_tokens")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(input_tokens + output_tokens)
+    }))
+}
+
+pub(crate) fn message_item(id: &str, text: &str, status: &str) -> Value {
+    json!({
+        "type": "message",
+        "id": id,
+        "status": status,
+        "role": "assistant",
+        "content": [{"type": "output_text", "text": text, "annotations": []}]
+    })
+}
+
+pub(crate) fn reasoning_item(id: &str, text: &str) -> Value {
+    json!({
+        "type": "reasoning",
+        "id": id,
+        "summary": [{"type": "summary_text", "text": text}]
+    })
+}
+
+pub(crate) fn function_call_item(id: &str, call_id: &str, name: &str, arguments: &str) -> Value {
+    json!({
+        "type": "function_call",
+        "id": id,
+        "call_id": call_id,
+        "name": name,
+        "arguments": arguments,
+        "status": "completed"
+    })
+}
+
+/// 由 Chat completion id 生成 Responses id
+pub(crate) fn response_id(chat_id: Option<&str>) -> String {
+    match chat_id {
+        Some(id) if !id.is_empty() => format!("resp_{}", id.trim_start_matches("chatcmpl-")),
+        _ => new_item_id("resp"),
+    }
+}
+
+pub(crate) fn new_item_id(prefix: &str) -> String {
+    format!("{prefix}_{}", uuid::Uuid::new_v4().simple())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_responses_to_chat_simple() {
+        let input = json!({
+            "model": "gpt-5-codex",
+            "instructions": "You are Codex.",
+            "input": "Hello",
+            "max_output_tokens": 256,
+            "stream": true,
+            "reasoning": {"effort": "high", "summary": "auto"}
+        });
+
+        let result = responses_to_chat(input).unwrap();
+        assert_eq!(result["model"], "gpt-5-codex");
+        assert_eq!(result["messages"][0]["role"], "system");
+        assert_eq!(result["messages"][0]["content"], "You are Codex.");
+        assert_eq!(result["messages"][1]["role"], "user");
+        assert_eq!(result["messages"][1]["content"], "Hello");
+        assert_eq!(result["max_tokens"], 256);
+        assert_eq!(result["reasoning_effort"], "high");
+        assert_eq!(result["stream_options"]["include_usage"], true);
+    }
+
+    #[test]
+    fn test_responses_to_chat_input_items_and_tool_roundtrip() {
+        let input = json!({
+            "model": "gpt-5",
+            "input": [
+                {"type": "message", "role": "developer", "content": [{"type": "input_text", "text": "be brief"}]},
+                {"type": "message", "role": "user", "content": [{"type": "input_text", "text": "list files"}]},
+                {"type": "reasoning", "id": "rs_1", "summary": []},
+                {"type": "function_call", "call_id": "call_1", "name": "shell", "arguments": "{\"cmd\":\"ls\"}"},
+                {"type": "function_call", "call_id": "call_2", "name": "shell", "arguments": "{\"cmd\":\"pwd\"}"},
+                {"type": "function_call_output", "call_id": "call_1", "output": "a.txt"},
+                {"type": "function_call_output", "call_id": "call_2", "output": "/tmp"}
+            ],
+            "tools": [
+                {"type": "function", "name": "shell", "description": "run", "parameters": {"type": "object"}},
+                {"type": "web_search"}
+            ],
+            "tool_choice": {"type": "function", "name": "shell"}
+        });
+
+        let result = responses_to_chat(input).unwrap();
+        let messages = result["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(messages[0]["content"], "be brief");
+        assert_eq!(messages[2]["role"], "assistant");
+        assert_eq!(messages[2]["tool_calls"].as_array().unwrap().len(), 2);
+        assert_eq!(messages[2]["tool_calls"][1]["id"], "call_2");
+        assert_eq!(messages[3]["role"], "tool");
+        assert_eq!(messages[3]["tool_call_id"], "call_1");
+        assert_eq!(messages[4]["content"], "/tmp");
+
+        let tools = result["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0]["function"]["name"], "shell");
+        assert_eq!(result["tool_choice"]["function"]["name"], "shell");
+    }
+
+    #[test]
+    fn test_responses_to_chat_image_content() {
+        let input = json!({
+            "model": "gpt-5",
+            "input": [{
+                "role": "user",
+                "content": [
+                    {"type": "input_text", "text": "what is this"},
+                    {"type": "input_image", "image_url": "data:image/png;base64,AAA"}
+                ]
+            }]
+        });
+
+        let result = responses_to_chat(input).unwrap();
+        let content = result["messages"][0]["content"].as_array().unwrap();
+        assert_eq!(content[0]["type"], "text");
+        assert_eq!(content[1]["image_url"]["url"], "data:image/png;base64,AAA");
+    }
+
+    #[test]
+    fn test_chat_to_responses_with_tool_calls() {
+        let input = json!({
+            "id": "chatcmpl-123",
+            "model": "deepseek-chat",
+            "created": 1700000000,
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": "Running it",
+                    "reasoning_content": "need shell",
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "shell", "arguments": "{\"cmd\":\"ls\"}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {
+                "prompt_tokens": 100,
+                "completion_tokens": 20,
+                "prompt_tokens_details": {"cached_tokens": 40}
+            }
+        });
+
+        let result = chat_to_responses(input).unwrap();
+        assert_eq!(result["id"], "resp_123");
+        assert_eq!(result["status"], "completed");
+        let output = result["output"].as_array().unwrap();
+        assert_eq!(output[0]["type"], "reasoning");
+        assert_eq!(output[1]["content"][0]["text"], "Running it");
+        assert_eq!(output[2]["call_id"], "call_1");
+        assert_eq!(result["usage"]["input_tokens"], 100);
+        assert_eq!(result["usage"]["output_tokens"], 20);
+        assert_eq!(result["usage"]["input_tokens_details"]["cached_tokens"], 40);
+    }
+
+    #[test]
+    fn test_chat_to_responses_length_is_incomplete() {
+        let input = json!({
+            "id": "chatcmpl-1",
+            "model": "m",
+            "choices": [{"message": {"content": "partial"}, "finish_reason": "length"}]
+        });
+
+        let result = chat_to_responses(input).unwrap();
+        assert_eq!(result["status"], "incomplete");
+        assert_eq!(result["incomplete_details"]["reason"], "max_output_tokens");
+        assert!(result.get("usage").is_none());
+    }
+}
@@ -0,0 +1,358 @@
+//! 速率限制模块
+//!
+//! 基于令牌桶实现 RPM（每分钟请求数）与 TPM（每分钟 Token 数）限制：
+//! - 供应商维度：`ProviderMeta.rateLimitRpm` / `rateLimitTpm`
+//! - 客户端维度：代理客户端令牌上配置的 `rpmLimit` / `tpmLimit`
+//!
+//! RPM 在请求发起前扣减；TPM 在请求发起前只要求桶内余量为正，
+//! 实际 Token 用量在记录使用量后扣减（允许透支，透支部分需等待回填）。
+
+use crate::provider::Provider;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// 限流时允许排队等待的最长时间（超过则故障转移或返回 429）
+pub const MAX_QUEUE_WAIT: Duration = Duration::from_secs(3);
+
+/// RPM/TPM 限制配置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    pub rpm: Option<u32>,
+    pub tpm: Option<u32>,
+}
+
+impl RateLimitConfig {
+    pub fn new(rpm: Option<u32>, tpm: Option<u32>) -> Self {
+        Self {
+            rpm: rpm.filter(|v| *v > 0),
+            tpm: tpm.filter(|v| *v > 0),
+        }
+    }
+
+    /// 从供应商 meta 读取限制
+    pub fn from_provider(provider: &Provider) -> Self {
+        provider
+            .meta
+            .as_ref()
+            .map(|meta| Self::new(meta.rate_limit_rpm, meta.rate_limit_tpm))
+            .unwrap_or_default()
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.rpm.is_none() && self.tpm.is_none()
+    }
+}
+
+/// 限流检查结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateDecision {
+    /// 放行（已扣减一次请求）
+    Allowed,
+    /// 被限流，需等待后重试
+    Limited { retry_after: Duration },
+}
+
+/// 令牌桶（容量为每分钟额度，匀速回填）
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn per_minute(limit: u32, now: Instant) -> Self {
+        let capacity = limit as f64;
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: capacity / 60.0,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated_at = now;
+    }
+
+    /// 桶内余量达到 `amount` 所需的等待时间
+    fn wait_for(&self, amount: f64) -> Duration {
+        if self.tokens >= amount {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64((amount - self.tokens) / self.refill_per_sec)
+    }
+}
+
+/// 单个限流对象的桶
+#[derive(Debug, Clone)]
+struct LimitState {
+    config: RateLimitConfig,
+    rpm: Option<TokenBucket>,
+    tpm: Option<TokenBucket>,
+}
+
+impl LimitState {
+    fn new(config: RateLimitConfig, now: Instant) -> Self {
+        Self {
+            config,
+            rpm: config.rpm.map(|v| TokenBucket::per_minute(v, now)),
+            tpm: config.tpm.map(|v| TokenBucket::per_minute(v, now)),
+        }
+    }
+
+    fn try_acquire(&mut self, now: Instant) -> RateDecision {
+        let mut wait = Duration::ZERO;
+        if let Some(bucket) = self.rpm.as_mut() {
+            bucket.refill(now);
+            wait = wait.max(bucket.wait_for(1.0));
+        }
+        if let Some(bucket) = self.tpm.as_mut() {
+            bucket.refill(now);
+            // TPM 只要求余量为正，用量在响应后扣减
+            wait = wait.max(bucket.wait_for(f64::MIN_POSITIVE));
+        }
+
+        if !wait.is_zero() {
+            return RateDecision::Limited { retry_after: wait };
+        }
+        if let Some(bucket) = self.rpm.as_mut() {
+            bucket.tokens -= 1.0;
+        }
+        RateDecision::Allowed
+    }
+}
+
+/// 限流桶水位（用于 ProxyStatus 展示）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitLevel {
+    /// 限流对象，格式为 `provider:{app_type}:{provider_id}` 或 `client:{token_id}`
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpm_limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpm_available: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tpm_limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tpm_available: Option<f64>,
+}
+
+/// 速率限制器（供应商与客户端共享）
+#[derive(Default)]
+pub struct RateLimiter {
+    states: RwLock<HashMap<String, LimitState>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn provider_key(app_type: &str, provider_id: &str) -> String {
+        format!("provider:{app_type}:{provider_id}")
+    }
+
+    pub fn client_key(token_id: &str) -> String {
+        format!("client:{token_id}")
+    }
+
+    /// 尝试放行一次请求
+    ///
+    /// 未配置限制时直接放行；配置变化时重建令牌桶。
+    pub async fn try_acquire(&self, key: &str, config: RateLimitConfig) -> RateDecision {
+        if config.is_unlimited() {
+            // 限制被移除后清理旧桶，避免状态中残留过期水位
+            if self.states.read().await.contains_key(key) {
+                self.states.write().await.remove(key);
+            }
+            return RateDecision::Allowed;
+        }
+
+        let now = Instant::now();
+        let mut states = self.states.write().await;
+        let state = states
+            .entry(key.to_string())
+            .or_insert_with(|| LimitState::new(config, now));
+        if state.config != config {
+            *state = LimitState::new(config, now);
+        }
+        state.try_acquire(now)
+    }
+
+    /// 尝试放行，等待时间不超过 `max_wait` 时排队后再试一次
+    pub async fn acquire_or_wait(
+        &self,
+        key: &str,
+        config: RateLimitConfig,
+        max_wait: Duration,
+    ) -> RateDecision {
+        match self.try_acquire(key, config).await {
+            RateDecision::Limited { retry_after } if retry_after <= max_wait => {
+                log::debug!(
+                    "[RateLimit] {key} 已限流，排队等待 {}ms",
+                    retry_after.as_millis()
+                );
+                tokio::time::sleep(retry_after).await;
+                self.try_acquire(key, config).await
+            }
+            decision => decision,
+        }
+    }
+
+    /// 扣减实际 Token 用量（仅对已配置 TPM 的对象生效）
+    pub async fn record_tokens(&self, key: &str, tokens: u64) {
+        if tokens == 0 {
+            return;
+        }
+        let now = Instant::now();
+        let mut states = self.states.write().await;
+        if let Some(bucket) = states.get_mut(key).and_then(|s| s.tpm.as_mut()) {
+            bucket.refill(now);
+            bucket.tokens -= tokens as f64;
+        }
+    }
+
+    /// 当前所有令牌桶的水位
+    pub async fn levels(&self) -> Vec<RateLimitLevel> {
+        let now = Instant::now();
+        let mut states = self.states.write().await;
+        let mut levels: Vec<RateLimitLevel> = states
+            .iter_mut()
+            .map(|(key, state)| {
+                let level = |bucket: Option<&mut TokenBucket>| {
+                    bucket.map(|b| {
+                        b.refill(now);
+                        (b.tokens * 100.0).round() / 100.0
+                    })
+                };
+                RateLimitLevel {
+                    key: key.clone(),
+                    rpm_limit: state.config.rpm,
+                    rpm_available: level(state.rpm.as_mut()),
+                    tpm_limit: state.config.tpm,
+                    tpm_available: level(state.tpm.as_mut()),
+                }
+            })
+            .collect();
+        levels.sort_by(|a, b| a.key.cmp(&b.key));
+        levels
+    }
+}
+
+/// 将等待时长转换为 `retry-after` 秒数（向上取整，至少 1 秒）
+pub fn retry_after_secs(wait: Duration) -> u64 {
+    (wait.as_secs_f64().ceil() as u64).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rpm_bucket_limits_and_reports_retry_after() {
+        let limiter = RateLimiter::new();
+        let config = RateLimitConfig::new(Some(2), None);
+
+        assert_eq!(
+            limiter.try_acquire("k", config).await,
+            RateDecision::Allowed
+        );
+        assert_eq!(
+            limiter.try_acquire("k", config).await,
+            RateDecision::Allowed
+        );
+        match limiter.try_acquire("k", config).await {
+            RateDecision::Limited { retry_after } => {
+                // 2 RPM 每 30 秒回填 1 次
+                assert!(retry_after > Duration::from_secs(29));
+                assert!(retry_after <= Duration::from_secs(30));
+                assert_eq!(retry_after_secs(retry_after), 30);
+            }
+            other => panic!("expected limited, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tpm_bucket_debits_actual_usage() {
+        let limiter = RateLimiter::new();
+        let config = RateLimitConfig::new(None, Some(600));
+
+        assert_eq!(
+            limiter.try_acquire("k", config).await,
+            RateDecision::Allowed
+        );
+        limiter.record_tokens("k", 900).await;
+
+        match limiter.try_acquire("k", config).await {
+            // 透支 300 Token，按 10 Token/秒回填约需 30 秒
+            RateDecision::Limited { retry_after } => {
+                assert!(retry_after > Duration::from_secs(29));
+            }
+            other => panic!("expected limited, got {other:?}"),
+        }
+
+        let levels = limiter.levels().await;
+        assert_eq!(levels.len(), 1);
+        assert_eq!(levels[0].tpm_limit, Some(600));
+        assert!(levels[0].tpm_available.unwrap() < 0.0);
+        assert_eq!(levels[0].rpm_limit, None);
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_and_config_change_reset() {
+        let limiter = RateLimiter::new();
+        assert_eq!(
+            limiter.try_acquire("k", RateLimitConfig::default()).await,
+            RateDecision::Allowed
+        );
+        assert!(limiter.levels().await.is_empty());
+
+        let strict = RateLimitConfig::new(Some(1), None);
+        assert_eq!(
+            limiter.try_acquire("k", strict).await,
+            RateDecision::Allowed
+        );
+        assert!(matches!(
+            limiter.try_acquire("k", strict).await,
+            RateDecision::Limited { .. }
+        ));
+
+        // 调整限制后重建令牌桶
+        let relaxed = RateLimitConfig::new(Some(10), None);
+        assert_eq!(
+            limiter.try_acquire("k", relaxed).await,
+            RateDecision::Allowed
+        );
+
+        // 移除限制后清理状态
+        limiter.try_acquire("k", RateLimitConfig::default()).await;
+        assert!(limiter.levels().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_or_wait_queues_short_waits() {
+        let limiter = RateLimiter::new();
+        // 6000 RPM = 每 10ms 回填 1 次
+        let config = RateLimitConfig::new(Some(6000), None);
+        for _ in 0..6000 {
+            limiter.try_acquire("k", config).await;
+        }
+        assert_eq!(
+            limiter
+                .acquire_or_wait("k", config, Duration::from_millis(100))
+                .await,
+            RateDecision::Allowed
+        );
+        assert!(matches!(
+            limiter.acquire_or_wait("k", config, Duration::ZERO).await,
+            RateDecision::Limited { .. }
+        ));
+    }
+}
@@ -0,0 +1,319 @@
+//! 抓包重放
+//!
+//! 将抓包记录中的上游请求重新发送到任意供应商，并与原始响应逐行对比：
+//! - 请求体原样发送（即抓包时转换后的上游格式），端点沿用原始请求
+//! - 认证使用目标供应商自身的凭证；抓包中被遮蔽的 Header 不会发送
+//! - JSON 响应先格式化再对比，SSE 响应按原始文本逐行对比
+
+use super::capture::is_masked_header;
+use super::providers::gemini_oauth::GeminiOAuthManager;
+use super::providers::{get_adapter, AuthStrategy};
+use crate::app_config::AppType;
+use crate::database::{Database, RequestCapture};
+use crate::error::AppError;
+use serde::Serialize;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// 不从抓包中复制的请求头（由 HTTP 客户端或认证逻辑重新生成）
+const SKIPPED_HEADERS: &[&str] = &["host", "content-length", "transfer-encoding"];
+
+/// 逐行对比时允许的最大计算量（行数乘积），超出时退化为整体替换
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// 对比结果中的一行
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    /// equal / removed / added
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Equal,
+    Removed,
+    Added,
+}
+
+impl DiffLine {
+    /// 统一 diff 格式的行前缀
+    pub fn prefix(&self) -> char {
+        match self.kind {
+            DiffKind::Equal => ' ',
+            DiffKind::Removed => '-',
+            DiffKind::Added => '+',
+        }
+    }
+}
+
+/// 重放结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayResult {
+    pub request_id: String,
+    pub provider_id: String,
+    pub url: String,
+    pub status: u16,
+    pub latency_ms: u64,
+    pub response_body: String,
+    /// 原始响应状态码（抓包时请求失败则为 None）
+    pub original_status: Option<u16>,
+    /// 状态码与响应体是否与原始响应完全一致
+    pub identical: bool,
+    /// 原始响应（-）与重放响应（+）的逐行对比
+    pub diff: Vec<DiffLine>,
+}
+
+/// 重放抓包记录
+///
+/// `provider_id` 为空时发送到抓包时的原供应商。
+pub async fn replay_capture(
+    db: &Arc<Database>,
+    request_id: &str,
+    provider_id: Option<&str>,
+) -> Result<ReplayResult, AppError> {
+    let capture = db
+        .get_request_capture(request_id)?
+        .ok_or_else(|| AppError::Message(format!("抓包记录不存在: {request_id}")))?;
+    if capture.request_truncated {
+        return Err(AppError::Message(
+            "抓包中的请求体已被截断，无法重放（可调大抓包大小上限）".to_string(),
+        ));
+    }
+
+    let app_type = AppType::from_str(&capture.app_type)?;
+    let provider_id = provider_id.unwrap_or(&capture.provider_id);
+    let provider = db
+        .get_provider_by_id(provider_id, &capture.app_type)?
+        .ok_or_else(|| {
+            AppError::localized(
+                "provider.not_found",
+                format!("供应商不存在: {provider_id}"),
+                format!("Provider not found: {provider_id}"),
+            )
+        })?;
+
+    let adapter = get_adapter(&app_type);
+    let base_url = adapter
+        .extract_base_url(&provider)
+        .map_err(|e| AppError::Message(e.to_string()))?;
+    let endpoint = captured_endpoint(db, &capture);
+    let url = adapter.build_url(&base_url, &endpoint);
+
+    let proxy_config = provider.meta.as_ref().and_then(|m| m.proxy_config.as_ref());
+    let client = super::http_client::get_for_provider(proxy_config);
+    let mut request = client.post(&url);
+    for (name, value) in &capture.request_headers {
+        if is_masked_header(name) || SKIPPED_HEADERS.iter().any(|h| name.eq_ignore_ascii_case(h)) {
+            continue;
+        }
+        request = request.header(name, value);
+    }
+
+    if let Some(mut auth) = adapter.extract_auth(&provider) {
+        match auth.strategy {
+            AuthStrategy::GitHubCopilot => {
+                return Err(AppError::Message(
+                    "暂不支持重放到 GitHub Copilot 供应商".to_string(),
+                ));
+            }
+            AuthStrategy::GoogleOAuth => {
+                auth = GeminiOAuthManager::new(db.clone())
+                    .resolve_auth(&provider, &capture.app_type, auth, false)
+                    .await
+                    .map_err(|e| AppError::Message(format!("Gemini OAuth 认证失败: {e}")))?;
+            }
+            _ => {}
+        }
+        request = adapter.add_auth_headers(request, &auth);
+    }
+
+    log::info!(
+        "[Replay] 重放请求 {request_id} -> {} ({url})",
+        provider.name
+    );
+    let start = Instant::now();
+    let response = request
+        .body(capture.request_body.clone())
+        .send()
+        .await
+        .map_err(|e| AppError::Message(format!("重放请求失败: {e}")))?;
+    let status = response.status().as_u16();
+    let response_body = response
+        .text()
+        .await
+        .map_err(|e| AppError::Message(format!("读取重放响应失败: {e}")))?;
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    let original_body = capture.response_body.as_deref().unwrap_or_default();
+    let identical = capture.response_status == Some(status) && original_body == response_body;
+    let diff = diff_lines(
+        &normalize_body(original_body),
+        &normalize_body(&response_body),
+    );
+
+    Ok(ReplayResult {
+        request_id: capture.request_id,
+        provider_id: provider.id,
+        url,
+        status,
+        latency_ms,
+        response_body,
+        original_status: capture.response_status,
+        identical,
+        diff,
+    })
+}
+
+/// 从抓包 URL 还原请求端点（去掉原供应商的 base_url 与被遮蔽的查询参数）
+fn captured_endpoint(db: &Database, capture: &RequestCapture) -> String {
+    let Ok(mut url) = url::Url::parse(&capture.url) else {
+        return capture.url.clone();
+    };
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(k, _)| k != "key")
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+
+    let full = url.to_string();
+    let original_base = AppType::from_str(&capture.app_type)
+        .ok()
+        .zip(
+            db.get_provider_by_id(&capture.provider_id, &capture.app_type)
+                .ok()
+                .flatten(),
+        )
+        .and_then(|(app_type, provider)| get_adapter(&app_type).extract_base_url(&provider).ok());
+    if let Some(endpoint) = original_base
+        .as_deref()
+        .and_then(|base| full.strip_prefix(base.trim_end_matches('/')))
+        .filter(|rest| rest.starts_with('/'))
+    {
+        return endpoint.to_string();
+    }
+
+    match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_string(),
+    }
+}
+
+/// JSON 响应格式化后再对比，便于定位字段差异
+fn normalize_body(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| serde_json::to_string_pretty(&value).ok())
+        .unwrap_or_else(|| body.to_string())
+}
+
+/// 逐行对比（最长公共子序列）
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // 公共前缀/后缀不参与 LCS 计算
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let line = |kind, text: &str| DiffLine {
+        kind,
+        text: text.to_string(),
+    };
+    let mut result: Vec<DiffLine> = old[..prefix]
+        .iter()
+        .map(|t| line(DiffKind::Equal, t))
+        .collect();
+
+    if old_mid.len().saturating_mul(new_mid.len()) > MAX_DIFF_CELLS {
+        result.extend(old_mid.iter().map(|t| line(DiffKind::Removed, t)));
+        result.extend(new_mid.iter().map(|t| line(DiffKind::Added, t)));
+    } else {
+        let (n, m) = (old_mid.len(), new_mid.len());
+        // lcs[i][j]：old_mid[i..] 与 new_mid[j..] 的最长公共子序列长度
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if old_mid[i] == new_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                result.push(line(DiffKind::Equal, old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                result.push(line(DiffKind::Removed, old_mid[i]));
+                i += 1;
+            } else {
+                result.push(line(DiffKind::Added, new_mid[j]));
+                j += 1;
+            }
+        }
+    }
+
+    result.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|t| line(DiffKind::Equal, t)),
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(diff: &[DiffLine]) -> Vec<String> {
+        diff.iter()
+            .map(|l| format!("{}{}", l.prefix(), l.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_lines_marks_changes() {
+        let diff = diff_lines("a\nb\nc\nd", "a\nc\nx\nd");
+        assert_eq!(render(&diff), vec![" a", "-b", " c", "+x", " d"]);
+
+        let same = diff_lines("a\nb", "a\nb");
+        assert!(same.iter().all(|l| l.kind == DiffKind::Equal));
+        assert_eq!(render(&diff_lines("", "a")), vec!["+a"]);
+    }
+
+    #[test]
+    fn test_normalize_body_pretty_prints_json() {
+        let old = normalize_body(r#"{"id":"1","text":"hi"}"#);
+        let new = normalize_body(r#"{"id":"2","text":"hi"}"#);
+        let changed: Vec<String> = render(&diff_lines(&old, &new))
+            .into_iter()
+            .filter(|l| !l.starts_with(' '))
+            .collect();
+        assert_eq!(changed, vec![r#"-  "id": "1","#, r#"+  "id": "2","#]);
+        assert_eq!(normalize_body("data: x"), "data: x");
+    }
+}
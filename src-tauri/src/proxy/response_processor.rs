@@ -3,9 +3,13 @@
 //! 统一处理流式和非流式 API 响应
 
 use super::{
+    client_auth::ClientIdentity,
     handler_config::UsageParserConfig,
     handler_context::{RequestContext, StreamingTimeoutConfig},
+    load_balancer::InFlightStream,
+    metrics::RequestObservation,
     server::ProxyState,
+    session_affinity::AffinityDecision,
     usage::parser::TokenUsage,
     ProxyError,
 };
@@ -59,9 +63,8 @@ pub async fn handle_streaming(
         builder = builder.header(key, value);
     }
 
-    // 创建字节流
-    let stream = response
-        .bytes_stream()
+    // 创建字节流（持有进行中计数守卫，直到流结束）
+    let stream = InFlightStream::new(response.bytes_stream(), ctx.in_flight.clone())
         .map(|chunk| chunk.map_err(|e| std::io::Error::other(e.to_string())));
 
     // 创建使用量收集器
@@ -277,7 +280,7 @@ impl SseUsageCollector {
 // ============================================================================
 
 /// 创建使用量收集器
-fn create_usage_collector(
+pub fn create_usage_collector(
     ctx: &RequestContext,
     state: &ProxyState,
     status_code: u16,
@@ -292,6 +295,10 @@ fn create_usage_collector(
     let stream_parser = parser_config.stream_parser;
     let model_extractor = parser_config.model_extractor;
     let session_id = ctx.session_id.clone();
+    let session_affinity = ctx.session_affinity;
+    let client = ctx.client.clone();
+    let attempts = ctx.attempts;
+    let request_id = ctx.request_id.clone();
 
     SseUsageCollector::new(start_time, move |events, first_token_ms| {
         if let Some(usage) = stream_parser(&events) {
@@ -302,10 +309,13 @@ fn create_usage_collector(
             let provider_id = provider_id.clone();
             let session_id = session_id.clone();
             let request_model = request_model.clone();
+            let client = client.clone();
+            let request_id = request_id.clone();
 
             tokio::spawn(async move {
                 log_usage_internal(
                     &state,
+                    request_id,
                     &provider_id,
                     app_type_str,
                     &model,
@@ -316,6 +326,9 @@ fn create_usage_collector(
                     true, // is_streaming
                     status_code,
                     Some(session_id),
+                    session_affinity,
+                    client,
+                    attempts,
                 )
                 .await;
             });
@@ -326,10 +339,13 @@ fn create_usage_collector(
             let provider_id = provider_id.clone();
             let session_id = session_id.clone();
             let request_model = request_model.clone();
+            let client = client.clone();
+            let request_id = request_id.clone();
 
             tokio::spawn(async move {
                 log_usage_internal(
                     &state,
+                    request_id,
                     &provider_id,
                     app_type_str,
                     &model,
@@ -340,6 +356,9 @@ fn create_usage_collector(
                     true, // is_streaming
                     status_code,
                     Some(session_id),
+                    session_affinity,
+                    client,
+                    attempts,
                 )
                 .await;
             });
@@ -349,7 +368,7 @@ fn create_usage_collector(
 }
 
 /// 异步记录使用量
-fn spawn_log_usage(
+pub fn spawn_log_usage(
     state: &ProxyState,
     ctx: &RequestContext,
     usage: TokenUsage,
@@ -365,10 +384,15 @@ fn spawn_log_usage(
     let request_model = request_model.to_string();
     let latency_ms = ctx.latency_ms();
     let session_id = ctx.session_id.clone();
+    let session_affinity = ctx.session_affinity;
+    let client = ctx.client.clone();
+    let attempts = ctx.attempts;
+    let request_id = ctx.request_id.clone();
 
     tokio::spawn(async move {
         log_usage_internal(
             &state,
+            request_id,
             &provider_id,
             &app_type_str,
             &model,
@@ -379,15 +403,19 @@ fn spawn_log_usage(
             is_streaming,
             status_code,
             Some(session_id),
+            session_affinity,
+            client,
+            attempts,
         )
         .await;
     });
 }
 
-/// 内部使用量记录函数
+/// 使用量记录函数（请求日志、消费统计、指标与 TPM 额度）
 #[allow(clippy::too_many_arguments)]
-async fn log_usage_internal(
+pub(crate) async fn log_usage_internal(
     state: &ProxyState,
+    request_id: String,
     provider_id: &str,
     app_type: &str,
     model: &str,
@@ -398,8 +426,12 @@ async fn log_usage_internal(
     is_streaming: bool,
     status_code: u16,
     session_id: Option<String>,
+    session_affinity: Option<AffinityDecision>,
+    client: Option<ClientIdentity>,
+    attempts: u32,
 ) {
     use super::usage::logger::UsageLogger;
+    use rust_decimal::prelude::ToPrimitive;
 
     let logger = UsageLogger::new(&state.db);
     let (multiplier, pricing_model_source) =
@@ -410,7 +442,8 @@ async fn log_usage_internal(
         model
     };
 
-    let request_id = uuid::Uuid::new_v4().to_string();
+    let rate_tokens = u64::from(usage.input_tokens) + u64::from(usage.output_tokens);
+    let observed_usage = usage.clone();
 
     log::debug!(
         "[{app_type}] 记录请求日志: id={request_id}, provider={provider_id}, model={model}, streaming={is_streaming}, status={status_code}, latency_ms={latency_ms}, first_token_ms={first_token_ms:?}, session={}, input={}, output={}, cache_read={}, cache_creation={}",
@@ -421,7 +454,7 @@ async fn log_usage_internal(
         usage.cache_creation_tokens
     );
 
-    if let Err(e) = logger.log_with_calculation(
+    let cost_usd = match logger.log_with_calculation(
         request_id,
         provider_id.to_string(),
         app_type.to_string(),
@@ -436,8 +469,50 @@ async fn log_usage_internal(
         session_id,
         None, // provider_type
         is_streaming,
+        session_affinity.map(|d| d.as_str().to_string()),
+        client.as_ref().map(|client| client.label.clone()),
+        attempts,
     ) {
-        log::warn!("[USG-001] 记录使用量失败: {e}");
+        Ok(Some(cost)) => {
+            // 累加到内存消费统计，供限额检查使用
+            let cost_usd = cost.total_cost.to_f64().unwrap_or(0.0);
+            state
+                .provider_router
+                .record_provider_spend(provider_id, app_type, cost_usd)
+                .await;
+            Some(cost_usd)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            log::warn!("[USG-001] 记录使用量失败: {e}");
+            None
+        }
+    };
+    state.metrics.observe(RequestObservation {
+        app_type,
+        provider_id,
+        model,
+        status_code,
+        latency_ms,
+        first_token_ms,
+        usage: Some(&observed_usage),
+        cost_usd,
+    });
+    // 扣减供应商与客户端的 TPM 额度
+    state
+        .provider_router
+        .record_rate_tokens(
+            provider_id,
+            app_type,
+            client.as_ref().map(|client| client.token_id.as_str()),
+            rate_tokens,
+        )
+        .await;
+    if (200..300).contains(&status_code) {
+        state
+            .provider_router
+            .record_provider_latency(provider_id, app_type, first_token_ms.unwrap_or(latency_ms))
+            .await;
     }
 }
 
@@ -568,6 +643,8 @@ mod tests {
     use crate::error::AppError;
     use crate::provider::ProviderMeta;
     use crate::proxy::failover_switch::FailoverSwitchManager;
+    use crate::proxy::metrics::ProxyMetrics;
+    use crate::proxy::model_catalog::ModelCatalog;
     use crate::proxy::provider_router::ProviderRouter;
     use crate::proxy::types::{ProxyConfig, ProxyStatus};
     use rust_decimal::Decimal;
@@ -585,7 +662,9 @@ mod tests {
             current_providers: Arc::new(RwLock::new(HashMap::new())),
             provider_router: Arc::new(ProviderRouter::new(db.clone())),
             app_handle: None,
-            failover_manager: Arc::new(FailoverSwitchManager::new(db)),
+            failover_manager: Arc::new(FailoverSwitchManager::new(db.clone())),
+            model_catalog: Arc::new(ModelCatalog::new(db)),
+            metrics: Arc::new(ProxyMetrics::new()),
         }
     }
 
@@ -649,6 +728,7 @@ mod tests {
 
         log_usage_internal(
             &state,
+            "req-provider-1".to_string(),
             "provider-1",
             app_type,
             "resp-model",
@@ -659,6 +739,9 @@ mod tests {
             false,
             200,
             None,
+            None,
+            None,
+            1,
         )
         .await;
 
@@ -708,6 +791,7 @@ mod tests {
 
         log_usage_internal(
             &state,
+            "req-provider-2".to_string(),
             "provider-2",
             app_type,
             "resp-model",
@@ -718,6 +802,9 @@ mod tests {
             false,
             200,
             None,
+            None,
+            None,
+            1,
         )
         .await;
 
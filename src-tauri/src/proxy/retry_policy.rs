@@ -0,0 +1,169 @@
+//! 同供应商重试策略
+//!
+//! 故障转移前，先在同一供应商上重试瞬时错误（429/5xx/超时/连接失败）：
+//! - 重试次数取自应用级 `max_retries`（0 表示不重试，直接故障转移）
+//! - 退避时间按指数增长，并加入随机抖动，避免并发请求同时重试
+//! - 上游返回 `retry-after` 时优先遵循；等待时间过长则放弃重试，交给故障转移处理
+//! - 同一供应商上的累计等待时间有上限（且不超过非流式超时的四分之一），避免拖慢故障转移
+
+use super::types::AppProxyConfig;
+use std::time::Duration;
+
+/// 首次重试的基础等待时间
+const BASE_DELAY: Duration = Duration::from_millis(500);
+/// 单次退避的上限
+const MAX_DELAY: Duration = Duration::from_secs(8);
+/// 可接受的上游 `retry-after` 上限（超过则直接故障转移）
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(10);
+/// 同一供应商上累计等待时间的上限
+const MAX_TOTAL_WAIT: Duration = Duration::from_secs(10);
+
+/// 重试策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 同一供应商上的最大重试次数（不含首次请求）
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub max_retry_after: Duration,
+    /// 同一供应商上所有重试的累计等待上限
+    pub max_total_wait: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            base_delay: BASE_DELAY,
+            max_delay: MAX_DELAY,
+            max_retry_after: MAX_RETRY_AFTER,
+            max_total_wait: MAX_TOTAL_WAIT,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_app_config(config: &AppProxyConfig) -> Self {
+        let mut max_total_wait = MAX_TOTAL_WAIT;
+        if config.non_streaming_timeout > 0 {
+            max_total_wait =
+                max_total_wait.min(Duration::from_secs(config.non_streaming_timeout as u64) / 4);
+        }
+        Self {
+            max_retries: config.max_retries,
+            max_total_wait,
+            ..Self::default()
+        }
+    }
+
+    /// 计算第 `retry`（从 0 开始）次重试前的等待时间
+    ///
+    /// `waited` 为此前在同一供应商上已等待的时间。
+    /// 返回 None 表示不应在同一供应商上继续重试。
+    pub fn delay_for(
+        &self,
+        retry: u32,
+        waited: Duration,
+        retry_after: Option<Duration>,
+    ) -> Option<Duration> {
+        if retry >= self.max_retries {
+            return None;
+        }
+        let delay = match retry_after {
+            Some(wait) if wait > self.max_retry_after => return None,
+            Some(wait) => wait,
+            None => {
+                let exp = self
+                    .base_delay
+                    .saturating_mul(2u32.saturating_pow(retry))
+                    .min(self.max_delay);
+                // 抖动：取 [50%, 100%] 区间内的随机值
+                exp.mul_f64(0.5 + random_unit() * 0.5)
+            }
+        };
+        (waited + delay <= self.max_total_wait).then_some(delay)
+    }
+}
+
+/// [0, 1) 区间的随机数
+fn random_unit() -> f64 {
+    // 取低 53 位（不含 UUID 版本/变体位）
+    let bits = uuid::Uuid::new_v4().as_u128() & ((1u128 << 53) - 1);
+    bits as f64 / (1u64 << 53) as f64
+}
+
+/// 解析 `retry-after` 头（支持秒数与 HTTP 日期两种格式）
+pub fn parse_retry_after(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(secs);
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let secs = date.timestamp() - chrono::Utc::now().timestamp();
+    Some(secs.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_retries(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_delay_grows_exponentially_with_jitter() {
+        let policy = with_retries(5);
+        for retry in 0..5 {
+            let delay = policy
+                .delay_for(retry, Duration::ZERO, None)
+                .expect("delay");
+            let exp = BASE_DELAY.saturating_mul(1 << retry).min(MAX_DELAY);
+            assert!(delay >= exp / 2, "retry {retry}: {delay:?}");
+            assert!(delay <= exp, "retry {retry}: {delay:?}");
+        }
+        assert_eq!(policy.delay_for(5, Duration::ZERO, None), None);
+    }
+
+    #[test]
+    fn test_retry_after_is_honored_within_limit() {
+        let policy = with_retries(2);
+        assert_eq!(
+            policy.delay_for(0, Duration::ZERO, Some(Duration::from_secs(3))),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            policy.delay_for(0, Duration::ZERO, Some(Duration::from_secs(120))),
+            None
+        );
+        assert_eq!(
+            with_retries(0).delay_for(0, Duration::ZERO, Some(Duration::from_secs(1))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_total_wait_is_capped() {
+        let policy = with_retries(3);
+        let wait = Some(Duration::from_secs(4));
+        assert_eq!(
+            policy.delay_for(1, Duration::from_secs(4), wait),
+            Some(Duration::from_secs(4))
+        );
+        // 4s + 4s 之后再等 4s 会超过累计上限，交给故障转移
+        assert_eq!(policy.delay_for(2, Duration::from_secs(8), wait), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("12"), Some(12));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(0));
+        let future = (chrono::Utc::now() + chrono::Duration::seconds(60)).to_rfc2822();
+        let secs = parse_retry_after(&future).expect("date");
+        assert!((58..=60).contains(&secs));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
@@ -3,8 +3,9 @@
 //! 基于Axum的HTTP服务器，处理代理请求
 
 use super::{
-    failover_switch::FailoverSwitchManager, handlers, log_codes::srv as log_srv,
-    provider_router::ProviderRouter, types::*, ProxyError,
+    client_auth, failover_switch::FailoverSwitchManager, handlers, log_codes::srv as log_srv,
+    metrics::ProxyMetrics, model_catalog::ModelCatalog, provider_router::ProviderRouter, types::*,
+    ProxyError,
 };
 use crate::database::Database;
 use axum::{
@@ -30,9 +31,13 @@ pub struct ProxyState {
     /// 共享的 ProviderRouter（持有熔断器状态，跨请求保持）
     pub provider_router: Arc<ProviderRouter>,
     /// AppHandle，用于发射事件和更新托盘菜单
-    pub app_handle: Option<tauri::AppHandle>,
+    pub app_handle: Option<crate::AppHandle>,
     /// 故障转移切换管理器
     pub failover_manager: Arc<FailoverSwitchManager>,
+    /// 聚合模型目录（`/v1/models`，带 TTL 缓存）
+    pub model_catalog: Arc<ModelCatalog>,
+    /// Prometheus 指标（`/metrics`）
+    pub metrics: Arc<ProxyMetrics>,
 }
 
 /// 代理HTTP服务器
@@ -48,12 +53,14 @@ impl ProxyServer {
     pub fn new(
         config: ProxyConfig,
         db: Arc<Database>,
-        app_handle: Option<tauri::AppHandle>,
+        app_handle: Option<crate::AppHandle>,
     ) -> Self {
         // 创建共享的 ProviderRouter（熔断器状态将跨所有请求保持）
         let provider_router = Arc::new(ProviderRouter::new(db.clone()));
         // 创建故障转移切换管理器
         let failover_manager = Arc::new(FailoverSwitchManager::new(db.clone()));
+        // 创建模型目录（缓存上游模型列表）
+        let model_catalog = Arc::new(ModelCatalog::new(db.clone()));
 
         let state = ProxyState {
             db,
@@ -64,6 +71,8 @@ impl ProxyServer {
             provider_router,
             app_handle,
             failover_manager,
+            model_catalog,
+            metrics: Arc::new(ProxyMetrics::new()),
         };
 
         Self {
@@ -171,6 +180,17 @@ impl ProxyServer {
         }
     }
 
+    /// 进行中的上游请求数（流式响应在客户端读完前都计入）
+    pub async fn in_flight_requests(&self) -> usize {
+        self.state
+            .provider_router
+            .in_flight_counts()
+            .await
+            .iter()
+            .map(|(_, count)| count)
+            .sum()
+    }
+
     pub async fn get_status(&self) -> ProxyStatus {
         let mut status = self.state.status.read().await.clone();
 
@@ -189,6 +209,7 @@ impl ProxyServer {
                 provider_name: provider_name.clone(),
             })
             .collect();
+        status.rate_limits = self.state.provider_router.rate_limit_levels().await;
 
         status
     }
@@ -203,6 +224,10 @@ impl ProxyServer {
             app_type.to_string(),
             (provider_id.to_string(), provider_name.to_string()),
         );
+        drop(current_providers);
+
+        // 当前供应商变化后，模型目录需重新拉取
+        self.state.model_catalog.invalidate().await;
     }
 
     fn build_router(&self) -> Router {
@@ -215,6 +240,12 @@ impl ProxyServer {
             // 健康检查
             .route("/health", get(handlers::health_check))
             .route("/status", get(handlers::get_status))
+            .route("/metrics", get(handlers::get_metrics))
+            // 模型列表（OpenAI / Anthropic 格式，聚合所有可用供应商）
+            .route("/models", get(handlers::handle_models))
+            .route("/v1/models", get(handlers::handle_models))
+            .route("/claude/v1/models", get(handlers::handle_claude_models))
+            .route("/codex/v1/models", get(handlers::handle_codex_models))
             // Claude API (支持带前缀和不带前缀两种格式)
             .route("/v1/messages", post(handlers::handle_messages))
             .route("/claude/v1/messages", post(handlers::handle_messages))
@@ -240,6 +271,11 @@ impl ProxyServer {
             // Gemini API (支持带前缀和不带前缀)
             .route("/v1beta/*path", post(handlers::handle_gemini))
             .route("/gemini/v1beta/*path", post(handlers::handle_gemini))
+            // 客户端认证（可选，识别代理签发的客户端令牌）
+            .layer(axum::middleware::from_fn_with_state(
+                self.state.clone(),
+                client_auth::client_auth_middleware,
+            ))
             // 提高默认请求体大小限制（避免 413 Payload Too Large）
             .layer(DefaultBodyLimit::max(200 * 1024 * 1024))
             .layer(cors)
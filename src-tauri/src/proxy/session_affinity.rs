@@ -0,0 +1,190 @@
+//! 会话粘性路由模块
+//!
+//! 将客户端提供的 Session ID 绑定到首次成功服务它的供应商，
+//! 使同一会话的后续请求优先发往该供应商，以保持 Prompt Cache 命中并避免 thinking 签名失效。
+//!
+//! - 绑定在 TTL 内无请求时过期
+//! - 绑定的供应商不在本次候选列表中（熔断/超出限额/移出队列）时回退到正常顺序，
+//!   并在请求成功后改绑到实际服务的供应商
+
+use crate::provider::Provider;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// 绑定数量超过该值时清理过期绑定
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// 会话粘性决策（写入请求日志 session_affinity 列）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AffinityDecision {
+    /// 会话尚无绑定（或已过期），按正常顺序路由后建立绑定
+    New,
+    /// 命中绑定，优先使用绑定的供应商
+    Hit,
+    /// 绑定的供应商不可用或请求已故障转移，改绑到实际服务的供应商
+    Fallback,
+}
+
+impl AffinityDecision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AffinityDecision::New => "new",
+            AffinityDecision::Hit => "hit",
+            AffinityDecision::Fallback => "fallback",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    provider_id: String,
+    last_seen: Instant,
+}
+
+/// 会话 → 供应商绑定表
+#[derive(Default)]
+pub struct SessionAffinity {
+    /// key 格式: "app_type:session_id"
+    bindings: RwLock<HashMap<String, Binding>>,
+}
+
+impl SessionAffinity {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按会话绑定调整供应商顺序
+    ///
+    /// 命中时将绑定的供应商移到队首，其余供应商保持原顺序作为故障转移链。
+    pub async fn apply(
+        &self,
+        app_type: &str,
+        session_id: &str,
+        ttl: Duration,
+        mut providers: Vec<Provider>,
+    ) -> (Vec<Provider>, AffinityDecision) {
+        let key = format!("{app_type}:{session_id}");
+        let now = Instant::now();
+
+        let bound_provider = {
+            let mut bindings = self.bindings.write().await;
+            match bindings.get(&key) {
+                Some(binding) if now.duration_since(binding.last_seen) < ttl => {
+                    Some(binding.provider_id.clone())
+                }
+                Some(_) => {
+                    bindings.remove(&key);
+                    None
+                }
+                None => None,
+            }
+        };
+
+        let Some(bound_provider) = bound_provider else {
+            return (providers, AffinityDecision::New);
+        };
+
+        match providers.iter().position(|p| p.id == bound_provider) {
+            Some(index) => {
+                let pinned = providers.remove(index);
+                providers.insert(0, pinned);
+                (providers, AffinityDecision::Hit)
+            }
+            None => {
+                log::info!(
+                    "[{app_type}] 会话 {session_id} 绑定的供应商 {bound_provider} 当前不可用，回退到正常路由"
+                );
+                (providers, AffinityDecision::Fallback)
+            }
+        }
+    }
+
+    /// 将会话绑定（或续期）到实际服务的供应商
+    pub async fn bind(&self, app_type: &str, session_id: &str, provider_id: &str, ttl: Duration) {
+        let key = format!("{app_type}:{session_id}");
+        let now = Instant::now();
+
+        let mut bindings = self.bindings.write().await;
+        bindings.insert(
+            key,
+            Binding {
+                provider_id: provider_id.to_string(),
+                last_seen: now,
+            },
+        );
+
+        if bindings.len() > PRUNE_THRESHOLD {
+            bindings.retain(|_, binding| now.duration_since(binding.last_seen) < ttl);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn providers(ids: &[&str]) -> Vec<Provider> {
+        ids.iter()
+            .map(|id| Provider::with_id(id.to_string(), id.to_uppercase(), json!({}), None))
+            .collect()
+    }
+
+    fn ids(providers: &[Provider]) -> Vec<&str> {
+        providers.iter().map(|p| p.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_new_session_then_hit_moves_bound_provider_first() {
+        let affinity = SessionAffinity::new();
+        let ttl = Duration::from_secs(60);
+
+        let (ordered, decision) = affinity
+            .apply("claude", "s1", ttl, providers(&["a", "b"]))
+            .await;
+        assert_eq!(decision, AffinityDecision::New);
+        assert_eq!(ids(&ordered), vec!["a", "b"]);
+
+        affinity.bind("claude", "s1", "b", ttl).await;
+        let (ordered, decision) = affinity
+            .apply("claude", "s1", ttl, providers(&["a", "b"]))
+            .await;
+        assert_eq!(decision, AffinityDecision::Hit);
+        assert_eq!(ids(&ordered), vec!["b", "a"]);
+
+        // 其他应用的同名会话互不影响
+        let (_, decision) = affinity
+            .apply("codex", "s1", ttl, providers(&["a", "b"]))
+            .await;
+        assert_eq!(decision, AffinityDecision::New);
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_bound_provider_falls_back() {
+        let affinity = SessionAffinity::new();
+        let ttl = Duration::from_secs(60);
+        affinity.bind("claude", "s1", "b", ttl).await;
+
+        // b 熔断后不在候选列表中
+        let (ordered, decision) = affinity
+            .apply("claude", "s1", ttl, providers(&["a", "c"]))
+            .await;
+        assert_eq!(decision, AffinityDecision::Fallback);
+        assert_eq!(ids(&ordered), vec!["a", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_expired_binding_is_dropped() {
+        let affinity = SessionAffinity::new();
+        affinity
+            .bind("claude", "s1", "b", Duration::from_secs(60))
+            .await;
+
+        let (ordered, decision) = affinity
+            .apply("claude", "s1", Duration::ZERO, providers(&["a", "b"]))
+            .await;
+        assert_eq!(decision, AffinityDecision::New);
+        assert_eq!(ids(&ordered), vec!["a", "b"]);
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// 代理服务器配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -89,6 +90,9 @@ pub struct ProxyStatus {
     /// 当前活跃的代理目标列表
     #[serde(default)]
     pub active_targets: Vec<ActiveTarget>,
+    /// 各供应商/客户端令牌的 RPM/TPM 令牌桶水位
+    #[serde(default)]
+    pub rate_limits: Vec<super::rate_limiter::RateLimitLevel>,
 }
 
 /// 活跃的代理目标信息
@@ -162,6 +166,9 @@ pub struct GlobalProxyConfig {
     pub listen_port: u16,
     /// 是否启用日志
     pub enable_logging: bool,
+    /// 是否要求客户端携带代理签发的令牌（监听 0.0.0.0 共享给他人时应开启）
+    #[serde(default)]
+    pub require_client_auth: bool,
 }
 
 /// 应用级代理配置（每个 app 独立）
@@ -192,6 +199,57 @@ pub struct AppProxyConfig {
     pub circuit_error_rate_threshold: f64,
     /// 计算错误率的最小请求数
     pub circuit_min_requests: u32,
+    /// 供应商路由策略（仅故障转移开启时生效）
+    #[serde(default)]
+    pub routing_strategy: RoutingStrategy,
+    /// 会话粘性路由开关（仅故障转移开启时生效）
+    #[serde(default)]
+    pub session_affinity_enabled: bool,
+    /// 会话粘性绑定过期时间（秒）
+    #[serde(default = "default_session_affinity_ttl_seconds")]
+    pub session_affinity_ttl_seconds: u32,
+}
+
+fn default_session_affinity_ttl_seconds() -> u32 {
+    1800
+}
+
+/// 供应商路由策略
+///
+/// 决定故障转移队列中各供应商的尝试顺序，存储在 proxy_config.routing_strategy 列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingStrategy {
+    /// 严格按队列顺序（P1 → P2 → ...）
+    #[default]
+    Priority,
+    /// 平滑加权轮询（权重来自供应商 meta.routingWeight）
+    WeightedRoundRobin,
+    /// 优先选择进行中请求最少的供应商
+    LeastInFlight,
+    /// 优先选择 EWMA 延迟最低的供应商
+    LowestLatency,
+}
+
+impl RoutingStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoutingStrategy::Priority => "priority",
+            RoutingStrategy::WeightedRoundRobin => "weighted_round_robin",
+            RoutingStrategy::LeastInFlight => "least_in_flight",
+            RoutingStrategy::LowestLatency => "lowest_latency",
+        }
+    }
+
+    /// 从数据库字符串解析，未知值回退为 Priority
+    pub fn from_db(value: &str) -> Self {
+        match value.trim() {
+            "weighted_round_robin" => RoutingStrategy::WeightedRoundRobin,
+            "least_in_flight" => RoutingStrategy::LeastInFlight,
+            "lowest_latency" => RoutingStrategy::LowestLatency,
+            _ => RoutingStrategy::Priority,
+        }
+    }
 }
 
 /// 整流器配置
@@ -273,6 +331,119 @@ impl LogConfig {
     }
 }
 
+/// 请求抓包配置
+///
+/// 存储在 settings 表的 capture_config 字段中（JSON 格式），默认关闭。
+/// 开启后记录发往上游的完整请求与原始响应（含 SSE 流），用于排查与重放。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureConfig {
+    /// 总开关：是否记录完整请求/响应（默认关闭）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 单个请求体/响应体保存的最大字节数，超出部分截断
+    #[serde(default = "default_capture_max_body_bytes")]
+    pub max_body_bytes: usize,
+    /// 抓包记录保留天数
+    #[serde(default = "default_capture_retention_days")]
+    pub retention_days: u32,
+}
+
+fn default_capture_max_body_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_capture_retention_days() -> u32 {
+    7
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_body_bytes: default_capture_max_body_bytes(),
+            retention_days: default_capture_retention_days(),
+        }
+    }
+}
+
+/// 请求日志保留策略
+///
+/// 定期清理超过保留天数的原始请求日志；按小时/按日的聚合统计不受影响。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRetentionConfig {
+    /// 是否自动清理（默认关闭，保留全部原始日志）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 原始日志保留天数
+    #[serde(default = "default_usage_retention_days")]
+    pub retention_days: u32,
+    /// 清理前是否将原始日志归档为 JSON Lines 文件
+    #[serde(default)]
+    pub archive: bool,
+}
+
+fn default_usage_retention_days() -> u32 {
+    90
+}
+
+impl Default for UsageRetentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: default_usage_retention_days(),
+            archive: false,
+        }
+    }
+}
+
+/// 预算提醒配置
+///
+/// 供应商（`limitDailyUsd` / `limitMonthlyUsd`）或应用整体的消费达到限额的指定百分比时，
+/// 每个周期提醒一次：发送前端事件，并可选地向 Webhook 推送 JSON。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetAlertConfig {
+    /// 是否启用预算提醒
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 提醒阈值（限额的百分比）
+    #[serde(default = "default_budget_alert_thresholds")]
+    pub thresholds: Vec<u32>,
+    /// 提醒推送地址（POST JSON，为空时仅发送应用内通知）
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// 应用整体限额（key 为 app_type）
+    #[serde(default)]
+    pub app_limits: HashMap<String, AppBudgetLimit>,
+}
+
+/// 应用整体消费限额（所有供应商合计，USD）
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AppBudgetLimit {
+    #[serde(default)]
+    pub limit_daily_usd: Option<String>,
+    #[serde(default)]
+    pub limit_monthly_usd: Option<String>,
+}
+
+fn default_budget_alert_thresholds() -> Vec<u32> {
+    vec![50, 80, 100]
+}
+
+impl Default for BudgetAlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            thresholds: default_budget_alert_thresholds(),
+            webhook_url: None,
+            app_limits: HashMap::new(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,8 +2,9 @@
 
 use super::calculator::{CostBreakdown, CostCalculator, ModelPricing};
 use super::parser::TokenUsage;
-use crate::database::Database;
+use crate::database::{apply_usage_rollup, Database, UsageRollupDelta};
 use crate::error::AppError;
+use crate::services::budget_alert::{self, check_budget_alerts};
 use crate::services::usage_stats::find_model_pricing_row;
 use rust_decimal::Decimal;
 use std::{str::FromStr, time::SystemTime};
@@ -29,6 +30,12 @@ pub struct RequestLog {
     pub is_streaming: bool,
     /// 成本倍数
     pub cost_multiplier: String,
+    /// 会话粘性决策（new/hit/fallback，未启用时为 None）
+    pub session_affinity: Option<String>,
+    /// 客户端令牌标签（未携带代理签发的令牌时为 None）
+    pub client_label: Option<String>,
+    /// 本次请求的上游请求总次数（含重试与故障转移）
+    pub attempts: u32,
 }
 
 /// 使用量记录器
@@ -42,8 +49,10 @@ impl<'a> UsageLogger<'a> {
     }
 
     /// 记录成功的请求
+    ///
+    /// 原始日志与小时/日聚合在同一事务中写入
     pub fn log_request(&self, log: &RequestLog) -> Result<(), AppError> {
-        let conn = crate::database::lock_conn!(self.db.conn);
+        let mut conn = crate::database::lock_conn!(self.db.conn);
 
         let (input_cost, output_cost, cache_read_cost, cache_creation_cost, total_cost) =
             if let Some(cost) = &log.cost {
@@ -72,14 +81,18 @@ impl<'a> UsageLogger<'a> {
                 0
             });
 
-        conn.execute(
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        tx.execute(
             "INSERT INTO proxy_request_logs (
                 request_id, provider_id, app_type, model, request_model,
                 input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
                 input_cost_usd, output_cost_usd, cache_read_cost_usd, cache_creation_cost_usd, total_cost_usd,
                 latency_ms, first_token_ms, status_code, error_message, session_id,
-                provider_type, is_streaming, cost_multiplier, created_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)",
+                provider_type, is_streaming, cost_multiplier, created_at, session_affinity, client_label,
+                attempts
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26)",
             rusqlite::params![
                 log.request_id,
                 log.provider_id,
@@ -104,10 +117,34 @@ impl<'a> UsageLogger<'a> {
                 log.is_streaming as i64,
                 log.cost_multiplier,
                 created_at,
+                log.session_affinity,
+                log.client_label,
+                log.attempts.max(1) as i64,
             ],
         )
         .map_err(|e| AppError::Database(format!("记录请求日志失败: {e}")))?;
 
+        apply_usage_rollup(
+            &tx,
+            &UsageRollupDelta {
+                created_at,
+                app_type: &log.app_type,
+                provider_id: &log.provider_id,
+                model: &log.model,
+                client_label: log.client_label.as_deref(),
+                request_count: 1,
+                success_count: (200..300).contains(&log.status_code) as i64,
+                input_tokens: log.usage.input_tokens as i64,
+                output_tokens: log.usage.output_tokens as i64,
+                cache_read_tokens: log.usage.cache_read_tokens as i64,
+                cache_creation_tokens: log.usage.cache_creation_tokens as i64,
+                total_cost: total_cost.parse().unwrap_or(0.0),
+                latency_ms: log.latency_ms as i64,
+            },
+        )?;
+
+        tx.commit()
+            .map_err(|e| AppError::Database(format!("记录请求日志失败: {e}")))?;
         Ok(())
     }
 
@@ -142,6 +179,9 @@ impl<'a> UsageLogger<'a> {
             provider_type: None,
             is_streaming: false,
             cost_multiplier: "1.0".to_string(),
+            session_affinity: None,
+            client_label: None,
+            attempts: 1,
         };
 
         self.log_request(&log)
@@ -163,6 +203,9 @@ impl<'a> UsageLogger<'a> {
         is_streaming: bool,
         session_id: Option<String>,
         provider_type: Option<String>,
+        session_affinity: Option<String>,
+        client_label: Option<String>,
+        attempts: u32,
     ) -> Result<(), AppError> {
         let request_model = model.clone();
         let log = RequestLog {
@@ -181,6 +224,9 @@ impl<'a> UsageLogger<'a> {
             provider_type,
             is_streaming,
             cost_multiplier: "1.0".to_string(),
+            session_affinity,
+            client_label,
+            attempts,
         };
 
         self.log_request(&log)
@@ -283,6 +329,8 @@ impl<'a> UsageLogger<'a> {
     }
 
     /// 计算并记录请求
+    ///
+    /// 返回本次请求计算出的成本（未找到模型定价时为 None）
     #[allow(clippy::too_many_arguments)]
     pub fn log_with_calculation(
         &self,
@@ -300,7 +348,10 @@ impl<'a> UsageLogger<'a> {
         session_id: Option<String>,
         provider_type: Option<String>,
         is_streaming: bool,
-    ) -> Result<(), AppError> {
+        session_affinity: Option<String>,
+        client_label: Option<String>,
+        attempts: u32,
+    ) -> Result<Option<CostBreakdown>, AppError> {
         let pricing = self.get_model_pricing(&pricing_model)?;
 
         if pricing.is_none() {
@@ -325,9 +376,25 @@ impl<'a> UsageLogger<'a> {
             provider_type,
             is_streaming,
             cost_multiplier: cost_multiplier.to_string(),
+            session_affinity,
+            client_label,
+            attempts,
         };
 
-        self.log_request(&log)
+        self.log_request(&log)?;
+
+        // 消费有变化时检查预算提醒阈值
+        if log
+            .cost
+            .as_ref()
+            .is_some_and(|c| c.total_cost > Decimal::ZERO)
+        {
+            match check_budget_alerts(self.db, &log.app_type, &log.provider_id) {
+                Ok(alerts) => alerts.into_iter().for_each(budget_alert::notify),
+                Err(e) => log::warn!("[USG-004] 检查预算提醒失败: {e}"),
+            }
+        }
+        Ok(log.cost)
     }
 }
 
@@ -375,19 +442,48 @@ mod tests {
             None,
             Some("claude".to_string()),
             false,
+            Some("hit".to_string()),
+            Some("alice".to_string()),
+            3,
         )?;
 
         // 验证记录已插入
         let conn = crate::database::lock_conn!(db.conn);
-        let (count, request_model): (i64, String) = conn
+        #[allow(clippy::type_complexity)]
+        let (count, request_model, session_affinity, client_label, attempts): (
+            i64,
+            String,
+            Option<String>,
+            Option<String>,
+            i64,
+        ) = conn
             .query_row(
-                "SELECT COUNT(*), request_model FROM proxy_request_logs WHERE request_id = 'req-123'",
+                "SELECT COUNT(*), request_model, session_affinity, client_label, attempts FROM proxy_request_logs WHERE request_id = 'req-123'",
                 [],
-                |row| Ok((row.get(0)?, row.get(1)?)),
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
             )
             .unwrap();
         assert_eq!(count, 1);
         assert_eq!(request_model, "req-model");
+        assert_eq!(session_affinity.as_deref(), Some("hit"));
+        assert_eq!(client_label.as_deref(), Some("alice"));
+        assert_eq!(attempts, 3);
+
+        // 同一事务中累加到小时/日聚合表
+        for table in ["usage_rollup_hourly", "usage_rollup_daily"] {
+            let (requests, input_tokens, cost, label): (i64, i64, f64, String) = conn
+                .query_row(
+                    &format!(
+                        "SELECT request_count, input_tokens, total_cost_usd, client_label FROM {table}"
+                    ),
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )
+                .unwrap();
+            assert_eq!((requests, input_tokens), (1, 1000));
+            assert!((cost - 0.0105).abs() < 1e-9);
+            assert_eq!(label, "alice");
+        }
         Ok(())
     }
 
@@ -109,6 +109,15 @@ impl TokenUsage {
                                     usage.input_tokens = input as u32;
                                 }
                             }
+                            // Gemini 转换后的流式响应：缓存命中数同样只在 message_delta 中
+                            if usage.cache_read_tokens == 0 {
+                                if let Some(cached) = delta_usage
+                                    .get("cache_read_input_tokens")
+                                    .and_then(|v| v.as_u64())
+                                {
+                                    usage.cache_read_tokens = cached as u32;
+                                }
+                            }
                         }
                     }
                     _ => {}
@@ -276,10 +285,10 @@ impl TokenUsage {
     pub fn from_codex_stream_events_auto(events: &[Value]) -> Option<Self> {
         log::debug!("[Codex] 智能解析流式事件，共 {} 个事件", events.len());
 
-        // 先尝试 Codex Responses API 格式 (response.completed 事件)
+        // 先尝试 Codex Responses API 格式 (response.completed / response.incomplete 事件)
         for event in events {
             if let Some(event_type) = event.get("type").and_then(|v| v.as_str()) {
-                if event_type == "response.completed" {
+                if event_type == "response.completed" || event_type == "response.incomplete" {
                     if let Some(response) = event.get("response") {
                         log::debug!("[Codex] 找到 response.completed 事件");
                         return Self::from_codex_response_auto(response);
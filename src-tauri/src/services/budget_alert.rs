@@ -0,0 +1,335 @@
+//! 预算提醒
+//!
+//! 在硬限额之外提供软提醒：供应商（`limitDailyUsd` / `limitMonthlyUsd`）或应用整体的
+//! 消费达到限额的指定百分比（默认 50/80/100%）时，每个周期只提醒一次。
+//! - 检查在 `UsageLogger::log_with_calculation` 写入日志后立即执行，越过阈值的那次请求即触发
+//! - 已触发的阈值持久化到 `budget_alert_state`，应用重启后不会重复提醒
+//! - 提醒由后台任务投递：发送 `budget-alert` 事件，并可选地向 Webhook POST JSON
+
+use crate::database::Database;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::proxy::http_client;
+use chrono::{Local, NaiveDate};
+use serde::Serialize;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::mpsc::{channel, Sender};
+
+/// 前端监听的提醒事件名
+pub const BUDGET_ALERT_EVENT: &str = "budget-alert";
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+static ALERT_TX: OnceLock<Sender<BudgetAlert>> = OnceLock::new();
+
+/// 限额周期（本地时区）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetPeriod {
+    Daily,
+    Monthly,
+}
+
+impl BudgetPeriod {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BudgetPeriod::Daily => "daily",
+            BudgetPeriod::Monthly => "monthly",
+        }
+    }
+
+    /// 周期标识（每日为 `YYYY-MM-DD`，每月为 `YYYY-MM`）
+    fn key(&self, today: NaiveDate) -> String {
+        match self {
+            BudgetPeriod::Daily => today.format("%Y-%m-%d").to_string(),
+            BudgetPeriod::Monthly => today.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// 一条预算提醒（同时作为事件与 Webhook 的负载）
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetAlert {
+    pub app_type: String,
+    /// 供应商 ID（应用整体限额时为 None）
+    pub provider_id: Option<String>,
+    pub provider_name: Option<String>,
+    pub period: BudgetPeriod,
+    pub period_key: String,
+    /// 本次达到的阈值（限额的百分比）
+    pub threshold: u32,
+    pub spend_usd: f64,
+    pub limit_usd: f64,
+    pub message: String,
+    pub triggered_at: i64,
+}
+
+fn parse_limit(value: Option<&String>) -> Option<f64> {
+    value
+        .and_then(|s| s.trim().parse::<f64>().ok())
+        .filter(|v| *v > 0.0)
+}
+
+/// 检查一次使用量记录后是否越过提醒阈值，返回新触发的提醒
+pub fn check_budget_alerts(
+    db: &Database,
+    app_type: &str,
+    provider_id: &str,
+) -> Result<Vec<BudgetAlert>, AppError> {
+    let config = db.get_budget_alert_config()?;
+    if !config.enabled || config.thresholds.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let today = Local::now().date_naive();
+    let mut alerts = Vec::new();
+
+    if let Some(provider) = db.get_provider_by_id(provider_id, app_type)? {
+        let meta = provider.meta.as_ref();
+        let daily = parse_limit(meta.and_then(|m| m.limit_daily_usd.as_ref()));
+        let monthly = parse_limit(meta.and_then(|m| m.limit_monthly_usd.as_ref()));
+        if daily.is_some() || monthly.is_some() {
+            let (daily_spend, monthly_spend) = db.get_provider_spend(provider_id, app_type)?;
+            for (period, limit, spend) in [
+                (BudgetPeriod::Daily, daily, daily_spend),
+                (BudgetPeriod::Monthly, monthly, monthly_spend),
+            ] {
+                if let Some(limit) = limit {
+                    alerts.extend(crossed_alert(
+                        db,
+                        &config.thresholds,
+                        app_type,
+                        Some(&provider),
+                        period,
+                        limit,
+                        spend,
+                        today,
+                    )?);
+                }
+            }
+        }
+    }
+
+    if let Some(limits) = config.app_limits.get(app_type) {
+        let daily = parse_limit(limits.limit_daily_usd.as_ref());
+        let monthly = parse_limit(limits.limit_monthly_usd.as_ref());
+        if daily.is_some() || monthly.is_some() {
+            let (daily_spend, monthly_spend) = db.get_app_spend(app_type)?;
+            for (period, limit, spend) in [
+                (BudgetPeriod::Daily, daily, daily_spend),
+                (BudgetPeriod::Monthly, monthly, monthly_spend),
+            ] {
+                if let Some(limit) = limit {
+                    alerts.extend(crossed_alert(
+                        db,
+                        &config.thresholds,
+                        app_type,
+                        None,
+                        period,
+                        limit,
+                        spend,
+                        today,
+                    )?);
+                }
+            }
+        }
+    }
+
+    Ok(alerts)
+}
+
+/// 记录已越过的阈值；一次越过多个阈值时只提醒最高的一个
+#[allow(clippy::too_many_arguments)]
+fn crossed_alert(
+    db: &Database,
+    thresholds: &[u32],
+    app_type: &str,
+    provider: Option<&Provider>,
+    period: BudgetPeriod,
+    limit: f64,
+    spend: f64,
+    today: NaiveDate,
+) -> Result<Option<BudgetAlert>, AppError> {
+    let crossed: Vec<u32> = thresholds
+        .iter()
+        .copied()
+        .filter(|t| spend >= limit * f64::from(*t) / 100.0)
+        .collect();
+    if crossed.is_empty() {
+        return Ok(None);
+    }
+
+    let period_key = period.key(today);
+    let fired = db.record_budget_alert_thresholds(
+        app_type,
+        provider.map(|p| p.id.as_str()).unwrap_or_default(),
+        period.as_str(),
+        &period_key,
+        &crossed,
+    )?;
+    let Some(threshold) = fired.into_iter().max() else {
+        return Ok(None);
+    };
+
+    let scope = match provider {
+        Some(provider) => provider.name.clone(),
+        None => format!("{app_type} 应用整体"),
+    };
+    let message = match period {
+        BudgetPeriod::Daily => {
+            format!("{scope} 今日消费 ${spend:.4} 已达到每日限额 ${limit:.2} 的 {threshold}%")
+        }
+        BudgetPeriod::Monthly => {
+            format!("{scope} 本月消费 ${spend:.4} 已达到每月限额 ${limit:.2} 的 {threshold}%")
+        }
+    };
+
+    Ok(Some(BudgetAlert {
+        app_type: app_type.to_string(),
+        provider_id: provider.map(|p| p.id.clone()),
+        provider_name: provider.map(|p| p.name.clone()),
+        period,
+        period_key,
+        threshold,
+        spend_usd: spend,
+        limit_usd: limit,
+        message,
+        triggered_at: chrono::Utc::now().timestamp(),
+    }))
+}
+
+/// 将提醒交给后台任务投递（投递任务未启动时仅记录日志）
+pub fn notify(alert: BudgetAlert) {
+    log::info!("[BudgetAlert] {}", alert.message);
+    let Some(tx) = ALERT_TX.get() else {
+        return;
+    };
+    if tx.try_send(alert).is_err() {
+        log::warn!("[BudgetAlert] 提醒队列已满，丢弃本次提醒");
+    }
+}
+
+/// 预算提醒投递任务
+///
+/// 桌面端传入 AppHandle 以发送前端事件；无界面模式下仅推送 Webhook。
+pub async fn run_alert_worker(db: Arc<Database>, app: Option<crate::AppHandle>) {
+    let (tx, mut rx) = channel::<BudgetAlert>(64);
+    if ALERT_TX.set(tx).is_err() {
+        return;
+    }
+
+    while let Some(alert) = rx.recv().await {
+        if let Some(app) = &app {
+            if let Err(e) = crate::emit_event(app, BUDGET_ALERT_EVENT, &alert) {
+                log::debug!("[BudgetAlert] 发送提醒事件失败: {e}");
+            }
+        }
+
+        let webhook_url = match db.get_budget_alert_config() {
+            Ok(config) => config.webhook_url.filter(|url| !url.trim().is_empty()),
+            Err(e) => {
+                log::warn!("[BudgetAlert] 读取提醒配置失败: {e}");
+                None
+            }
+        };
+        if let Some(url) = webhook_url {
+            if let Err(e) = post_webhook(url.trim(), &alert).await {
+                log::warn!("[BudgetAlert] {e}");
+            }
+        }
+    }
+}
+
+async fn post_webhook(url: &str, alert: &BudgetAlert) -> Result<(), AppError> {
+    let response = http_client::get()
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(alert)
+        .send()
+        .await
+        .map_err(|e| AppError::Message(format!("推送预算提醒到 {url} 失败: {e}")))?;
+    if !response.status().is_success() {
+        return Err(AppError::Message(format!(
+            "推送预算提醒到 {url} 失败: HTTP {}",
+            response.status()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::lock_conn;
+    use crate::provider::ProviderMeta;
+    use crate::proxy::types::{AppBudgetLimit, BudgetAlertConfig};
+    use serde_json::json;
+
+    fn log_spend(db: &Database, provider_id: &str, cost: f64) -> Result<(), AppError> {
+        let conn = lock_conn!(db.conn);
+        crate::database::apply_usage_rollup(
+            &conn,
+            &crate::database::UsageRollupDelta {
+                created_at: chrono::Utc::now().timestamp(),
+                app_type: "claude",
+                provider_id,
+                model: "m",
+                request_count: 1,
+                total_cost: cost,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_budget_alerts_fire_once_per_threshold() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let mut provider =
+            Provider::with_id("p1".to_string(), "Relay".to_string(), json!({}), None);
+        provider.meta = Some(ProviderMeta {
+            limit_daily_usd: Some("10".to_string()),
+            ..Default::default()
+        });
+        db.save_provider("claude", &provider)?;
+
+        log_spend(&db, "p1", 4.0)?;
+        assert!(check_budget_alerts(&db, "claude", "p1")?.is_empty());
+
+        // 一次越过 50% 与 80% 时只提醒最高阈值
+        log_spend(&db, "p1", 4.5)?;
+        let alerts = check_budget_alerts(&db, "claude", "p1")?;
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].threshold, 80);
+        assert_eq!(alerts[0].period, BudgetPeriod::Daily);
+        assert_eq!(alerts[0].provider_name.as_deref(), Some("Relay"));
+
+        log_spend(&db, "p1", 0.5)?;
+        assert!(check_budget_alerts(&db, "claude", "p1")?.is_empty());
+
+        // 应用整体限额与供应商限额分别提醒
+        let mut config = BudgetAlertConfig::default();
+        config.app_limits.insert(
+            "claude".to_string(),
+            AppBudgetLimit {
+                limit_daily_usd: None,
+                limit_monthly_usd: Some("20".to_string()),
+            },
+        );
+        db.set_budget_alert_config(&config)?;
+        log_spend(&db, "p1", 2.0)?;
+        let alerts = check_budget_alerts(&db, "claude", "p1")?;
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].threshold, 100);
+        assert_eq!(alerts[1].provider_id, None);
+        assert_eq!(alerts[1].period, BudgetPeriod::Monthly);
+        assert_eq!(alerts[1].threshold, 50);
+
+        config.enabled = false;
+        db.set_budget_alert_config(&config)?;
+        log_spend(&db, "p1", 100.0)?;
+        assert!(check_budget_alerts(&db, "claude", "p1")?.is_empty());
+        Ok(())
+    }
+}
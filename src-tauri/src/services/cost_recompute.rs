@@ -0,0 +1,410 @@
+//! 历史成本重算
+//!
+//! 请求成本在写入日志时即已固定；修正模型定价或供应商倍率后，可按筛选条件
+//! （时间范围 / 应用 / 供应商 / 模型，与请求日志筛选一致）用当前 `model_pricing`
+//! 与日志中记录的 token 数重新计算 `input_cost_usd…total_cost_usd`：
+//! - 计费模型按当前的计费模式（请求模型 / 响应模型）选择，与实时记录一致
+//! - 倍率默认沿用日志中记录的倍率，也可改用供应商当前倍率
+//! - 按批次更新，批次之间释放数据库锁，不阻塞代理写入日志
+//! - 成本差额同步累加到小时/日聚合表
+
+use crate::database::{apply_usage_rollup, lock_conn, Database, UsageRollupDelta};
+use crate::error::AppError;
+use crate::proxy::usage::calculator::{CostCalculator, ModelPricing};
+use crate::proxy::usage::logger::UsageLogger;
+use crate::proxy::usage::parser::TokenUsage;
+use crate::services::usage_stats::{find_model_pricing_row, log_filter_conditions, LogFilters};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::Arc;
+
+const RECOMPUTE_BATCH_SIZE: i64 = 500;
+
+/// 供应商当前的计费设置：(倍率, 计费模式来源 "request" | "response")
+type PricingConfigs = HashMap<(String, String), (Decimal, String)>;
+
+/// 单个模型的成本变化
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostRecomputeDelta {
+    pub model: String,
+    /// 成本发生变化的请求数
+    pub updated: u64,
+    pub previous_cost_usd: String,
+    pub new_cost_usd: String,
+    pub delta_usd: String,
+}
+
+/// 重算结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostRecomputeReport {
+    /// 扫描的请求数
+    pub scanned: u64,
+    /// 成本发生变化的请求数
+    pub updated: u64,
+    /// 未找到定价、保持原值的请求数
+    pub missing_pricing: u64,
+    pub previous_total_usd: String,
+    pub new_total_usd: String,
+    pub delta_usd: String,
+    /// 按模型统计的变化（仅包含有变化的模型）
+    pub by_model: Vec<CostRecomputeDelta>,
+    /// 是否仅预览（未写入数据库）
+    pub dry_run: bool,
+}
+
+#[derive(Default)]
+struct ModelTotals {
+    updated: u64,
+    previous: Decimal,
+    new: Decimal,
+}
+
+fn parse_decimal(value: &str) -> Decimal {
+    Decimal::from_str(value.trim()).unwrap_or(Decimal::ZERO)
+}
+
+/// 按当前定价重算历史成本
+pub async fn recompute_costs(
+    db: Arc<Database>,
+    filters: LogFilters,
+    use_current_multiplier: bool,
+    dry_run: bool,
+) -> Result<CostRecomputeReport, AppError> {
+    // 先解析各供应商当前的计费设置（与实时记录使用同一套回退规则）
+    let mut configs = PricingConfigs::new();
+    let logger = UsageLogger::new(&db);
+    for (provider_id, app_type) in db.list_request_log_providers()? {
+        let config = logger.resolve_pricing_config(&provider_id, &app_type).await;
+        configs.insert((provider_id, app_type), config);
+    }
+
+    tokio::task::spawn_blocking(move || {
+        db.recompute_request_log_costs(&filters, &configs, use_current_multiplier, dry_run)
+    })
+    .await
+    .map_err(|e| AppError::Message(format!("重算历史成本失败: {e}")))?
+}
+
+impl Database {
+    /// 请求日志中出现过的 (provider_id, app_type)
+    fn list_request_log_providers(&self) -> Result<Vec<(String, String)>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt =
+            conn.prepare("SELECT DISTINCT provider_id, app_type FROM proxy_request_logs")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    fn recompute_request_log_costs(
+        &self,
+        filters: &LogFilters,
+        configs: &PricingConfigs,
+        use_current_multiplier: bool,
+        dry_run: bool,
+    ) -> Result<CostRecomputeReport, AppError> {
+        let mut pricing_cache: HashMap<String, Option<ModelPricing>> = HashMap::new();
+        let mut by_model: BTreeMap<String, ModelTotals> = BTreeMap::new();
+        let (mut scanned, mut updated, mut missing_pricing) = (0u64, 0u64, 0u64);
+        let (mut previous_total, mut new_total) = (Decimal::ZERO, Decimal::ZERO);
+        // 按 (created_at, rowid) 分批处理，批次之间不持有数据库锁
+        let mut cursor: Option<(i64, i64)> = None;
+
+        loop {
+            let mut conn = lock_conn!(self.conn);
+            let tx = conn.transaction()?;
+
+            let (mut conditions, mut params) = log_filter_conditions(filters);
+            if let Some((created_at, rowid)) = cursor {
+                conditions.push("(l.created_at, l.rowid) > (?, ?)");
+                params.push(Box::new(created_at));
+                params.push(Box::new(rowid));
+            }
+            let where_clause = if conditions.is_empty() {
+                String::new()
+            } else {
+                format!("WHERE {}", conditions.join(" AND "))
+            };
+            params.push(Box::new(RECOMPUTE_BATCH_SIZE));
+
+            let sql = format!(
+                "SELECT l.rowid, l.request_id, l.provider_id, l.app_type, l.model, l.request_model,
+                        l.input_tokens, l.output_tokens, l.cache_read_tokens, l.cache_creation_tokens,
+                        l.total_cost_usd, l.cost_multiplier, l.created_at, l.client_label
+                 FROM proxy_request_logs l
+                 LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
+                 {where_clause}
+                 ORDER BY l.created_at ASC, l.rowid ASC
+                 LIMIT ?"
+            );
+            #[allow(clippy::type_complexity)]
+            let rows: Vec<(
+                i64,
+                String,
+                String,
+                String,
+                String,
+                Option<String>,
+                [u32; 4],
+                String,
+                String,
+                i64,
+                Option<String>,
+            )> = {
+                let mut stmt = tx.prepare(&sql)?;
+                let params_refs: Vec<&dyn rusqlite::ToSql> =
+                    params.iter().map(|p| p.as_ref()).collect();
+                let rows = stmt.query_map(params_refs.as_slice(), |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                        row.get(5)?,
+                        [row.get(6)?, row.get(7)?, row.get(8)?, row.get(9)?],
+                        row.get(10)?,
+                        row.get(11)?,
+                        row.get(12)?,
+                        row.get(13)?,
+                    ))
+                })?;
+                rows.collect::<Result<_, _>>()?
+            };
+            if rows.is_empty() {
+                break;
+            }
+
+            for (
+                rowid,
+                request_id,
+                provider_id,
+                app_type,
+                model,
+                request_model,
+                tokens,
+                total_cost,
+                stored_multiplier,
+                created_at,
+                client_label,
+            ) in rows
+            {
+                cursor = Some((created_at, rowid));
+                scanned += 1;
+
+                let previous = parse_decimal(&total_cost);
+                previous_total += previous;
+
+                let config = configs.get(&(provider_id.clone(), app_type.clone()));
+                let pricing_model = match (config, request_model.as_deref()) {
+                    (Some((_, source)), Some(request_model))
+                        if source == "request" && !request_model.is_empty() =>
+                    {
+                        request_model
+                    }
+                    _ => model.as_str(),
+                };
+                let pricing = match pricing_cache.get(pricing_model) {
+                    Some(cached) => cached.clone(),
+                    None => {
+                        let pricing = find_model_pricing_row(&tx, pricing_model)?
+                            .map(|(input, output, cache_read, cache_creation)| {
+                                ModelPricing::from_strings(
+                                    &input,
+                                    &output,
+                                    &cache_read,
+                                    &cache_creation,
+                                )
+                                .map_err(|e| AppError::Database(format!("解析定价数据失败: {e}")))
+                            })
+                            .transpose()?;
+                        pricing_cache.insert(pricing_model.to_string(), pricing.clone());
+                        pricing
+                    }
+                };
+                let Some(pricing) = pricing else {
+                    missing_pricing += 1;
+                    new_total += previous;
+                    continue;
+                };
+
+                let multiplier = match config {
+                    Some((current, _)) if use_current_multiplier => *current,
+                    _ => Decimal::from_str(stored_multiplier.trim()).unwrap_or(Decimal::ONE),
+                };
+                let [input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens] =
+                    tokens;
+                let usage = TokenUsage {
+                    input_tokens,
+                    output_tokens,
+                    cache_read_tokens,
+                    cache_creation_tokens,
+                    model: None,
+                };
+                let cost = CostCalculator::calculate(&usage, &pricing, multiplier);
+                new_total += cost.total_cost;
+
+                if cost.total_cost == previous {
+                    continue;
+                }
+                updated += 1;
+                let totals = by_model.entry(model.clone()).or_default();
+                totals.updated += 1;
+                totals.previous += previous;
+                totals.new += cost.total_cost;
+
+                if dry_run {
+                    continue;
+                }
+                tx.execute(
+                    "UPDATE proxy_request_logs
+                     SET input_cost_usd = ?1, output_cost_usd = ?2,
+                         cache_read_cost_usd = ?3, cache_creation_cost_usd = ?4,
+                         total_cost_usd = ?5, cost_multiplier = ?6
+                     WHERE request_id = ?7",
+                    rusqlite::params![
+                        cost.input_cost.to_string(),
+                        cost.output_cost.to_string(),
+                        cost.cache_read_cost.to_string(),
+                        cost.cache_creation_cost.to_string(),
+                        cost.total_cost.to_string(),
+                        multiplier.to_string(),
+                        request_id,
+                    ],
+                )
+                .map_err(|e| AppError::Database(format!("更新请求成本失败: {e}")))?;
+
+                // 聚合表同步累加成本差额
+                apply_usage_rollup(
+                    &tx,
+                    &UsageRollupDelta {
+                        created_at,
+                        app_type: &app_type,
+                        provider_id: &provider_id,
+                        model: &model,
+                        client_label: client_label.as_deref(),
+                        total_cost: (cost.total_cost - previous).to_f64().unwrap_or(0.0),
+                        ..Default::default()
+                    },
+                )?;
+            }
+
+            tx.commit()?;
+        }
+
+        if !dry_run && updated > 0 {
+            log::info!(
+                "已重算 {updated} 条请求的成本，总成本 ${previous_total:.6} → ${new_total:.6}"
+            );
+        }
+
+        Ok(CostRecomputeReport {
+            scanned,
+            updated,
+            missing_pricing,
+            previous_total_usd: format!("{previous_total:.6}"),
+            new_total_usd: format!("{new_total:.6}"),
+            delta_usd: format!("{:.6}", new_total - previous_total),
+            by_model: by_model
+                .into_iter()
+                .map(|(model, totals)| CostRecomputeDelta {
+                    model,
+                    updated: totals.updated,
+                    previous_cost_usd: format!("{:.6}", totals.previous),
+                    new_cost_usd: format!("{:.6}", totals.new),
+                    delta_usd: format!("{:.6}", totals.new - totals.previous),
+                })
+                .collect(),
+            dry_run,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    fn seed(db: &Database) -> Result<(), AppError> {
+        let conn = lock_conn!(db.conn);
+        conn.execute(
+            "INSERT INTO model_pricing (model_id, display_name, input_cost_per_million, output_cost_per_million)
+             VALUES ('recompute-model', 'Recompute Model', '2', '10')",
+            [],
+        )?;
+        for (id, model, cost, multiplier, created_at) in [
+            ("req1", "recompute-model", "0.001", "1", 1_000),
+            ("req2", "recompute-model", "0.001", "2", 2_000),
+            ("req3", "unknown-model", "0.5", "1", 3_000),
+        ] {
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model,
+                    input_tokens, output_tokens, total_cost_usd, cost_multiplier,
+                    latency_ms, status_code, created_at
+                ) VALUES (?1, 'p1', 'claude', ?2, 1000, 100, ?3, ?4, 100, 200, ?5)",
+                params![id, model, cost, multiplier, created_at],
+            )?;
+        }
+        crate::database::rebuild_usage_rollups(&conn)
+    }
+
+    fn configs(multiplier: i64) -> PricingConfigs {
+        PricingConfigs::from([(
+            ("p1".to_string(), "claude".to_string()),
+            (Decimal::from(multiplier), "response".to_string()),
+        )])
+    }
+
+    fn rollup_cost(db: &Database) -> Result<f64, AppError> {
+        let conn = lock_conn!(db.conn);
+        Ok(conn.query_row(
+            "SELECT SUM(total_cost_usd) FROM usage_rollup_daily",
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    #[test]
+    fn test_recompute_costs_dry_run_and_apply() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        seed(&db)?;
+        let filters = LogFilters::default();
+
+        // 1000 × $2/M + 100 × $10/M = $0.003（req2 沿用记录的 2 倍倍率）
+        let preview = db.recompute_request_log_costs(&filters, &configs(1), false, true)?;
+        assert_eq!(preview.scanned, 3);
+        assert_eq!(preview.updated, 2);
+        assert_eq!(preview.missing_pricing, 1);
+        assert_eq!(preview.previous_total_usd, "0.502000");
+        assert_eq!(preview.new_total_usd, "0.509000");
+        assert_eq!(preview.by_model.len(), 1);
+        assert_eq!(preview.by_model[0].delta_usd, "0.007000");
+        assert!((rollup_cost(&db)? - 0.502).abs() < 1e-9);
+
+        let applied = db.recompute_request_log_costs(&filters, &configs(1), false, false)?;
+        assert_eq!(applied.updated, 2);
+        assert!((rollup_cost(&db)? - 0.509).abs() < 1e-9);
+
+        // 改用供应商当前倍率，只重算指定时间范围
+        let filters = LogFilters {
+            start_date: Some(2_000),
+            ..Default::default()
+        };
+        let report = db.recompute_request_log_costs(&filters, &configs(1), true, false)?;
+        assert_eq!((report.scanned, report.updated), (2, 1));
+        assert_eq!(report.delta_usd, "-0.003000");
+        assert!((rollup_cost(&db)? - 0.506).abs() < 1e-9);
+
+        let again =
+            db.recompute_request_log_costs(&LogFilters::default(), &configs(1), true, true)?;
+        assert_eq!(again.updated, 0);
+        Ok(())
+    }
+}
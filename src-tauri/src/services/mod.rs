@@ -1,14 +1,20 @@
+pub mod budget_alert;
 pub mod config;
+pub mod cost_recompute;
 pub mod env_checker;
 pub mod env_manager;
 pub mod mcp;
 pub mod omo;
+pub mod pricing_import;
 pub mod prompt;
 pub mod provider;
 pub mod proxy;
+pub mod session_usage;
 pub mod skill;
 pub mod speedtest;
 pub mod stream_check;
+pub mod usage_export;
+pub mod usage_retention;
 pub mod usage_stats;
 pub mod webdav;
 pub mod webdav_auto_sync;
@@ -0,0 +1,487 @@
+//! 模型定价导入
+//!
+//! 从 LiteLLM `model_prices_and_context_window.json` 或 OpenRouter `/models` 响应
+//! （本地文件或 URL）导入模型定价：
+//! - 按每 token 价格换算为每百万 token 价格，包含缓存读取/写入价格
+//! - 模型 ID 先按计费时的规则清洗（去供应商前缀等），再按定价别名归并到已有定价
+//! - `:free` 等零价格变体直接跳过，其余变体与基础模型同名时以基础模型为准
+//! - 先生成新增/变更差异供预览，确认后再写入 `model_pricing`
+
+use crate::database::{load_pricing_aliases, lock_conn, match_pricing_alias, Database};
+use crate::error::AppError;
+use crate::proxy::http_client;
+use crate::services::usage_stats::{clean_model_id, ModelPricingInfo};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// LiteLLM 中按 token 计费的对话类模型
+const LITELLM_CHAT_MODES: [&str; 3] = ["chat", "completion", "responses"];
+
+/// 价格目录格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PricingCatalogFormat {
+    LiteLlm,
+    OpenRouter,
+}
+
+/// 单条定价变更
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingChange {
+    pub before: ModelPricingInfo,
+    pub after: ModelPricingInfo,
+}
+
+/// 导入差异（预览与实际导入共用）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingImportDiff {
+    pub format: PricingCatalogFormat,
+    pub added: Vec<ModelPricingInfo>,
+    pub changed: Vec<PricingChange>,
+    /// 价格未变化的模型数
+    pub unchanged: usize,
+    /// 缺少价格或非对话类而跳过的条目数
+    pub skipped: usize,
+    /// 是否已写入数据库
+    pub applied: bool,
+}
+
+/// 目录中解析出的一条定价
+#[derive(Debug, Clone)]
+struct CatalogEntry {
+    raw_id: String,
+    /// 是否为 `:free`、`:beta` 等变体（清洗后与基础模型同名）
+    variant: bool,
+    pricing: ModelPricingInfo,
+}
+
+/// 读取价格目录（`http(s)://` 开头时按 URL 下载，否则按本地文件读取）
+pub async fn load_pricing_catalog(source: &str) -> Result<Value, AppError> {
+    let source = source.trim();
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let response = http_client::get()
+            .get(source)
+            .timeout(FETCH_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| AppError::Message(format!("下载价格目录失败: {e}")))?;
+        if !response.status().is_success() {
+            return Err(AppError::Message(format!(
+                "下载价格目录失败: HTTP {}",
+                response.status()
+            )));
+        }
+        return response
+            .json()
+            .await
+            .map_err(|e| AppError::Message(format!("解析价格目录失败: {e}")));
+    }
+
+    let content = std::fs::read_to_string(source).map_err(|e| AppError::io(source, e))?;
+    serde_json::from_str(&content).map_err(|e| AppError::json(source, e))
+}
+
+/// 解析每 token 价格并换算为每百万 token 价格（负数表示不可用）
+fn per_million(value: Option<&Value>) -> Option<Decimal> {
+    let raw = match value? {
+        Value::String(s) => s.trim().to_string(),
+        Value::Number(n) => n.to_string(),
+        _ => return None,
+    };
+    let per_token = Decimal::from_str(&raw)
+        .or_else(|_| Decimal::from_scientific(&raw))
+        .ok()?;
+    if per_token.is_sign_negative() {
+        return None;
+    }
+    Some(
+        (per_token * Decimal::from(1_000_000))
+            .round_dp(6)
+            .normalize(),
+    )
+}
+
+fn entry(
+    raw_id: &str,
+    display_name: Option<&str>,
+    prices: [Option<&Value>; 4],
+) -> Option<CatalogEntry> {
+    let [input, output, cache_read, cache_creation] = prices;
+    let model_id = clean_model_id(raw_id);
+    if model_id.is_empty() {
+        return None;
+    }
+    let (input, output) = (per_million(input)?, per_million(output)?);
+    // 免费变体的价格不代表基础模型，不能覆盖或新增为基础模型的定价
+    let variant = raw_id.rsplit('/').next().is_some_and(|id| id.contains(':'));
+    if variant && input.is_zero() && output.is_zero() {
+        return None;
+    }
+    let price = |value| per_million(value).unwrap_or_default().to_string();
+    Some(CatalogEntry {
+        raw_id: raw_id.to_string(),
+        variant,
+        pricing: ModelPricingInfo {
+            display_name: display_name.unwrap_or(&model_id).to_string(),
+            input_cost_per_million: input.to_string(),
+            output_cost_per_million: output.to_string(),
+            cache_read_cost_per_million: price(cache_read),
+            cache_creation_cost_per_million: price(cache_creation),
+            model_id,
+        },
+    })
+}
+
+/// 解析价格目录，返回格式、定价条目与跳过的条目数
+fn parse_pricing_catalog(
+    catalog: &Value,
+) -> Result<(PricingCatalogFormat, Vec<CatalogEntry>, usize), AppError> {
+    let openrouter_models = match catalog {
+        Value::Array(models) => Some(models),
+        Value::Object(map) => map.get("data").and_then(Value::as_array),
+        _ => None,
+    };
+
+    if let Some(models) = openrouter_models {
+        let mut entries = Vec::new();
+        for model in models {
+            let pricing = model.get("pricing");
+            let parsed = model.get("id").and_then(Value::as_str).and_then(|id| {
+                entry(
+                    id,
+                    model.get("name").and_then(Value::as_str),
+                    [
+                        pricing.and_then(|p| p.get("prompt")),
+                        pricing.and_then(|p| p.get("completion")),
+                        pricing.and_then(|p| p.get("input_cache_read")),
+                        pricing.and_then(|p| p.get("input_cache_write")),
+                    ],
+                )
+            });
+            entries.extend(parsed);
+        }
+        let skipped = models.len() - entries.len();
+        return Ok((PricingCatalogFormat::OpenRouter, entries, skipped));
+    }
+
+    let Value::Object(map) = catalog else {
+        return Err(AppError::InvalidInput(
+            "无法识别的价格目录格式（应为 LiteLLM 或 OpenRouter 的 JSON）".to_string(),
+        ));
+    };
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for (id, spec) in map {
+        if id == "sample_spec" {
+            continue;
+        }
+        let is_chat = spec
+            .get("mode")
+            .and_then(Value::as_str)
+            .is_none_or(|mode| LITELLM_CHAT_MODES.contains(&mode));
+        let parsed = is_chat
+            .then(|| {
+                entry(
+                    id,
+                    None,
+                    [
+                        spec.get("input_cost_per_token"),
+                        spec.get("output_cost_per_token"),
+                        spec.get("cache_read_input_token_cost"),
+                        spec.get("cache_creation_input_token_cost"),
+                    ],
+                )
+            })
+            .flatten();
+        match parsed {
+            Some(parsed) => entries.push(parsed),
+            None => skipped += 1,
+        }
+    }
+    Ok((PricingCatalogFormat::LiteLlm, entries, skipped))
+}
+
+/// 定价是否一致（按数值比较，`3` 与 `3.00` 视为相同）
+fn same_prices(a: &ModelPricingInfo, b: &ModelPricingInfo) -> bool {
+    let prices = |p: &ModelPricingInfo| {
+        [
+            &p.input_cost_per_million,
+            &p.output_cost_per_million,
+            &p.cache_read_cost_per_million,
+            &p.cache_creation_cost_per_million,
+        ]
+        .map(|v| Decimal::from_str(v.trim()).ok().map(|d| d.normalize()))
+    };
+    prices(a) == prices(b)
+}
+
+impl Database {
+    /// 对比价格目录与现有定价，生成导入差异（不写入）
+    fn diff_model_pricing(
+        &self,
+        format: PricingCatalogFormat,
+        entries: Vec<CatalogEntry>,
+        skipped: usize,
+    ) -> Result<PricingImportDiff, AppError> {
+        let conn = lock_conn!(self.conn);
+        let aliases = load_pricing_aliases(&conn)?;
+        let mut stmt = conn.prepare(
+            "SELECT model_id, display_name, input_cost_per_million, output_cost_per_million,
+                    cache_read_cost_per_million, cache_creation_cost_per_million
+             FROM model_pricing",
+        )?;
+        let existing = stmt
+            .query_map([], |row| {
+                Ok(ModelPricingInfo {
+                    model_id: row.get(0)?,
+                    display_name: row.get(1)?,
+                    input_cost_per_million: row.get(2)?,
+                    output_cost_per_million: row.get(3)?,
+                    cache_read_cost_per_million: row.get(4)?,
+                    cache_creation_cost_per_million: row.get(5)?,
+                })
+            })?
+            .map(|row| row.map(|p| (p.model_id.clone(), p)))
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        // 同一模型出现多次时（变体或按别名归并），优先采用非变体、ID 无需清洗的条目
+        let rank = |entry: &CatalogEntry| (entry.variant, entry.raw_id != entry.pricing.model_id);
+        let mut resolved: HashMap<String, CatalogEntry> = HashMap::new();
+        for mut entry in entries {
+            if !existing.contains_key(&entry.pricing.model_id) {
+                if let Some(target) = match_pricing_alias(&aliases, &entry.pricing.model_id) {
+                    entry.pricing.model_id = target.to_string();
+                }
+            }
+            let model_id = entry.pricing.model_id.clone();
+            match resolved.get(&model_id) {
+                Some(current) if rank(current) <= rank(&entry) => {}
+                _ => {
+                    resolved.insert(model_id, entry);
+                }
+            }
+        }
+
+        let mut diff = PricingImportDiff {
+            format,
+            added: Vec::new(),
+            changed: Vec::new(),
+            unchanged: 0,
+            skipped,
+            applied: false,
+        };
+        for (model_id, entry) in resolved {
+            match existing.get(&model_id) {
+                Some(before) if same_prices(before, &entry.pricing) => diff.unchanged += 1,
+                Some(before) => {
+                    // 保留现有显示名称，只更新价格
+                    let mut after = entry.pricing;
+                    after.display_name = before.display_name.clone();
+                    diff.changed.push(PricingChange {
+                        before: before.clone(),
+                        after,
+                    });
+                }
+                None => diff.added.push(entry.pricing),
+            }
+        }
+        diff.added.sort_by(|a, b| a.model_id.cmp(&b.model_id));
+        diff.changed
+            .sort_by(|a, b| a.after.model_id.cmp(&b.after.model_id));
+        Ok(diff)
+    }
+
+    /// 写入导入差异中的新增与变更定价
+    fn apply_model_pricing_diff(&self, diff: &PricingImportDiff) -> Result<(), AppError> {
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO model_pricing (
+                    model_id, display_name, input_cost_per_million, output_cost_per_million,
+                    cache_read_cost_per_million, cache_creation_cost_per_million
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for pricing in diff
+                .added
+                .iter()
+                .chain(diff.changed.iter().map(|change| &change.after))
+            {
+                stmt.execute(rusqlite::params![
+                    pricing.model_id,
+                    pricing.display_name,
+                    pricing.input_cost_per_million,
+                    pricing.output_cost_per_million,
+                    pricing.cache_read_cost_per_million,
+                    pricing.cache_creation_cost_per_million,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/// 从已读取的价格目录生成差异，`apply` 为 true 时写入数据库
+pub fn import_pricing_catalog(
+    db: &Database,
+    catalog: &Value,
+    apply: bool,
+) -> Result<PricingImportDiff, AppError> {
+    let (format, entries, skipped) = parse_pricing_catalog(catalog)?;
+    let mut diff = db.diff_model_pricing(format, entries, skipped)?;
+    if apply {
+        db.apply_model_pricing_diff(&diff)?;
+        diff.applied = true;
+        log::info!(
+            "已导入模型定价: 新增 {} 条，更新 {} 条",
+            diff.added.len(),
+            diff.changed.len()
+        );
+    }
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::ModelPricingAlias;
+    use serde_json::json;
+
+    #[test]
+    fn test_import_litellm_catalog_diff_and_apply() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let catalog = json!({
+            "sample_spec": { "input_cost_per_token": 0 },
+            // 与预置定价相同
+            "claude-sonnet-4-5-20250929": {
+                "input_cost_per_token": 3e-06,
+                "output_cost_per_token": 1.5e-05,
+                "cache_read_input_token_cost": 3e-07,
+                "cache_creation_input_token_cost": 3.75e-06,
+                "mode": "chat"
+            },
+            "claude-haiku-4-5-20251001": {
+                "input_cost_per_token": 2e-06,
+                "output_cost_per_token": 5e-06,
+                "mode": "chat"
+            },
+            "brand-new-model": {
+                "input_cost_per_token": "0.0000004",
+                "output_cost_per_token": "0.0000016"
+            },
+            "text-embedding-3-small": {
+                "input_cost_per_token": 2e-08,
+                "output_cost_per_token": 0,
+                "mode": "embedding"
+            },
+            "no-price-model": { "mode": "chat" }
+        });
+
+        let diff = import_pricing_catalog(&db, &catalog, false)?;
+        assert_eq!(diff.format, PricingCatalogFormat::LiteLlm);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.skipped, 2);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].model_id, "brand-new-model");
+        assert_eq!(diff.added[0].input_cost_per_million, "0.4");
+        assert_eq!(diff.added[0].cache_read_cost_per_million, "0");
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].after.input_cost_per_million, "2");
+        assert_eq!(
+            diff.changed[0].after.display_name,
+            diff.changed[0].before.display_name
+        );
+        assert!(!diff.applied);
+
+        // 预览不写入；导入后再次对比无差异
+        assert!(import_pricing_catalog(&db, &catalog, false)?.added.len() == 1);
+        assert!(import_pricing_catalog(&db, &catalog, true)?.applied);
+        let again = import_pricing_catalog(&db, &catalog, false)?;
+        assert!(again.added.is_empty() && again.changed.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_openrouter_catalog_uses_aliases() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        db.save_model_pricing_alias(&ModelPricingAlias {
+            pattern: "claude-sonnet-4?5*".to_string(),
+            model_id: "claude-sonnet-4-5-20250929".to_string(),
+        })?;
+        let catalog = json!({
+            "data": [
+                {
+                    "id": "anthropic/claude-sonnet-4.5",
+                    "name": "Anthropic: Claude Sonnet 4.5",
+                    "pricing": {
+                        "prompt": "0.0000035",
+                        "completion": "0.000015",
+                        "input_cache_read": "0.0000003",
+                        "input_cache_write": "0.00000375"
+                    }
+                },
+                {
+                    "id": "openrouter/auto",
+                    "name": "Auto Router",
+                    "pricing": { "prompt": "-1", "completion": "-1" }
+                },
+                {
+                    "id": "acme/new-model:beta",
+                    "name": "Acme: New Model (beta)",
+                    "pricing": { "prompt": "0.000002", "completion": "0.000004" }
+                },
+                {
+                    "id": "acme/new-model",
+                    "name": "Acme: New Model",
+                    "pricing": { "prompt": "0.000001", "completion": "0.000002" }
+                },
+                {
+                    "id": "acme/new-model:free",
+                    "name": "Acme: New Model (free)",
+                    "pricing": { "prompt": "0", "completion": "0" }
+                },
+                {
+                    "id": "acme/free-only:free",
+                    "name": "Acme: Free Only",
+                    "pricing": { "prompt": "0", "completion": "0" }
+                }
+            ]
+        });
+
+        let diff = import_pricing_catalog(&db, &catalog, true)?;
+        assert_eq!(diff.format, PricingCatalogFormat::OpenRouter);
+        // 免费变体被跳过，`new-model` 采用基础模型而不是 `:beta` 变体的价格
+        assert_eq!(diff.skipped, 3);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].after.model_id, "claude-sonnet-4-5-20250929");
+        assert_eq!(diff.changed[0].after.input_cost_per_million, "3.5");
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].model_id, "new-model");
+        assert_eq!(diff.added[0].display_name, "Acme: New Model");
+        assert_eq!(diff.added[0].input_cost_per_million, "1");
+
+        let conn = lock_conn!(db.conn);
+        let input: String = conn.query_row(
+            "SELECT input_cost_per_million FROM model_pricing WHERE model_id = 'claude-sonnet-4-5-20250929'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(input, "3.5");
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_rejects_unknown_catalog() {
+        let db = Database::memory().unwrap();
+        assert!(import_pricing_catalog(&db, &json!("nope"), false).is_err());
+    }
+}
@@ -12,11 +12,15 @@ use crate::services::provider::write_live_snapshot;
 use serde_json::{json, Value};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
 /// 用于接管 Live 配置时的占位符（避免客户端提示缺少 key，同时不泄露真实 Token）
 const PROXY_TOKEN_PLACEHOLDER: &str = "PROXY_MANAGED";
 
+/// 等待进行中请求结束时的轮询间隔
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 /// 代理接管模式下需要从 Claude Live 配置中移除的“模型覆盖”字段。
 ///
 /// 原因：接管模式切换供应商时不会写回 Live 配置，如果保留这些字段，
@@ -36,7 +40,7 @@ pub struct ProxyService {
     db: Arc<Database>,
     server: Arc<RwLock<Option<ProxyServer>>>,
     /// AppHandle，用于传递给 ProxyServer 以支持故障转移时的 UI 更新
-    app_handle: Arc<RwLock<Option<tauri::AppHandle>>>,
+    app_handle: Arc<RwLock<Option<crate::AppHandle>>>,
 }
 
 impl ProxyService {
@@ -74,7 +78,7 @@ impl ProxyService {
     }
 
     /// 设置 AppHandle（在应用初始化时调用）
-    pub fn set_app_handle(&self, handle: tauri::AppHandle) {
+    pub fn set_app_handle(&self, handle: crate::AppHandle) {
         futures::executor::block_on(async {
             *self.app_handle.write().await = Some(handle);
         });
@@ -625,6 +629,27 @@ impl ProxyService {
         Ok(())
     }
 
+    /// 等待进行中的请求（含流式响应）结束，最长等待 `deadline`
+    ///
+    /// 返回 `true` 表示已全部结束或代理未运行，`false` 表示超时。
+    pub async fn drain_in_flight(&self, deadline: Duration) -> bool {
+        let started = Instant::now();
+        loop {
+            let in_flight = match self.server.read().await.as_ref() {
+                Some(server) => server.in_flight_requests().await,
+                None => return true,
+            };
+            if in_flight == 0 {
+                return true;
+            }
+            if started.elapsed() >= deadline {
+                log::warn!("仍有 {in_flight} 个请求未结束");
+                return false;
+            }
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+        }
+    }
+
     /// 停止代理服务器
     pub async fn stop(&self) -> Result<(), String> {
         if let Some(server) = self.server.write().await.take() {
@@ -1872,6 +1897,37 @@ impl ProxyService {
         Ok(())
     }
 
+    /// 从数据库重新加载代理配置并实时应用（headless `serve` 收到 SIGHUP 时调用）
+    ///
+    /// 监听地址/端口变更需要重启进程才能生效，这里只记录警告。
+    pub async fn reload_runtime_config(&self) -> Result<(), String> {
+        let config = self.get_config().await?;
+
+        let server_guard = self.server.read().await;
+        let Some(server) = server_guard.as_ref() else {
+            return Err("代理服务器未运行".to_string());
+        };
+
+        let status = server.get_status().await;
+        if status.address != config.listen_address || status.port != config.listen_port {
+            log::warn!(
+                "监听地址已变更为 {}:{}，需重启代理进程后生效（当前 {}:{}）",
+                config.listen_address,
+                config.listen_port,
+                status.address,
+                status.port
+            );
+        }
+
+        server.apply_runtime_config(&config).await;
+        match self.db.get_circuit_breaker_config().await {
+            Ok(breaker) => server.update_circuit_breaker_configs(breaker).await,
+            Err(e) => log::warn!("重新加载熔断器配置失败: {e}"),
+        }
+        log::info!("已重新加载代理配置");
+        Ok(())
+    }
+
     /// 检查服务器是否正在运行
     pub async fn is_running(&self) -> bool {
         self.server.read().await.is_some()
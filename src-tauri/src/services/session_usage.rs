@@ -0,0 +1,282 @@
+//! 会话用量归因服务
+//!
+//! 代理请求日志的 `session_id` 由 `extract_session_id` 从客户端请求中提取，
+//! 与本地会话文件中的 `SessionMeta.session_id` 是同一个 ID（Codex 会话带 `codex_` 前缀）。
+//! 本服务按会话聚合请求数、Token、费用以及使用过的供应商和模型，并回填到会话列表。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::session_manager::SessionMeta;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Codex 请求日志中的会话 ID 前缀
+const CODEX_SESSION_PREFIX: &str = "codex_";
+
+/// 每次查询的会话数（每个会话占用两个参数，远低于 SQLite 参数上限）
+const SESSION_QUERY_BATCH_SIZE: usize = 400;
+
+/// 单个会话的用量汇总
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionUsage {
+    pub session_id: String,
+    pub request_count: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub total_cost: String,
+    /// 使用过的供应商名称（供应商已删除时为 ID），按请求数降序
+    pub providers: Vec<String>,
+    /// 使用过的模型，按请求数降序
+    pub models: Vec<String>,
+    pub first_request_at: i64,
+    pub last_request_at: i64,
+}
+
+/// 将请求日志中的会话 ID 还原为本地会话 ID
+fn normalize_session_id(session_id: &str) -> &str {
+    session_id
+        .strip_prefix(CODEX_SESSION_PREFIX)
+        .unwrap_or(session_id)
+}
+
+/// 按名称累计请求数，输出时按请求数降序、名称升序
+fn push_count(counts: &mut Vec<(String, u64)>, name: String, count: u64) {
+    match counts.iter_mut().find(|(existing, _)| *existing == name) {
+        Some((_, total)) => *total += count,
+        None => counts.push((name, count)),
+    }
+}
+
+fn sorted_names(mut counts: Vec<(String, u64)>) -> Vec<String> {
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.into_iter().map(|(name, _)| name).collect()
+}
+
+impl Database {
+    /// 按会话聚合指定会话的代理请求日志，以本地会话 ID 为键
+    ///
+    /// 每个会话同时按原始 ID 和带 `codex_` 前缀的 ID 查询，走 `session_id` 索引。
+    pub fn get_session_usage(
+        &self,
+        session_ids: &[&str],
+    ) -> Result<HashMap<String, SessionUsage>, AppError> {
+        let mut session_ids = session_ids.to_vec();
+        session_ids.sort_unstable();
+        session_ids.dedup();
+
+        let conn = lock_conn!(self.conn);
+        type Accumulator = (SessionUsage, f64, Vec<(String, u64)>, Vec<(String, u64)>);
+        let mut sessions: HashMap<String, Accumulator> = HashMap::new();
+        for batch in session_ids.chunks(SESSION_QUERY_BATCH_SIZE) {
+            let ids: Vec<String> = batch
+                .iter()
+                .flat_map(|id| [id.to_string(), format!("{CODEX_SESSION_PREFIX}{id}")])
+                .collect();
+            let placeholders = vec!["?"; ids.len()].join(", ");
+            let sql = format!(
+                "SELECT
+                    l.session_id,
+                    COALESCE(p.name, l.provider_id) as provider_name,
+                    l.model,
+                    COUNT(*) as request_count,
+                    COALESCE(SUM(l.input_tokens), 0),
+                    COALESCE(SUM(l.output_tokens), 0),
+                    COALESCE(SUM(l.cache_read_tokens), 0),
+                    COALESCE(SUM(l.cache_creation_tokens), 0),
+                    COALESCE(SUM(CAST(l.total_cost_usd AS REAL)), 0),
+                    MIN(l.created_at),
+                    MAX(l.created_at)
+                 FROM proxy_request_logs l
+                 LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
+                 WHERE l.session_id IN ({placeholders})
+                 GROUP BY l.session_id, l.provider_id, l.app_type, l.model"
+            );
+
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(&ids), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)? as u64,
+                    [
+                        row.get::<_, i64>(4)? as u64,
+                        row.get::<_, i64>(5)? as u64,
+                        row.get::<_, i64>(6)? as u64,
+                        row.get::<_, i64>(7)? as u64,
+                    ],
+                    row.get::<_, f64>(8)?,
+                    (row.get::<_, i64>(9)?, row.get::<_, i64>(10)?),
+                ))
+            })?;
+
+            for row in rows {
+                let (session_id, provider, model, count, tokens, cost, (first, last)) = row?;
+                let session_id = normalize_session_id(&session_id).to_string();
+                let (usage, total_cost, providers, models) =
+                    sessions.entry(session_id.clone()).or_insert_with(|| {
+                        (
+                            SessionUsage {
+                                session_id,
+                                first_request_at: first,
+                                last_request_at: last,
+                                ..Default::default()
+                            },
+                            0.0,
+                            Vec::new(),
+                            Vec::new(),
+                        )
+                    });
+
+                usage.request_count += count;
+                usage.input_tokens += tokens[0];
+                usage.output_tokens += tokens[1];
+                usage.cache_read_tokens += tokens[2];
+                usage.cache_creation_tokens += tokens[3];
+                usage.first_request_at = usage.first_request_at.min(first);
+                usage.last_request_at = usage.last_request_at.max(last);
+                *total_cost += cost;
+                push_count(providers, provider, count);
+                push_count(models, model, count);
+            }
+        }
+
+        Ok(sessions
+            .into_iter()
+            .map(|(session_id, (mut usage, total_cost, providers, models))| {
+                usage.total_cost = format!("{total_cost:.6}");
+                usage.providers = sorted_names(providers);
+                usage.models = sorted_names(models);
+                (session_id, usage)
+            })
+            .collect())
+    }
+
+    /// 为会话列表回填用量汇总，没有代理记录的会话保持为空
+    pub fn attach_session_usage<'a>(
+        &self,
+        sessions: impl IntoIterator<Item = &'a mut SessionMeta>,
+    ) -> Result<(), AppError> {
+        let mut sessions: Vec<&mut SessionMeta> = sessions.into_iter().collect();
+        if sessions.is_empty() {
+            return Ok(());
+        }
+
+        let session_ids: Vec<&str> = sessions.iter().map(|s| s.session_id.as_str()).collect();
+        let usage = self.get_session_usage(&session_ids)?;
+        for session in sessions.iter_mut() {
+            session.usage = usage.get(&session.session_id).cloned();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    fn insert_log(
+        db: &Database,
+        request_id: &str,
+        app_type: &str,
+        model: &str,
+        cost: &str,
+        session_id: Option<&str>,
+        created_at: i64,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(db.conn);
+        conn.execute(
+            "INSERT INTO proxy_request_logs (
+                request_id, provider_id, app_type, model,
+                input_tokens, output_tokens, cache_read_tokens, total_cost_usd,
+                latency_ms, status_code, session_id, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                request_id, "p1", app_type, model, 100, 50, 10, cost, 100, 200, session_id,
+                created_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn session(provider_id: &str, session_id: &str) -> SessionMeta {
+        SessionMeta {
+            provider_id: provider_id.to_string(),
+            session_id: session_id.to_string(),
+            title: None,
+            summary: None,
+            project_dir: None,
+            created_at: None,
+            last_active_at: None,
+            source_path: None,
+            resume_command: None,
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn test_attach_session_usage() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        insert_log(
+            &db,
+            "r1",
+            "claude",
+            "claude-sonnet",
+            "0.01",
+            Some("s1"),
+            1000,
+        )?;
+        insert_log(&db, "r2", "claude", "claude-opus", "0.05", Some("s1"), 3000)?;
+        insert_log(&db, "r3", "claude", "claude-opus", "0.05", Some("s1"), 2000)?;
+        insert_log(&db, "r4", "codex", "gpt-5", "0.02", Some("codex_c1"), 4000)?;
+        insert_log(&db, "r5", "claude", "claude-opus", "0.30", None, 5000)?;
+        insert_log(
+            &db,
+            "r6",
+            "claude",
+            "claude-opus",
+            "0.30",
+            Some("other"),
+            6000,
+        )?;
+
+        let mut sessions = vec![
+            session("claude", "s1"),
+            session("codex", "c1"),
+            session("claude", "idle"),
+        ];
+        db.attach_session_usage(&mut sessions)?;
+
+        let usage = sessions[0].usage.as_ref().expect("s1 has usage");
+        assert_eq!(usage.request_count, 3);
+        assert_eq!(usage.input_tokens, 300);
+        assert_eq!(usage.output_tokens, 150);
+        assert_eq!(usage.cache_read_tokens, 30);
+        assert_eq!(usage.total_cost, "0.110000");
+        assert_eq!(usage.providers, vec!["p1"]);
+        assert_eq!(usage.models, vec!["claude-opus", "claude-sonnet"]);
+        assert_eq!(
+            (usage.first_request_at, usage.last_request_at),
+            (1000, 3000)
+        );
+
+        let codex = sessions[1]
+            .usage
+            .as_ref()
+            .expect("codex prefix is stripped");
+        assert_eq!(codex.session_id, "c1");
+        assert_eq!(codex.request_count, 1);
+        assert!(sessions[2].usage.is_none());
+
+        // 只查询列表中的会话
+        let usage = db.get_session_usage(&["c1", "s1", "c1"])?;
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage["c1"].request_count, 1);
+        assert!(!usage.contains_key("other"));
+        Ok(())
+    }
+}
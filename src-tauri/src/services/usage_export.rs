@@ -0,0 +1,648 @@
+//! 使用量导出
+//!
+//! 将请求日志或按维度聚合的汇总以 CSV / JSON Lines 写入任意输出流，用于费用分摊。
+//! 过滤条件与 `get_request_logs` 一致。
+//!
+//! 按供应商、模型、应用、日期汇总时读取使用量聚合表（与统计页一致），
+//! 原始日志按保留策略清理后汇总结果不受影响；聚合表不含状态码，这些维度不支持按状态码过滤。
+//! 请求明细与按会话汇总只能读取原始请求日志，已清理的日志不会出现在导出结果中。
+
+use super::usage_stats::{
+    log_filter_conditions, request_log_from_row, LogFilters, REQUEST_LOG_COLUMNS,
+};
+use crate::database::{lock_conn, usage_source, Database};
+use crate::error::AppError;
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Write;
+use std::str::FromStr;
+
+/// 每批读取的请求日志条数（批次之间释放数据库锁）
+const EXPORT_BATCH_SIZE: i64 = 2000;
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageExportFormat {
+    Csv,
+    Jsonl,
+}
+
+impl FromStr for UsageExportFormat {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "jsonl" | "ndjson" => Ok(Self::Jsonl),
+            other => Err(AppError::InvalidInput(format!(
+                "不支持的导出格式: {other}（可选 csv、jsonl）"
+            ))),
+        }
+    }
+}
+
+/// 汇总维度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageGroupBy {
+    Provider,
+    Model,
+    App,
+    Session,
+    Day,
+}
+
+impl FromStr for UsageGroupBy {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "provider" => Ok(Self::Provider),
+            "model" => Ok(Self::Model),
+            "app" => Ok(Self::App),
+            "session" => Ok(Self::Session),
+            "day" => Ok(Self::Day),
+            other => Err(AppError::InvalidInput(format!(
+                "不支持的汇总维度: {other}（可选 provider、model、app、session、day）"
+            ))),
+        }
+    }
+}
+
+impl UsageGroupBy {
+    /// 分组键：(输出列名, SQL 表达式)
+    fn keys(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Provider => &[
+                ("app_type", "l.app_type"),
+                ("provider_id", "l.provider_id"),
+                ("provider_name", "MAX(p.name)"),
+            ],
+            Self::Model => &[("model", "l.model")],
+            Self::App => &[("app_type", "l.app_type")],
+            Self::Session => &[("session_id", "l.session_id")],
+            Self::Day => &[("day", "date(l.ts, 'unixepoch', 'localtime')")],
+        }
+    }
+
+    /// 是否从聚合表汇总（聚合表不含会话 ID，按会话汇总只能读取原始日志）
+    fn uses_rollup(self) -> bool {
+        !matches!(self, Self::Session)
+    }
+
+    fn group_clause(self) -> &'static str {
+        match self {
+            Self::Provider => "l.app_type, l.provider_id",
+            Self::Model => "l.model",
+            Self::App => "l.app_type",
+            Self::Session => "l.session_id",
+            Self::Day => "1",
+        }
+    }
+
+    fn order_clause(self) -> &'static str {
+        match self {
+            Self::Day => "1 ASC",
+            _ => "total_cost DESC",
+        }
+    }
+}
+
+const LOG_COLUMNS: &[&str] = &[
+    "request_id",
+    "created_at",
+    "time",
+    "app_type",
+    "provider_id",
+    "provider_name",
+    "model",
+    "request_model",
+    "session_id",
+    "client_label",
+    "status_code",
+    "is_streaming",
+    "input_tokens",
+    "output_tokens",
+    "cache_read_tokens",
+    "cache_creation_tokens",
+    "input_cost_usd",
+    "output_cost_usd",
+    "cache_read_cost_usd",
+    "cache_creation_cost_usd",
+    "total_cost_usd",
+    "cost_multiplier",
+    "latency_ms",
+    "first_token_ms",
+    "attempts",
+    "error_message",
+];
+
+const SUMMARY_METRICS: &[&str] = &[
+    "request_count",
+    "success_count",
+    "input_tokens",
+    "output_tokens",
+    "cache_read_tokens",
+    "cache_creation_tokens",
+    "total_cost_usd",
+];
+
+/// 汇总指标的 SQL 表达式（原始日志），与 `SUMMARY_METRICS` 一一对应，最后一列为费用
+const LOG_METRIC_EXPRS: &[&str] = &[
+    "COUNT(*)",
+    "SUM(CASE WHEN l.status_code >= 200 AND l.status_code < 300 THEN 1 ELSE 0 END)",
+    "SUM(l.input_tokens)",
+    "SUM(l.output_tokens)",
+    "SUM(l.cache_read_tokens)",
+    "SUM(l.cache_creation_tokens)",
+    "SUM(CAST(l.total_cost_usd AS REAL))",
+];
+
+/// 汇总指标的 SQL 表达式（`usage_source` 聚合数据源）
+const ROLLUP_METRIC_EXPRS: &[&str] = &[
+    "SUM(l.request_count)",
+    "SUM(l.success_count)",
+    "SUM(l.input_tokens)",
+    "SUM(l.output_tokens)",
+    "SUM(l.cache_read_tokens)",
+    "SUM(l.cache_creation_tokens)",
+    "SUM(l.total_cost)",
+];
+
+/// 按格式逐行写出记录
+struct RecordWriter<'a> {
+    format: UsageExportFormat,
+    columns: Vec<&'static str>,
+    out: &'a mut dyn Write,
+    rows: u64,
+}
+
+impl<'a> RecordWriter<'a> {
+    fn new(
+        format: UsageExportFormat,
+        columns: Vec<&'static str>,
+        out: &'a mut dyn Write,
+    ) -> Result<Self, AppError> {
+        let mut writer = Self {
+            format,
+            columns,
+            out,
+            rows: 0,
+        };
+        if format == UsageExportFormat::Csv {
+            let header = writer.columns.join(",");
+            writer.write_line(&header)?;
+        }
+        Ok(writer)
+    }
+
+    fn write(&mut self, values: Vec<Value>) -> Result<(), AppError> {
+        let line = match self.format {
+            UsageExportFormat::Csv => values.iter().map(csv_field).collect::<Vec<_>>().join(","),
+            UsageExportFormat::Jsonl => {
+                let record: serde_json::Map<String, Value> = self
+                    .columns
+                    .iter()
+                    .map(|c| c.to_string())
+                    .zip(values)
+                    .collect();
+                serde_json::to_string(&record)
+                    .map_err(|e| AppError::Message(format!("序列化导出记录失败: {e}")))?
+            }
+        };
+        self.write_line(&line)?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), AppError> {
+        writeln!(self.out, "{line}")
+            .map_err(|e| AppError::Message(format!("写入导出数据失败: {e}")))
+    }
+
+    fn finish(self) -> Result<u64, AppError> {
+        self.out
+            .flush()
+            .map_err(|e| AppError::Message(format!("写入导出数据失败: {e}")))?;
+        Ok(self.rows)
+    }
+}
+
+/// CSV 字段转义（含逗号、引号或换行时加引号）
+fn csv_field(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// 导出请求明细（`group_by` 为 None）或按维度聚合的汇总，返回导出行数
+pub fn export_usage(
+    db: &Database,
+    filters: &LogFilters,
+    group_by: Option<UsageGroupBy>,
+    format: UsageExportFormat,
+    out: &mut dyn Write,
+) -> Result<u64, AppError> {
+    match group_by {
+        Some(group_by) => db.export_usage_summary(filters, group_by, format, out),
+        None => db.export_request_logs(filters, format, out),
+    }
+}
+
+impl Database {
+    /// 导出请求日志（按时间升序），返回导出行数
+    pub fn export_request_logs(
+        &self,
+        filters: &LogFilters,
+        format: UsageExportFormat,
+        out: &mut dyn Write,
+    ) -> Result<u64, AppError> {
+        let mut writer = RecordWriter::new(format, LOG_COLUMNS.to_vec(), out)?;
+        let mut provider_cache = HashMap::new();
+        let mut pricing_cache = HashMap::new();
+        // 按 (created_at, rowid) 分批读取，批次之间不持有数据库锁
+        let mut cursor: Option<(i64, i64)> = None;
+
+        loop {
+            let conn = lock_conn!(self.conn);
+            let (mut conditions, mut params) = log_filter_conditions(filters);
+            if let Some((created_at, rowid)) = cursor {
+                conditions.push("(l.created_at, l.rowid) > (?, ?)");
+                params.push(Box::new(created_at));
+                params.push(Box::new(rowid));
+            }
+            let where_clause = if conditions.is_empty() {
+                String::new()
+            } else {
+                format!("WHERE {}", conditions.join(" AND "))
+            };
+            params.push(Box::new(EXPORT_BATCH_SIZE));
+
+            let sql = format!(
+                "SELECT {REQUEST_LOG_COLUMNS}, l.session_id, l.rowid
+                 FROM proxy_request_logs l
+                 LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
+                 {where_clause}
+                 ORDER BY l.created_at ASC, l.rowid ASC
+                 LIMIT ?"
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let params_refs: Vec<&dyn rusqlite::ToSql> =
+                params.iter().map(|p| p.as_ref()).collect();
+            let rows = stmt.query_map(params_refs.as_slice(), |row| {
+                Ok((
+                    request_log_from_row(row)?,
+                    row.get::<_, Option<String>>(26)?,
+                    row.get::<_, i64>(27)?,
+                ))
+            })?;
+
+            let mut batch = 0;
+            for row in rows {
+                let (mut log, session_id, rowid) = row?;
+                Self::maybe_backfill_log_costs(
+                    &conn,
+                    &mut log,
+                    &mut provider_cache,
+                    &mut pricing_cache,
+                )?;
+                cursor = Some((log.created_at, rowid));
+                batch += 1;
+
+                let time = Local
+                    .timestamp_opt(log.created_at, 0)
+                    .single()
+                    .map(|t| t.to_rfc3339());
+                writer.write(vec![
+                    log.request_id.into(),
+                    log.created_at.into(),
+                    time.into(),
+                    log.app_type.into(),
+                    log.provider_id.into(),
+                    log.provider_name.into(),
+                    log.model.into(),
+                    log.request_model.into(),
+                    session_id.into(),
+                    log.client_label.into(),
+                    log.status_code.into(),
+                    log.is_streaming.into(),
+                    log.input_tokens.into(),
+                    log.output_tokens.into(),
+                    log.cache_read_tokens.into(),
+                    log.cache_creation_tokens.into(),
+                    log.input_cost_usd.into(),
+                    log.output_cost_usd.into(),
+                    log.cache_read_cost_usd.into(),
+                    log.cache_creation_cost_usd.into(),
+                    log.total_cost_usd.into(),
+                    log.cost_multiplier.into(),
+                    log.latency_ms.into(),
+                    log.first_token_ms.into(),
+                    log.attempts.into(),
+                    log.error_message.into(),
+                ])?;
+            }
+
+            if batch < EXPORT_BATCH_SIZE {
+                break;
+            }
+        }
+
+        writer.finish()
+    }
+
+    /// 按维度导出聚合汇总，返回导出行数
+    pub fn export_usage_summary(
+        &self,
+        filters: &LogFilters,
+        group_by: UsageGroupBy,
+        format: UsageExportFormat,
+        out: &mut dyn Write,
+    ) -> Result<u64, AppError> {
+        let keys = group_by.keys();
+        let columns: Vec<&'static str> = keys
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(SUMMARY_METRICS.iter().copied())
+            .collect();
+        if group_by.uses_rollup() && filters.status_code.is_some() {
+            return Err(AppError::InvalidInput(
+                "按状态码过滤仅支持请求明细和按会话汇总".to_string(),
+            ));
+        }
+        let mut writer = RecordWriter::new(format, columns, out)?;
+
+        let conn = lock_conn!(self.conn);
+        let (from_clause, metric_exprs, conditions, params) = if group_by.uses_rollup() {
+            // 时间范围由数据源处理（占用 ?1–?4），其余条件的匿名参数从 ?5 开始编号
+            let (source, source_params) = usage_source(filters.start_date, filters.end_date);
+            let (conditions, filter_params) = log_filter_conditions(&LogFilters {
+                start_date: None,
+                end_date: None,
+                ..filters.clone()
+            });
+            let params: Vec<Box<dyn rusqlite::ToSql>> = source_params
+                .into_iter()
+                .map(|p| Box::new(p) as Box<dyn rusqlite::ToSql>)
+                .chain(filter_params)
+                .collect();
+            (
+                format!("({source}) l"),
+                ROLLUP_METRIC_EXPRS,
+                conditions,
+                params,
+            )
+        } else {
+            let (conditions, params) = log_filter_conditions(filters);
+            (
+                "proxy_request_logs l".to_string(),
+                LOG_METRIC_EXPRS,
+                conditions,
+                params,
+            )
+        };
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let key_exprs = keys
+            .iter()
+            .map(|(_, expr)| *expr)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let metric_exprs = metric_exprs
+            .iter()
+            .map(|expr| format!("COALESCE({expr}, 0)"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "SELECT {key_exprs}, {metric_exprs} as total_cost
+             FROM {from_clause}
+             LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
+             {where_clause}
+             GROUP BY {}
+             ORDER BY {}",
+            group_by.group_clause(),
+            group_by.order_clause()
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        let mut rows = stmt.query(params_refs.as_slice())?;
+        while let Some(row) = rows.next()? {
+            let mut values: Vec<Value> = Vec::with_capacity(keys.len() + SUMMARY_METRICS.len());
+            for idx in 0..keys.len() {
+                values.push(row.get::<_, Option<String>>(idx)?.into());
+            }
+            for idx in keys.len()..keys.len() + SUMMARY_METRICS.len() - 1 {
+                values.push(row.get::<_, i64>(idx)?.into());
+            }
+            let total_cost: f64 = row.get(keys.len() + SUMMARY_METRICS.len() - 1)?;
+            values.push(format!("{total_cost:.6}").into());
+            writer.write(values)?;
+        }
+
+        writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{apply_usage_rollup, UsageRollupDelta};
+    use rusqlite::params;
+
+    fn seed(db: &Database) -> Result<(), AppError> {
+        let conn = lock_conn!(db.conn);
+        for (id, model, session, cost, status, created_at) in [
+            ("req1", "claude-3", Some("s1"), "0.5", 200, 1_000),
+            ("req2", "claude-3", Some("s1"), "0.25", 500, 2_000),
+            ("req3", "gpt-5", None, "1", 200, 3_000),
+        ] {
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, session_id,
+                    input_tokens, output_tokens, total_cost_usd,
+                    latency_ms, status_code, created_at, error_message
+                ) VALUES (?1, 'p1', 'claude', ?2, ?3, 10, 5, ?4, 100, ?5, ?6, ?7)",
+                params![
+                    id,
+                    model,
+                    session,
+                    cost,
+                    status,
+                    created_at,
+                    (status != 200).then_some("upstream said \"no\", retry")
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_request_logs_csv_and_jsonl() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        seed(&db)?;
+
+        let filters = LogFilters {
+            model: Some("claude".to_string()),
+            ..Default::default()
+        };
+        let mut csv = Vec::new();
+        assert_eq!(
+            db.export_request_logs(&filters, UsageExportFormat::Csv, &mut csv)?,
+            2
+        );
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("request_id,created_at,time,"));
+        assert!(lines[1].starts_with("req1,1000,"));
+        assert!(lines[2].ends_with(r#","upstream said ""no"", retry""#));
+
+        let mut jsonl = Vec::new();
+        db.export_request_logs(&LogFilters::default(), UsageExportFormat::Jsonl, &mut jsonl)?;
+        let records: Vec<Value> = String::from_utf8(jsonl)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["session_id"], "s1");
+        assert_eq!(records[2]["total_cost_usd"], "1");
+        assert_eq!(records[2]["session_id"], Value::Null);
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_usage_summary_by_session_and_model() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        seed(&db)?;
+
+        let mut out = Vec::new();
+        db.export_usage_summary(
+            &LogFilters::default(),
+            UsageGroupBy::Session,
+            UsageExportFormat::Jsonl,
+            &mut out,
+        )?;
+        let records: Vec<Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["session_id"], Value::Null);
+        assert_eq!(records[1]["session_id"], "s1");
+        assert_eq!(records[1]["request_count"], 2);
+        assert_eq!(records[1]["success_count"], 1);
+        assert_eq!(records[1]["total_cost_usd"], "0.750000");
+
+        let filters = LogFilters {
+            start_date: Some(1_500),
+            ..Default::default()
+        };
+        let mut csv = Vec::new();
+        db.export_usage_summary(
+            &filters,
+            UsageGroupBy::Model,
+            UsageExportFormat::Csv,
+            &mut csv,
+        )?;
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "model,request_count,success_count,input_tokens,output_tokens,cache_read_tokens,cache_creation_tokens,total_cost_usd\n\
+             gpt-5,1,1,10,5,0,0,1.000000\n\
+             claude-3,1,0,10,5,0,0,0.250000\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_usage_summary_reads_rollups_after_retention() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = lock_conn!(db.conn);
+            for (app_type, model, success, cost) in [
+                ("claude", "claude-3", 1, 0.5),
+                ("claude", "claude-3", 0, 0.25),
+                ("codex", "gpt-5", 1, 1.0),
+            ] {
+                apply_usage_rollup(
+                    &conn,
+                    &UsageRollupDelta {
+                        created_at: 7_200,
+                        app_type,
+                        provider_id: "p1",
+                        model,
+                        request_count: 1,
+                        success_count: success,
+                        input_tokens: 10,
+                        output_tokens: 5,
+                        total_cost: cost,
+                        ..Default::default()
+                    },
+                )?;
+            }
+        }
+
+        // 原始日志已全部清理，汇总仍来自聚合表
+        let mut csv = Vec::new();
+        db.export_usage_summary(
+            &LogFilters {
+                app_type: Some("claude".to_string()),
+                start_date: Some(0),
+                ..Default::default()
+            },
+            UsageGroupBy::Provider,
+            UsageExportFormat::Csv,
+            &mut csv,
+        )?;
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "app_type,provider_id,provider_name,request_count,success_count,input_tokens,output_tokens,cache_read_tokens,cache_creation_tokens,total_cost_usd\n\
+             claude,p1,,2,1,20,10,0,0,0.750000\n"
+        );
+
+        let mut out = Vec::new();
+        db.export_usage_summary(
+            &LogFilters::default(),
+            UsageGroupBy::App,
+            UsageExportFormat::Jsonl,
+            &mut out,
+        )?;
+        let records: Vec<Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["app_type"], "codex");
+        assert_eq!(records[1]["request_count"], 2);
+
+        let err = db
+            .export_usage_summary(
+                &LogFilters {
+                    status_code: Some(200),
+                    ..Default::default()
+                },
+                UsageGroupBy::Model,
+                UsageExportFormat::Csv,
+                &mut Vec::new(),
+            )
+            .unwrap_err();
+        assert!(matches!(err, AppError::InvalidInput(_)));
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+//! 请求日志保留策略
+//!
+//! 按 `UsageRetentionConfig` 清理超过保留天数的原始请求日志，按小时/按日的
+//! 聚合统计不受影响。开启归档时，删除前先写入 `~/.cc-switch/usage-archive/*.jsonl`。
+
+use crate::config::get_app_config_dir;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::proxy::types::UsageRetentionConfig;
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 自动清理的检查间隔
+const RETENTION_CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// 一次清理的结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRetentionReport {
+    /// 删除的原始日志条数
+    pub pruned: u64,
+    /// 早于该时间戳（秒）的日志被清理
+    pub cutoff: i64,
+    /// 归档文件路径（未归档或无可清理日志时为 None）
+    pub archive_path: Option<String>,
+}
+
+/// 按保留策略清理一次原始请求日志（不检查 `enabled`，供手动触发使用）
+pub fn apply_usage_retention(
+    db: &Database,
+    config: &UsageRetentionConfig,
+) -> Result<UsageRetentionReport, AppError> {
+    if config.retention_days == 0 {
+        return Err(AppError::InvalidInput(
+            "日志保留天数至少为 1 天".to_string(),
+        ));
+    }
+    let cutoff = chrono::Utc::now().timestamp() - i64::from(config.retention_days) * 86_400;
+
+    if !config.archive {
+        let pruned = db.prune_request_logs(cutoff, None)?;
+        return Ok(UsageRetentionReport {
+            pruned,
+            cutoff,
+            archive_path: None,
+        });
+    }
+
+    if db.count_request_logs_before(cutoff)? == 0 {
+        return Ok(UsageRetentionReport {
+            pruned: 0,
+            cutoff,
+            archive_path: None,
+        });
+    }
+
+    let dir = get_app_config_dir().join("usage-archive");
+    std::fs::create_dir_all(&dir).map_err(|e| AppError::io(&dir, e))?;
+    let path = dir.join(format!(
+        "proxy_request_logs-{}.jsonl",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    let file = File::create(&path).map_err(|e| AppError::io(&path, e))?;
+    let mut writer = BufWriter::new(file);
+    let pruned = db.prune_request_logs(cutoff, Some(&mut writer))?;
+
+    Ok(UsageRetentionReport {
+        pruned,
+        cutoff,
+        archive_path: Some(path.display().to_string()),
+    })
+}
+
+/// 后台定期执行保留策略（启动时立即检查一次）
+pub async fn run_usage_retention_loop(db: Arc<Database>) {
+    loop {
+        let task_db = db.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let config = task_db.get_usage_retention_config()?;
+            if !config.enabled {
+                return Ok(None);
+            }
+            apply_usage_retention(&task_db, &config).map(Some)
+        })
+        .await;
+
+        match result {
+            Ok(Ok(Some(report))) if report.pruned > 0 => {
+                log::info!(
+                    "[Retention] 已清理 {} 条过期请求日志{}",
+                    report.pruned,
+                    report
+                        .archive_path
+                        .map(|p| format!("，归档至 {p}"))
+                        .unwrap_or_default()
+                );
+            }
+            Ok(Err(e)) => log::warn!("[Retention] 清理请求日志失败: {e}"),
+            Err(e) => log::warn!("[Retention] 清理任务异常退出: {e}"),
+            _ => {}
+        }
+
+        tokio::time::sleep(RETENTION_CHECK_INTERVAL).await;
+    }
+}
@@ -1,8 +1,11 @@
 //! 使用统计服务
 //!
 //! 提供使用量数据的聚合查询功能
+//!
+//! 汇总、趋势与分组统计读取 `usage_rollup_hourly` / `usage_rollup_daily` 聚合表，
+//! 请求日志列表与详情仍读取原始日志。
 
-use crate::database::{lock_conn, Database};
+use crate::database::{apply_usage_rollup, lock_conn, usage_source, Database, UsageRollupDelta};
 use crate::error::AppError;
 use chrono::{Local, TimeZone};
 use rusqlite::{params, Connection, OptionalExtension};
@@ -51,6 +54,18 @@ pub struct ProviderStats {
     pub avg_latency_ms: u64,
 }
 
+/// 客户端统计（按代理客户端令牌标签聚合）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientStats {
+    /// 客户端令牌标签（未携带令牌的请求为 None）
+    pub client_label: Option<String>,
+    pub request_count: u64,
+    pub total_tokens: u64,
+    pub total_cost: String,
+    pub success_rate: f32,
+}
+
 /// 模型统计
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -63,13 +78,14 @@ pub struct ModelStats {
 }
 
 /// 请求日志过滤器
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LogFilters {
     pub app_type: Option<String>,
     pub provider_name: Option<String>,
     pub model: Option<String>,
     pub status_code: Option<u16>,
+    pub client_label: Option<String>,
     pub start_date: Option<i64>,
     pub end_date: Option<i64>,
 }
@@ -113,6 +129,14 @@ pub struct RequestLogDetail {
     pub status_code: u16,
     pub error_message: Option<String>,
     pub created_at: i64,
+    /// 会话粘性决策（new/hit/fallback）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_affinity: Option<String>,
+    /// 客户端令牌标签
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_label: Option<String>,
+    /// 本次请求的上游请求总次数（含重试与故障转移）
+    pub attempts: u32,
 }
 
 impl Database {
@@ -124,38 +148,20 @@ impl Database {
     ) -> Result<UsageSummary, AppError> {
         let conn = lock_conn!(self.conn);
 
-        let (where_clause, params_vec) = if start_date.is_some() || end_date.is_some() {
-            let mut conditions = Vec::new();
-            let mut params = Vec::new();
-
-            if let Some(start) = start_date {
-                conditions.push("created_at >= ?");
-                params.push(start);
-            }
-            if let Some(end) = end_date {
-                conditions.push("created_at <= ?");
-                params.push(end);
-            }
-
-            (format!("WHERE {}", conditions.join(" AND ")), params)
-        } else {
-            (String::new(), Vec::new())
-        };
-
+        let (source, source_params) = usage_source(start_date, end_date);
         let sql = format!(
             "SELECT
-                COUNT(*) as total_requests,
-                COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0) as total_cost,
+                COALESCE(SUM(request_count), 0) as total_requests,
+                COALESCE(SUM(total_cost), 0) as total_cost,
                 COALESCE(SUM(input_tokens), 0) as total_input_tokens,
                 COALESCE(SUM(output_tokens), 0) as total_output_tokens,
                 COALESCE(SUM(cache_creation_tokens), 0) as total_cache_creation_tokens,
                 COALESCE(SUM(cache_read_tokens), 0) as total_cache_read_tokens,
-                COALESCE(SUM(CASE WHEN status_code >= 200 AND status_code < 300 THEN 1 ELSE 0 END), 0) as success_count
-             FROM proxy_request_logs
-             {where_clause}"
+                COALESCE(SUM(success_count), 0) as success_count
+             FROM ({source})"
         );
 
-        let result = conn.query_row(&sql, rusqlite::params_from_iter(params_vec), |row| {
+        let result = conn.query_row(&sql, source_params, |row| {
             let total_requests: i64 = row.get(0)?;
             let total_cost: f64 = row.get(1)?;
             let total_input_tokens: i64 = row.get(2)?;
@@ -220,23 +226,25 @@ impl Database {
             bucket_count = 1;
         }
 
-        let sql = "
-            SELECT
-                CAST((created_at - ?1) / ?3 AS INTEGER) as bucket_idx,
-                COUNT(*) as request_count,
-                COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0) as total_cost,
+        // 整点小时的聚合数据按小时起点归入趋势桶
+        let (source, [p1, p2, p3, p4]) = usage_source(Some(start_ts), Some(end_ts));
+        let sql = format!(
+            "SELECT
+                CAST((ts - ?1) / ?5 AS INTEGER) as bucket_idx,
+                COALESCE(SUM(request_count), 0) as request_count,
+                COALESCE(SUM(total_cost), 0) as total_cost,
                 COALESCE(SUM(input_tokens + output_tokens), 0) as total_tokens,
                 COALESCE(SUM(input_tokens), 0) as total_input_tokens,
                 COALESCE(SUM(output_tokens), 0) as total_output_tokens,
                 COALESCE(SUM(cache_creation_tokens), 0) as total_cache_creation_tokens,
                 COALESCE(SUM(cache_read_tokens), 0) as total_cache_read_tokens
-            FROM proxy_request_logs
-            WHERE created_at >= ?1 AND created_at <= ?2
+            FROM ({source})
             GROUP BY bucket_idx
-            ORDER BY bucket_idx ASC";
+            ORDER BY bucket_idx ASC"
+        );
 
-        let mut stmt = conn.prepare(sql)?;
-        let rows = stmt.query_map(params![start_ts, end_ts, bucket_seconds], |row| {
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params![p1, p2, p3, p4, bucket_seconds], |row| {
             Ok((
                 row.get::<_, i64>(0)?,
                 DailyStats {
@@ -301,14 +309,15 @@ impl Database {
         let sql = "SELECT
                 l.provider_id,
                 p.name as provider_name,
-                COUNT(*) as request_count,
+                SUM(l.request_count) as request_count,
                 COALESCE(SUM(l.input_tokens + l.output_tokens), 0) as total_tokens,
-                COALESCE(SUM(CAST(l.total_cost_usd AS REAL)), 0) as total_cost,
-                COALESCE(SUM(CASE WHEN l.status_code >= 200 AND l.status_code < 300 THEN 1 ELSE 0 END), 0) as success_count,
-                COALESCE(AVG(l.latency_ms), 0) as avg_latency
-             FROM proxy_request_logs l
+                COALESCE(SUM(l.total_cost_usd), 0) as total_cost,
+                COALESCE(SUM(l.success_count), 0) as success_count,
+                COALESCE(CAST(SUM(l.latency_ms_sum) AS REAL) / NULLIF(SUM(l.request_count), 0), 0) as avg_latency
+             FROM usage_rollup_daily l
              LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
              GROUP BY l.provider_id, l.app_type
+             HAVING SUM(l.request_count) > 0
              ORDER BY total_cost DESC";
 
         let mut stmt = conn.prepare(sql)?;
@@ -342,17 +351,67 @@ impl Database {
         Ok(stats)
     }
 
+    /// 获取客户端（令牌标签）统计，可按时间范围过滤
+    pub fn get_client_stats(
+        &self,
+        start_date: Option<i64>,
+        end_date: Option<i64>,
+    ) -> Result<Vec<ClientStats>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let (source, source_params) = usage_source(start_date, end_date);
+        let sql = format!(
+            "SELECT
+                client_label,
+                SUM(request_count) as request_count,
+                COALESCE(SUM(input_tokens + output_tokens), 0) as total_tokens,
+                COALESCE(SUM(total_cost), 0) as total_cost,
+                COALESCE(SUM(success_count), 0) as success_count
+             FROM ({source})
+             GROUP BY client_label
+             HAVING SUM(request_count) > 0
+             ORDER BY total_cost DESC"
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(source_params, |row| {
+            let request_count: i64 = row.get(1)?;
+            let success_count: i64 = row.get(4)?;
+            let success_rate = if request_count > 0 {
+                (success_count as f32 / request_count as f32) * 100.0
+            } else {
+                0.0
+            };
+
+            Ok(ClientStats {
+                client_label: row.get(0)?,
+                request_count: request_count as u64,
+                total_tokens: row.get::<_, i64>(2)? as u64,
+                total_cost: format!("{:.6}", row.get::<_, f64>(3)?),
+                success_rate,
+            })
+        })?;
+
+        let mut stats = Vec::new();
+        for row in rows {
+            stats.push(row?);
+        }
+
+        Ok(stats)
+    }
+
     /// 获取模型统计
     pub fn get_model_stats(&self) -> Result<Vec<ModelStats>, AppError> {
         let conn = lock_conn!(self.conn);
 
         let sql = "SELECT
                 model,
-                COUNT(*) as request_count,
+                SUM(request_count) as request_count,
                 COALESCE(SUM(input_tokens + output_tokens), 0) as total_tokens,
-                COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0) as total_cost
-             FROM proxy_request_logs
+                COALESCE(SUM(total_cost_usd), 0) as total_cost
+             FROM usage_rollup_daily
              GROUP BY model
+             HAVING SUM(request_count) > 0
              ORDER BY total_cost DESC";
 
         let mut stmt = conn.prepare(sql)?;
@@ -391,34 +450,7 @@ impl Database {
     ) -> Result<PaginatedLogs, AppError> {
         let conn = lock_conn!(self.conn);
 
-        let mut conditions = Vec::new();
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-        if let Some(ref app_type) = filters.app_type {
-            conditions.push("l.app_type = ?");
-            params.push(Box::new(app_type.clone()));
-        }
-        if let Some(ref provider_name) = filters.provider_name {
-            conditions.push("p.name LIKE ?");
-            params.push(Box::new(format!("%{provider_name}%")));
-        }
-        if let Some(ref model) = filters.model {
-            conditions.push("l.model LIKE ?");
-            params.push(Box::new(format!("%{model}%")));
-        }
-        if let Some(status) = filters.status_code {
-            conditions.push("l.status_code = ?");
-            params.push(Box::new(status as i64));
-        }
-        if let Some(start) = filters.start_date {
-            conditions.push("l.created_at >= ?");
-            params.push(Box::new(start));
-        }
-        if let Some(end) = filters.end_date {
-            conditions.push("l.created_at <= ?");
-            params.push(Box::new(end));
-        }
-
+        let (conditions, mut params) = log_filter_conditions(filters);
         let where_clause = if conditions.is_empty() {
             String::new()
         } else {
@@ -442,12 +474,7 @@ impl Database {
         params.push(Box::new(offset as i64));
 
         let sql = format!(
-            "SELECT l.request_id, l.provider_id, p.name as provider_name, l.app_type, l.model,
-                    l.request_model, l.cost_multiplier,
-                    l.input_tokens, l.output_tokens, l.cache_read_tokens, l.cache_creation_tokens,
-                    l.input_cost_usd, l.output_cost_usd, l.cache_read_cost_usd, l.cache_creation_cost_usd, l.total_cost_usd,
-                    l.is_streaming, l.latency_ms, l.first_token_ms, l.duration_ms,
-                    l.status_code, l.error_message, l.created_at
+            "SELECT {REQUEST_LOG_COLUMNS}
              FROM proxy_request_logs l
              LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
              {where_clause}
@@ -457,35 +484,7 @@ impl Database {
 
         let mut stmt = conn.prepare(&sql)?;
         let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-        let rows = stmt.query_map(params_refs.as_slice(), |row| {
-            Ok(RequestLogDetail {
-                request_id: row.get(0)?,
-                provider_id: row.get(1)?,
-                provider_name: row.get(2)?,
-                app_type: row.get(3)?,
-                model: row.get(4)?,
-                request_model: row.get(5)?,
-                cost_multiplier: row
-                    .get::<_, Option<String>>(6)?
-                    .unwrap_or_else(|| "1".to_string()),
-                input_tokens: row.get::<_, i64>(7)? as u32,
-                output_tokens: row.get::<_, i64>(8)? as u32,
-                cache_read_tokens: row.get::<_, i64>(9)? as u32,
-                cache_creation_tokens: row.get::<_, i64>(10)? as u32,
-                input_cost_usd: row.get(11)?,
-                output_cost_usd: row.get(12)?,
-                cache_read_cost_usd: row.get(13)?,
-                cache_creation_cost_usd: row.get(14)?,
-                total_cost_usd: row.get(15)?,
-                is_streaming: row.get::<_, i64>(16)? != 0,
-                latency_ms: row.get::<_, i64>(17)? as u64,
-                first_token_ms: row.get::<_, Option<i64>>(18)?.map(|v| v as u64),
-                duration_ms: row.get::<_, Option<i64>>(19)?.map(|v| v as u64),
-                status_code: row.get::<_, i64>(20)? as u16,
-                error_message: row.get(21)?,
-                created_at: row.get(22)?,
-            })
-        })?;
+        let rows = stmt.query_map(params_refs.as_slice(), request_log_from_row)?;
 
         let mut logs = Vec::new();
         let mut provider_cache = HashMap::new();
@@ -523,7 +522,8 @@ impl Database {
                     input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
                     input_cost_usd, output_cost_usd, cache_read_cost_usd, cache_creation_cost_usd, total_cost_usd,
                     is_streaming, latency_ms, first_token_ms, duration_ms,
-                    status_code, error_message, created_at
+                    status_code, error_message, created_at, l.session_affinity, l.client_label,
+                    l.attempts
              FROM proxy_request_logs l
              LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
              WHERE l.request_id = ?",
@@ -553,6 +553,9 @@ impl Database {
                     status_code: row.get::<_, i64>(20)? as u16,
                     error_message: row.get(21)?,
                     created_at: row.get(22)?,
+                    session_affinity: row.get(23)?,
+                    client_label: row.get(24)?,
+                    attempts: row.get::<_, i64>(25)? as u32,
                 })
             },
         );
@@ -607,29 +610,7 @@ impl Database {
             })
             .unwrap_or((None, None));
 
-        // 计算今日使用量
-        let daily_usage: f64 = conn
-            .query_row(
-                "SELECT COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0)
-             FROM proxy_request_logs
-             WHERE provider_id = ? AND app_type = ?
-               AND date(datetime(created_at, 'unixepoch', 'localtime')) = date('now', 'localtime')",
-                params![provider_id, app_type],
-                |row| row.get(0),
-            )
-            .unwrap_or(0.0);
-
-        // 计算本月使用量
-        let monthly_usage: f64 = conn
-            .query_row(
-                "SELECT COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0)
-             FROM proxy_request_logs
-             WHERE provider_id = ? AND app_type = ?
-               AND strftime('%Y-%m', datetime(created_at, 'unixepoch', 'localtime')) = strftime('%Y-%m', 'now', 'localtime')",
-                params![provider_id, app_type],
-                |row| row.get(0),
-            )
-            .unwrap_or(0.0);
+        let (daily_usage, monthly_usage) = query_provider_spend(&conn, provider_id, app_type);
 
         let daily_exceeded = limit_daily
             .map(|limit| daily_usage >= limit)
@@ -648,6 +629,183 @@ impl Database {
             monthly_exceeded,
         })
     }
+
+    /// 获取 Provider 今日/本月累计消费（USD，本地时区）
+    ///
+    /// 供代理限额跟踪器初始化内存累计值使用
+    pub fn get_provider_spend(
+        &self,
+        provider_id: &str,
+        app_type: &str,
+    ) -> Result<(f64, f64), AppError> {
+        let conn = lock_conn!(self.conn);
+        Ok(query_provider_spend(&conn, provider_id, app_type))
+    }
+
+    /// 获取应用整体（所有供应商合计）今日/本月累计消费（USD，本地时区）
+    ///
+    /// 供预算提醒检查应用整体限额使用
+    pub fn get_app_spend(&self, app_type: &str) -> Result<(f64, f64), AppError> {
+        let conn = lock_conn!(self.conn);
+        let (daily, monthly) = conn.query_row(
+            "SELECT
+                COALESCE(SUM(CASE WHEN day = date('now', 'localtime') THEN total_cost_usd END), 0),
+                COALESCE(SUM(total_cost_usd), 0)
+             FROM usage_rollup_daily
+             WHERE app_type = ?1 AND day >= strftime('%Y-%m-01', 'now', 'localtime')",
+            [app_type],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok((daily, monthly))
+    }
+
+    /// 获取 Provider 最近成功请求的平均延迟（毫秒）
+    ///
+    /// 流式请求取首字延迟，非流式取总延迟；没有历史记录时返回 None。
+    /// 供代理负载均衡器初始化 EWMA 延迟使用
+    pub fn get_provider_recent_latency(
+        &self,
+        provider_id: &str,
+        app_type: &str,
+        sample_size: u32,
+    ) -> Result<Option<f64>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let avg: Option<f64> = conn.query_row(
+            "SELECT AVG(latency) FROM (
+                SELECT COALESCE(first_token_ms, latency_ms) AS latency
+                FROM proxy_request_logs
+                WHERE provider_id = ?1 AND app_type = ?2
+                  AND status_code >= 200 AND status_code < 300
+                ORDER BY created_at DESC
+                LIMIT ?3
+            )",
+            params![provider_id, app_type, sample_size],
+            |row| row.get(0),
+        )?;
+        Ok(avg)
+    }
+}
+
+/// 请求日志详情查询列（与 `request_log_from_row` 一一对应，`l` 为 proxy_request_logs，`p` 为 providers）
+pub(crate) const REQUEST_LOG_COLUMNS: &str =
+    "l.request_id, l.provider_id, p.name as provider_name, l.app_type, l.model,
+     l.request_model, l.cost_multiplier,
+     l.input_tokens, l.output_tokens, l.cache_read_tokens, l.cache_creation_tokens,
+     l.input_cost_usd, l.output_cost_usd, l.cache_read_cost_usd, l.cache_creation_cost_usd, l.total_cost_usd,
+     l.is_streaming, l.latency_ms, l.first_token_ms, l.duration_ms,
+     l.status_code, l.error_message, l.created_at, l.session_affinity,
+     l.client_label, l.attempts";
+
+pub(crate) fn request_log_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<RequestLogDetail> {
+    Ok(RequestLogDetail {
+        request_id: row.get(0)?,
+        provider_id: row.get(1)?,
+        provider_name: row.get(2)?,
+        app_type: row.get(3)?,
+        model: row.get(4)?,
+        request_model: row.get(5)?,
+        cost_multiplier: row
+            .get::<_, Option<String>>(6)?
+            .unwrap_or_else(|| "1".to_string()),
+        input_tokens: row.get::<_, i64>(7)? as u32,
+        output_tokens: row.get::<_, i64>(8)? as u32,
+        cache_read_tokens: row.get::<_, i64>(9)? as u32,
+        cache_creation_tokens: row.get::<_, i64>(10)? as u32,
+        input_cost_usd: row.get(11)?,
+        output_cost_usd: row.get(12)?,
+        cache_read_cost_usd: row.get(13)?,
+        cache_creation_cost_usd: row.get(14)?,
+        total_cost_usd: row.get(15)?,
+        is_streaming: row.get::<_, i64>(16)? != 0,
+        latency_ms: row.get::<_, i64>(17)? as u64,
+        first_token_ms: row.get::<_, Option<i64>>(18)?.map(|v| v as u64),
+        duration_ms: row.get::<_, Option<i64>>(19)?.map(|v| v as u64),
+        status_code: row.get::<_, i64>(20)? as u16,
+        error_message: row.get(21)?,
+        created_at: row.get(22)?,
+        session_affinity: row.get(23)?,
+        client_label: row.get(24)?,
+        attempts: row.get::<_, i64>(25)? as u32,
+    })
+}
+
+/// 根据请求日志过滤器构造 WHERE 条件（`l` 为 proxy_request_logs，`p` 为 providers）
+pub(crate) fn log_filter_conditions(
+    filters: &LogFilters,
+) -> (Vec<&'static str>, Vec<Box<dyn rusqlite::ToSql>>) {
+    let mut conditions = Vec::new();
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    if let Some(ref app_type) = filters.app_type {
+        conditions.push("l.app_type = ?");
+        params.push(Box::new(app_type.clone()));
+    }
+    if let Some(ref provider_name) = filters.provider_name {
+        conditions.push("p.name LIKE ?");
+        params.push(Box::new(format!("%{provider_name}%")));
+    }
+    if let Some(ref model) = filters.model {
+        conditions.push("l.model LIKE ?");
+        params.push(Box::new(format!("%{model}%")));
+    }
+    if let Some(status) = filters.status_code {
+        conditions.push("l.status_code = ?");
+        params.push(Box::new(status as i64));
+    }
+    if let Some(ref client_label) = filters.client_label {
+        conditions.push("l.client_label = ?");
+        params.push(Box::new(client_label.clone()));
+    }
+    if let Some(start) = filters.start_date {
+        conditions.push("l.created_at >= ?");
+        params.push(Box::new(start));
+    }
+    if let Some(end) = filters.end_date {
+        conditions.push("l.created_at <= ?");
+        params.push(Box::new(end));
+    }
+
+    (conditions, params)
+}
+
+/// 查询 Provider 今日/本月累计消费（查询失败时按 0 处理）
+fn query_provider_spend(conn: &Connection, provider_id: &str, app_type: &str) -> (f64, f64) {
+    // 计算今日使用量
+    let daily_usage: f64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(total_cost_usd), 0)
+         FROM usage_rollup_daily
+         WHERE provider_id = ? AND app_type = ? AND day = date('now', 'localtime')",
+            params![provider_id, app_type],
+            |row| row.get(0),
+        )
+        .unwrap_or(0.0);
+
+    // 计算本月使用量
+    let monthly_usage: f64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(total_cost_usd), 0)
+         FROM usage_rollup_daily
+         WHERE provider_id = ? AND app_type = ?
+           AND day >= strftime('%Y-%m-01', 'now', 'localtime')",
+            params![provider_id, app_type],
+            |row| row.get(0),
+        )
+        .unwrap_or(0.0);
+
+    (daily_usage, monthly_usage)
+}
+
+/// 模型定价信息
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPricingInfo {
+    pub model_id: String,
+    pub display_name: String,
+    pub input_cost_per_million: String,
+    pub output_cost_per_million: String,
+    pub cache_read_cost_per_million: String,
+    pub cache_creation_cost_per_million: String,
 }
 
 /// Provider 限额状态
@@ -664,7 +822,7 @@ pub struct ProviderLimitStatus {
 }
 
 #[derive(Clone)]
-struct PricingInfo {
+pub(crate) struct PricingInfo {
     input: rust_decimal::Decimal,
     output: rust_decimal::Decimal,
     cache_read: rust_decimal::Decimal,
@@ -672,15 +830,15 @@ struct PricingInfo {
 }
 
 impl Database {
-    fn maybe_backfill_log_costs(
+    pub(crate) fn maybe_backfill_log_costs(
         conn: &Connection,
         log: &mut RequestLogDetail,
         provider_cache: &mut HashMap<(String, String), rust_decimal::Decimal>,
         pricing_cache: &mut HashMap<String, PricingInfo>,
     ) -> Result<(), AppError> {
-        let total_cost = rust_decimal::Decimal::from_str(&log.total_cost_usd)
+        let previous_cost = rust_decimal::Decimal::from_str(&log.total_cost_usd)
             .unwrap_or(rust_decimal::Decimal::ZERO);
-        let has_cost = total_cost > rust_decimal::Decimal::ZERO;
+        let has_cost = previous_cost > rust_decimal::Decimal::ZERO;
         let has_usage = log.input_tokens > 0
             || log.output_tokens > 0
             || log.cache_read_tokens > 0
@@ -748,6 +906,23 @@ impl Database {
         )
         .map_err(|e| AppError::Database(format!("更新请求成本失败: {e}")))?;
 
+        // 聚合表同步补记成本差额
+        apply_usage_rollup(
+            conn,
+            &UsageRollupDelta {
+                created_at: log.created_at,
+                app_type: &log.app_type,
+                provider_id: &log.provider_id,
+                model: &log.model,
+                client_label: log.client_label.as_deref(),
+                total_cost: (total_cost - previous_cost)
+                    .to_string()
+                    .parse()
+                    .unwrap_or(0.0),
+                ..Default::default()
+            },
+        )?;
+
         Ok(())
     }
 
@@ -814,29 +989,33 @@ impl Database {
     }
 }
 
-pub(crate) fn find_model_pricing_row(
-    conn: &Connection,
-    model_id: &str,
-) -> Result<Option<(String, String, String, String)>, AppError> {
-    // 清洗模型名称：去前缀(/)、去后缀(:)、@ 替换为 -
-    // 例如 moonshotai/gpt-5.2-codex@low:v2 → gpt-5.2-codex-low
-    let cleaned = model_id
+/// 清洗模型名称：去前缀(/)、去后缀(:)、@ 替换为 -
+///
+/// 例如 moonshotai/gpt-5.2-codex@low:v2 → gpt-5.2-codex-low
+pub(crate) fn clean_model_id(model_id: &str) -> String {
+    model_id
         .rsplit_once('/')
         .map_or(model_id, |(_, r)| r)
         .split(':')
         .next()
         .unwrap_or(model_id)
         .trim()
-        .replace('@', "-");
+        .replace('@', "-")
+}
 
-    // 精确匹配清洗后的名称
-    let exact = conn
-        .query_row(
+pub(crate) fn find_model_pricing_row(
+    conn: &Connection,
+    model_id: &str,
+) -> Result<Option<(String, String, String, String)>, AppError> {
+    let cleaned = clean_model_id(model_id);
+
+    let query = |id: &str| {
+        conn.query_row(
             "SELECT input_cost_per_million, output_cost_per_million,
                     cache_read_cost_per_million, cache_creation_cost_per_million
              FROM model_pricing
              WHERE model_id = ?1",
-            [&cleaned],
+            [id],
             |row| {
                 Ok((
                     row.get::<_, String>(0)?,
@@ -847,13 +1026,23 @@ pub(crate) fn find_model_pricing_row(
             },
         )
         .optional()
-        .map_err(|e| AppError::Database(format!("查询模型定价失败: {e}")))?;
+        .map_err(|e| AppError::Database(format!("查询模型定价失败: {e}")))
+    };
+
+    // 精确匹配清洗后的名称，未命中时按定价别名查找
+    let mut found = query(&cleaned)?;
+    if found.is_none() {
+        let aliases = crate::database::load_pricing_aliases(conn)?;
+        if let Some(target) = crate::database::match_pricing_alias(&aliases, &cleaned) {
+            found = query(target)?;
+        }
+    }
 
-    if exact.is_none() {
+    if found.is_none() {
         log::warn!("模型 {model_id}（清洗后: {cleaned}）未找到定价信息，成本将记录为 0");
     }
 
-    Ok(exact)
+    Ok(found)
 }
 
 #[cfg(test)]
@@ -883,6 +1072,7 @@ mod tests {
                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
                 params!["req2", "p1", "claude", "claude-3", 200, 100, "0.02", 150, 200, 2000],
             )?;
+            crate::database::rebuild_usage_rollups(&conn)?;
         }
 
         let summary = db.get_usage_summary(None, None)?;
@@ -918,6 +1108,7 @@ mod tests {
                     1000
                 ],
             )?;
+            crate::database::rebuild_usage_rollups(&conn)?;
         }
 
         let stats = db.get_model_stats()?;
@@ -928,6 +1119,56 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_get_client_stats() -> Result<(), AppError> {
+        let db = Database::memory()?;
+
+        {
+            let conn = lock_conn!(db.conn);
+            for (id, label, cost, status, created_at) in [
+                ("req1", Some("alice"), "0.03", 200, 1000),
+                ("req2", Some("alice"), "0.01", 500, 2000),
+                ("req3", Some("bob"), "0.01", 200, 3000),
+                ("req4", None, "0.10", 200, 4000),
+            ] {
+                conn.execute(
+                    "INSERT INTO proxy_request_logs (
+                        request_id, provider_id, app_type, model,
+                        input_tokens, output_tokens, total_cost_usd,
+                        latency_ms, status_code, created_at, client_label
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    params![
+                        id, "p1", "claude", "claude-3", 10, 5, cost, 100, status, created_at, label
+                    ],
+                )?;
+            }
+            crate::database::rebuild_usage_rollups(&conn)?;
+        }
+
+        let stats = db.get_client_stats(None, None)?;
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats[0].client_label, None);
+        assert_eq!(stats[1].client_label.as_deref(), Some("alice"));
+        assert_eq!(stats[1].request_count, 2);
+        assert_eq!(stats[1].success_rate, 50.0);
+
+        let ranged = db.get_client_stats(Some(2500), None)?;
+        assert_eq!(ranged.len(), 2);
+        assert!(ranged
+            .iter()
+            .all(|s| s.client_label.as_deref() != Some("alice")));
+
+        let filters = LogFilters {
+            client_label: Some("bob".to_string()),
+            ..Default::default()
+        };
+        let logs = db.get_request_logs(&filters, 0, 10)?;
+        assert_eq!(logs.total, 1);
+        assert_eq!(logs.data[0].client_label.as_deref(), Some("bob"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_model_pricing_matching() -> Result<(), AppError> {
         let db = Database::memory()?;
@@ -975,6 +1216,17 @@ mod tests {
             "带 @ 分隔符的模型 gpt-5.2-codex@low 应能匹配到 gpt-5.2-codex-low"
         );
 
+        // 别名：不同写法指向同一条定价
+        conn.execute(
+            "INSERT INTO model_pricing_aliases (pattern, model_id, created_at)
+             VALUES ('claude-sonnet-4?5*', 'claude-sonnet-4-5-20250929', 0)",
+            [],
+        )?;
+        assert_eq!(
+            find_model_pricing_row(&conn, "anthropic/claude-sonnet-4.5")?,
+            find_model_pricing_row(&conn, "claude-sonnet-4-5-20250929")?
+        );
+
         // 测试不存在的模型
         let result = find_model_pricing_row(&conn, "unknown-model-123")?;
         assert!(result.is_none(), "不应该匹配不存在的模型");
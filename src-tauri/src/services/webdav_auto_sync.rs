@@ -4,13 +4,13 @@ use std::sync::OnceLock;
 use std::time::{Duration, Instant};
 
 use serde_json::json;
-use tauri::{AppHandle, Emitter};
 use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 
 use crate::error::AppError;
 use crate::services::webdav_sync as webdav_sync_service;
 use crate::settings::{self, WebDavSyncSettings};
+use crate::AppHandle;
 
 const AUTO_SYNC_DEBOUNCE_MS: u64 = 1000;
 pub(crate) const MAX_AUTO_SYNC_WAIT_MS: u64 = 10_000;
@@ -98,7 +98,7 @@ fn emit_auto_sync_status_updated(app: &AppHandle, status: &str, error: Option<&s
         }),
     };
 
-    if let Err(err) = app.emit("webdav-sync-status-updated", payload) {
+    if let Err(err) = crate::emit_event(app, "webdav-sync-status-updated", payload) {
         log::debug!("[WebDAV] failed to emit sync status update event: {err}");
     }
 }
@@ -148,7 +148,8 @@ pub fn notify_db_changed(table: &str) {
     let _ = enqueue_change_signal(tx, table);
 }
 
-pub fn start_worker(db: Arc<crate::database::Database>, app: tauri::AppHandle) {
+#[cfg(feature = "gui")]
+pub fn start_worker(db: Arc<crate::database::Database>, app: AppHandle) {
     if DB_CHANGE_TX.get().is_some() {
         return;
     }
@@ -167,7 +168,7 @@ pub fn start_worker(db: Arc<crate::database::Database>, app: tauri::AppHandle) {
 async fn run_worker_loop(
     db: Arc<crate::database::Database>,
     mut rx: Receiver<String>,
-    app: tauri::AppHandle,
+    app: AppHandle,
 ) {
     while let Some(first_table) = rx.recv().await {
         let started_at = Instant::now();
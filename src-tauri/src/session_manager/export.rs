@@ -0,0 +1,487 @@
+//! Session transcript export.
+//!
+//! Unlike `load_messages`, which flattens every message to plain text, transcripts keep
+//! tool calls, tool results and thinking blocks so they can be rendered as Markdown,
+//! a self-contained HTML page or provider-neutral JSONL.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::providers::{claude, codex, gemini, opencode};
+use super::{parse_session_file, SessionMeta};
+
+/// Version of the JSONL schema written by [`render_jsonl`]
+const JSONL_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionExportFormat {
+    Markdown,
+    Html,
+    Jsonl,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum TranscriptBlock {
+    Text {
+        text: String,
+    },
+    Thinking {
+        text: String,
+    },
+    ToolCall {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        name: String,
+        input: Value,
+    },
+    ToolResult {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        content: String,
+        is_error: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptMessage {
+    /// `user`, `assistant`, `system` or `tool` (a turn made only of tool results)
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ts: Option<i64>,
+    pub blocks: Vec<TranscriptBlock>,
+}
+
+pub fn load_transcript(
+    provider_id: &str,
+    source_path: &str,
+) -> Result<Vec<TranscriptMessage>, String> {
+    let path = Path::new(source_path);
+    match provider_id {
+        "codex" => codex::load_transcript(path),
+        "claude" => claude::load_transcript(path),
+        "gemini" => gemini::load_transcript(path),
+        "opencode" => opencode::load_transcript(path),
+        _ => Err(format!("Unsupported provider: {provider_id}")),
+    }
+}
+
+/// Renders a session in the given format; returns the document and the message count.
+pub fn export_session(
+    provider_id: &str,
+    source_path: &str,
+    format: SessionExportFormat,
+) -> Result<(String, usize), String> {
+    let messages = load_transcript(provider_id, source_path)?;
+    let meta = parse_session_file(provider_id, Path::new(source_path))
+        .ok_or_else(|| format!("Failed to read session: {source_path}"))?;
+    let document = match format {
+        SessionExportFormat::Markdown => render_markdown(&meta, &messages),
+        SessionExportFormat::Html => render_html(&meta, &messages),
+        SessionExportFormat::Jsonl => render_jsonl(&meta, &messages)?,
+    };
+    Ok((document, messages.len()))
+}
+
+pub fn render_markdown(meta: &SessionMeta, messages: &[TranscriptMessage]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n", session_title(meta));
+    for (label, value, code) in header_fields(meta) {
+        if code {
+            let _ = writeln!(out, "- **{label}:** `{value}`");
+        } else {
+            let _ = writeln!(out, "- **{label}:** {value}");
+        }
+    }
+
+    for message in messages {
+        let _ = write!(out, "\n## {}", role_label(&message.role));
+        if let Some(ts) = message.ts {
+            let _ = write!(out, " · {}", format_ts(ts));
+        }
+        out.push_str("\n\n");
+
+        for block in &message.blocks {
+            match block {
+                TranscriptBlock::Text { text } => {
+                    let _ = writeln!(out, "{}\n", text.trim_end());
+                }
+                TranscriptBlock::Thinking { text } => {
+                    let _ = writeln!(
+                        out,
+                        "<details>\n<summary>Thinking</summary>\n\n{}\n\n</details>\n",
+                        text.trim_end()
+                    );
+                }
+                TranscriptBlock::ToolCall { name, input, .. } => {
+                    let _ = writeln!(out, "**Tool call:** `{name}`\n");
+                    let lang = if input.is_string() { "text" } else { "json" };
+                    out.push_str(&fenced(lang, &format_input(input)));
+                }
+                TranscriptBlock::ToolResult {
+                    content, is_error, ..
+                } => {
+                    let label = if *is_error {
+                        "Tool error"
+                    } else {
+                        "Tool result"
+                    };
+                    let _ = writeln!(out, "**{label}:**\n");
+                    out.push_str(&fenced("text", content));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+pub fn render_html(meta: &SessionMeta, messages: &[TranscriptMessage]) -> String {
+    let title = escape_html(&session_title(meta));
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<main>\n<h1>{title}</h1>\n<dl>\n"
+    );
+    for (label, value, code) in header_fields(meta) {
+        let value = escape_html(&value);
+        if code {
+            let _ = writeln!(out, "<dt>{label}</dt><dd><code>{value}</code></dd>");
+        } else {
+            let _ = writeln!(out, "<dt>{label}</dt><dd>{value}</dd>");
+        }
+    }
+    out.push_str("</dl>\n");
+
+    for message in messages {
+        let role = escape_html(&message.role);
+        let _ = write!(
+            out,
+            "<section class=\"message {role}\">\n<header><span class=\"role\">{}</span>",
+            role_label(&message.role)
+        );
+        if let Some(ts) = message.ts {
+            let _ = write!(out, "<time>{}</time>", format_ts(ts));
+        }
+        out.push_str("</header>\n");
+
+        for block in &message.blocks {
+            match block {
+                TranscriptBlock::Text { text } => {
+                    let _ = writeln!(out, "<div class=\"text\">{}</div>", escape_html(text));
+                }
+                TranscriptBlock::Thinking { text } => {
+                    let _ = writeln!(
+                        out,
+                        "<details class=\"thinking\"><summary>Thinking</summary><div class=\"text\">{}</div></details>",
+                        escape_html(text)
+                    );
+                }
+                TranscriptBlock::ToolCall { name, input, .. } => {
+                    let _ = writeln!(
+                        out,
+                        "<details class=\"tool-call\" open><summary>Tool call: <code>{}</code></summary><pre>{}</pre></details>",
+                        escape_html(name),
+                        escape_html(&format_input(input))
+                    );
+                }
+                TranscriptBlock::ToolResult {
+                    content, is_error, ..
+                } => {
+                    let (class, label) = if *is_error {
+                        ("tool-result error", "Tool error")
+                    } else {
+                        ("tool-result", "Tool result")
+                    };
+                    let _ = writeln!(
+                        out,
+                        "<details class=\"{class}\"><summary>{label}</summary><pre>{}</pre></details>",
+                        escape_html(content)
+                    );
+                }
+            }
+        }
+        out.push_str("</section>\n");
+    }
+
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
+
+/// One `session` header line followed by one `message` line per message.
+pub fn render_jsonl(meta: &SessionMeta, messages: &[TranscriptMessage]) -> Result<String, String> {
+    let header = serde_json::json!({
+        "type": "session",
+        "schemaVersion": JSONL_SCHEMA_VERSION,
+        "providerId": meta.provider_id,
+        "sessionId": meta.session_id,
+        "title": meta.title,
+        "projectDir": meta.project_dir,
+        "createdAt": meta.created_at,
+        "lastActiveAt": meta.last_active_at,
+        "resumeCommand": meta.resume_command,
+    });
+
+    let mut out = String::new();
+    out.push_str(&header.to_string());
+    out.push('\n');
+    for message in messages {
+        let mut line = serde_json::to_value(message).map_err(|e| e.to_string())?;
+        if let Value::Object(map) = &mut line {
+            map.insert("type".to_string(), Value::String("message".to_string()));
+        }
+        out.push_str(&line.to_string());
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn session_title(meta: &SessionMeta) -> String {
+    meta.title
+        .clone()
+        .filter(|title| !title.trim().is_empty())
+        .unwrap_or_else(|| format!("{} session {}", meta.provider_id, meta.session_id))
+}
+
+/// (label, value, rendered as code)
+fn header_fields(meta: &SessionMeta) -> Vec<(&'static str, String, bool)> {
+    let mut fields = vec![
+        ("Provider", meta.provider_id.clone(), false),
+        ("Session", meta.session_id.clone(), true),
+    ];
+    if let Some(dir) = &meta.project_dir {
+        fields.push(("Project", dir.clone(), true));
+    }
+    if let Some(ts) = meta.created_at {
+        fields.push(("Started", format_ts(ts), false));
+    }
+    if let Some(ts) = meta.last_active_at {
+        fields.push(("Last active", format_ts(ts), false));
+    }
+    if let Some(command) = &meta.resume_command {
+        fields.push(("Resume", command.clone(), true));
+    }
+    fields
+}
+
+fn role_label(role: &str) -> &str {
+    match role {
+        "user" => "User",
+        "assistant" => "Assistant",
+        "system" | "developer" => "System",
+        "tool" => "Tool",
+        other => other,
+    }
+}
+
+fn format_ts(ts: i64) -> String {
+    Local
+        .timestamp_millis_opt(ts)
+        .single()
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
+fn format_input(input: &Value) -> String {
+    match input {
+        Value::String(text) => text.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_else(|_| other.to_string()),
+    }
+}
+
+/// Fences `content` with a backtick run longer than any it contains.
+fn fenced(lang: &str, content: &str) -> String {
+    let mut longest = 0;
+    let mut current = 0;
+    for c in content.chars() {
+        if c == '`' {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{fence}{lang}\n{}\n{fence}\n\n", content.trim_end())
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const HTML_STYLE: &str = "\
+body{margin:0;background:#f6f7f9;color:#1f2328;font:14px/1.6 -apple-system,BlinkMacSystemFont,\"Segoe UI\",sans-serif}\
+main{max-width:900px;margin:0 auto;padding:24px}\
+h1{font-size:22px;margin:0 0 12px}\
+dl{display:grid;grid-template-columns:max-content 1fr;gap:2px 12px;margin:0 0 24px;color:#57606a}\
+dt{font-weight:600}dd{margin:0;word-break:break-all}\
+.message{background:#fff;border:1px solid #d0d7de;border-radius:8px;padding:12px 16px;margin:12px 0}\
+.message.user{border-left:4px solid #1a7f37}.message.assistant{border-left:4px solid #0969da}\
+.message.tool{border-left:4px solid #8250df}\
+header{display:flex;gap:12px;align-items:baseline;margin-bottom:8px}\
+.role{font-weight:600}time{color:#57606a;font-size:12px}\
+.text{white-space:pre-wrap;word-wrap:break-word}\
+details{margin:8px 0;border:1px solid #d0d7de;border-radius:6px;padding:6px 10px;background:#f6f8fa}\
+summary{cursor:pointer;color:#57606a}\
+.tool-result.error{border-color:#cf222e}\
+pre{margin:8px 0 0;white-space:pre-wrap;word-wrap:break-word;font:12px/1.5 ui-monospace,SFMono-Regular,Menlo,monospace}\
+@media (prefers-color-scheme:dark){body{background:#0d1117;color:#e6edf3}\
+.message{background:#161b22;border-color:#30363d}details{background:#0d1117;border-color:#30363d}\
+dl,time,summary{color:#8b949e}}";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> (SessionMeta, Vec<TranscriptMessage>) {
+        let meta = SessionMeta {
+            provider_id: "claude".to_string(),
+            session_id: "abc".to_string(),
+            title: Some("api".to_string()),
+            summary: None,
+            project_dir: Some("/home/me/api".to_string()),
+            created_at: None,
+            last_active_at: None,
+            source_path: None,
+            resume_command: Some("claude --resume abc".to_string()),
+            usage: None,
+        };
+        let messages = vec![
+            TranscriptMessage {
+                role: "assistant".to_string(),
+                ts: None,
+                blocks: vec![
+                    TranscriptBlock::Thinking {
+                        text: "check <config>".to_string(),
+                    },
+                    TranscriptBlock::ToolCall {
+                        id: Some("t1".to_string()),
+                        name: "Bash".to_string(),
+                        input: json!({"command": "ls"}),
+                    },
+                ],
+            },
+            TranscriptMessage {
+                role: "tool".to_string(),
+                ts: None,
+                blocks: vec![TranscriptBlock::ToolResult {
+                    id: Some("t1".to_string()),
+                    content: "```\nREADME.md".to_string(),
+                    is_error: false,
+                }],
+            },
+        ];
+        (meta, messages)
+    }
+
+    #[test]
+    fn renders_all_formats() {
+        let (meta, messages) = sample();
+
+        let markdown = render_markdown(&meta, &messages);
+        assert!(markdown.starts_with("# api\n"));
+        assert!(markdown.contains("<summary>Thinking</summary>"));
+        assert!(markdown.contains("**Tool call:** `Bash`"));
+        // Fence grows past backticks inside the tool output
+        assert!(markdown.contains("````text\n```\nREADME.md\n````"));
+
+        let html = render_html(&meta, &messages);
+        assert!(html.contains("check &lt;config&gt;"));
+        assert!(html.contains("<section class=\"message tool\">"));
+
+        let jsonl = render_jsonl(&meta, &messages).unwrap();
+        let lines: Vec<Value> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["type"], "session");
+        assert_eq!(lines[0]["schemaVersion"], 1);
+        assert_eq!(lines[1]["type"], "message");
+        assert_eq!(lines[1]["blocks"][1]["type"], "toolCall");
+        assert_eq!(lines[2]["blocks"][0]["isError"], false);
+    }
+
+    #[test]
+    fn loads_tool_calls_and_thinking() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let claude_path = dir.path().join("claude.jsonl");
+        let claude_lines = [
+            json!({"message": {"role": "assistant", "content": [
+                {"type": "thinking", "thinking": "look at the tests"},
+                {"type": "tool_use", "id": "t1", "name": "Bash", "input": {"command": "cargo test"}}
+            ]}}),
+            json!({"message": {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "t1", "is_error": true,
+                 "content": [{"type": "text", "text": "1 failed"}]}
+            ]}}),
+        ];
+        std::fs::write(
+            &claude_path,
+            claude_lines.map(|line| line.to_string()).join("\n"),
+        )
+        .unwrap();
+        let transcript = load_transcript("claude", claude_path.to_str().unwrap()).unwrap();
+        assert_eq!(transcript.len(), 2);
+        assert_eq!(
+            transcript[0].blocks[0],
+            TranscriptBlock::Thinking {
+                text: "look at the tests".to_string()
+            }
+        );
+        assert_eq!(transcript[1].role, "tool");
+        assert_eq!(
+            transcript[1].blocks[0],
+            TranscriptBlock::ToolResult {
+                id: Some("t1".to_string()),
+                content: "1 failed".to_string(),
+                is_error: true,
+            }
+        );
+
+        let codex_path = dir.path().join("codex.jsonl");
+        let codex_lines = [
+            json!({"type": "response_item", "payload": {"type": "function_call", "name": "shell",
+                "call_id": "c1", "arguments": "{\"command\":[\"ls\"]}"}}),
+            json!({"type": "response_item", "payload": {"type": "function_call_output",
+                "call_id": "c1", "output": "{\"output\":\"src\\n\",\"metadata\":{}}"}}),
+        ];
+        std::fs::write(
+            &codex_path,
+            codex_lines.map(|line| line.to_string()).join("\n"),
+        )
+        .unwrap();
+        let transcript = load_transcript("codex", codex_path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            transcript[0].blocks[0],
+            TranscriptBlock::ToolCall {
+                id: Some("c1".to_string()),
+                name: "shell".to_string(),
+                input: json!({"command": ["ls"]}),
+            }
+        );
+        assert_eq!(transcript[1].role, "tool");
+        assert!(matches!(
+            &transcript[1].blocks[0],
+            TranscriptBlock::ToolResult { content, .. } if content == "src\n"
+        ));
+    }
+}
@@ -0,0 +1,309 @@
+//! Persistent full-text index over local agent sessions.
+//!
+//! Session metadata and message text are stored in the app database (FTS5 with the
+//! trigram tokenizer, so CJK text and partial words match). Files are re-parsed only
+//! when their mtime or size changes; files that disappear are dropped from the index.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::error::AppError;
+
+use super::{list_session_files, load_messages, parse_session_file, SessionMeta};
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
+const MAX_SNIPPETS: usize = 3;
+const SNIPPET_BEFORE: usize = 40;
+const SNIPPET_AFTER: usize = 100;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchQuery {
+    pub query: String,
+    #[serde(default)]
+    pub provider_id: Option<String>,
+    /// Matches the project dir itself and everything below it
+    #[serde(default)]
+    pub project_dir: Option<String>,
+    /// Epoch milliseconds; sessions active within the range match
+    #[serde(default)]
+    pub start_time: Option<i64>,
+    #[serde(default)]
+    pub end_time: Option<i64>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchSnippet {
+    pub role: String,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ts: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchHit {
+    pub session: SessionMeta,
+    /// Higher is better (negated FTS5 bm25 of the best matching message)
+    pub score: f64,
+    pub match_count: u32,
+    pub snippets: Vec<SessionSearchSnippet>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionIndexReport {
+    pub indexed: u32,
+    pub unchanged: u32,
+    pub removed: u32,
+}
+
+/// Brings the index up to date with the session files on disk.
+pub fn sync_session_index(db: &Database) -> Result<SessionIndexReport, AppError> {
+    sync_files(db, list_session_files())
+}
+
+fn sync_files(
+    db: &Database,
+    files: Vec<(&'static str, PathBuf)>,
+) -> Result<SessionIndexReport, AppError> {
+    let known = db.get_session_index_states()?;
+    let mut report = SessionIndexReport::default();
+    let mut seen = HashSet::new();
+
+    for (provider_id, path) in files {
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        let source_path = path.to_string_lossy().to_string();
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_millis() as i64)
+            .unwrap_or(0);
+        let size = metadata.len() as i64;
+
+        if known.get(&source_path) == Some(&(mtime, size)) {
+            seen.insert(source_path);
+            report.unchanged += 1;
+            continue;
+        }
+
+        let Some(meta) = parse_session_file(provider_id, &path) else {
+            continue;
+        };
+        let messages = match load_messages(provider_id, &source_path) {
+            Ok(messages) => messages,
+            Err(e) => {
+                log::debug!("Skipping session {source_path} in search index: {e}");
+                continue;
+            }
+        };
+        db.replace_session_index_entry(&source_path, &meta, mtime, size, &messages)?;
+        seen.insert(source_path);
+        report.indexed += 1;
+    }
+
+    let stale: Vec<String> = known
+        .into_keys()
+        .filter(|path| !seen.contains(path))
+        .collect();
+    report.removed = db.remove_session_index_entries(&stale)?;
+    Ok(report)
+}
+
+/// Searches indexed messages and returns matching sessions, best match first.
+pub fn search_sessions(
+    db: &Database,
+    query: &SessionSearchQuery,
+) -> Result<Vec<SessionSearchHit>, AppError> {
+    let terms = split_terms(&query.query);
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut hits: Vec<SessionSearchHit> = Vec::new();
+    for row in db.search_session_index_messages(query, &terms)? {
+        let score = -row.rank;
+        let position = hits
+            .iter()
+            .position(|hit| hit.session.source_path.as_deref() == Some(row.source_path.as_str()));
+        let hit = match position {
+            Some(index) => &mut hits[index],
+            None => {
+                hits.push(SessionSearchHit {
+                    session: row.session,
+                    score,
+                    match_count: 0,
+                    snippets: Vec::new(),
+                });
+                hits.last_mut().expect("hit was just pushed")
+            }
+        };
+
+        hit.match_count += 1;
+        hit.score = hit.score.max(score);
+        if hit.snippets.len() < MAX_SNIPPETS {
+            hit.snippets.push(SessionSearchSnippet {
+                role: row.role,
+                text: make_snippet(&row.content, &terms),
+                ts: row.ts,
+            });
+        }
+    }
+
+    hits.sort_by(|a, b| {
+        b.score.total_cmp(&a.score).then_with(|| {
+            let a_ts = a.session.last_active_at.or(a.session.created_at);
+            let b_ts = b.session.last_active_at.or(b.session.created_at);
+            b_ts.cmp(&a_ts)
+        })
+    });
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT) as usize;
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+/// Splits the query on whitespace; every term must match (AND).
+fn split_terms(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .map(|term| term.trim_matches('"').to_string())
+        .filter(|term| !term.is_empty())
+        .collect()
+}
+
+/// Cuts a window around the first matching term, collapsing whitespace.
+fn make_snippet(content: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let lowered: Vec<char> = chars.iter().map(|c| lower_char(*c)).collect();
+
+    let first_match = terms
+        .iter()
+        .filter_map(|term| {
+            let needle: Vec<char> = term.chars().map(lower_char).collect();
+            lowered
+                .windows(needle.len())
+                .position(|window| window == needle.as_slice())
+        })
+        .min()
+        .unwrap_or(0);
+
+    let start = first_match.saturating_sub(SNIPPET_BEFORE);
+    let end = (first_match + SNIPPET_AFTER).min(chars.len());
+    let text: String = chars[start..end].iter().collect();
+    let mut snippet = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+fn lower_char(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write_claude_session(path: &std::path::Path, session_id: &str, lines: &[(&str, &str)]) {
+        let content = lines
+            .iter()
+            .enumerate()
+            .map(|(i, (role, text))| {
+                json!({
+                    "sessionId": session_id,
+                    "cwd": "/home/me/api",
+                    "timestamp": format!("2026-02-0{}T08:00:00Z", i + 1),
+                    "message": {"role": role, "content": text}
+                })
+                .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn indexes_incrementally_and_ranks_matches() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("a.jsonl");
+        let second = dir.path().join("b.jsonl");
+        write_claude_session(
+            &first,
+            "s1",
+            &[
+                ("user", "the migration fails on startup"),
+                ("assistant", "Fixed the migration bug in schema.rs"),
+            ],
+        );
+        write_claude_session(&second, "s2", &[("user", "修复数据库迁移问题")]);
+        let files = || vec![("claude", first.clone()), ("claude", second.clone())];
+
+        let report = sync_files(&db, files())?;
+        assert_eq!(report.indexed, 2);
+        assert_eq!(sync_files(&db, files())?.unchanged, 2);
+
+        let search = |text: &str| {
+            search_sessions(
+                &db,
+                &SessionSearchQuery {
+                    query: text.to_string(),
+                    ..Default::default()
+                },
+            )
+        };
+        let hits = search("migration BUG")?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session.session_id, "s1");
+        assert_eq!(
+            hits[0].snippets[0].text,
+            "Fixed the migration bug in schema.rs"
+        );
+        assert_eq!(search("migration")?[0].match_count, 2);
+        // Terms shorter than a trigram still match
+        assert_eq!(search("迁移")?[0].session.session_id, "s2");
+
+        let filtered = search_sessions(
+            &db,
+            &SessionSearchQuery {
+                query: "migration".to_string(),
+                provider_id: Some("codex".to_string()),
+                ..Default::default()
+            },
+        )?;
+        assert!(filtered.is_empty());
+
+        // Rewritten files are re-indexed, deleted ones dropped
+        write_claude_session(&first, "s1", &[("user", "rename the config loader")]);
+        std::fs::remove_file(&second).unwrap();
+        let report = sync_files(&db, vec![("claude", first.clone())])?;
+        assert_eq!((report.indexed, report.removed), (1, 1));
+        assert!(search("migration")?.is_empty());
+        assert_eq!(search("loader")?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn snippet_centers_on_first_match() {
+        let content = format!("{}needle here\n\nand more", "x ".repeat(60));
+        let snippet = make_snippet(&content, &["NEEDLE".to_string()]);
+        assert!(snippet.starts_with('…'));
+        assert!(snippet.contains("needle here and more"));
+    }
+}
@@ -1,10 +1,13 @@
+pub mod export;
+pub mod index;
 pub mod providers;
 pub mod terminal;
 
 use serde::Serialize;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use providers::{claude, codex};
+use crate::services::session_usage::SessionUsage;
+use providers::{claude, codex, gemini, opencode};
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -25,6 +28,9 @@ pub struct SessionMeta {
     pub source_path: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub resume_command: Option<String>,
+    /// Proxy usage attributed to this session (filled in by the caller, never by providers)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<SessionUsage>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -40,6 +46,8 @@ pub fn scan_sessions() -> Vec<SessionMeta> {
     let mut sessions = Vec::new();
     sessions.extend(codex::scan_sessions());
     sessions.extend(claude::scan_sessions());
+    sessions.extend(gemini::scan_sessions());
+    sessions.extend(opencode::scan_sessions());
 
     sessions.sort_by(|a, b| {
         let a_ts = a.last_active_at.or(a.created_at).unwrap_or(0);
@@ -55,6 +63,32 @@ pub fn load_messages(provider_id: &str, source_path: &str) -> Result<Vec<Session
     match provider_id {
         "codex" => codex::load_messages(path),
         "claude" => claude::load_messages(path),
+        "gemini" => gemini::load_messages(path),
+        "opencode" => opencode::load_messages(path),
         _ => Err(format!("Unsupported provider: {provider_id}")),
     }
 }
+
+/// Lists every session file with its provider id, without parsing the files.
+pub fn list_session_files() -> Vec<(&'static str, PathBuf)> {
+    let mut files = Vec::new();
+    files.extend(codex::session_files().into_iter().map(|p| ("codex", p)));
+    files.extend(claude::session_files().into_iter().map(|p| ("claude", p)));
+    files.extend(gemini::session_files().into_iter().map(|p| ("gemini", p)));
+    files.extend(
+        opencode::session_files()
+            .into_iter()
+            .map(|p| ("opencode", p)),
+    );
+    files
+}
+
+pub fn parse_session_file(provider_id: &str, path: &Path) -> Option<SessionMeta> {
+    match provider_id {
+        "codex" => codex::parse_session(path),
+        "claude" => claude::parse_session(path),
+        "gemini" => gemini::parse_session_file(path),
+        "opencode" => opencode::parse_session_file(path),
+        _ => None,
+    }
+}
@@ -5,19 +5,18 @@ use std::path::{Path, PathBuf};
 use serde_json::Value;
 
 use crate::config::get_claude_config_dir;
+use crate::session_manager::export::{TranscriptBlock, TranscriptMessage};
 use crate::session_manager::{SessionMessage, SessionMeta};
 
-use super::utils::{extract_text, parse_timestamp_to_ms, path_basename, truncate_summary};
+use super::utils::{
+    extract_text, parse_timestamp_to_ms, path_basename, tool_output_text, truncate_summary,
+};
 
 const PROVIDER_ID: &str = "claude";
 
 pub fn scan_sessions() -> Vec<SessionMeta> {
-    let root = get_claude_config_dir().join("projects");
-    let mut files = Vec::new();
-    collect_jsonl_files(&root, &mut files);
-
     let mut sessions = Vec::new();
-    for path in files {
+    for path in session_files() {
         if let Some(meta) = parse_session(&path) {
             sessions.push(meta);
         }
@@ -26,6 +25,13 @@ pub fn scan_sessions() -> Vec<SessionMeta> {
     sessions
 }
 
+pub fn session_files() -> Vec<PathBuf> {
+    let root = get_claude_config_dir().join("projects");
+    let mut files = Vec::new();
+    collect_jsonl_files(&root, &mut files);
+    files
+}
+
 pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
     let file = File::open(path).map_err(|e| format!("Failed to open session file: {e}"))?;
     let reader = BufReader::new(file);
@@ -68,7 +74,98 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
     Ok(messages)
 }
 
-fn parse_session(path: &Path) -> Option<SessionMeta> {
+pub fn load_transcript(path: &Path) -> Result<Vec<TranscriptMessage>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open session file: {e}"))?;
+    let reader = BufReader::new(file);
+    let mut messages = Vec::new();
+
+    for line in reader.lines() {
+        let line = match line {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        let value: Value = match serde_json::from_str(&line) {
+            Ok(parsed) => parsed,
+            Err(_) => continue,
+        };
+
+        if value.get("isMeta").and_then(Value::as_bool) == Some(true) {
+            continue;
+        }
+
+        let message = match value.get("message") {
+            Some(message) => message,
+            None => continue,
+        };
+
+        let blocks: Vec<TranscriptBlock> = match message.get("content") {
+            Some(Value::String(text)) if !text.trim().is_empty() => {
+                vec![TranscriptBlock::Text { text: text.clone() }]
+            }
+            Some(Value::Array(items)) => items.iter().filter_map(transcript_block).collect(),
+            _ => Vec::new(),
+        };
+        if blocks.is_empty() {
+            continue;
+        }
+
+        // Tool results are sent back as user messages
+        let only_results = blocks
+            .iter()
+            .all(|block| matches!(block, TranscriptBlock::ToolResult { .. }));
+        let role = if only_results {
+            "tool".to_string()
+        } else {
+            message
+                .get("role")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown")
+                .to_string()
+        };
+
+        messages.push(TranscriptMessage {
+            role,
+            ts: value.get("timestamp").and_then(parse_timestamp_to_ms),
+            blocks,
+        });
+    }
+
+    Ok(messages)
+}
+
+fn transcript_block(item: &Value) -> Option<TranscriptBlock> {
+    let text_field = |key: &str| {
+        item.get(key)
+            .and_then(Value::as_str)
+            .filter(|text| !text.trim().is_empty())
+            .map(|text| text.to_string())
+    };
+
+    match item.get("type").and_then(Value::as_str)? {
+        "text" => Some(TranscriptBlock::Text {
+            text: text_field("text")?,
+        }),
+        "thinking" => Some(TranscriptBlock::Thinking {
+            text: text_field("thinking")?,
+        }),
+        "tool_use" => Some(TranscriptBlock::ToolCall {
+            id: text_field("id"),
+            name: text_field("name").unwrap_or_else(|| "tool".to_string()),
+            input: item.get("input").cloned().unwrap_or(Value::Null),
+        }),
+        "tool_result" => Some(TranscriptBlock::ToolResult {
+            id: text_field("tool_use_id"),
+            content: item
+                .get("content")
+                .map(tool_output_text)
+                .unwrap_or_default(),
+            is_error: item.get("is_error").and_then(Value::as_bool) == Some(true),
+        }),
+        _ => None,
+    }
+}
+
+pub fn parse_session(path: &Path) -> Option<SessionMeta> {
     if is_agent_session(path) {
         return None;
     }
@@ -149,6 +246,7 @@ fn parse_session(path: &Path) -> Option<SessionMeta> {
         last_active_at,
         source_path: Some(path.to_string_lossy().to_string()),
         resume_command: Some(format!("claude --resume {session_id}")),
+        usage: None,
     })
 }
 
@@ -6,19 +6,18 @@ use regex::Regex;
 use serde_json::Value;
 
 use crate::codex_config::get_codex_config_dir;
+use crate::session_manager::export::{TranscriptBlock, TranscriptMessage};
 use crate::session_manager::{SessionMessage, SessionMeta};
 
-use super::utils::{extract_text, parse_timestamp_to_ms, path_basename, truncate_summary};
+use super::utils::{
+    extract_text, parse_timestamp_to_ms, path_basename, tool_output_text, truncate_summary,
+};
 
 const PROVIDER_ID: &str = "codex";
 
 pub fn scan_sessions() -> Vec<SessionMeta> {
-    let root = get_codex_config_dir().join("sessions");
-    let mut files = Vec::new();
-    collect_jsonl_files(&root, &mut files);
-
     let mut sessions = Vec::new();
-    for path in files {
+    for path in session_files() {
         if let Some(meta) = parse_session(&path) {
             sessions.push(meta);
         }
@@ -27,6 +26,13 @@ pub fn scan_sessions() -> Vec<SessionMeta> {
     sessions
 }
 
+pub fn session_files() -> Vec<PathBuf> {
+    let root = get_codex_config_dir().join("sessions");
+    let mut files = Vec::new();
+    collect_jsonl_files(&root, &mut files);
+    files
+}
+
 pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
     let file = File::open(path).map_err(|e| format!("Failed to open session file: {e}"))?;
     let reader = BufReader::new(file);
@@ -73,7 +79,124 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
     Ok(messages)
 }
 
-fn parse_session(path: &Path) -> Option<SessionMeta> {
+pub fn load_transcript(path: &Path) -> Result<Vec<TranscriptMessage>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open session file: {e}"))?;
+    let reader = BufReader::new(file);
+    let mut messages = Vec::new();
+
+    for line in reader.lines() {
+        let line = match line {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        let value: Value = match serde_json::from_str(&line) {
+            Ok(parsed) => parsed,
+            Err(_) => continue,
+        };
+
+        if value.get("type").and_then(Value::as_str) != Some("response_item") {
+            continue;
+        }
+        let payload = match value.get("payload") {
+            Some(payload) => payload,
+            None => continue,
+        };
+
+        if let Some((role, block)) = transcript_item(payload) {
+            messages.push(TranscriptMessage {
+                role: role.to_string(),
+                ts: value.get("timestamp").and_then(parse_timestamp_to_ms),
+                blocks: vec![block],
+            });
+        }
+    }
+
+    Ok(messages)
+}
+
+fn transcript_item(payload: &Value) -> Option<(&str, TranscriptBlock)> {
+    let str_field = |key: &str| {
+        payload
+            .get(key)
+            .and_then(Value::as_str)
+            .map(|s| s.to_string())
+    };
+
+    match payload.get("type").and_then(Value::as_str)? {
+        "message" => {
+            let text = payload.get("content").map(extract_text).unwrap_or_default();
+            if text.trim().is_empty() {
+                return None;
+            }
+            let role = payload
+                .get("role")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown");
+            Some((role, TranscriptBlock::Text { text }))
+        }
+        // Only the reasoning summary is stored in clear text
+        "reasoning" => {
+            let text = payload.get("summary").map(extract_text).unwrap_or_default();
+            if text.trim().is_empty() {
+                return None;
+            }
+            Some(("assistant", TranscriptBlock::Thinking { text }))
+        }
+        "function_call" | "custom_tool_call" => {
+            let raw = payload
+                .get("arguments")
+                .or_else(|| payload.get("input"))
+                .cloned()
+                .unwrap_or(Value::Null);
+            // function_call arguments are a JSON document encoded as a string
+            let input = match &raw {
+                Value::String(text) => serde_json::from_str(text).unwrap_or(raw),
+                _ => raw,
+            };
+            Some((
+                "assistant",
+                TranscriptBlock::ToolCall {
+                    id: str_field("call_id"),
+                    name: str_field("name").unwrap_or_else(|| "tool".to_string()),
+                    input,
+                },
+            ))
+        }
+        "local_shell_call" => Some((
+            "assistant",
+            TranscriptBlock::ToolCall {
+                id: str_field("call_id"),
+                name: "shell".to_string(),
+                input: payload.get("action").cloned().unwrap_or(Value::Null),
+            },
+        )),
+        "function_call_output" | "custom_tool_call_output" | "local_shell_call_output" => {
+            let output = payload.get("output").cloned().unwrap_or(Value::Null);
+            // Shell results are wrapped as {"output": "...", "metadata": {...}}
+            let output = match &output {
+                Value::String(text) => serde_json::from_str::<Value>(text)
+                    .ok()
+                    .and_then(|parsed| parsed.get("output").cloned())
+                    .unwrap_or(output),
+                _ => output,
+            };
+            // Structured outputs carry {"content": ..., "success": bool}
+            let is_error = output.get("success").and_then(Value::as_bool) == Some(false);
+            let content = output.get("content").unwrap_or(&output);
+            Some((
+                "tool",
+                TranscriptBlock::ToolResult {
+                    id: str_field("call_id"),
+                    content: tool_output_text(content),
+                    is_error,
+                },
+            ))
+        }
+        _ => None,
+    }
+}
+
+pub fn parse_session(path: &Path) -> Option<SessionMeta> {
     let file = File::open(path).ok()?;
     let reader = BufReader::new(file);
 
@@ -161,6 +284,7 @@ fn parse_session(path: &Path) -> Option<SessionMeta> {
         last_active_at,
         source_path: Some(path.to_string_lossy().to_string()),
         resume_command: Some(format!("codex resume {session_id}")),
+        usage: None,
     })
 }
 
@@ -0,0 +1,380 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::gemini_config::get_gemini_dir;
+use crate::session_manager::export::{TranscriptBlock, TranscriptMessage};
+use crate::session_manager::{SessionMessage, SessionMeta};
+
+use super::utils::{
+    extract_text, parse_timestamp_to_ms, path_basename, read_dir_paths, read_json,
+    tool_output_text, truncate_summary,
+};
+
+const PROVIDER_ID: &str = "gemini";
+
+/// Gemini CLI stores one JSON file per chat in `~/.gemini/tmp/<project>/chats/`.
+pub fn scan_sessions() -> Vec<SessionMeta> {
+    scan_sessions_in(&get_gemini_dir())
+}
+
+fn scan_sessions_in(gemini_dir: &Path) -> Vec<SessionMeta> {
+    let tmp_root = gemini_dir.join("tmp");
+    let entries = match std::fs::read_dir(&tmp_root) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let registry = read_project_registry(gemini_dir);
+
+    let mut sessions = Vec::new();
+    for entry in entries.flatten() {
+        let project_tmp = entry.path();
+        let chats_dir = project_tmp.join("chats");
+        let chats = match std::fs::read_dir(&chats_dir) {
+            Ok(chats) => chats,
+            Err(_) => continue,
+        };
+
+        let project_dir = resolve_project_dir(&project_tmp, &registry);
+        for chat in chats.flatten() {
+            let path = chat.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            if let Some(meta) = parse_session(&path, project_dir.clone()) {
+                sessions.push(meta);
+            }
+        }
+    }
+
+    sessions
+}
+
+pub fn session_files() -> Vec<PathBuf> {
+    let tmp_root = get_gemini_dir().join("tmp");
+    let mut files = Vec::new();
+    for project_tmp in read_dir_paths(&tmp_root) {
+        for path in read_dir_paths(&project_tmp.join("chats")) {
+            if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+/// Parses a single chat file, resolving the project from its `tmp/<project>` dir.
+pub fn parse_session_file(path: &Path) -> Option<SessionMeta> {
+    let project_tmp = path.parent()?.parent()?;
+    let gemini_dir = project_tmp.parent()?.parent()?;
+    let registry = read_project_registry(gemini_dir);
+    parse_session(path, resolve_project_dir(project_tmp, &registry))
+}
+
+pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
+    let value = read_json(path).map_err(|e| format!("Failed to open session file: {e}"))?;
+    let messages = value
+        .get("messages")
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(parse_message).collect())
+        .unwrap_or_default();
+    Ok(messages)
+}
+
+pub fn load_transcript(path: &Path) -> Result<Vec<TranscriptMessage>, String> {
+    let value = read_json(path).map_err(|e| format!("Failed to open session file: {e}"))?;
+    let items = value
+        .get("messages")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut messages = Vec::new();
+    for item in items {
+        let role = match item.get("type").and_then(Value::as_str) {
+            Some("user") => "user",
+            Some("gemini") => "assistant",
+            _ => continue,
+        };
+
+        let mut blocks = Vec::new();
+        for thought in item
+            .get("thoughts")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let subject = thought.get("subject").and_then(Value::as_str).unwrap_or("");
+            let description = thought
+                .get("description")
+                .and_then(Value::as_str)
+                .unwrap_or("");
+            let text = [subject, description]
+                .into_iter()
+                .filter(|part| !part.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            if !text.is_empty() {
+                blocks.push(TranscriptBlock::Thinking { text });
+            }
+        }
+
+        let text = item.get("content").map(extract_text).unwrap_or_default();
+        if !text.trim().is_empty() {
+            blocks.push(TranscriptBlock::Text { text });
+        }
+
+        for call in item
+            .get("toolCalls")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let id = call
+                .get("id")
+                .and_then(Value::as_str)
+                .map(|s| s.to_string());
+            blocks.push(TranscriptBlock::ToolCall {
+                id: id.clone(),
+                name: call
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("tool")
+                    .to_string(),
+                input: call.get("args").cloned().unwrap_or(Value::Null),
+            });
+            if call.get("result").is_some() || call.get("resultDisplay").is_some() {
+                blocks.push(TranscriptBlock::ToolResult {
+                    id,
+                    content: tool_call_result(call),
+                    is_error: call.get("status").and_then(Value::as_str) == Some("error"),
+                });
+            }
+        }
+
+        if !blocks.is_empty() {
+            messages.push(TranscriptMessage {
+                role: role.to_string(),
+                ts: item.get("timestamp").and_then(parse_timestamp_to_ms),
+                blocks,
+            });
+        }
+    }
+
+    Ok(messages)
+}
+
+/// Prefers the text shown in the CLI (`resultDisplay`, or the diff for edits) over the
+/// raw function response sent back to the model.
+fn tool_call_result(call: &Value) -> String {
+    match call.get("resultDisplay") {
+        Some(Value::String(text)) if !text.trim().is_empty() => return text.clone(),
+        Some(display) => {
+            if let Some(diff) = display.get("fileDiff").and_then(Value::as_str) {
+                return diff.to_string();
+            }
+        }
+        None => {}
+    }
+
+    let Some(result) = call.get("result") else {
+        return String::new();
+    };
+    let responses: Vec<String> = result
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|part| part.get("functionResponse")?.get("response"))
+        .filter_map(|response| response.get("output").or_else(|| response.get("error")))
+        .map(tool_output_text)
+        .collect();
+    if responses.is_empty() {
+        tool_output_text(result)
+    } else {
+        responses.join("\n")
+    }
+}
+
+fn parse_message(item: &Value) -> Option<SessionMessage> {
+    // "info" / "error" / "warning" entries are CLI notices, not conversation turns
+    let role = match item.get("type").and_then(Value::as_str)? {
+        "user" => "user",
+        "gemini" => "assistant",
+        _ => return None,
+    };
+    let content = item.get("content").map(extract_text).unwrap_or_default();
+    if content.trim().is_empty() {
+        return None;
+    }
+
+    Some(SessionMessage {
+        role: role.to_string(),
+        content,
+        ts: item.get("timestamp").and_then(parse_timestamp_to_ms),
+    })
+}
+
+fn parse_session(path: &Path, project_dir: Option<String>) -> Option<SessionMeta> {
+    let value = read_json(path).ok()?;
+    let session_id = value.get("sessionId").and_then(Value::as_str)?.to_string();
+
+    let messages: Vec<SessionMessage> = value
+        .get("messages")
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(parse_message).collect())
+        .unwrap_or_default();
+    // Gemini CLI writes the chat file before the first prompt is sent
+    if messages.is_empty() {
+        return None;
+    }
+
+    let created_at = value
+        .get("startTime")
+        .and_then(parse_timestamp_to_ms)
+        .or_else(|| messages.first().and_then(|m| m.ts));
+    let last_active_at = value
+        .get("lastUpdated")
+        .and_then(parse_timestamp_to_ms)
+        .or_else(|| messages.last().and_then(|m| m.ts));
+
+    let summary = value
+        .get("summary")
+        .and_then(Value::as_str)
+        .filter(|text| !text.trim().is_empty())
+        .map(|text| text.to_string())
+        .or_else(|| messages.last().map(|m| m.content.clone()))
+        .map(|text| truncate_summary(&text, 160));
+
+    let title = project_dir
+        .as_deref()
+        .and_then(path_basename)
+        .map(|value| value.to_string());
+
+    Some(SessionMeta {
+        provider_id: PROVIDER_ID.to_string(),
+        session_id: session_id.clone(),
+        title,
+        summary,
+        project_dir,
+        created_at,
+        last_active_at,
+        source_path: Some(path.to_string_lossy().to_string()),
+        resume_command: Some(format!("gemini --resume {session_id}")),
+        usage: None,
+    })
+}
+
+/// Project temp dirs are named by a hash (or short id) of the project root, so the
+/// root is recovered from the `.project_root` marker or the `projects.json` registry.
+fn resolve_project_dir(project_tmp: &Path, registry: &[(String, String)]) -> Option<String> {
+    if let Ok(root) = std::fs::read_to_string(project_tmp.join(".project_root")) {
+        let root = root.trim();
+        if !root.is_empty() {
+            return Some(root.to_string());
+        }
+    }
+
+    let dir_name = project_tmp.file_name()?.to_str()?;
+    registry
+        .iter()
+        .find(|(_, id)| id == dir_name)
+        .map(|(root, _)| root.clone())
+}
+
+/// Reads `projects.json` (`{"projects": {"<root>": "<id>"}}`) as (root, id) pairs.
+fn read_project_registry(gemini_dir: &Path) -> Vec<(String, String)> {
+    let Ok(value) = read_json(&gemini_dir.join("projects.json")) else {
+        return Vec::new();
+    };
+    value
+        .get("projects")
+        .and_then(Value::as_object)
+        .map(|projects| {
+            projects
+                .iter()
+                .filter_map(|(root, id)| Some((root.clone(), id.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_gemini_chat_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let gemini_dir = dir.path();
+        let chats = gemini_dir.join("tmp/abc123/chats");
+        std::fs::create_dir_all(&chats).unwrap();
+        std::fs::write(
+            gemini_dir.join("tmp/abc123/.project_root"),
+            "/home/me/webapp\n",
+        )
+        .unwrap();
+
+        let chat = json!({
+            "sessionId": "5f0c7d2e-1111-2222-3333-444455556666",
+            "projectHash": "abc123",
+            "startTime": "2026-03-01T10:00:00.000Z",
+            "lastUpdated": "2026-03-01T10:05:00.000Z",
+            "messages": [
+                {"type": "user", "content": "fix the build", "timestamp": "2026-03-01T10:00:01.000Z"},
+                {"type": "info", "content": "Switched model"},
+                {"type": "gemini", "content": [{"text": "Done, the build passes."}], "timestamp": "2026-03-01T10:04:00.000Z"}
+            ]
+        });
+        let path = chats.join("session-2026-03-01T10-00-5f0c7d2e.json");
+        std::fs::write(&path, chat.to_string()).unwrap();
+        // Empty chats (no prompt sent yet) are skipped
+        std::fs::write(
+            chats.join("session-empty.json"),
+            json!({"sessionId": "empty", "messages": []}).to_string(),
+        )
+        .unwrap();
+
+        let sessions = scan_sessions_in(gemini_dir);
+        assert_eq!(sessions.len(), 1);
+        let meta = &sessions[0];
+        assert_eq!(meta.title.as_deref(), Some("webapp"));
+        assert_eq!(meta.project_dir.as_deref(), Some("/home/me/webapp"));
+        assert_eq!(meta.summary.as_deref(), Some("Done, the build passes."));
+        assert_eq!(meta.created_at, Some(1772359200000));
+        assert_eq!(meta.last_active_at, Some(1772359500000));
+        assert_eq!(
+            meta.resume_command.as_deref(),
+            Some("gemini --resume 5f0c7d2e-1111-2222-3333-444455556666")
+        );
+
+        let single = parse_session_file(&path).unwrap();
+        assert_eq!(single.project_dir.as_deref(), Some("/home/me/webapp"));
+
+        let messages = load_messages(&path).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, "user");
+        assert_eq!(messages[1].role, "assistant");
+        assert_eq!(messages[1].content, "Done, the build passes.");
+    }
+
+    #[test]
+    fn resolves_project_dir_from_registry() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("projects.json"),
+            json!({"projects": {"/srv/api": "api-1"}}).to_string(),
+        )
+        .unwrap();
+        let registry = read_project_registry(dir.path());
+
+        assert_eq!(
+            resolve_project_dir(&dir.path().join("tmp/api-1"), &registry).as_deref(),
+            Some("/srv/api")
+        );
+        assert_eq!(
+            resolve_project_dir(&dir.path().join("tmp/unknown"), &registry),
+            None
+        );
+    }
+}
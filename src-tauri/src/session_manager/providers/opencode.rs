@@ -0,0 +1,398 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::config::get_home_dir;
+use crate::session_manager::export::{TranscriptBlock, TranscriptMessage};
+use crate::session_manager::{SessionMessage, SessionMeta};
+use crate::settings::get_opencode_override_dir;
+
+use super::utils::{path_basename, read_dir_paths, read_json, tool_output_text, truncate_summary};
+
+const PROVIDER_ID: &str = "opencode";
+
+/// OpenCode keeps its history as JSON documents under `storage/`:
+/// `session/<projectID>/<sessionID>.json`, `message/<sessionID>/<messageID>.json`
+/// and `part/<messageID>/<partID>.json`.
+pub fn scan_sessions() -> Vec<SessionMeta> {
+    scan_sessions_in(&get_opencode_storage_dir())
+}
+
+/// The storage directory lives in OpenCode's data dir (`~/.local/share/opencode`);
+/// an override dir is used instead when it contains a `storage` directory.
+fn get_opencode_storage_dir() -> PathBuf {
+    if let Some(override_dir) = get_opencode_override_dir() {
+        let storage = override_dir.join("storage");
+        if storage.is_dir() {
+            return storage;
+        }
+    }
+
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| get_home_dir().join(".local").join("share"));
+    data_home.join("opencode").join("storage")
+}
+
+fn scan_sessions_in(storage: &Path) -> Vec<SessionMeta> {
+    let mut sessions = Vec::new();
+    for path in session_files_in(storage) {
+        if let Some(meta) = parse_session(storage, &path) {
+            sessions.push(meta);
+        }
+    }
+
+    sessions
+}
+
+pub fn session_files() -> Vec<PathBuf> {
+    session_files_in(&get_opencode_storage_dir())
+}
+
+fn session_files_in(storage: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for project in read_dir_paths(&storage.join("session")) {
+        for path in read_dir_paths(&project) {
+            if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+pub fn parse_session_file(path: &Path) -> Option<SessionMeta> {
+    parse_session(storage_root_for(path)?, path)
+}
+
+pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
+    let session = read_json(path).map_err(|e| format!("Failed to open session file: {e}"))?;
+    let session_id = session
+        .get("id")
+        .and_then(Value::as_str)
+        .ok_or("Session file has no id")?;
+    let storage = storage_root_for(path).ok_or("Unexpected OpenCode session path")?;
+    Ok(read_session_messages(storage, session_id))
+}
+
+fn parse_session(storage: &Path, path: &Path) -> Option<SessionMeta> {
+    let session = read_json(path).ok()?;
+    // Child sessions are spawned by subagents and cannot be resumed on their own
+    if session.get("parentID").and_then(Value::as_str).is_some() {
+        return None;
+    }
+
+    let session_id = session.get("id").and_then(Value::as_str)?.to_string();
+    let project_dir = session
+        .get("directory")
+        .and_then(Value::as_str)
+        .map(|s| s.to_string())
+        .or_else(|| project_worktree(storage, &session));
+
+    let time = session.get("time");
+    let created_at = time.and_then(|t| t.get("created")).and_then(Value::as_i64);
+    let last_active_at = time
+        .and_then(|t| t.get("updated"))
+        .and_then(Value::as_i64)
+        .or(created_at);
+
+    let title = session
+        .get("title")
+        .and_then(Value::as_str)
+        .filter(|title| !title.trim().is_empty())
+        .map(|title| title.trim().to_string())
+        .or_else(|| project_dir.as_deref().and_then(path_basename));
+
+    // Only the newest message with visible text is needed, so read parts from the end
+    let summary = read_message_files(storage, &session_id)
+        .iter()
+        .rev()
+        .map(|message| visible_text(&read_parts(storage, message)))
+        .find(|text| !text.trim().is_empty())
+        .map(|text| truncate_summary(&text, 160));
+
+    Some(SessionMeta {
+        provider_id: PROVIDER_ID.to_string(),
+        session_id: session_id.clone(),
+        title,
+        summary,
+        project_dir,
+        created_at,
+        last_active_at,
+        source_path: Some(path.to_string_lossy().to_string()),
+        resume_command: Some(format!("opencode --session {session_id}")),
+        usage: None,
+    })
+}
+
+fn read_session_messages(storage: &Path, session_id: &str) -> Vec<SessionMessage> {
+    read_messages(storage, session_id)
+        .into_iter()
+        .filter_map(|(message, parts)| {
+            let content = visible_text(&parts);
+            if content.trim().is_empty() {
+                return None;
+            }
+            Some(SessionMessage {
+                role: message_role(&message),
+                content,
+                ts: message_created_at(&message),
+            })
+        })
+        .collect()
+}
+
+pub fn load_transcript(path: &Path) -> Result<Vec<TranscriptMessage>, String> {
+    let session = read_json(path).map_err(|e| format!("Failed to open session file: {e}"))?;
+    let session_id = session
+        .get("id")
+        .and_then(Value::as_str)
+        .ok_or("Session file has no id")?;
+    let storage = storage_root_for(path).ok_or("Unexpected OpenCode session path")?;
+
+    let messages = read_messages(storage, session_id)
+        .into_iter()
+        .filter_map(|(message, parts)| {
+            let blocks: Vec<TranscriptBlock> = parts.iter().flat_map(transcript_blocks).collect();
+            if blocks.is_empty() {
+                return None;
+            }
+            Some(TranscriptMessage {
+                role: message_role(&message),
+                ts: message_created_at(&message),
+                blocks,
+            })
+        })
+        .collect();
+    Ok(messages)
+}
+
+fn transcript_blocks(part: &Value) -> Vec<TranscriptBlock> {
+    let text = part
+        .get("text")
+        .and_then(Value::as_str)
+        .filter(|text| !text.trim().is_empty())
+        .map(|text| text.to_string());
+
+    match part.get("type").and_then(Value::as_str) {
+        Some("text") if is_visible_text(part) => text
+            .map(|text| vec![TranscriptBlock::Text { text }])
+            .unwrap_or_default(),
+        Some("reasoning") => text
+            .map(|text| vec![TranscriptBlock::Thinking { text }])
+            .unwrap_or_default(),
+        Some("tool") => {
+            let id = part
+                .get("callID")
+                .and_then(Value::as_str)
+                .map(|s| s.to_string());
+            let state = part.get("state");
+            let mut blocks = vec![TranscriptBlock::ToolCall {
+                id: id.clone(),
+                name: part
+                    .get("tool")
+                    .and_then(Value::as_str)
+                    .unwrap_or("tool")
+                    .to_string(),
+                input: state
+                    .and_then(|s| s.get("input"))
+                    .cloned()
+                    .unwrap_or(Value::Null),
+            }];
+            let status = state.and_then(|s| s.get("status")).and_then(Value::as_str);
+            let result = match status {
+                Some("completed") => state.and_then(|s| s.get("output")).map(|v| (v, false)),
+                Some("error") => state.and_then(|s| s.get("error")).map(|v| (v, true)),
+                _ => None,
+            };
+            if let Some((output, is_error)) = result {
+                blocks.push(TranscriptBlock::ToolResult {
+                    id,
+                    content: tool_output_text(output),
+                    is_error,
+                });
+            }
+            blocks
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Loads a session's messages with their parts, both in creation order.
+fn read_messages(storage: &Path, session_id: &str) -> Vec<(Value, Vec<Value>)> {
+    read_message_files(storage, session_id)
+        .into_iter()
+        .map(|message| {
+            let parts = read_parts(storage, &message);
+            (message, parts)
+        })
+        .collect()
+}
+
+/// Loads a session's messages (without parts) in creation order.
+fn read_message_files(storage: &Path, session_id: &str) -> Vec<Value> {
+    let mut messages: Vec<Value> = read_dir_paths(&storage.join("message").join(session_id))
+        .iter()
+        .filter_map(|path| read_json(path).ok())
+        .filter(|message| message.get("id").and_then(Value::as_str).is_some())
+        .collect();
+    // IDs are time-ordered, so they break ties between equal timestamps
+    messages.sort_by(|a, b| {
+        message_created_at(a)
+            .unwrap_or(0)
+            .cmp(&message_created_at(b).unwrap_or(0))
+            .then_with(|| value_id(a).cmp(value_id(b)))
+    });
+    messages
+}
+
+/// Loads a message's parts in creation order.
+fn read_parts(storage: &Path, message: &Value) -> Vec<Value> {
+    let mut parts: Vec<Value> = read_dir_paths(&storage.join("part").join(value_id(message)))
+        .iter()
+        .filter_map(|path| read_json(path).ok())
+        .collect();
+    parts.sort_by(|a, b| value_id(a).cmp(value_id(b)));
+    parts
+}
+
+/// Joins a message's visible text parts.
+fn visible_text(parts: &[Value]) -> String {
+    parts
+        .iter()
+        .filter(|part| is_visible_text(part))
+        .filter_map(|part| part.get("text").and_then(Value::as_str))
+        .filter(|text| !text.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Text parts shown to the user (OpenCode also injects synthetic ones)
+fn is_visible_text(part: &Value) -> bool {
+    part.get("type").and_then(Value::as_str) == Some("text")
+        && part.get("synthetic").and_then(Value::as_bool) != Some(true)
+}
+
+fn value_id(value: &Value) -> &str {
+    value.get("id").and_then(Value::as_str).unwrap_or_default()
+}
+
+fn message_role(message: &Value) -> String {
+    message
+        .get("role")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+fn message_created_at(message: &Value) -> Option<i64> {
+    message
+        .get("time")
+        .and_then(|t| t.get("created"))
+        .and_then(Value::as_i64)
+}
+
+fn project_worktree(storage: &Path, session: &Value) -> Option<String> {
+    let project_id = session.get("projectID").and_then(Value::as_str)?;
+    let project = read_json(&storage.join("project").join(format!("{project_id}.json"))).ok()?;
+    project
+        .get("worktree")
+        .and_then(Value::as_str)
+        .filter(|worktree| *worktree != "/")
+        .map(|s| s.to_string())
+}
+
+/// `<storage>/session/<projectID>/<sessionID>.json` → `<storage>`
+fn storage_root_for(session_path: &Path) -> Option<&Path> {
+    session_path.parent()?.parent()?.parent()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write(path: PathBuf, value: Value) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, value.to_string()).unwrap();
+    }
+
+    #[test]
+    fn parses_opencode_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = dir.path();
+        let session_path = storage.join("session/proj1/ses_1.json");
+        write(
+            session_path.clone(),
+            json!({
+                "id": "ses_1",
+                "projectID": "proj1",
+                "directory": "/home/me/cli-tool",
+                "title": "Add retry flag",
+                "time": {"created": 1_700_000_000_000i64, "updated": 1_700_000_090_000i64}
+            }),
+        );
+        write(
+            storage.join("session/proj1/ses_2.json"),
+            json!({"id": "ses_2", "parentID": "ses_1", "time": {"created": 1}}),
+        );
+
+        write(
+            storage.join("message/ses_1/msg_a.json"),
+            json!({"id": "msg_a", "sessionID": "ses_1", "role": "user", "time": {"created": 1_700_000_010_000i64}}),
+        );
+        write(
+            storage.join("part/msg_a/prt_1.json"),
+            json!({"id": "prt_1", "type": "text", "text": "add a --retry flag"}),
+        );
+        write(
+            storage.join("message/ses_1/msg_b.json"),
+            json!({"id": "msg_b", "sessionID": "ses_1", "role": "assistant", "time": {"created": 1_700_000_020_000i64}}),
+        );
+        write(
+            storage.join("part/msg_b/prt_2.json"),
+            json!({"id": "prt_2", "type": "text", "text": "Added the flag."}),
+        );
+        write(
+            storage.join("part/msg_b/prt_3.json"),
+            json!({"id": "prt_3", "type": "tool", "tool": "edit", "callID": "c1",
+                   "state": {"status": "completed", "input": {"path": "cli.rs"}, "output": "ok"}}),
+        );
+        write(
+            storage.join("part/msg_b/prt_4.json"),
+            json!({"id": "prt_4", "type": "text", "text": "<system>", "synthetic": true}),
+        );
+
+        let sessions = scan_sessions_in(storage);
+        assert_eq!(sessions.len(), 1);
+        let meta = &sessions[0];
+        assert_eq!(meta.title.as_deref(), Some("Add retry flag"));
+        assert_eq!(meta.project_dir.as_deref(), Some("/home/me/cli-tool"));
+        assert_eq!(meta.summary.as_deref(), Some("Added the flag."));
+        assert_eq!(meta.created_at, Some(1_700_000_000_000));
+        assert_eq!(meta.last_active_at, Some(1_700_000_090_000));
+        assert_eq!(
+            meta.resume_command.as_deref(),
+            Some("opencode --session ses_1")
+        );
+
+        assert_eq!(
+            parse_session_file(&session_path).unwrap().session_id,
+            "ses_1"
+        );
+
+        let messages = load_messages(&session_path).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, "user");
+        assert_eq!(messages[0].content, "add a --retry flag");
+        assert_eq!(messages[1].content, "Added the flag.");
+
+        let transcript = load_transcript(&session_path).unwrap();
+        assert_eq!(transcript[1].blocks.len(), 3);
+        assert!(matches!(
+            &transcript[1].blocks[2],
+            TranscriptBlock::ToolResult { content, is_error: false, .. } if content == "ok"
+        ));
+    }
+}
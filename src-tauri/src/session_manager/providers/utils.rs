@@ -1,3 +1,5 @@
+use std::path::{Path, PathBuf};
+
 use chrono::{DateTime, FixedOffset};
 use serde_json::Value;
 
@@ -75,3 +77,37 @@ pub fn path_basename(value: &str) -> Option<String> {
         .filter(|segment| !segment.is_empty())?;
     Some(last.to_string())
 }
+
+/// Tool outputs are plain strings, content-block arrays or arbitrary JSON depending on the agent.
+pub fn tool_output_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.to_string(),
+        Value::Null => String::new(),
+        Value::Array(_) => {
+            let text = extract_text(value);
+            if text.is_empty() {
+                pretty_json(value)
+            } else {
+                text
+            }
+        }
+        other => pretty_json(other),
+    }
+}
+
+pub fn pretty_json(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+/// Entries of `dir`, or nothing when it is missing or unreadable.
+pub fn read_dir_paths(dir: &Path) -> Vec<PathBuf> {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn read_json(path: &Path) -> Result<Value, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
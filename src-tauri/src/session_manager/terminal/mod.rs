@@ -1,5 +1,21 @@
+use std::path::PathBuf;
 use std::process::Command;
 
+/// Linux terminals supported for session resume, in auto-detection order.
+pub const LINUX_TERMINALS: &[&str] = &[
+    "gnome-terminal",
+    "konsole",
+    "xfce4-terminal",
+    "kitty",
+    "wezterm",
+    "alacritty",
+    "ghostty",
+    "foot",
+];
+
+/// Generic fallback launcher provided by Debian-based distributions.
+const LINUX_FALLBACK_TERMINAL: &str = "x-terminal-emulator";
+
 pub fn launch_terminal(
     target: &str,
     command: &str,
@@ -10,8 +26,12 @@ pub fn launch_terminal(
         return Err("Resume command is empty".to_string());
     }
 
+    if cfg!(target_os = "linux") {
+        return launch_linux(target, command, cwd, custom_config);
+    }
+
     if !cfg!(target_os = "macos") {
-        return Err("Terminal resume is only supported on macOS".to_string());
+        return Err("Terminal resume is only supported on macOS and Linux".to_string());
     }
 
     match target {
@@ -238,6 +258,165 @@ fn launch_custom(
     }
 }
 
+fn launch_linux(
+    target: &str,
+    command: &str,
+    cwd: Option<&str>,
+    custom_config: Option<&str>,
+) -> Result<(), String> {
+    if target == "custom" {
+        return launch_custom(command, cwd, custom_config);
+    }
+
+    let env_terminal = std::env::var("TERMINAL").ok();
+    let candidates = linux_terminal_candidates(target, env_terminal.as_deref(), |name| {
+        find_in_path(name).is_some()
+    });
+    if candidates.is_empty() {
+        return Err(
+            "No supported terminal found. Install one or set a custom terminal command."
+                .to_string(),
+        );
+    }
+
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
+    let mut last_error = String::new();
+    for terminal in candidates {
+        let args = linux_terminal_args(&terminal, &shell, command, cwd);
+        let mut cmd = Command::new(&terminal);
+        cmd.args(&args);
+        if let Some(dir) = cwd.filter(|dir| !dir.trim().is_empty()) {
+            cmd.current_dir(dir);
+        }
+        // Terminals may keep running until the window closes, so reap them off-thread.
+        match cmd.spawn() {
+            Ok(mut child) => {
+                std::thread::spawn(move || {
+                    let _ = child.wait();
+                });
+                return Ok(());
+            }
+            Err(e) => last_error = format!("Failed to launch {terminal}: {e}"),
+        }
+    }
+    Err(last_error)
+}
+
+/// Orders the terminals to try: the requested one if installed, then `$TERMINAL`,
+/// then every other detected terminal, then `x-terminal-emulator`.
+fn linux_terminal_candidates(
+    target: &str,
+    env_terminal: Option<&str>,
+    is_installed: impl Fn(&str) -> bool,
+) -> Vec<String> {
+    let env_terminal = env_terminal
+        .and_then(|value| value.split_whitespace().next())
+        .filter(|value| !value.is_empty());
+
+    let mut ordered: Vec<&str> = Vec::new();
+    if target != "auto" {
+        ordered.push(target);
+    }
+    ordered.extend(env_terminal);
+    ordered.extend(LINUX_TERMINALS.iter().copied());
+    ordered.push(LINUX_FALLBACK_TERMINAL);
+
+    let mut candidates: Vec<String> = Vec::new();
+    for name in ordered {
+        if !candidates.iter().any(|c| c == name) && is_installed(name) {
+            candidates.push(name.to_string());
+        }
+    }
+    candidates
+}
+
+/// Builds the arguments that open a new window in `cwd` running `command`.
+///
+/// The command runs through a login shell, which is kept open afterwards so the
+/// window stays usable once the resumed session exits.
+fn linux_terminal_args(
+    terminal: &str,
+    shell: &str,
+    command: &str,
+    cwd: Option<&str>,
+) -> Vec<String> {
+    let cwd = cwd.filter(|dir| !dir.trim().is_empty());
+    let script = format!(
+        "{}; exec {}",
+        build_shell_command(command, cwd),
+        shell_escape(shell)
+    );
+    let exec = [shell.to_string(), "-lc".to_string(), script];
+
+    let binary = terminal.rsplit('/').next().unwrap_or(terminal);
+    let mut args: Vec<String> = Vec::new();
+    match binary {
+        "gnome-terminal" => {
+            if let Some(dir) = cwd {
+                args.push(format!("--working-directory={dir}"));
+            }
+            args.push("--".to_string());
+        }
+        "konsole" => {
+            if let Some(dir) = cwd {
+                args.extend(["--workdir".to_string(), dir.to_string()]);
+            }
+            args.push("-e".to_string());
+        }
+        "xfce4-terminal" => {
+            if let Some(dir) = cwd {
+                args.extend(["--working-directory".to_string(), dir.to_string()]);
+            }
+            // -x passes the remaining arguments through unchanged (-e expects one string)
+            args.push("-x".to_string());
+        }
+        "kitty" => {
+            if let Some(dir) = cwd {
+                args.extend(["--directory".to_string(), dir.to_string()]);
+            }
+        }
+        "wezterm" => {
+            args.push("start".to_string());
+            if let Some(dir) = cwd {
+                args.extend(["--cwd".to_string(), dir.to_string()]);
+            }
+            args.push("--".to_string());
+        }
+        "alacritty" => {
+            if let Some(dir) = cwd {
+                args.extend(["--working-directory".to_string(), dir.to_string()]);
+            }
+            args.push("-e".to_string());
+        }
+        "ghostty" => {
+            if let Some(dir) = cwd {
+                args.push(format!("--working-directory={dir}"));
+            }
+            args.push("-e".to_string());
+        }
+        "foot" => {
+            if let Some(dir) = cwd {
+                args.push(format!("--working-directory={dir}"));
+            }
+        }
+        // $TERMINAL, x-terminal-emulator and anything else: the common `-e` convention
+        _ => args.push("-e".to_string()),
+    }
+    args.extend(exec);
+    args
+}
+
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    if name.contains('/') {
+        let path = PathBuf::from(name);
+        return path.is_file().then_some(path);
+    }
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
 fn build_shell_command(command: &str, cwd: Option<&str>) -> String {
     match cwd {
         Some(dir) if !dir.trim().is_empty() => {
@@ -255,3 +434,59 @@ fn shell_escape(value: &str) -> String {
 fn escape_osascript(value: &str) -> String {
     value.replace('\\', "\\\\").replace('"', "\\\"")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linux_args_open_window_in_project_dir() {
+        let args = linux_terminal_args(
+            "gnome-terminal",
+            "/bin/bash",
+            "claude --resume abc",
+            Some("/home/me/proj"),
+        );
+        assert_eq!(
+            args,
+            vec![
+                "--working-directory=/home/me/proj",
+                "--",
+                "/bin/bash",
+                "-lc",
+                "cd \"/home/me/proj\" && claude --resume abc; exec \"/bin/bash\"",
+            ]
+        );
+
+        let args = linux_terminal_args("wezterm", "/bin/zsh", "codex resume x", Some("/tmp/p"));
+        assert_eq!(&args[..4], ["start", "--cwd", "/tmp/p", "--"]);
+
+        let args = linux_terminal_args("foot", "/bin/zsh", "codex resume x", None);
+        assert_eq!(args[0], "/bin/zsh");
+        assert_eq!(args[2], "codex resume x; exec \"/bin/zsh\"");
+
+        let args = linux_terminal_args("/usr/bin/x-terminal-emulator", "/bin/sh", "c", None);
+        assert_eq!(args[0], "-e");
+    }
+
+    #[test]
+    fn linux_candidates_fall_back_to_installed_terminals() {
+        let installed = |name: &str| matches!(name, "konsole" | "foot" | "x-terminal-emulator");
+
+        assert_eq!(
+            linux_terminal_candidates("foot", None, installed),
+            vec!["foot", "konsole", "x-terminal-emulator"]
+        );
+        // Preferred terminal not installed: use $TERMINAL, then auto-detected ones
+        assert_eq!(
+            linux_terminal_candidates("kitty", Some("foot --server"), installed),
+            vec!["foot", "konsole", "x-terminal-emulator"]
+        );
+        // No explicit setting: $TERMINAL first, then detected terminals
+        assert_eq!(
+            linux_terminal_candidates("auto", Some("foot"), installed),
+            vec!["foot", "konsole", "x-terminal-emulator"]
+        );
+        assert!(linux_terminal_candidates("auto", None, |_| false).is_empty());
+    }
+}
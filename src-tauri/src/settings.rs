@@ -240,7 +240,7 @@ pub struct AppSettings {
     /// 首选终端应用（可选，默认使用系统默认终端）
     /// - macOS: "terminal" | "iterm2" | "warp" | "alacritty" | "kitty" | "ghostty"
     /// - Windows: "cmd" | "powershell" | "wt" (Windows Terminal)
-    /// - Linux: "gnome-terminal" | "konsole" | "xfce4-terminal" | "alacritty" | "kitty" | "ghostty"
+    /// - Linux: "gnome-terminal" | "konsole" | "xfce4-terminal" | "kitty" | "wezterm" | "alacritty" | "ghostty" | "foot"
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub preferred_terminal: Option<String>,
 }
@@ -24,6 +24,7 @@ import {
 } from "lucide-react";
 import type { Provider, VisibleApps } from "@/types";
 import type { EnvConflict } from "@/types/env";
+import type { BudgetAlert } from "@/types/usage";
 import { useProvidersQuery, useSettingsQuery } from "@/lib/query";
 import {
   providersApi,
@@ -342,6 +343,48 @@ function App() {
     };
   }, [queryClient, t]);
 
+  useEffect(() => {
+    let unsubscribe: (() => void) | undefined;
+    let active = true;
+
+    const setupListener = async () => {
+      try {
+        const off = await listen<BudgetAlert>("budget-alert", (event) => {
+          const alert = event.payload;
+          const scope =
+            alert.providerName ||
+            t("usage.budgetAlertAppScope", { app: alert.appType });
+          toast.warning(
+            t(
+              alert.period === "daily"
+                ? "usage.budgetAlertDaily"
+                : "usage.budgetAlertMonthly",
+              {
+                scope,
+                spend: alert.spendUsd.toFixed(4),
+                limit: alert.limitUsd.toFixed(2),
+                threshold: alert.threshold,
+              },
+            ),
+          );
+        });
+        if (!active) {
+          off();
+          return;
+        }
+        unsubscribe = off;
+      } catch (error) {
+        console.error("[App] Failed to subscribe budget-alert event", error);
+      }
+    };
+
+    void setupListener();
+    return () => {
+      active = false;
+      unsubscribe?.();
+    };
+  }, [t]);
+
   useEffect(() => {
     const checkEnvOnStartup = async () => {
       try {
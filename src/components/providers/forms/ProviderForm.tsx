@@ -407,7 +407,8 @@ export function ProviderForm({
 
   const [localApiFormat, setLocalApiFormat] = useState<ClaudeApiFormat>(() => {
     if (appId !== "claude") return "anthropic";
-    return initialData?.meta?.apiFormat ?? "anthropic";
+    const format = initialData?.meta?.apiFormat;
+    return format === "openai_chat" || format === "gemini" ? format : "anthropic";
   });
 
   const handleApiFormatChange = useCallback((format: ClaudeApiFormat) => {
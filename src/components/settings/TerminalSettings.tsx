@@ -37,9 +37,11 @@ const LINUX_TERMINALS = [
     value: "xfce4-terminal",
     labelKey: "settings.terminal.options.linux.xfce4Terminal",
   },
-  { value: "alacritty", labelKey: "settings.terminal.options.linux.alacritty" },
   { value: "kitty", labelKey: "settings.terminal.options.linux.kitty" },
+  { value: "wezterm", labelKey: "settings.terminal.options.linux.wezterm" },
+  { value: "alacritty", labelKey: "settings.terminal.options.linux.alacritty" },
   { value: "ghostty", labelKey: "settings.terminal.options.linux.ghostty" },
+  { value: "foot", labelKey: "settings.terminal.options.linux.foot" },
 ] as const;
 
 // Get terminals for the current platform
@@ -141,9 +141,10 @@ export function useProviderActions(activeApp: AppId) {
         if (
           activeApp === "claude" &&
           provider.category !== "official" &&
-          provider.meta?.apiFormat === "openai_chat"
+          (provider.meta?.apiFormat === "openai_chat" ||
+            provider.meta?.apiFormat === "gemini")
         ) {
-          // OpenAI Chat 格式供应商：显示代理提示
+          // OpenAI Chat / Gemini 格式供应商：显示代理提示
           toast.info(
             t("notifications.openAIChatFormatHint", {
               defaultValue:
@@ -6,6 +6,12 @@ import type {
   ProxyTakeoverStatus,
   GlobalProxyConfig,
   AppProxyConfig,
+  ProxyClientToken,
+  IssuedProxyClientToken,
+  ModelRoutingRule,
+  RequestCapture,
+  RequestCaptureSummary,
+  ReplayResult,
 } from "@/types/proxy";
 
 export const proxyApi = {
@@ -117,4 +123,82 @@ export const proxyApi = {
   async setPricingModelSource(appType: string, value: string): Promise<void> {
     return invoke("set_pricing_model_source", { appType, value });
   },
+
+  // ========== 客户端令牌 API ==========
+
+  // 获取代理客户端令牌列表
+  async listClientTokens(): Promise<ProxyClientToken[]> {
+    return invoke("list_proxy_client_tokens");
+  },
+
+  // 签发代理客户端令牌（明文仅返回一次）
+  async createClientToken(label: string): Promise<IssuedProxyClientToken> {
+    return invoke("create_proxy_client_token", { label });
+  },
+
+  // 启用/停用代理客户端令牌
+  async setClientTokenEnabled(id: string, enabled: boolean): Promise<void> {
+    return invoke("set_proxy_client_token_enabled", { id, enabled });
+  },
+
+  // 设置代理客户端令牌的 RPM/TPM 限制
+  async setClientTokenLimits(
+    id: string,
+    rpmLimit?: number,
+    tpmLimit?: number,
+  ): Promise<void> {
+    return invoke("set_proxy_client_token_limits", { id, rpmLimit, tpmLimit });
+  },
+
+  // 删除代理客户端令牌
+  async deleteClientToken(id: string): Promise<void> {
+    return invoke("delete_proxy_client_token", { id });
+  },
+
+  // ========== 模型路由规则 API ==========
+
+  // 获取应用的模型路由规则（按匹配顺序排列）
+  async listModelRoutingRules(appType: string): Promise<ModelRoutingRule[]> {
+    return invoke("list_model_routing_rules", { appType });
+  },
+
+  // 新增或更新模型路由规则（id 为空时新建）
+  async saveModelRoutingRule(
+    rule: ModelRoutingRule,
+  ): Promise<ModelRoutingRule> {
+    return invoke("save_model_routing_rule", { rule });
+  },
+
+  // 删除模型路由规则
+  async deleteModelRoutingRule(id: string): Promise<void> {
+    return invoke("delete_model_routing_rule", { id });
+  },
+
+  // ========== 请求抓包 API ==========
+
+  // 获取最近的请求抓包记录
+  async listRequestCaptures(
+    appType?: string,
+    limit?: number,
+  ): Promise<RequestCaptureSummary[]> {
+    return invoke("list_request_captures", { appType, limit });
+  },
+
+  // 获取完整的请求抓包记录
+  async getRequestCapture(requestId: string): Promise<RequestCapture | null> {
+    return invoke("get_request_capture", { requestId });
+  },
+
+  // 清空请求抓包记录
+  async clearRequestCaptures(): Promise<number> {
+    return invoke("clear_request_captures");
+  },
+
+  // 重放抓包请求（providerId 为空时发往原供应商）
+  async replayRequestCapture(
+    requestId: string,
+    providerId?: string,
+  ): Promise<ReplayResult> {
+    return invoke("replay_request_capture", { requestId, providerId });
+  },
 };
@@ -1,11 +1,21 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { SessionMessage, SessionMeta } from "@/types";
+import type {
+  SessionExportFormat,
+  SessionMessage,
+  SessionMeta,
+  SessionSearchHit,
+  SessionSearchQuery,
+} from "@/types";
 
 export const sessionsApi = {
   async list(): Promise<SessionMeta[]> {
     return await invoke("list_sessions");
   },
 
+  async search(query: SessionSearchQuery): Promise<SessionSearchHit[]> {
+    return await invoke("search_sessions", { query });
+  },
+
   async getMessages(
     providerId: string,
     sourcePath: string,
@@ -13,6 +23,21 @@ export const sessionsApi = {
     return await invoke("get_session_messages", { providerId, sourcePath });
   },
 
+  // 导出会话记录（含工具调用、工具结果与思考过程），返回导出的消息数
+  async exportSession(
+    providerId: string,
+    sourcePath: string,
+    format: SessionExportFormat,
+    filePath: string,
+  ): Promise<number> {
+    return await invoke("export_session", {
+      providerId,
+      sourcePath,
+      format,
+      filePath,
+    });
+  },
+
   async launchTerminal(options: {
     command: string;
     cwd?: string | null;
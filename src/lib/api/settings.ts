@@ -195,6 +195,14 @@ export const settingsApi = {
   async setLogConfig(config: LogConfig): Promise<boolean> {
     return await invoke("set_log_config", { config });
   },
+
+  async getCaptureConfig(): Promise<CaptureConfig> {
+    return await invoke("get_capture_config");
+  },
+
+  async setCaptureConfig(config: CaptureConfig): Promise<boolean> {
+    return await invoke("set_capture_config", { config });
+  },
 };
 
 export interface RectifierConfig {
@@ -207,3 +215,9 @@ export interface LogConfig {
   enabled: boolean;
   level: "error" | "warn" | "info" | "debug" | "trace";
 }
+
+export interface CaptureConfig {
+  enabled: boolean;
+  maxBodyBytes: number;
+  retentionDays: number;
+}
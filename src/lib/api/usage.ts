@@ -3,12 +3,21 @@ import type {
   UsageSummary,
   DailyStats,
   ProviderStats,
+  ClientStats,
   ModelStats,
   RequestLog,
   LogFilters,
   ModelPricing,
+  ModelPricingAlias,
+  PricingImportDiff,
+  CostRecomputeReport,
   ProviderLimitStatus,
   PaginatedLogs,
+  UsageRetentionConfig,
+  UsageRetentionReport,
+  BudgetAlertConfig,
+  UsageExportFormat,
+  UsageGroupBy,
 } from "@/types/usage";
 import type { UsageResult } from "@/types";
 import type { AppId } from "./types";
@@ -63,10 +72,37 @@ export const usageApi = {
     return invoke("get_provider_stats");
   },
 
+  getClientStats: async (
+    startDate?: number,
+    endDate?: number,
+  ): Promise<ClientStats[]> => {
+    return invoke("get_client_stats", { startDate, endDate });
+  },
+
   getModelStats: async (): Promise<ModelStats[]> => {
     return invoke("get_model_stats");
   },
 
+  getRetentionConfig: async (): Promise<UsageRetentionConfig> => {
+    return invoke("get_usage_retention_config");
+  },
+
+  setRetentionConfig: async (config: UsageRetentionConfig): Promise<void> => {
+    return invoke("set_usage_retention_config", { config });
+  },
+
+  pruneLogs: async (): Promise<UsageRetentionReport> => {
+    return invoke("prune_usage_logs");
+  },
+
+  getBudgetAlertConfig: async (): Promise<BudgetAlertConfig> => {
+    return invoke("get_budget_alert_config");
+  },
+
+  setBudgetAlertConfig: async (config: BudgetAlertConfig): Promise<void> => {
+    return invoke("set_budget_alert_config", { config });
+  },
+
   getRequestLogs: async (
     filters: LogFilters,
     page: number = 0,
@@ -79,6 +115,15 @@ export const usageApi = {
     });
   },
 
+  exportUsage: async (
+    filePath: string,
+    filters: LogFilters,
+    format: UsageExportFormat,
+    groupBy?: UsageGroupBy,
+  ): Promise<number> => {
+    return invoke("export_usage", { filePath, filters, format, groupBy });
+  },
+
   getRequestDetail: async (requestId: string): Promise<RequestLog | null> => {
     return invoke("get_request_detail", { requestId });
   },
@@ -109,6 +154,37 @@ export const usageApi = {
     return invoke("delete_model_pricing", { modelId });
   },
 
+  importModelPricing: async (
+    source: string,
+    apply: boolean,
+  ): Promise<PricingImportDiff> => {
+    return invoke("import_model_pricing", { source, apply });
+  },
+
+  recomputeCosts: async (
+    filters: LogFilters,
+    useCurrentMultiplier: boolean,
+    dryRun: boolean,
+  ): Promise<CostRecomputeReport> => {
+    return invoke("recompute_usage_costs", {
+      filters,
+      useCurrentMultiplier,
+      dryRun,
+    });
+  },
+
+  getModelPricingAliases: async (): Promise<ModelPricingAlias[]> => {
+    return invoke("get_model_pricing_aliases");
+  },
+
+  saveModelPricingAlias: async (alias: ModelPricingAlias): Promise<void> => {
+    return invoke("save_model_pricing_alias", { alias });
+  },
+
+  deleteModelPricingAlias: async (pattern: string): Promise<void> => {
+    return invoke("delete_model_pricing_alias", { pattern });
+  },
+
   checkProviderLimits: async (
     providerId: string,
     appType: string,
@@ -142,10 +142,17 @@ export interface ProviderMeta {
   costMultiplier?: string;
   // 供应商计费模式来源
   pricingModelSource?: string;
-  // Claude API 格式（仅 Claude 供应商使用）
-  // - "anthropic": 原生 Anthropic Messages API 格式，直接透传
+  // 加权轮询权重（默认 1，0 表示仅作为备用）
+  routingWeight?: number;
+  // 每分钟请求数 / Token 数限制
+  rateLimitRpm?: number;
+  rateLimitTpm?: number;
+  // API 格式（Claude / Codex 供应商使用）
+  // - "anthropic": 原生 Anthropic Messages API 格式，直接透传（Claude）
+  // - "responses": OpenAI Responses API 格式，直接透传（Codex 默认）
   // - "openai_chat": OpenAI Chat Completions 格式，需要格式转换
-  apiFormat?: "anthropic" | "openai_chat";
+  // - "gemini": Gemini generateContent 格式，需要格式转换（Claude）
+  apiFormat?: "anthropic" | "responses" | "openai_chat" | "gemini";
   // 供应商类型（用于识别 Copilot 等特殊供应商）
   providerType?: string;
 }
@@ -156,7 +163,8 @@ export type SkillSyncMethod = "auto" | "symlink" | "copy";
 // Claude API 格式类型
 // - "anthropic": 原生 Anthropic Messages API 格式，直接透传
 // - "openai_chat": OpenAI Chat Completions 格式，需要格式转换
-export type ClaudeApiFormat = "anthropic" | "openai_chat";
+// - "gemini": Gemini generateContent 格式，需要格式转换
+export type ClaudeApiFormat = "anthropic" | "openai_chat" | "gemini";
 
 // 主页面显示的应用配置
 export interface VisibleApps {
@@ -252,7 +260,7 @@ export interface Settings {
   // 首选终端应用（可选，默认使用系统默认终端）
   // macOS: "terminal" | "iterm2" | "warp" | "alacritty" | "kitty" | "ghostty"
   // Windows: "cmd" | "powershell" | "wt"
-  // Linux: "gnome-terminal" | "konsole" | "xfce4-terminal" | "alacritty" | "kitty" | "ghostty"
+  // Linux: "gnome-terminal" | "konsole" | "xfce4-terminal" | "kitty" | "wezterm" | "alacritty" | "ghostty" | "foot"
   preferredTerminal?: string;
 }
 
@@ -266,6 +274,22 @@ export interface SessionMeta {
   lastActiveAt?: number;
   sourcePath?: string;
   resumeCommand?: string;
+  usage?: SessionUsage;
+}
+
+// 代理请求日志按会话聚合的用量
+export interface SessionUsage {
+  sessionId: string;
+  requestCount: number;
+  inputTokens: number;
+  outputTokens: number;
+  cacheReadTokens: number;
+  cacheCreationTokens: number;
+  totalCost: string;
+  providers: string[];
+  models: string[];
+  firstRequestAt: number;
+  lastRequestAt: number;
 }
 
 export interface SessionMessage {
@@ -274,6 +298,32 @@ export interface SessionMessage {
   ts?: number;
 }
 
+export type SessionExportFormat = "markdown" | "html" | "jsonl";
+
+export interface SessionSearchQuery {
+  query: string;
+  providerId?: string;
+  // 匹配该目录及其子目录
+  projectDir?: string;
+  // 毫秒时间戳，会话活跃时间落在范围内即匹配
+  startTime?: number;
+  endTime?: number;
+  limit?: number;
+}
+
+export interface SessionSearchSnippet {
+  role: string;
+  text: string;
+  ts?: number;
+}
+
+export interface SessionSearchHit {
+  session: SessionMeta;
+  score: number;
+  matchCount: number;
+  snippets: SessionSearchSnippet[];
+}
+
 // MCP 服务器连接参数（宽松：允许扩展字段）
 export interface McpServerSpec {
   // 可选：社区常见 .mcp.json 中 stdio 配置可不写 type
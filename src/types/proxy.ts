@@ -27,6 +27,16 @@ export interface ProxyStatus {
   last_error: string | null;
   failover_count: number;
   active_targets?: ActiveTarget[];
+  rate_limits?: RateLimitLevel[];
+}
+
+// RPM/TPM 令牌桶水位（key: provider:{app}:{id} 或 client:{tokenId}）
+export interface RateLimitLevel {
+  key: string;
+  rpmLimit?: number;
+  rpmAvailable?: number;
+  tpmLimit?: number;
+  tpmAvailable?: number;
 }
 
 export interface ActiveTarget {
@@ -118,6 +128,76 @@ export interface GlobalProxyConfig {
   listenAddress: string;
   listenPort: number;
   enableLogging: boolean;
+  requireClientAuth?: boolean;
+}
+
+// 代理客户端令牌（不含明文）
+export interface ProxyClientToken {
+  id: string;
+  label: string;
+  tokenPrefix: string;
+  enabled: boolean;
+  createdAt: number;
+  lastUsedAt?: number;
+  rpmLimit?: number;
+  tpmLimit?: number;
+}
+
+// 新签发的代理客户端令牌（secret 仅返回一次）
+export interface IssuedProxyClientToken extends ProxyClientToken {
+  secret: string;
+}
+
+// 模型路由规则（请求模型匹配 pattern 时按 providerIds 顺序选择供应商）
+export interface ModelRoutingRule {
+  id: string;
+  appType: string;
+  pattern: string;
+  providerIds: string[];
+  enabled: boolean;
+  sortIndex: number;
+  createdAt: number;
+}
+
+// 请求抓包列表项（不含请求体与响应体）
+export interface RequestCaptureSummary {
+  requestId: string;
+  appType: string;
+  providerId: string;
+  url: string;
+  responseStatus?: number | null;
+  errorMessage?: string | null;
+  createdAt: number;
+}
+
+// 完整的请求抓包记录（认证类 Header 已遮蔽）
+export interface RequestCapture extends RequestCaptureSummary {
+  method: string;
+  requestHeaders: [string, string][];
+  requestBody: string;
+  requestTruncated: boolean;
+  responseHeaders: [string, string][];
+  responseBody?: string | null;
+  responseTruncated: boolean;
+}
+
+// 抓包重放的逐行对比
+export interface ReplayDiffLine {
+  kind: "equal" | "removed" | "added";
+  text: string;
+}
+
+// 抓包重放结果
+export interface ReplayResult {
+  requestId: string;
+  providerId: string;
+  url: string;
+  status: number;
+  latencyMs: number;
+  responseBody: string;
+  originalStatus?: number | null;
+  identical: boolean;
+  diff: ReplayDiffLine[];
 }
 
 // 应用级代理配置（每个 app 独立）
@@ -134,4 +214,14 @@ export interface AppProxyConfig {
   circuitTimeoutSeconds: number;
   circuitErrorRateThreshold: number;
   circuitMinRequests: number;
+  routingStrategy?: RoutingStrategy;
+  sessionAffinityEnabled?: boolean;
+  sessionAffinityTtlSeconds?: number;
 }
+
+// 供应商路由策略（仅故障转移开启时生效）
+export type RoutingStrategy =
+  | "priority"
+  | "weighted_round_robin"
+  | "least_in_flight"
+  | "lowest_latency";
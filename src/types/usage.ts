@@ -31,6 +31,10 @@ export interface RequestLog {
   statusCode: number;
   errorMessage?: string;
   createdAt: number;
+  sessionAffinity?: "new" | "hit" | "fallback";
+  clientLabel?: string;
+  // 该供应商上的上游请求次数（含重试）
+  attempts: number;
 }
 
 export interface PaginatedLogs {
@@ -49,6 +53,47 @@ export interface ModelPricing {
   cacheCreationCostPerMillion: string;
 }
 
+// 定价别名：通配符模式（* / ?）指向已有定价的模型 ID
+export interface ModelPricingAlias {
+  pattern: string;
+  modelId: string;
+}
+
+export interface PricingChange {
+  before: ModelPricing;
+  after: ModelPricing;
+}
+
+// 从 LiteLLM / OpenRouter 价格目录导入定价的差异
+export interface PricingImportDiff {
+  format: "litellm" | "openrouter";
+  added: ModelPricing[];
+  changed: PricingChange[];
+  unchanged: number;
+  skipped: number;
+  applied: boolean;
+}
+
+// 按当前定价重算历史成本的结果
+export interface CostRecomputeDelta {
+  model: string;
+  updated: number;
+  previousCostUsd: string;
+  newCostUsd: string;
+  deltaUsd: string;
+}
+
+export interface CostRecomputeReport {
+  scanned: number;
+  updated: number;
+  missingPricing: number;
+  previousTotalUsd: string;
+  newTotalUsd: string;
+  deltaUsd: string;
+  byModel: CostRecomputeDelta[];
+  dryRun: boolean;
+}
+
 export interface UsageSummary {
   totalRequests: number;
   totalCost: string;
@@ -80,6 +125,14 @@ export interface ProviderStats {
   avgLatencyMs: number;
 }
 
+export interface ClientStats {
+  clientLabel?: string | null;
+  requestCount: number;
+  totalTokens: number;
+  totalCost: string;
+  successRate: number;
+}
+
 export interface ModelStats {
   model: string;
   requestCount: number;
@@ -88,15 +141,61 @@ export interface ModelStats {
   avgCostPerRequest: string;
 }
 
+// 请求日志保留策略（聚合统计不受清理影响）
+export interface UsageRetentionConfig {
+  enabled: boolean;
+  retentionDays: number;
+  archive: boolean;
+}
+
+export interface UsageRetentionReport {
+  pruned: number;
+  cutoff: number;
+  archivePath?: string | null;
+}
+
+// 预算提醒（达到限额百分比时每个周期提醒一次）
+export interface AppBudgetLimit {
+  limitDailyUsd?: string | null;
+  limitMonthlyUsd?: string | null;
+}
+
+export interface BudgetAlertConfig {
+  enabled: boolean;
+  thresholds: number[];
+  webhookUrl?: string | null;
+  appLimits: Record<string, AppBudgetLimit>;
+}
+
+// "budget-alert" 事件负载（同时作为 Webhook 推送内容）
+export interface BudgetAlert {
+  appType: string;
+  providerId?: string | null;
+  providerName?: string | null;
+  period: "daily" | "monthly";
+  periodKey: string;
+  threshold: number;
+  spendUsd: number;
+  limitUsd: number;
+  message: string;
+  triggeredAt: number;
+}
+
 export interface LogFilters {
   appType?: string;
   providerName?: string;
   model?: string;
   statusCode?: number;
+  clientLabel?: string;
   startDate?: number;
   endDate?: number;
 }
 
+// 用量导出（不指定分组时导出逐条请求明细）
+export type UsageExportFormat = "csv" | "jsonl";
+
+export type UsageGroupBy = "provider" | "model" | "app" | "session" | "day";
+
 export interface ProviderLimitStatus {
   providerId: string;
   dailyUsage: string;